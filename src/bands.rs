@@ -0,0 +1,105 @@
+//! Observing-band templates read from `bands.toml`, and the small TOML subset they use.
+
+/// A named band preset. Fields left out of the file are `None` and keep the current
+/// setting when the band is applied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BandTemplate {
+    pub name: String,
+    pub frequency_ghz: Option<f64>,
+    pub elevation_floor_deg: Option<f64>,
+    pub sun_avoid_deg: Option<f64>,
+    pub moon_avoid_deg: Option<f64>,
+    /// Zenith opacity; zero turns the opacity floor off.
+    pub tau0: Option<f64>,
+    pub max_line_opacity: Option<f64>,
+}
+
+/// Parses `[[band]]` tables of `key = value` lines, where values are numbers or, for
+/// `name`, double-quoted strings. `#` starts a comment. Other TOML constructs, unknown
+/// keys and unnamed bands are errors, so typos are reported instead of silently ignored.
+pub fn parse_band_templates(content: &str) -> Result<Vec<BandTemplate>, String> {
+    let mut bands: Vec<BandTemplate> = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        let error = |message: &str| format!("bands.toml line {}: {}", line_no + 1, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.split('#').next().unwrap_or("").trim() == "[[band]]" {
+            bands.push(BandTemplate::default());
+            continue;
+        }
+        if line.starts_with('[') {
+            return Err(error("only [[band]] tables are supported"));
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected key = value"))?;
+        let band = bands
+            .last_mut()
+            .ok_or_else(|| error("key outside a [[band]] table"))?;
+        let key = key.trim();
+        let value = value.trim();
+        if key == "name" {
+            let quoted = value
+                .strip_prefix('"')
+                .and_then(|rest| rest.split_once('"'))
+                .filter(|(_, rest)| rest.trim().is_empty() || rest.trim().starts_with('#'));
+            match quoted {
+                Some((name, _)) if !name.trim().is_empty() => band.name = name.to_string(),
+                _ => return Err(error("name must be a non-empty quoted string")),
+            }
+            continue;
+        }
+        let number = value
+            .split('#')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| error(&format!("invalid number for {}", key)))?;
+        let field = match key {
+            "frequency_ghz" => &mut band.frequency_ghz,
+            "elevation_floor_deg" => &mut band.elevation_floor_deg,
+            "sun_avoid_deg" => &mut band.sun_avoid_deg,
+            "moon_avoid_deg" => &mut band.moon_avoid_deg,
+            "tau0" => &mut band.tau0,
+            "max_line_opacity" => &mut band.max_line_opacity,
+            _ => return Err(error(&format!("unknown key {}", key))),
+        };
+        *field = Some(number);
+    }
+    if let Some(index) = bands.iter().position(|band| band.name.is_empty()) {
+        return Err(format!("bands.toml: band {} has no name", index + 1));
+    }
+    Ok(bands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_band_templates() {
+        let bands = parse_band_templates(
+            "# comment\n[[band]]\nname = \"K-band # maser\" # trailing\nfrequency_ghz = 22.2\ntau0 = 0.1 # zenith\n\n[[band]]\nname = \"L\"\n",
+        )
+        .unwrap();
+        assert_eq!(bands.len(), 2);
+        assert_eq!(bands[0].name, "K-band # maser");
+        assert_eq!(bands[0].frequency_ghz, Some(22.2));
+        assert_eq!(bands[0].tau0, Some(0.1));
+        assert_eq!(bands[0].sun_avoid_deg, None);
+        assert_eq!(bands[1].name, "L");
+
+        assert!(parse_band_templates("frequency_ghz = 1.0").is_err());
+        assert!(parse_band_templates("[[band]]\nname = \"X\"\nfrequncy_ghz = 8.4").is_err());
+        assert!(parse_band_templates("[[band]]\nfrequency_ghz = 8.4").is_err());
+        assert!(parse_band_templates("[band]\nname = \"X\"").is_err());
+
+        let defaults = include_str!("../bands.toml");
+        assert!(parse_band_templates(defaults).is_ok_and(|bands| bands.len() >= 3));
+    }
+}
@@ -0,0 +1,781 @@
+//! Station and source catalogues and their text-file line parsers.
+
+use crate::utils::{parse_dec, parse_ra};
+use std::path::Path;
+
+/// A station from `station.txt`: ECEF position in metres, optional hour-angle limits and
+/// optional pointing-model offsets.
+#[derive(Clone, Debug)]
+pub struct Station {
+    pub name: String,
+    pub pos: [f64; 3],
+    /// Allowed local hour angle range in hours.
+    pub ha_limits: Option<(f64, f64)>,
+    /// Pointing-model corrections (arcmin) added to the computed azimuth and elevation to give
+    /// the commanded angles; zero when the station line has none.
+    pub az_offset_arcmin: f64,
+    pub el_offset_arcmin: f64,
+}
+
+impl Station {
+    /// `(az, el)` in degrees with the pointing-model offsets applied, azimuth kept in 0-360°.
+    pub fn commanded_az_el(&self, az_deg: f64, el_deg: f64) -> (f64, f64) {
+        (
+            (az_deg + self.az_offset_arcmin / 60.0).rem_euclid(360.0),
+            el_deg + self.el_offset_arcmin / 60.0,
+        )
+    }
+
+    pub fn ha_allows(&self, ha_hours: f64) -> bool {
+        self.ha_limits
+            .is_none_or(|(ha_min, ha_max)| ha_hours >= ha_min && ha_hours <= ha_max)
+    }
+
+    /// Distance (hours) inside the HA limits; negative outside, infinite without limits.
+    pub fn ha_margin(&self, ha_hours: f64) -> f64 {
+        self.ha_limits.map_or(f64::INFINITY, |(ha_min, ha_max)| {
+            (ha_hours - ha_min).min(ha_max - ha_hours)
+        })
+    }
+}
+
+/// A source from `source.txt`, keeping the sexagesimal fields for DRG/SKD output.
+#[derive(Clone, Debug)]
+pub struct Source {
+    pub name: String,
+    pub ra_rad: f64,
+    pub dec_rad: f64,
+    pub ra_h: i32,
+    pub ra_m: i32,
+    pub ra_s: f64,
+    pub dec_sign: char,
+    pub dec_d: i32,
+    pub dec_m: i32,
+    pub dec_s: f64,
+    pub epoch: String,
+    /// Optional group tag (e.g. "target", "fringe-finder") from the column after the epoch.
+    pub group: Option<String>,
+    /// Science priority from an optional `P<integer>` column after the epoch; higher is more
+    /// important. `DEFAULT_PRIORITY` when absent.
+    pub priority: i32,
+}
+
+/// Priority of sources whose catalogue line has no priority column.
+pub const DEFAULT_PRIORITY: i32 = 0;
+
+/// Reads the optional columns after a source's epoch: `P` followed by an integer (`P3`,
+/// `P-1`) is the priority, the first other word is the group tag, so numeric tags such as
+/// `2` stay groups. Either may be absent and they may come in either order.
+pub fn parse_source_extras(tokens: &[&str]) -> (Option<String>, i32) {
+    let mut group = None;
+    let mut priority = DEFAULT_PRIORITY;
+    for token in tokens {
+        match token.strip_prefix('P').map(str::parse::<i32>) {
+            Some(Ok(value)) => priority = value,
+            _ if group.is_none() => group = Some(token.to_string()),
+            _ => {}
+        }
+    }
+    (group, priority)
+}
+
+/// Parses `NAME X Y Z [HA_MIN HA_MAX [AZ_OFFSET EL_OFFSET]]`, with the HA limits in hours
+/// and the pointing offsets in arcmin. `- -` in place of the HA limits gives offsets without
+/// limits.
+pub fn parse_station_line(line: &str) -> Result<Station, String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 4 && parts.len() != 6 && parts.len() != 8 {
+        return Err(format!("Invalid line format in station file: {}", line));
+    }
+    let (Ok(pos_x), Ok(pos_y), Ok(pos_z)) = (
+        parts[1].parse::<f64>(),
+        parts[2].parse::<f64>(),
+        parts[3].parse::<f64>(),
+    ) else {
+        return Err(format!("Invalid number format in station file: {}", line));
+    };
+    let ha_limits = if parts.len() >= 6 && parts[4..6] != ["-", "-"] {
+        match (parts[4].parse::<f64>(), parts[5].parse::<f64>()) {
+            (Ok(ha_min), Ok(ha_max)) if ha_min < ha_max => Some((ha_min, ha_max)),
+            _ => return Err(format!("Invalid HA limits in station file: {}", line)),
+        }
+    } else {
+        None
+    };
+    let (az_offset_arcmin, el_offset_arcmin) = if parts.len() == 8 {
+        match (parts[6].parse::<f64>(), parts[7].parse::<f64>()) {
+            (Ok(az), Ok(el)) if az.is_finite() && el.is_finite() => (az, el),
+            _ => {
+                return Err(format!(
+                    "Invalid pointing offsets in station file: {}",
+                    line
+                ))
+            }
+        }
+    } else {
+        (0.0, 0.0)
+    };
+    Ok(Station {
+        name: parts[0].to_string(),
+        pos: [pos_x, pos_y, pos_z],
+        ha_limits,
+        az_offset_arcmin,
+        el_offset_arcmin,
+    })
+}
+
+pub fn parse_source_tokens(
+    parts: &[&str],
+    name_idx: usize,
+    coord_start: usize,
+    line: &str,
+) -> Result<Source, String> {
+    if parts.len() <= coord_start + 6 {
+        return Err(format!("Invalid source line: {}", line));
+    }
+
+    let name = parts[name_idx].to_string();
+    let ra_h: i32 = parts[coord_start]
+        .parse()
+        .map_err(|_| format!("Invalid RA hour: {}", line))?;
+    let ra_m: i32 = parts[coord_start + 1]
+        .parse()
+        .map_err(|_| format!("Invalid RA minute: {}", line))?;
+    let ra_s: f64 = parts[coord_start + 2]
+        .parse()
+        .map_err(|_| format!("Invalid RA second: {}", line))?;
+    if !(0..24).contains(&ra_h) {
+        return Err(format!("RA hour out of range (0-23): {}", line));
+    }
+    if !(0..60).contains(&ra_m) {
+        return Err(format!("RA minute out of range (0-59): {}", line));
+    }
+    if !(0.0..60.0).contains(&ra_s) {
+        return Err(format!("RA second out of range (0-60): {}", line));
+    }
+    let ra_hours = ra_h as f64 + ra_m as f64 / 60.0 + ra_s / 3600.0;
+    let ra_rad = ra_hours * 15.0_f64.to_radians();
+
+    let dec_d_str = parts[coord_start + 3];
+    let dec_sign = if dec_d_str.starts_with('-') { '-' } else { '+' };
+    let dec_d_raw: i32 = dec_d_str
+        .parse()
+        .map_err(|_| format!("Invalid Dec degree: {}", line))?;
+    let dec_m: i32 = parts[coord_start + 4]
+        .parse()
+        .map_err(|_| format!("Invalid Dec minute: {}", line))?;
+    let dec_s: f64 = parts[coord_start + 5]
+        .parse()
+        .map_err(|_| format!("Invalid Dec second: {}", line))?;
+    let dec_d = dec_d_raw.abs();
+    if !(0..60).contains(&dec_m) {
+        return Err(format!("Dec minute out of range (0-59): {}", line));
+    }
+    if !(0.0..60.0).contains(&dec_s) {
+        return Err(format!("Dec second out of range (0-60): {}", line));
+    }
+    let sign = if dec_sign == '-' { -1.0 } else { 1.0 };
+    let dec_deg = sign * (dec_d as f64 + dec_m as f64 / 60.0 + dec_s / 3600.0);
+    if dec_deg.abs() > 90.0 {
+        return Err(format!("Dec out of range (-90 to +90): {}", line));
+    }
+    let dec_rad = dec_deg.to_radians();
+    let epoch = parts
+        .get(coord_start + 6)
+        .copied()
+        .unwrap_or("2000.0")
+        .to_string();
+
+    Ok(Source {
+        name,
+        ra_rad,
+        dec_rad,
+        ra_h,
+        ra_m,
+        ra_s,
+        dec_sign,
+        dec_d,
+        dec_m,
+        dec_s,
+        epoch,
+        group: None,
+        priority: DEFAULT_PRIORITY,
+    })
+}
+
+/// True if `path` has a `.csv`/`.tsv` extension or the first non-comment line of `content` is
+/// a comma- or tab-separated header with a `name` column.
+pub fn is_csv_catalog(path: &Path, content: &str) -> bool {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv") || ext.eq_ignore_ascii_case("tsv"))
+    {
+        return true;
+    }
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('*') && !line.starts_with('#'))
+        .is_some_and(|header| {
+            let delimiter = csv_delimiter(header);
+            header.contains(delimiter)
+                && split_csv_row(header, delimiter)
+                    .iter()
+                    .any(|cell| CSV_NAME_COLUMNS.contains(&cell.to_ascii_lowercase().as_str()))
+        })
+}
+
+const CSV_NAME_COLUMNS: [&str; 3] = ["name", "source", "source_name"];
+
+fn csv_delimiter(header: &str) -> char {
+    if header.contains('\t') {
+        '\t'
+    } else {
+        ','
+    }
+}
+
+/// Splits one CSV row into trimmed cells. A cell in double quotes may hold the delimiter, and
+/// `""` inside it is a literal quote, as spreadsheets write them.
+fn split_csv_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if in_quotes || cell.trim().is_empty() => {
+                in_quotes = !in_quotes;
+                if in_quotes {
+                    cell.clear();
+                }
+            }
+            c if c == delimiter && !in_quotes => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+        .into_iter()
+        .map(|cell| cell.trim().to_string())
+        .collect()
+}
+
+/// How a CSV header gives one coordinate: decimal degrees, separate sexagesimal columns, or
+/// one sexagesimal text column.
+enum CsvCoordinate {
+    Degrees(usize),
+    Fields(usize, usize, usize),
+    Text(usize),
+}
+
+impl CsvCoordinate {
+    fn from_header(
+        columns: &[String],
+        axis: &str,
+        first_field: &str,
+        fields: [&str; 2],
+    ) -> Result<Self, String> {
+        let find = |name: &str| columns.iter().position(|column| column == name);
+        if let Some(idx) = find(&format!("{}_deg", axis)) {
+            return Ok(Self::Degrees(idx));
+        }
+        let first_key = format!("{}_{}", axis, first_field);
+        let [middle_key, last_key] = fields.map(|field| format!("{}_{}", axis, field));
+        match (find(&first_key), find(&middle_key), find(&last_key)) {
+            (Some(first), Some(middle), Some(last)) => {
+                return Ok(Self::Fields(first, middle, last))
+            }
+            (None, None, None) => {}
+            found => {
+                let missing: Vec<&str> = [
+                    (found.0, &first_key),
+                    (found.1, &middle_key),
+                    (found.2, &last_key),
+                ]
+                .into_iter()
+                .filter(|(idx, _)| idx.is_none())
+                .map(|(_, key)| key.as_str())
+                .collect();
+                return Err(format!(
+                    "CSV header has sexagesimal {} columns but no {} column.",
+                    axis.to_ascii_uppercase(),
+                    missing.join(" or ")
+                ));
+            }
+        }
+        find(axis).map(Self::Text).ok_or_else(|| {
+            format!(
+                "CSV header has no {} column; expected {}_deg, {} (sexagesimal) or {} with {} and {}.",
+                axis.to_ascii_uppercase(),
+                axis,
+                axis,
+                first_key,
+                middle_key,
+                last_key
+            )
+        })
+    }
+
+    /// The coordinate in degrees from one row; `scale` converts the leading sexagesimal field
+    /// to degrees (15 for RA hours, 1 for Dec degrees).
+    fn degrees(
+        &self,
+        cells: &[String],
+        scale: f64,
+        parse_text: fn(&str) -> Result<f64, String>,
+    ) -> Result<f64, String> {
+        let cell = |idx: usize| cells.get(idx).map(String::as_str).unwrap_or("");
+        let number = |idx: usize| {
+            cell(idx)
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid number '{}'", cell(idx)))
+        };
+        match *self {
+            Self::Degrees(idx) => number(idx),
+            Self::Text(idx) => parse_text(cell(idx)).map(f64::to_degrees),
+            Self::Fields(first, middle, last) => {
+                let lead = number(first)?;
+                let minutes = number(middle)?;
+                let seconds = number(last)?;
+                if !(0.0..60.0).contains(&minutes) || !(0.0..60.0).contains(&seconds) {
+                    return Err("Minutes and seconds must be in 0-60".to_string());
+                }
+                let sign = if cell(first).starts_with('-') {
+                    -1.0
+                } else {
+                    1.0
+                };
+                Ok(sign * (lead.abs() + minutes / 60.0 + seconds / 3600.0) * scale)
+            }
+        }
+    }
+}
+
+/// Splits a non-negative value into whole units, whole minutes and seconds.
+fn sexagesimal_parts(value: f64) -> (i32, i32, f64) {
+    let total_seconds = value * 3600.0;
+    let units = (total_seconds / 3600.0).floor();
+    let minutes = ((total_seconds - units * 3600.0) / 60.0).floor();
+    let seconds = (total_seconds - units * 3600.0 - minutes * 60.0).clamp(0.0, 60.0 - 1e-9);
+    (units as i32, minutes as i32, seconds)
+}
+
+/// Parses a source catalogue in CSV (comma or tab separated) with a header row, mapping
+/// columns by header name: `name`, RA as `ra_deg`, `ra_h`/`ra_m`/`ra_s` or `ra` (sexagesimal
+/// text), Dec as `dec_deg`, `dec_d`/`dec_m`/`dec_s` or `dec`, and optional `epoch`, `group`
+/// and `priority`. Header names are case-insensitive and unknown columns are ignored.
+///
+/// A header that cannot be mapped is an error; bad rows are returned as messages beside the
+/// parsed sources, so one typo does not hide the rest of the catalogue.
+pub fn parse_source_csv(content: &str) -> Result<(Vec<Source>, Vec<String>), String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(line_no, line)| (line_no + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('*') && !line.starts_with('#'));
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| "CSV source file has no header row.".to_string())?;
+    let delimiter = csv_delimiter(header);
+    let columns: Vec<String> = split_csv_row(header, delimiter)
+        .iter()
+        .map(|column| column.to_ascii_lowercase())
+        .collect();
+    for (i, column) in columns.iter().enumerate() {
+        if !column.is_empty() && columns[..i].contains(column) {
+            return Err(format!("CSV header names column '{}' twice.", column));
+        }
+    }
+    let find = |name: &str| columns.iter().position(|column| column == name);
+    let name_idx = CSV_NAME_COLUMNS
+        .iter()
+        .find_map(|name| find(name))
+        .ok_or_else(|| {
+            format!(
+                "CSV header has no name column (expected one of: {}); found: {}.",
+                CSV_NAME_COLUMNS.join(", "),
+                columns.join(", ")
+            )
+        })?;
+    let ra = CsvCoordinate::from_header(&columns, "ra", "h", ["m", "s"])?;
+    let dec = CsvCoordinate::from_header(&columns, "dec", "d", ["m", "s"])?;
+    let (epoch_idx, group_idx, priority_idx) = (find("epoch"), find("group"), find("priority"));
+
+    let mut sources = Vec::new();
+    let mut invalid = Vec::new();
+    for (line_no, line) in lines {
+        let cells = split_csv_row(line, delimiter);
+        let cell = |idx: Option<usize>| {
+            idx.and_then(|idx| cells.get(idx))
+                .map(String::as_str)
+                .filter(|cell| !cell.is_empty())
+        };
+        let row = || -> Result<Source, String> {
+            let name = cell(Some(name_idx)).ok_or("Missing name")?;
+            if name.contains(char::is_whitespace) {
+                return Err(format!("Source name '{}' must be a single word", name));
+            }
+            let ra_deg = ra.degrees(&cells, 15.0, parse_ra)?;
+            let dec_deg = dec.degrees(&cells, 1.0, parse_dec)?;
+            if !(0.0..360.0).contains(&ra_deg) {
+                return Err(format!("RA out of range (0-24h): {}", ra_deg / 15.0));
+            }
+            if !(-90.0..=90.0).contains(&dec_deg) {
+                return Err(format!("Dec out of range (-90 to +90): {}", dec_deg));
+            }
+            let priority = match cell(priority_idx) {
+                Some(text) => text
+                    .parse()
+                    .map_err(|_| format!("Invalid priority '{}'", text))?,
+                None => DEFAULT_PRIORITY,
+            };
+            let (ra_h, ra_m, ra_s) = sexagesimal_parts(ra_deg / 15.0);
+            let (dec_d, dec_m, dec_s) = sexagesimal_parts(dec_deg.abs());
+            Ok(Source {
+                name: name.to_string(),
+                ra_rad: ra_deg.to_radians(),
+                dec_rad: dec_deg.to_radians(),
+                ra_h,
+                ra_m,
+                ra_s,
+                dec_sign: if dec_deg < 0.0 { '-' } else { '+' },
+                dec_d,
+                dec_m,
+                dec_s,
+                epoch: cell(epoch_idx).unwrap_or("2000.0").to_string(),
+                group: cell(group_idx).map(str::to_string),
+                priority,
+            })
+        };
+        match row() {
+            Ok(source) => sources.push(source),
+            Err(e) => invalid.push(format!("line {}: {}: {}", line_no, e, line)),
+        }
+    }
+    Ok((sources, invalid))
+}
+
+/// True if `path` has a `.vex` extension or `content` starts with a `VEX_rev` header.
+pub fn is_vex_file(path: &Path, content: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vex"))
+        || content.trim_start().starts_with("VEX_rev")
+}
+
+/// Extracts the `site_position` entries of a VEX `$SITE` block as stations, named by
+/// `site_name` when present and by the `def` name otherwise.
+pub fn parse_vex_sites(content: &str) -> Result<Vec<Station>, String> {
+    let without_comments: String = content
+        .lines()
+        .map(|line| line.split('*').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut stations = Vec::new();
+    let mut in_site_block = false;
+    let mut def_name: Option<String> = None;
+    let mut site_name: Option<String> = None;
+    let mut position: Option<[f64; 3]> = None;
+
+    for statement in without_comments.split(';').map(str::trim) {
+        if statement.starts_with('$') {
+            in_site_block = statement == "$SITE";
+            continue;
+        }
+        if !in_site_block {
+            continue;
+        }
+        if let Some(name) = statement.strip_prefix("def ") {
+            def_name = Some(name.trim().to_string());
+            site_name = None;
+            position = None;
+        } else if statement == "enddef" {
+            if let (Some(def), Some(pos)) = (def_name.take(), position.take()) {
+                stations.push(Station {
+                    name: site_name.take().unwrap_or(def),
+                    pos,
+                    ha_limits: None,
+                    az_offset_arcmin: 0.0,
+                    el_offset_arcmin: 0.0,
+                });
+            }
+        } else if let Some((key, value)) = statement.split_once('=') {
+            match key.trim() {
+                "site_name" => site_name = Some(value.trim().to_string()),
+                "site_position" => position = Some(parse_vex_position(value)?),
+                _ => {}
+            }
+        }
+    }
+
+    if stations.is_empty() {
+        return Err("No site_position entries found in VEX $SITE block.".to_string());
+    }
+    Ok(stations)
+}
+
+fn parse_vex_position(value: &str) -> Result<[f64; 3], String> {
+    let coords: Vec<f64> = value
+        .split(':')
+        .map(|field| {
+            let field = field.trim();
+            field
+                .strip_suffix('m')
+                .unwrap_or(field)
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid site_position in VEX file: {}", value.trim()))
+        })
+        .collect::<Result<_, _>>()?;
+    match coords.as_slice() {
+        [x, y, z] => Ok([*x, *y, *z]),
+        _ => Err(format!(
+            "Invalid site_position in VEX file: {}",
+            value.trim()
+        )),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CatalogKind {
+    Station,
+    Source,
+}
+
+/// Guesses whether `content` is a station file or a source catalogue from the shape of its
+/// data lines: ECEF triples near the Earth's radius versus sexagesimal RA/Dec. Returns
+/// `None` when neither kind has a clear majority.
+pub fn guess_catalog_kind(content: &str) -> Option<CatalogKind> {
+    let (mut station_like, mut source_like, mut total) = (0usize, 0usize, 0usize);
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        total += 1;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parse_station_line(line).is_ok_and(|station| {
+            let radius = station.pos.iter().map(|c| c * c).sum::<f64>().sqrt();
+            (6.30e6..6.45e6).contains(&radius)
+        }) {
+            station_like += 1;
+        } else if parse_source_tokens(&parts, 0, 1, line).is_ok_and(|source| {
+            source.ra_h < 24 && source.ra_m < 60 && source.dec_d <= 90 && source.dec_m < 60
+        }) {
+            source_like += 1;
+        }
+    }
+    if total == 0 {
+        None
+    } else if station_like * 2 > total {
+        Some(CatalogKind::Station)
+    } else if source_like * 2 > total {
+        Some(CatalogKind::Source)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vex_sites() {
+        let vex = "VEX_rev = 1.5;
+*------------------------
+$STATION;
+def Ks;
+     ref $SITE = KASHIM34;
+enddef;
+$SITE;
+def KASHIM34;
+     site_type = fixed;
+     site_name = KASHIM34;
+     site_ID = Ks;
+     site_position = -3997649.2290 m :  3276690.7100 m :  3724278.7500 m;
+enddef;
+def YAMAGU32;
+     site_position = -3502544.587 m : 3950966.235 m : 3566381.192 m; * comment
+enddef;
+$SOURCE;
+";
+        assert!(is_vex_file(Path::new("exp.txt"), vex));
+        let stations = parse_vex_sites(vex).unwrap();
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0].name, "KASHIM34");
+        assert_eq!(stations[0].pos, [-3997649.229, 3276690.71, 3724278.75]);
+        assert_eq!(stations[1].name, "YAMAGU32");
+    }
+
+    #[test]
+    fn test_parse_station_line() {
+        let plain = parse_station_line("YAMAGU32 -3502544.587 3950966.235 3566381.192").unwrap();
+        assert_eq!(plain.ha_limits, None);
+        assert_eq!((plain.az_offset_arcmin, plain.el_offset_arcmin), (0.0, 0.0));
+
+        let limited = parse_station_line("EQ1 1 2 3 -4 4").unwrap();
+        assert_eq!(limited.ha_limits, Some((-4.0, 4.0)));
+
+        let offset = parse_station_line("YA 1 2 3 - - 1.5 -0.6").unwrap();
+        assert_eq!(offset.ha_limits, None);
+        assert_eq!(
+            (offset.az_offset_arcmin, offset.el_offset_arcmin),
+            (1.5, -0.6)
+        );
+        let (az, el) = offset.commanded_az_el(359.99, 45.0);
+        assert!((az - 0.015).abs() < 1e-9 && (el - 44.99).abs() < 1e-9);
+
+        assert!(parse_station_line("YA 1 2 3 -4 4 x 0").is_err());
+        assert!(parse_station_line("YA 1 2 3 - 4").is_err());
+    }
+
+    #[test]
+    fn test_parse_source_extras() {
+        assert_eq!(parse_source_extras(&[]), (None, DEFAULT_PRIORITY));
+        assert_eq!(
+            parse_source_extras(&["target", "P3"]),
+            (Some("target".to_string()), 3)
+        );
+        assert_eq!(
+            parse_source_extras(&["P-1", "fringe-finder"]),
+            (Some("fringe-finder".to_string()), -1)
+        );
+        // A bare number is a group tag, not a priority.
+        assert_eq!(
+            parse_source_extras(&["2", "P1"]),
+            (Some("2".to_string()), 1)
+        );
+        assert_eq!(
+            parse_source_extras(&["Pilot"]),
+            (Some("Pilot".to_string()), DEFAULT_PRIORITY)
+        );
+    }
+
+    #[test]
+    fn test_parse_source_tokens_zero_degree_sign() {
+        // The sign lives only on the degree field, which parses as 0 for "-00" and "+00".
+        let south = "S1 01 00 00.0 -00 30 00.0 2000.0";
+        let parts: Vec<&str> = south.split_whitespace().collect();
+        let source = parse_source_tokens(&parts, 0, 1, south).unwrap();
+        assert_eq!(source.dec_sign, '-');
+        assert_eq!(source.dec_d, 0);
+        assert!((source.dec_rad.to_degrees() + 0.5).abs() < 1e-12);
+
+        let north = "N1 01 00 00.0 +00 30 00.0 2000.0";
+        let parts: Vec<&str> = north.split_whitespace().collect();
+        let source = parse_source_tokens(&parts, 0, 1, north).unwrap();
+        assert_eq!(source.dec_sign, '+');
+        assert!((source.dec_rad.to_degrees() - 0.5).abs() < 1e-12);
+
+        let unsigned = "U1 01 00 00.0 00 00 30.0 2000.0";
+        let parts: Vec<&str> = unsigned.split_whitespace().collect();
+        let source = parse_source_tokens(&parts, 0, 1, unsigned).unwrap();
+        assert!(source.dec_rad > 0.0);
+    }
+
+    #[test]
+    fn test_parse_source_tokens_rejects_out_of_range() {
+        let parse = |line: &str| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            parse_source_tokens(&parts, 0, 1, line)
+        };
+        assert!(parse("OK 23 59 59.99 +90 00 00.0 2000.0").is_ok());
+        assert!(parse("OK -00 00 00.0 -90 00 00.0 2000.0").is_ok());
+        for bad in [
+            "RA 25 00 00.0 +10 00 00.0 2000.0",
+            "RA 24 00 00.0 +10 00 00.0 2000.0",
+            "RA 12 60 00.0 +10 00 00.0 2000.0",
+            "RA 12 00 60.0 +10 00 00.0 2000.0",
+            "DE 12 00 00.0 +91 00 00.0 2000.0",
+            "DE 12 00 00.0 -90 00 01.0 2000.0",
+            "DE 12 00 00.0 +10 75 00.0 2000.0",
+            "DE 12 00 00.0 +10 00 -1.0 2000.0",
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+        assert!(parse("RA 25 00 00.0 +10 00 00.0 2000.0")
+            .unwrap_err()
+            .contains("RA hour"));
+    }
+
+    #[test]
+    fn test_parse_source_csv() {
+        let csv = "Name,RA_deg,Dec_deg,group\n3C84,49.950667,41.511696,fringe-finder\nBAD,400,0\n";
+        assert!(is_csv_catalog(Path::new("sources.txt"), csv));
+        assert!(!is_csv_catalog(
+            Path::new("source.txt"),
+            "3C84 03 19 48.160 +41 30 42.10600 2000.0\n"
+        ));
+        let (sources, invalid) = parse_source_csv(csv).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].starts_with("line 3:"));
+        let source = &sources[0];
+        assert_eq!(
+            (source.ra_h, source.ra_m, source.dec_d, source.dec_m),
+            (3, 19, 41, 30)
+        );
+        assert!((source.ra_s - 48.16).abs() < 1e-3);
+        assert_eq!(source.group.as_deref(), Some("fringe-finder"));
+
+        let tsv = "name\tra_h\tra_m\tra_s\tdec_d\tdec_m\tdec_s\n\
+                   S1\t12\t30\t00\t-00\t30\t00\n\
+                   S2\t01\t00\t00\t+10\t00\t00\n";
+        let (sources, invalid) = parse_source_csv(tsv).unwrap();
+        assert!(invalid.is_empty());
+        assert!((sources[0].ra_rad.to_degrees() - 187.5).abs() < 1e-9);
+        assert!((sources[0].dec_rad.to_degrees() + 0.5).abs() < 1e-9);
+        assert_eq!(sources[0].dec_sign, '-');
+
+        let text = "name,ra,dec\nS3,12:30:00,-05:00:00\n";
+        let (sources, _) = parse_source_csv(text).unwrap();
+        assert!((sources[0].dec_rad.to_degrees() + 5.0).abs() < 1e-9);
+
+        assert!(parse_source_csv("name,dec_deg\nX,10\n")
+            .unwrap_err()
+            .contains("no RA column"));
+        assert!(parse_source_csv("ra_deg,dec_deg\n1,2\n")
+            .unwrap_err()
+            .contains("no name column"));
+        assert!(parse_source_csv("name,ra_m,ra_s,dec_deg\nX,1,2,3\n")
+            .unwrap_err()
+            .contains("no ra_h column"));
+        // Every sexagesimal field is required once one is given.
+        assert!(parse_source_csv("name,ra_h,ra_m,dec_deg\nX,1,2,3\n")
+            .unwrap_err()
+            .contains("no ra_s column"));
+        assert!(parse_source_csv("name,ra_deg,dec_d\nX,1,2\n")
+            .unwrap_err()
+            .contains("no dec_m or dec_s column"));
+
+        // Quoted cells may hold the delimiter and doubled quotes.
+        let quoted = "name,ra_deg,dec_deg,group\n\"3C84\",\"49.950667\",41.511696,\"fringe, \"\"bright\"\"\"\n";
+        let (sources, invalid) = parse_source_csv(quoted).unwrap();
+        assert!(invalid.is_empty());
+        assert_eq!(sources[0].name, "3C84");
+        assert_eq!(sources[0].group.as_deref(), Some("fringe, \"bright\""));
+    }
+
+    #[test]
+    fn test_split_csv_row() {
+        assert_eq!(
+            split_csv_row(" a , \"b, c\" ,\"d\"\"e\",", ','),
+            ["a", "b, c", "d\"e", ""]
+        );
+        assert_eq!(split_csv_row("a\t\"b\tc\"", '\t'), ["a", "b\tc"]);
+    }
+
+    #[test]
+    fn test_guess_catalog_kind() {
+        let stations = "* comment\nYAMAGU32 -3502544.587 3950966.235 3566381.192\n";
+        let sources = "3C84 03 19 48.160 +41 30 42.10600 2000.0\nJ2202+42 22 02 43.291 +42 16 39.97987 2000.0\n";
+        assert_eq!(guess_catalog_kind(stations), Some(CatalogKind::Station));
+        assert_eq!(guess_catalog_kind(sources), Some(CatalogKind::Source));
+        assert_eq!(guess_catalog_kind("hello world\n"), None);
+    }
+}
@@ -0,0 +1,36 @@
+//! Core calculations behind uptimeplot, usable without the GUI.
+//!
+//! Build with `default-features = false` to depend on this crate without eframe/egui:
+//!
+//! ```
+//! use chrono::{TimeZone, Utc};
+//! use uptimeplot::{parse_station_line, radec2azalt};
+//!
+//! let station = parse_station_line("YAMAGU32 -3502544.587 3950966.235 3566381.192").unwrap();
+//! let time = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+//! let pointing = radec2azalt(station.pos, time, 0.0, 0.5);
+//! assert!(pointing.el_deg.is_finite());
+//! ```
+
+pub mod bands;
+pub mod catalog;
+pub mod pdf;
+pub mod utils;
+
+pub use bands::{parse_band_templates, BandTemplate};
+pub use catalog::{
+    is_csv_catalog, parse_source_csv, parse_source_extras, parse_source_tokens, parse_station_line,
+    Source, Station, DEFAULT_PRIORITY,
+};
+pub use utils::{
+    angular_separation_deg, az_sector_margin, best_night_dates, catmull_rom_track, close_pairs,
+    complement_windows, ecef_direction, ecliptic_radec, finite_runs, fit_scans, format_dec,
+    format_ra, galactic_to_radec, geometric_delay_along, geometric_delay_seconds,
+    high_opacity_elevation_deg, icrs_to_apparent, intersect_windows, merge_close_windows,
+    moon_radec, mosaic_offsets, opacity_floor_deg, order_minimizing_cost, parse_az_sectors,
+    parse_dec, parse_ra, path_cost, priority_weight, radec2azalt, radec2azalt_on, radec2azalt_with,
+    refraction_deg, sidereal_time, sun_radec, total_window_hours, union_windows, unwrap_azimuths,
+    ut1_from_utc, utc_to_lst_hours, visibility_mask, visibility_windows,
+    visibility_windows_from_margin, Ellipsoid, NightVisibility, PointingResult, ReferenceFrame,
+    SiderealMode, SiderealTime, APPARENT_HORIZON_DEG, SPEED_OF_LIGHT_M_PER_S,
+};
@@ -3,14 +3,22 @@
 use chrono::{Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
 use clap::{CommandFactory, Parser};
 use eframe::egui;
-use egui_plot::{Corner, GridMark, Legend, Line, Plot, PlotPoints, Points};
-use image;
-use std::collections::HashMap;
+use egui_plot::{Corner, GridInput, GridMark, Legend, Line, Plot, PlotPoints, Points};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-
-mod utils;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Instant, SystemTime};
+
+use uptimeplot::bands::{parse_band_templates, BandTemplate};
+use uptimeplot::catalog::{
+    guess_catalog_kind, is_csv_catalog, is_vex_file, parse_source_csv, parse_source_extras,
+    parse_source_tokens, parse_station_line, parse_vex_sites, CatalogKind, Source, Station,
+    DEFAULT_PRIORITY,
+};
+use uptimeplot::pdf::{PdfFont, PdfImage, PdfPage};
+use uptimeplot::utils;
 
 fn apply_station_line_style(line: Line, station_idx: usize) -> Line {
     match station_idx % 4 {
@@ -21,12 +29,338 @@ fn apply_station_line_style(line: Line, station_idx: usize) -> Line {
         2 => line
             .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 0, 200)))
             .style(egui_plot::LineStyle::Dotted { spacing: 5.0 }),
-        _ => line
-            .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(200, 200, 0))),
+        _ => line.stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(200, 200, 0))),
     }
 }
 
 const PLOT_Y_AXIS_MIN_WIDTH: f32 = 96.0;
+const HOVER_SNAP_PX: f32 = 12.0;
+const HOVER_HIGHLIGHT_EXTRA_WIDTH: f32 = 2.0;
+/// Points drawn per sample interval when "Smooth curves" is on.
+const SMOOTH_SUBDIVISIONS: usize = 4;
+const PREFERENCES_FILE: &str = "preferences.txt";
+/// Catalogues, selection, date and observation settings saved on exit for auto-restore.
+const SESSION_FILE: &str = "last_session.txt";
+/// Blank space (hours) right of the last sample on UT plots, where the colour bar sits.
+const UT_PLOT_RIGHT_MARGIN_HOURS: f64 = 0.7;
+/// Longest sampling extension past midnight into the next date.
+const MAX_OVERNIGHT_HOURS: f64 = 12.0;
+/// Narrowest UT range (hours) the time-axis zoom allows.
+const MIN_TIME_VIEW_HOURS: f64 = 0.25;
+
+/// Keeps a zoomed UT range within `full` and no narrower than `MIN_TIME_VIEW_HOURS`.
+fn clamp_time_view(min: f64, max: f64, full: (f64, f64)) -> (f64, f64) {
+    let (full_min, full_max) = full;
+    let width = (max - min).clamp(MIN_TIME_VIEW_HOURS, full_max - full_min);
+    let start = min.clamp(full_min, full_max - width);
+    (start, start + width)
+}
+
+/// Hourly UT grid marks up to `last_hour`, plus quarter hours once the view is zoomed in to a
+/// few hours.
+fn ut_grid_marks(input: GridInput, last_hour: f64) -> Vec<GridMark> {
+    let (min, max) = input.bounds;
+    let step = if max - min < 6.0 { 0.25 } else { 1.0 };
+    let first = (min.max(0.0) / step).ceil() as i64;
+    let last = (max.min(last_hour) / step).floor() as i64;
+    (first..=last)
+        .map(|i| GridMark {
+            value: i as f64 * step,
+            step_size: if step < 1.0 { step } else { 3.0 },
+        })
+        .collect()
+}
+
+/// Sidereal hours elapsed per UT hour.
+const SIDEREAL_PER_UT_HOUR: f64 = 1.002_737_909_350_795;
+
+/// Grid marks at the UT positions of whole LST hours (quarter hours once zoomed in to a few
+/// hours), given the LST at 00:00 UT.
+fn lst_grid_marks(input: GridInput, last_hour: f64, lst_at_midnight: f64) -> Vec<GridMark> {
+    let (min, max) = input.bounds;
+    let (min, max) = (min.max(0.0), max.min(last_hour));
+    let step = if max - min < 6.0 { 0.25 } else { 1.0 };
+    let lst_at = |ut: f64| lst_at_midnight + ut * SIDEREAL_PER_UT_HOUR;
+    let first = (lst_at(min) / step).ceil() as i64;
+    let last = (lst_at(max) / step).floor() as i64;
+    (first..=last)
+        .map(|i| GridMark {
+            value: (i as f64 * step - lst_at_midnight) / SIDEREAL_PER_UT_HOUR,
+            step_size: if step < 1.0 { step } else { 3.0 },
+        })
+        .collect()
+}
+
+/// Marker shape of one source's points on the polar plot, so overlapping tracks can be told
+/// apart without relying on colour.
+#[derive(Clone, Copy, PartialEq)]
+enum SourceMarker {
+    Circle,
+    Cross,
+    Diamond,
+    Square,
+}
+
+impl SourceMarker {
+    const ALL: [SourceMarker; 4] = [
+        SourceMarker::Circle,
+        SourceMarker::Cross,
+        SourceMarker::Diamond,
+        SourceMarker::Square,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SourceMarker::Circle => "Circle",
+            SourceMarker::Cross => "Cross",
+            SourceMarker::Diamond => "Diamond",
+            SourceMarker::Square => "Square",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            SourceMarker::Circle => "circle",
+            SourceMarker::Cross => "cross",
+            SourceMarker::Diamond => "diamond",
+            SourceMarker::Square => "square",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|marker| marker.key() == key)
+    }
+
+    fn next(self) -> Self {
+        let idx = Self::ALL
+            .iter()
+            .position(|&marker| marker == self)
+            .unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn shape(self) -> egui_plot::MarkerShape {
+        match self {
+            SourceMarker::Circle => egui_plot::MarkerShape::Circle,
+            SourceMarker::Cross => egui_plot::MarkerShape::Cross,
+            SourceMarker::Diamond => egui_plot::MarkerShape::Diamond,
+            SourceMarker::Square => egui_plot::MarkerShape::Square,
+        }
+    }
+
+    /// Draws the marker centred on `center`, for the shape legend outside the plot.
+    fn paint(self, painter: &egui::Painter, center: egui::Pos2, radius: f32, color: egui::Color32) {
+        match self {
+            SourceMarker::Circle => {
+                painter.circle_filled(center, radius, color);
+            }
+            SourceMarker::Cross => {
+                let stroke = egui::Stroke::new(2.0, color);
+                let d = radius * std::f32::consts::FRAC_1_SQRT_2;
+                painter.line_segment(
+                    [center + egui::vec2(-d, -d), center + egui::vec2(d, d)],
+                    stroke,
+                );
+                painter.line_segment(
+                    [center + egui::vec2(-d, d), center + egui::vec2(d, -d)],
+                    stroke,
+                );
+            }
+            SourceMarker::Diamond => {
+                let points = vec![
+                    center + egui::vec2(0.0, -radius),
+                    center + egui::vec2(radius, 0.0),
+                    center + egui::vec2(0.0, radius),
+                    center + egui::vec2(-radius, 0.0),
+                ];
+                painter.add(egui::Shape::convex_polygon(
+                    points,
+                    color,
+                    egui::Stroke::NONE,
+                ));
+            }
+            SourceMarker::Square => {
+                let half = radius * 0.8;
+                painter.rect_filled(
+                    egui::Rect::from_center_size(center, egui::vec2(2.0 * half, 2.0 * half)),
+                    0.0,
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// Where the vertical grid lines of the UT plots fall.
+#[derive(Clone, Copy, PartialEq)]
+enum GridAlignment {
+    Ut,
+    Lst,
+}
+
+impl GridAlignment {
+    const ALL: [GridAlignment; 2] = [GridAlignment::Ut, GridAlignment::Lst];
+
+    fn label(self) -> &'static str {
+        match self {
+            GridAlignment::Ut => "UT hours",
+            GridAlignment::Lst => "LST hours",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            GridAlignment::Ut => "ut",
+            GridAlignment::Lst => "lst",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|alignment| alignment.key() == key)
+    }
+}
+
+/// Vertical grid of a UT plot: whole UT hours, or whole LST hours of one station labelled in
+/// LST when `lst_at_midnight` is set.
+#[derive(Clone, Copy)]
+struct TimeGrid {
+    last_hour: f64,
+    lst_at_midnight: Option<f64>,
+}
+
+impl TimeGrid {
+    fn marks(self, input: GridInput) -> Vec<GridMark> {
+        match self.lst_at_midnight {
+            Some(lst) => lst_grid_marks(input, self.last_hour, lst),
+            None => ut_grid_marks(input, self.last_hour),
+        }
+    }
+
+    fn label(self, ut_hour: f64) -> String {
+        let Some(lst) = self.lst_at_midnight else {
+            return format_ut_axis_mark(ut_hour);
+        };
+        // Marks sit on whole LST minutes; rounding hides the float error of the inversion.
+        let lst = ((lst + ut_hour * SIDEREAL_PER_UT_HOUR) * 60.0).round() / 60.0;
+        let lst = lst.rem_euclid(24.0);
+        if lst.fract() == 0.0 {
+            format!("{:.0}", lst)
+        } else {
+            TimeDisplay::default().format(lst)
+        }
+    }
+
+    fn axis_label(self) -> &'static str {
+        if self.lst_at_midnight.is_some() {
+            "Time (UT), grid at LST hours"
+        } else {
+            "Time (UT)"
+        }
+    }
+}
+
+/// Axis label for a UT mark; hours past 24 belong to the next date and restart from 1.
+fn format_ut_axis_mark(hour: f64) -> String {
+    let hour = if hour > 24.0 { hour - 24.0 } else { hour };
+    if hour.fract() == 0.0 {
+        format!("{:.0}", hour)
+    } else {
+        TimeDisplay::default().format(hour)
+    }
+}
+
+/// How UT clock times are shown in tooltips, readouts and summaries. The default, 24-hour
+/// `HH:MM`, is used for axis labels.
+#[derive(Clone, Copy, Default)]
+struct TimeDisplay {
+    twelve_hour: bool,
+    seconds: bool,
+}
+
+impl TimeDisplay {
+    /// Formats `hour` rounded to the nearest shown unit, so 1.9999 h reads 02:00, not 01:59.
+    fn format(self, hour: f64) -> String {
+        if !hour.is_finite() {
+            return if self.seconds { "--:--:--" } else { "--:--" }.to_string();
+        }
+        let unit = if self.seconds { 1 } else { 60 };
+        let total = ((hour.rem_euclid(24.0) * 3600.0 / unit as f64).round() as i64 * unit)
+            .rem_euclid(86_400);
+        let (hh, mm, ss) = (total / 3600, (total % 3600) / 60, total % 60);
+        let (hh, suffix) = if self.twelve_hour {
+            let suffix = if hh < 12 { " AM" } else { " PM" };
+            (if hh % 12 == 0 { 12 } else { hh % 12 }, suffix)
+        } else {
+            (hh, "")
+        };
+        if self.seconds {
+            format!("{:02}:{:02}:{:02}{}", hh, mm, ss, suffix)
+        } else {
+            format!("{:02}:{:02}{}", hh, mm, suffix)
+        }
+    }
+}
+
+/// Adds a coloured, empty legend entry naming the group of `series[idx]` when it starts a new
+/// group section; with insertion-ordered legends the entries then read as sections.
+fn draw_group_legend_header(plot_ui: &mut egui_plot::PlotUi, series: &[PlotSeries], idx: usize) {
+    if !series.iter().any(|s| s.group.is_some()) {
+        return;
+    }
+    let group = &series[idx].group;
+    if idx > 0 && &series[idx - 1].group == group {
+        return;
+    }
+    let (name, color) = match group {
+        Some(group) => (format!("[{}]", group), group_color(group)),
+        None => ("[ungrouped]".to_string(), egui::Color32::GRAY),
+    };
+    plot_ui.line(Line::new(name, PlotPoints::from(Vec::<[f64; 2]>::new())).color(color));
+}
+
+/// Finds the plotted sample closest to the pointer (in screen pixels) across `tracks`,
+/// returning the track index and the sample if it lies within `HOVER_SNAP_PX`.
+fn nearest_plot_sample<'a>(
+    plot_ui: &egui_plot::PlotUi,
+    tracks: impl Iterator<Item = &'a [[f64; 2]]>,
+) -> Option<(usize, [f64; 2])> {
+    if !plot_ui.response().hovered() {
+        return None;
+    }
+    let pointer = plot_ui.ctx().input(|i| i.pointer.latest_pos())?;
+    let mut best: Option<(f32, usize, [f64; 2])> = None;
+    for (track_idx, points) in tracks.enumerate() {
+        for &[x, y] in points {
+            if !x.is_finite() || !y.is_finite() {
+                continue;
+            }
+            let distance = plot_ui
+                .screen_from_plot(egui_plot::PlotPoint::new(x, y))
+                .distance(pointer);
+            if distance <= HOVER_SNAP_PX && best.is_none_or(|(d, _, _)| distance < d) {
+                best = Some((distance, track_idx, [x, y]));
+            }
+        }
+    }
+    best.map(|(_, track_idx, point)| (track_idx, point))
+}
+
+/// Plot x value under the pointer while the plot is hovered.
+fn hovered_plot_x<R>(response: &egui_plot::PlotResponse<R>) -> Option<f64> {
+    response
+        .response
+        .hover_pos()
+        .map(|pos| response.transform.value_from_position(pos).x)
+}
+
+/// Sample spacing (minutes) of the plotted tracks.
+const PLOT_STEP_MIN: usize = 3;
+
+/// Farthest a polar-track sample may be from the shared cursor time and still be marked: one
+/// sample interval.
+const CURSOR_SNAP_HOURS: f64 = PLOT_STEP_MIN as f64 / 60.0;
 
 const SKD_COL_NUM: f32 = 24.0;
 const SKD_COL_SOURCE: f32 = 116.0;
@@ -52,9 +386,230 @@ enum AppTab {
     Parameters,
     PolarPlot,
     LstPlot,
+    HaPlot,
+    CoVisibility,
+    StripChart,
+    Separation,
+    StationComparison,
+    StationPair,
     SkdTable,
 }
 
+/// How the Uptime Plotters tab arranges several stations.
+#[derive(Clone, Copy, PartialEq)]
+enum UptimeLayout {
+    /// All stations on one az/el pair.
+    Overlay,
+    /// One az/el pair per station, picked from a tab row.
+    PerStation,
+}
+
+impl UptimeLayout {
+    const ALL: [UptimeLayout; 2] = [UptimeLayout::Overlay, UptimeLayout::PerStation];
+
+    fn label(self) -> &'static str {
+        match self {
+            UptimeLayout::Overlay => "Overlay",
+            UptimeLayout::PerStation => "Per-station panels",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ElevationColorMap {
+    Flat,
+    Viridis,
+    Plasma,
+}
+
+impl ElevationColorMap {
+    const ALL: [ElevationColorMap; 3] = [
+        ElevationColorMap::Flat,
+        ElevationColorMap::Viridis,
+        ElevationColorMap::Plasma,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ElevationColorMap::Flat => "Flat (per station)",
+            ElevationColorMap::Viridis => "Viridis",
+            ElevationColorMap::Plasma => "Plasma",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            ElevationColorMap::Flat => "flat",
+            ElevationColorMap::Viridis => "viridis",
+            ElevationColorMap::Plasma => "plasma",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|color_map| color_map.key() == key)
+    }
+
+    /// Evenly spaced colour stops for low to high elevation.
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            ElevationColorMap::Flat => &[],
+            ElevationColorMap::Viridis => &[
+                [68, 1, 84],
+                [59, 82, 139],
+                [33, 145, 140],
+                [94, 201, 98],
+                [253, 231, 37],
+            ],
+            ElevationColorMap::Plasma => &[
+                [13, 8, 135],
+                [126, 3, 168],
+                [204, 71, 120],
+                [248, 149, 64],
+                [240, 249, 33],
+            ],
+        }
+    }
+
+    /// Colour for `el_deg` on a 0-90° scale, or `None` for the flat style.
+    fn color_at(self, el_deg: f64) -> Option<egui::Color32> {
+        let stops = self.stops();
+        if stops.is_empty() {
+            return None;
+        }
+        let t = (el_deg / 90.0).clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (t.floor() as usize).min(stops.len() - 2);
+        let f = t - i as f64;
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+        let (a, b) = (stops[i], stops[i + 1]);
+        Some(egui::Color32::from_rgb(
+            lerp(a[0], b[0]),
+            lerp(a[1], b[1]),
+            lerp(a[2], b[2]),
+        ))
+    }
+}
+
+const ELEVATION_COLOR_BIN_DEG: f64 = 5.0;
+
+/// Which cardinal point sits at the top of the polar plot and which way azimuth increases
+/// from it on screen. Azimuth itself is always measured from north through east.
+#[derive(Clone, Copy, PartialEq)]
+enum PolarOrientation {
+    /// Map view: N up, E right.
+    NorthCw,
+    /// Sky view looking up: N up, E left.
+    NorthCcw,
+    SouthCw,
+    SouthCcw,
+}
+
+impl PolarOrientation {
+    const ALL: [PolarOrientation; 4] = [
+        PolarOrientation::NorthCw,
+        PolarOrientation::NorthCcw,
+        PolarOrientation::SouthCw,
+        PolarOrientation::SouthCcw,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PolarOrientation::NorthCw => "N up, clockwise (E right)",
+            PolarOrientation::NorthCcw => "N up, counter-clockwise (E left)",
+            PolarOrientation::SouthCw => "S up, clockwise (W right)",
+            PolarOrientation::SouthCcw => "S up, counter-clockwise (W left)",
+        }
+    }
+
+    /// Preferences-file key.
+    fn key(self) -> &'static str {
+        match self {
+            PolarOrientation::NorthCw => "north_cw",
+            PolarOrientation::NorthCcw => "north_ccw",
+            PolarOrientation::SouthCw => "south_cw",
+            PolarOrientation::SouthCcw => "south_ccw",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|orientation| orientation.key() == key)
+    }
+
+    /// Angle from the plot's +x axis (radians, counter-clockwise) of azimuth `az_deg`.
+    fn screen_angle_rad(self, az_deg: f64) -> f64 {
+        let angle_deg = match self {
+            PolarOrientation::NorthCw => 90.0 - az_deg,
+            PolarOrientation::NorthCcw => 90.0 + az_deg,
+            PolarOrientation::SouthCw => 270.0 - az_deg,
+            PolarOrientation::SouthCcw => az_deg - 90.0,
+        };
+        angle_deg.to_radians()
+    }
+
+    /// Plot coordinates of (`az_deg`, `el_deg`): the zenith at the origin, the horizon on
+    /// the unit circle.
+    fn polar_xy(self, az_deg: f64, el_deg: f64) -> [f64; 2] {
+        let angle_rad = self.screen_angle_rad(az_deg);
+        let radius = (90.0 - el_deg) / 90.0;
+        [radius * angle_rad.cos(), radius * angle_rad.sin()]
+    }
+
+    /// Inverse of `polar_xy`: (az, el) in degrees at plot point (`x`, `y`), or `None` outside
+    /// the horizon circle.
+    fn az_el_at(self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let radius = x.hypot(y);
+        if !radius.is_finite() || radius > 1.0 {
+            return None;
+        }
+        let angle_deg = y.atan2(x).to_degrees();
+        let az_deg = match self {
+            PolarOrientation::NorthCw => 90.0 - angle_deg,
+            PolarOrientation::NorthCcw => angle_deg - 90.0,
+            PolarOrientation::SouthCw => 270.0 - angle_deg,
+            PolarOrientation::SouthCcw => angle_deg + 90.0,
+        };
+        Some((az_deg.rem_euclid(360.0), 90.0 - radius * 90.0))
+    }
+}
+
+/// One line per finite run of `points`, all named `name` so the legend toggles the whole
+/// track together.
+fn track_lines(name: &str, points: &[[f64; 2]]) -> Vec<Line<'static>> {
+    utils::finite_runs(points)
+        .into_iter()
+        .map(|run| Line::new(name, PlotPoints::from(run)))
+        .collect()
+}
+
+/// Splits an elevation track into runs whose samples fall in the same
+/// `ELEVATION_COLOR_BIN_DEG` band, returning each run with its band-centre elevation.
+/// Adjacent runs share their boundary sample so the drawn line stays continuous; NaN
+/// samples end a run.
+fn split_by_elevation_bin(points: &[[f64; 2]]) -> Vec<(f64, Vec<[f64; 2]>)> {
+    let mut runs: Vec<(f64, Vec<[f64; 2]>)> = Vec::new();
+    let mut current: Option<(f64, Vec<[f64; 2]>)> = None;
+    for &point in points {
+        if !point[1].is_finite() {
+            runs.extend(current.take());
+            continue;
+        }
+        let bin = ((point[1] / ELEVATION_COLOR_BIN_DEG).floor() + 0.5) * ELEVATION_COLOR_BIN_DEG;
+        match current.as_mut() {
+            Some((current_bin, run)) if *current_bin == bin => run.push(point),
+            Some((_, run)) => {
+                run.push(point);
+                runs.extend(current.replace((bin, vec![point])));
+            }
+            None => current = Some((bin, vec![point])),
+        }
+    }
+    runs.extend(current);
+    runs
+}
+
 #[derive(Clone, Copy)]
 enum OutputTarget {
     UtAzel,
@@ -62,11 +617,296 @@ enum OutputTarget {
     Lst,
 }
 
+impl OutputTarget {
+    const ALL: [OutputTarget; 3] = [OutputTarget::UtAzel, OutputTarget::Polar, OutputTarget::Lst];
+
+    /// File name the "output" button saves this plot under, in the working directory.
+    fn file_name(self) -> &'static str {
+        match self {
+            OutputTarget::UtAzel => "upt_azel.png",
+            OutputTarget::Polar => "upt_polar.png",
+            OutputTarget::Lst => "upt_lst.png",
+        }
+    }
+}
+
 struct OutputCaptureState {
     targets: Vec<OutputTarget>,
     index: usize,
     previous_tab: AppTab,
     screenshot_requested: bool,
+    /// When set, the captures are collected into a PDF report instead of PNG files.
+    report_path: Option<PathBuf>,
+    report_images: Vec<image::RgbaImage>,
+    /// When set, a single UT az/el capture with a title block above the plots is saved here.
+    titled_path: Option<PathBuf>,
+    /// When set, one UT az/el capture is taken per date instead.
+    batch: Option<BatchOutput>,
+    /// Fixed output size in pixels; the plots are rendered offscreen at it, or the window is
+    /// resized to it when no wgpu device is available.
+    image_size: Option<[u32; 2]>,
+    /// Frames spent waiting for the window to reach `image_size`.
+    resize_frames: u32,
+}
+
+/// Preset sizes offered for exported images, in pixels.
+const IMAGE_SIZE_PRESETS: [[u32; 2]; 4] = [[1280, 720], [1920, 1080], [2560, 1440], [3840, 2160]];
+const MIN_IMAGE_SIDE: u32 = 320;
+const MAX_IMAGE_SIDE: u32 = 7680;
+/// Frames to wait for a window resize before capturing at whatever size was reached.
+const MAX_RESIZE_FRAMES: u32 = 30;
+
+/// Largest |dUT1| accepted, in seconds; IERS keeps UT1 - UTC within 0.9 s.
+const MAX_DUT1_SECONDS: f64 = 0.9;
+
+struct BatchOutput {
+    dates: Vec<NaiveDate>,
+    output_dir: PathBuf,
+    original_date: NaiveDate,
+}
+
+/// Which windows the calendar export turns into events.
+#[derive(Clone, Copy, PartialEq)]
+enum CalendarWindows {
+    AboveFloor,
+    AboveHorizon,
+    CoVisibility,
+}
+
+impl CalendarWindows {
+    const ALL: [CalendarWindows; 3] = [
+        CalendarWindows::AboveFloor,
+        CalendarWindows::AboveHorizon,
+        CalendarWindows::CoVisibility,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            CalendarWindows::AboveFloor => "Above the floor",
+            CalendarWindows::AboveHorizon => "Above the horizon",
+            CalendarWindows::CoVisibility => "Co-visibility (station pairs)",
+        }
+    }
+}
+
+/// One visibility window as a calendar event, in UTC.
+struct CalendarEvent {
+    summary: String,
+    description: String,
+    start: chrono::DateTime<Utc>,
+    end: chrono::DateTime<Utc>,
+}
+
+/// Escapes a TEXT value for iCalendar (RFC 5545 §3.3.11).
+fn ics_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends one content line, folded at 75 octets with CRLF and a leading space as RFC 5545
+/// requires.
+fn push_ics_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// An iCalendar file with one VEVENT per event, all times in UTC; `stamp` is the creation
+/// time written as DTSTAMP and into the UIDs.
+fn ics_calendar(events: &[CalendarEvent], stamp: chrono::DateTime<Utc>) -> String {
+    const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+    let stamp_text = stamp.format(TIME_FORMAT).to_string();
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//uptimeplot//visibility windows//EN",
+        "CALSCALE:GREGORIAN",
+    ] {
+        push_ics_line(&mut out, line);
+    }
+    for (idx, event) in events.iter().enumerate() {
+        push_ics_line(&mut out, "BEGIN:VEVENT");
+        push_ics_line(
+            &mut out,
+            &format!("UID:{}-{}@uptimeplot", stamp_text, idx + 1),
+        );
+        push_ics_line(&mut out, &format!("DTSTAMP:{}", stamp_text));
+        push_ics_line(
+            &mut out,
+            &format!("DTSTART:{}", event.start.format(TIME_FORMAT)),
+        );
+        push_ics_line(
+            &mut out,
+            &format!("DTEND:{}", event.end.format(TIME_FORMAT)),
+        );
+        push_ics_line(&mut out, &format!("SUMMARY:{}", ics_text(&event.summary)));
+        push_ics_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", ics_text(&event.description)),
+        );
+        push_ics_line(&mut out, "TRANSP:TRANSPARENT");
+        push_ics_line(&mut out, "END:VEVENT");
+    }
+    push_ics_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CsvDelimiter {
+    Comma,
+    Tab,
+    Semicolon,
+}
+
+impl CsvDelimiter {
+    const ALL: [CsvDelimiter; 3] = [
+        CsvDelimiter::Comma,
+        CsvDelimiter::Tab,
+        CsvDelimiter::Semicolon,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "Comma (,)",
+            CsvDelimiter::Tab => "Tab",
+            CsvDelimiter::Semicolon => "Semicolon (;)",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "comma",
+            CsvDelimiter::Tab => "tab",
+            CsvDelimiter::Semicolon => "semicolon",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|delimiter| delimiter.key() == key)
+    }
+
+    fn as_char(self) -> char {
+        match self {
+            CsvDelimiter::Comma => ',',
+            CsvDelimiter::Tab => '\t',
+            CsvDelimiter::Semicolon => ';',
+        }
+    }
+}
+
+/// Column layout of the az/el CSV export.
+struct CsvExportOptions {
+    delimiter: CsvDelimiter,
+    time_decimals: usize,
+    az_decimals: usize,
+    el_decimals: usize,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: CsvDelimiter::Comma,
+            time_decimals: 2,
+            az_decimals: 1,
+            el_decimals: 1,
+        }
+    }
+}
+
+const MAX_CSV_DECIMALS: usize = 6;
+
+/// Line format of the plot log appended on each "Plot Selected".
+#[derive(Clone, Copy, PartialEq)]
+enum PlotLogFormat {
+    Csv,
+    Jsonl,
+}
+
+impl PlotLogFormat {
+    const ALL: [PlotLogFormat; 2] = [PlotLogFormat::Csv, PlotLogFormat::Jsonl];
+
+    fn label(self) -> &'static str {
+        match self {
+            PlotLogFormat::Csv => "CSV",
+            PlotLogFormat::Jsonl => "JSON Lines",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            PlotLogFormat::Csv => "csv",
+            PlotLogFormat::Jsonl => "jsonl",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.key() == key)
+    }
+}
+
+/// Columns of the CSV plot log, written as its header line.
+const PLOT_LOG_CSV_HEADER: &str = "logged_at,date,stations,sources,elevation_floor_deg,effective_floor_deg,session,observing_frequency_ghz,station_file,source_file";
+
+/// Largest gap, in minutes, the window merging accepts.
+const MAX_MERGE_GAP_MIN: f64 = 120.0;
+
+/// Inputs of the antenna tracking-table export: one source, one antenna from antenna.sch,
+/// a UT span on the selected date and a fixed cadence.
+struct TrackingExport {
+    source_name: String,
+    antenna: usize,
+    start_time: String,
+    end_time: String,
+    cadence_sec: u32,
+}
+
+impl Default for TrackingExport {
+    fn default() -> Self {
+        Self {
+            source_name: String::new(),
+            antenna: 0,
+            start_time: "00:00:00".to_string(),
+            end_time: "23:59:59".to_string(),
+            cadence_sec: 10,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisibilityStatus {
+    NeverAboveFloor,
+    NotInSession,
+    Ok,
+}
+
+impl VisibilityStatus {
+    fn label(self) -> &'static str {
+        match self {
+            VisibilityStatus::NeverAboveFloor => "Never above floor",
+            VisibilityStatus::NotInSession => "Not observable this session",
+            VisibilityStatus::Ok => "OK",
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -79,10 +919,37 @@ struct CliArgs {
     /// Path to the source.txt file
     #[arg(long)]
     source_path: Option<PathBuf>,
+
+    /// Print the resolved settings as JSON to stdout at startup
+    #[arg(long)]
+    print_config: bool,
+
+    /// Start with default settings even when "Restore last session" is enabled
+    #[arg(long)]
+    fresh: bool,
+
+    /// Plot the selected sources, save the titled az/el image to this PNG and exit
+    #[arg(long)]
+    export_azel: Option<PathBuf>,
+
+    /// Size of exported images as WIDTHxHEIGHT pixels, e.g. 1920x1080
+    #[arg(long, value_parser = parse_image_size)]
+    image_size: Option<[u32; 2]>,
+
+    /// Print the supported computation features as JSON and exit
+    #[arg(long)]
+    capabilities: bool,
 }
 
 fn main() -> Result<(), eframe::Error> {
     let cli_args = CliArgs::parse();
+    if cli_args.capabilities {
+        println!("{}", capabilities_json());
+        return Ok(());
+    }
+    // Built before the window opens so --print-config output does not wait on a display.
+    let app = Box::new(UptimePlotApp::new(cli_args));
+    let export_failed = Arc::clone(&app.cli_export_failed);
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1280.0, 720.0]),
@@ -92,9 +959,6 @@ fn main() -> Result<(), eframe::Error> {
         "Uptime Plotter",
         options,
         Box::new(move |cc| {
-            // Use move to capture cli_args
-            let app = Box::new(UptimePlotApp::new(cli_args)); // Call new constructor
-
             // Increase font size
             let mut style = (*cc.egui_ctx.global_style()).clone();
             for (_text_style, font_id) in style.text_styles.iter_mut() {
@@ -105,13 +969,510 @@ fn main() -> Result<(), eframe::Error> {
 
             Ok(app)
         }),
-    )
+    )?;
+    if export_failed.load(Ordering::Relaxed) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+struct PlotSeries {
+    source_name: String,
+    station_name: String,
+    az_points: Vec<[f64; 2]>,
+    el_points: Vec<[f64; 2]>,
+    ha_points: Vec<[f64; 2]>,
+    /// Local sidereal time (hours) of each sample in `az_points`.
+    lst_hours: Vec<f64>,
+    /// UT hour intervals with the source above the elevation floor and inside the HA limits.
+    windows: Vec<(f64, f64)>,
+    /// Interpolated UT hours where the source crosses the elevation floor the windows use
+    /// (refracted onto it when `rise_set_refracted` is set).
+    rise_hours: Vec<f64>,
+    set_hours: Vec<f64>,
+    station_idx: usize,
+    group: Option<String>,
+}
+
+/// Group tag shown for positions entered in the ad-hoc form. It is only a label: a catalogue
+/// group of the same name stays a catalogue group, since `adhoc_sources` tells them apart.
+const ADHOC_GROUP: &str = "ad-hoc";
+
+/// A grid of pointings around one loaded source, plotted as a single track whose elevation
+/// is the lowest across the pattern.
+struct MosaicSettings {
+    enabled: bool,
+    center: Option<String>,
+    columns: usize,
+    rows: usize,
+    spacing_arcmin: f64,
+}
+
+impl Default for MosaicSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            center: None,
+            columns: 3,
+            rows: 3,
+            spacing_arcmin: 10.0,
+        }
+    }
 }
 
-struct Station {
+/// Inputs of the manual station form, as text so partial entries can be typed.
+struct ManualStationForm {
     name: String,
-    pos: [f64; 3],
-    selected: bool,
+    /// Geodetic latitude/longitude/height when true, ECEF X/Y/Z otherwise.
+    geodetic: bool,
+    latitude: String,
+    longitude: String,
+    height: String,
+    x: String,
+    y: String,
+    z: String,
+}
+
+impl Default for ManualStationForm {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            geodetic: true,
+            latitude: String::new(),
+            longitude: String::new(),
+            height: "0".to_string(),
+            x: String::new(),
+            y: String::new(),
+            z: String::new(),
+        }
+    }
+}
+
+impl ManualStationForm {
+    /// The entered position as a station. Either form is converted both ways and rejected
+    /// unless it sits near the Earth's surface and survives the round trip.
+    fn to_station(&self, ellipsoid: utils::Ellipsoid) -> Result<Station, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err("Station name must be one word.".to_string());
+        }
+        let number = |label: &str, text: &str| {
+            text.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("Invalid {}: '{}'", label, text.trim()))
+        };
+        let pos = if self.geodetic {
+            let latitude = number("latitude", &self.latitude)?;
+            let longitude = number("longitude", &self.longitude)?;
+            let height = number("height", &self.height)?;
+            if !(-90.0..=90.0).contains(&latitude) {
+                return Err(format!("Latitude {}° is outside ±90°.", latitude));
+            }
+            if !(-180.0..=360.0).contains(&longitude) {
+                return Err(format!("Longitude {}° is outside -180..360°.", longitude));
+            }
+            ellipsoid.ecef(latitude.to_radians(), longitude.to_radians(), height)
+        } else {
+            [
+                number("X", &self.x)?,
+                number("Y", &self.y)?,
+                number("Z", &self.z)?,
+            ]
+        };
+        let (latitude, longitude, height) = ellipsoid.geodetic(pos);
+        if !(MANUAL_STATION_MIN_HEIGHT_M..=MANUAL_STATION_MAX_HEIGHT_M).contains(&height) {
+            return Err(format!(
+                "The position is {:.0} m from the {} surface; check the units (metres) and signs.",
+                height,
+                ellipsoid.label()
+            ));
+        }
+        let back = ellipsoid.ecef(latitude, longitude, height);
+        let residual = (0..3)
+            .map(|i| (back[i] - pos[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if residual > 1e-3 {
+            return Err(format!(
+                "The geodetic/ECEF conversion does not close ({:.3} m).",
+                residual
+            ));
+        }
+        Ok(Station {
+            name: name.to_string(),
+            pos,
+            ha_limits: None,
+            az_offset_arcmin: 0.0,
+            el_offset_arcmin: 0.0,
+        })
+    }
+}
+
+/// Ellipsoidal heights accepted for a manual station: a little below sea level to high
+/// mountain sites, airborne at most.
+const MANUAL_STATION_MIN_HEIGHT_M: f64 = -500.0;
+const MANUAL_STATION_MAX_HEIGHT_M: f64 = 20_000.0;
+
+/// In-app edit of one `source.txt` entry; `original_name` is `None` for a new source.
+struct SourceEditor {
+    original_name: Option<String>,
+    name: String,
+    ra: String,
+    dec: String,
+    epoch: String,
+    group: String,
+    priority: i32,
+}
+
+impl SourceEditor {
+    fn new_source() -> Self {
+        Self {
+            original_name: None,
+            name: String::new(),
+            ra: "00 00 00.000".to_string(),
+            dec: "+00 00 00.00".to_string(),
+            epoch: "2000.0".to_string(),
+            group: String::new(),
+            priority: DEFAULT_PRIORITY,
+        }
+    }
+
+    fn from_source(source: &Source) -> Self {
+        Self {
+            original_name: Some(source.name.clone()),
+            name: source.name.clone(),
+            ra: format!("{:02} {:02} {:06.3}", source.ra_h, source.ra_m, source.ra_s),
+            dec: format!(
+                "{}{:02} {:02} {:05.2}",
+                source.dec_sign, source.dec_d, source.dec_m, source.dec_s
+            ),
+            epoch: source.epoch.clone(),
+            group: source.group.clone().unwrap_or_default(),
+            priority: source.priority,
+        }
+    }
+
+    fn to_source(&self) -> Result<Source, String> {
+        let line = format!(
+            "{} {} {} {}",
+            self.name.trim(),
+            self.ra,
+            self.dec,
+            self.epoch
+        );
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 8 || self.name.trim().contains(char::is_whitespace) {
+            return Err(
+                "Enter a single-word name, RA as HH MM SS.sss and Dec as ±DD MM SS.ss.".to_string(),
+            );
+        }
+        if self.group.trim().contains(char::is_whitespace) {
+            return Err("Group tags must be a single word.".to_string());
+        }
+        let mut source = parse_source_tokens(&parts, 0, 1, &line)?;
+        source.group = Some(self.group.trim().to_string()).filter(|group| !group.is_empty());
+        source.priority = self.priority;
+        if source.ra_h >= 24 || source.ra_m >= 60 || source.ra_s >= 60.0 || source.ra_s < 0.0 {
+            return Err(format!("RA out of range: {}", self.ra));
+        }
+        if source.dec_d > 90 || source.dec_m >= 60 || source.dec_s >= 60.0 || source.dec_s < 0.0 {
+            return Err(format!("Dec out of range: {}", self.dec));
+        }
+        Ok(source)
+    }
+}
+
+/// A selected source that stays below the horizon all day for a station.
+struct NeverVisible {
+    source_name: String,
+    station_name: String,
+    max_el_deg: f64,
+    /// Peak elevation with the declination sign flipped, once the diagnostic has been run.
+    flipped_max_el_deg: Option<f64>,
+}
+
+/// UT spans where a visible track's azimuth rate exceeds `max_az_rate_deg_per_sec`.
+struct FastTrackWarning {
+    source_name: String,
+    station_name: String,
+    windows: Vec<(f64, f64)>,
+    peak_az_rate: f64,
+    peak_el_rate: f64,
+}
+
+/// Peak elevation and time above the floor of one source, for the source list preview.
+#[derive(Clone, Copy)]
+struct SourcePreview {
+    max_el_deg: f64,
+    up_hours: f64,
+}
+
+/// Everything a preview is computed from, floats as bits, so changing any input starts a new
+/// preview instead of showing a stale one.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PreviewKey {
+    station: String,
+    /// ECEF position and hour-angle limits of the station.
+    station_bits: Vec<u64>,
+    date: NaiveDate,
+    floor_bits: u64,
+    ellipsoid: utils::Ellipsoid,
+    sidereal_mode: utils::SiderealMode,
+    reference_frame: utils::ReferenceFrame,
+    dut1_bits: u64,
+    az_sector_bits: Vec<u64>,
+    source_count: usize,
+}
+
+/// A background preview run; results arrive one source at a time.
+struct PreviewJob {
+    key: PreviewKey,
+    receiver: mpsc::Receiver<(String, SourcePreview)>,
+    total: usize,
+    results: HashMap<String, SourcePreview>,
+}
+
+const PREVIEW_STEP_MIN: i64 = 5;
+
+/// Next horizon crossings of one source from the time the countdown was computed.
+struct CountdownEntry {
+    source_name: String,
+    up_now: bool,
+    next_rise: Option<chrono::DateTime<Utc>>,
+    next_set: Option<chrono::DateTime<Utc>>,
+}
+
+struct CountdownCache {
+    computed_at: chrono::DateTime<Utc>,
+    station_name: String,
+    sources: Vec<String>,
+    entries: Vec<CountdownEntry>,
+}
+
+/// How far ahead the countdown looks for events, and how often the events are recomputed.
+const COUNTDOWN_HORIZON_HOURS: i64 = 48;
+const COUNTDOWN_REFRESH_SECONDS: i64 = 60;
+
+/// Inputs and last result of the best-night-date search.
+struct NightSearch {
+    source_name: String,
+    start_date: String,
+    days: u32,
+    max_sun_el_deg: f64,
+    /// Station searched for and the ranked dates found.
+    result: Option<(String, Vec<utils::NightVisibility>)>,
+}
+
+impl NightSearch {
+    fn new(start: NaiveDate) -> Self {
+        Self {
+            source_name: String::new(),
+            start_date: start.format("%Y-%m-%d").to_string(),
+            days: 182,
+            max_sun_el_deg: -12.0,
+            result: None,
+        }
+    }
+}
+
+/// Inputs of the station-pair plot: one plotted source seen from two plotted stations.
+#[derive(Default)]
+struct StationPairPlot {
+    source_name: String,
+    station_a: String,
+    station_b: String,
+    /// Geometric delay of B relative to A instead of the elevation difference.
+    delay: bool,
+}
+
+/// One station's elevation track for the station comparison, with what it is ranked by.
+struct StationTrack {
+    station_name: String,
+    el_points: Vec<[f64; 2]>,
+    peak_el_deg: f64,
+    up_hours: f64,
+}
+
+/// Inputs and last result of the one-source, all-stations comparison.
+struct StationComparison {
+    source_name: String,
+    /// Best-ranked stations drawn on the plot; the table lists them all.
+    max_stations: usize,
+    /// Date compared and the loaded stations, most hours above the floor first.
+    result: Option<(NaiveDate, Vec<StationTrack>)>,
+}
+
+impl Default for StationComparison {
+    fn default() -> Self {
+        Self {
+            source_name: String::new(),
+            max_stations: 8,
+            result: None,
+        }
+    }
+}
+
+/// Playback of the polar plot through the day: each frame advances the overlay time by
+/// `step_minutes` of observing time, at `frames_per_second` frames of wall-clock time.
+struct PolarAnimation {
+    enabled: bool,
+    playing: bool,
+    step_minutes: f64,
+    frames_per_second: f64,
+    /// Wall-clock time of the last frame advanced, while playing.
+    last_frame: Option<Instant>,
+}
+
+impl Default for PolarAnimation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            playing: false,
+            step_minutes: 10.0,
+            frames_per_second: 10.0,
+            last_frame: None,
+        }
+    }
+}
+
+/// Ranked dates listed by the best-night search.
+const NIGHT_SEARCH_SHOWN: usize = 10;
+
+/// Height of the drag bar between the az and el plots, and the smallest share either gets.
+const PLOT_SPLITTER_HEIGHT: f32 = 6.0;
+const MIN_PLOT_FRACTION: f32 = 0.15;
+
+const LOG_CAPACITY: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+
+    fn color(self) -> egui::Color32 {
+        match self {
+            LogLevel::Info => egui::Color32::LIGHT_GRAY,
+            LogLevel::Warn => egui::Color32::YELLOW,
+            LogLevel::Error => egui::Color32::RED,
+        }
+    }
+}
+
+struct LogEntry {
+    time: chrono::DateTime<Utc>,
+    level: LogLevel,
+    message: String,
+}
+
+/// How often the loaded catalogue files are checked for changes on disk.
+const CATALOG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+/// A changed file must keep the same modification time this long before it is reloaded, so
+/// editors that save in several writes trigger a single reload.
+const CATALOG_RELOAD_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(800);
+
+fn require_catalog_path(path: &str, kind: &str) -> Result<(), String> {
+    if path.trim().is_empty() {
+        return Err(format!("No {} file path is set.", kind));
+    }
+    Ok(())
+}
+
+fn file_modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Modification-time watcher for one loaded catalogue file.
+#[derive(Default)]
+struct CatalogWatch {
+    loaded: Option<SystemTime>,
+    /// Modification time seen since the load, and when it was first seen.
+    pending: Option<(Option<SystemTime>, Instant)>,
+}
+
+impl CatalogWatch {
+    fn mark_loaded(&mut self, path: &str) {
+        self.loaded = file_modified(path);
+        self.pending = None;
+    }
+
+    /// True while there is a loaded file to watch or a change waiting out the debounce.
+    fn is_active(&self) -> bool {
+        self.loaded.is_some() || self.pending.is_some()
+    }
+
+    /// True once `path` has changed since it was loaded and then stayed unchanged for
+    /// `CATALOG_RELOAD_DEBOUNCE`. Reports each change once.
+    fn poll(&mut self, path: &str, now: Instant) -> bool {
+        let modified = file_modified(path);
+        if modified == self.loaded {
+            self.pending = None;
+            return false;
+        }
+        match self.pending {
+            Some((seen, since)) if seen == modified => {
+                if now.duration_since(since) < CATALOG_RELOAD_DEBOUNCE {
+                    return false;
+                }
+                self.loaded = modified;
+                self.pending = None;
+                true
+            }
+            _ => {
+                self.pending = Some((modified, now));
+                false
+            }
+        }
+    }
+}
+
+struct CoVisibility {
+    source_name: String,
+    baseline: String,
+    windows: Vec<(f64, f64)>,
+    /// The two `plot_data` series intersected.
+    series: (usize, usize),
+}
+
+/// The `idx`-th colour of egui_plot's automatic palette, for items that must share a colour.
+fn series_color(idx: usize) -> egui::Color32 {
+    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    egui::ecolor::Hsva::new(idx as f32 * golden_ratio, 0.85, 0.5, 1.0).into()
+}
+
+/// Sample spacing (minutes) of the Sun/Moon separation curves.
+const SEPARATION_STEP_MIN: usize = 10;
+
+/// Geocentric Sun and Moon separation of one plotted source over the day, as (UT hour, deg).
+struct SeparationSeries {
+    source_name: String,
+    sun: Vec<[f64; 2]>,
+    moon: Vec<[f64; 2]>,
+}
+
+struct PolarSeries {
+    source_name: String,
+    station_name: String,
+    polar_points: Vec<[f64; 2]>,
+    /// True elevation (deg, pointing offset removed) and UT hour of each point in
+    /// `polar_points`.
+    polar_el: Vec<f64>,
+    polar_hours: Vec<f64>,
+    hour_marker_points: Vec<[f64; 2]>,
+    hour_labels: Vec<(f64, f64, String)>,
+    station_idx: usize,
 }
 
 #[derive(Clone)]
@@ -127,21 +1488,6 @@ struct Antenna {
     el_max_deg: f64,
 }
 
-#[derive(Clone)]
-struct Source {
-    name: String,
-    ra_rad: f64,
-    dec_rad: f64,
-    ra_h: i32,
-    ra_m: i32,
-    ra_s: f64,
-    dec_sign: char,
-    dec_d: i32,
-    dec_m: i32,
-    dec_s: f64,
-    epoch: String,
-}
-
 #[derive(Clone)]
 struct SkdRow {
     source_name: String,
@@ -214,15 +1560,27 @@ impl Antenna {
 
 type ScanEnd = (chrono::NaiveDateTime, f64, f64);
 
+struct SuggestedOrder {
+    source_names: Vec<String>,
+    total_slew_sec: f64,
+    reference_hour: f64,
+    antenna_name: String,
+    /// Selected sources outside the antenna limits at the reference time.
+    skipped: Vec<String>,
+}
+
 fn antenna_motion_status(
     row: &SkdRow,
     source: &Source,
     antenna: &Antenna,
     prev_end: Option<ScanEnd>,
+    ellipsoid: utils::Ellipsoid,
+    sidereal: utils::SiderealMode,
+    frame: utils::ReferenceFrame,
 ) -> (String, Option<ScanEnd>) {
     match (
-        scan_az_el_for(row, source, antenna.pos, false),
-        scan_az_el_for(row, source, antenna.pos, true),
+        scan_az_el_for(row, source, antenna.pos, false, ellipsoid, sidereal, frame),
+        scan_az_el_for(row, source, antenna.pos, true, ellipsoid, sidereal, frame),
     ) {
         (Some((start_dt, start_az, start_el)), Some((end_dt, end_az, end_el))) => {
             let limit_text = if antenna.allows(start_az, start_el) && antenna.allows(end_az, end_el)
@@ -256,6 +1614,52 @@ fn antenna_motion_status(
     }
 }
 
+/// Half-width of the polar plot's data bounds (the horizon being 1) that leaves room outside
+/// the horizon for azimuth labels of `label_size` points on a plot `side` points across.
+fn polar_label_extent(label_size: f32, side: f32) -> f64 {
+    // The widest label, "315°", is about 2.5 font sizes wide.
+    let label = 2.5 * label_size as f64;
+    let free = (1.0 - 2.0 * label / side.max(1.0) as f64).max(0.5);
+    1.03 / free
+}
+
+/// Text anchor placing a label outwards from the plot centre along the direction (`x`, `y`).
+fn outward_anchor(x: f64, y: f64) -> egui::Align2 {
+    let horizontal = if x > 0.3 {
+        egui::Align::Min
+    } else if x < -0.3 {
+        egui::Align::Max
+    } else {
+        egui::Align::Center
+    };
+    let vertical = if y > 0.3 {
+        egui::Align::Max
+    } else if y < -0.3 {
+        egui::Align::Min
+    } else {
+        egui::Align::Center
+    };
+    egui::Align2([horizontal, vertical])
+}
+
+/// Splits a polar track into contiguous runs above/below the floor. Adjacent runs share
+/// their boundary point so the track stays connected.
+fn split_by_floor(points: &[[f64; 2]], el: &[f64], floor_deg: f64) -> Vec<(bool, Vec<[f64; 2]>)> {
+    let mut runs: Vec<(bool, Vec<[f64; 2]>)> = Vec::new();
+    for (point, &el) in points.iter().zip(el) {
+        let below_floor = el < floor_deg;
+        match runs.last_mut() {
+            Some((run_below, run)) if *run_below == below_floor => run.push(*point),
+            Some((_, run)) => {
+                let boundary = *run.last().unwrap_or(point);
+                runs.push((below_floor, vec![boundary, *point]));
+            }
+            None => runs.push((below_floor, vec![*point])),
+        }
+    }
+    runs
+}
+
 fn source_table_text(name: &str) -> String {
     let mut value: String = name.chars().take(8).collect();
     while value.chars().count() < 8 {
@@ -306,7 +1710,7 @@ fn show_status_token(ui: &mut egui::Ui, token: &str, width: f32) {
 }
 
 struct UptimePlotApp {
-    stations: Vec<Station>,
+    stations: Vec<(Station, bool)>,
     selected_date: NaiveDate,
     station_file_path: String,
     source_file_path: String,
@@ -347,29 +1751,201 @@ struct UptimePlotApp {
     target_picker_filter: String,
     cal_picker_filter: String,
     five_point_picker_filter: String,
-    plot_data: Vec<(String, String, Vec<[f64; 2]>, Vec<[f64; 2]>, usize)>,
-    lst_plot_data: Vec<(String, String, Vec<[f64; 2]>, Vec<[f64; 2]>, usize)>,
-    polar_plot_data: Vec<(
-        String,
-        String,
-        Vec<[f64; 2]>,
-        Vec<[f64; 2]>,
-        Vec<(f64, f64, String)>,
-        usize,
-    )>,
-    error_msg: Option<String>,
+    plot_data: Vec<PlotSeries>,
+    lst_plot_data: Vec<PlotSeries>,
+    polar_plot_data: Vec<PolarSeries>,
+    /// Recent messages, oldest first, capped at `LOG_CAPACITY`.
+    log_entries: VecDeque<LogEntry>,
     show_calendar: bool,
     show_new_skd_calendar: bool,
     search_query: String,
+    /// List only the checked sources, for reviewing a selection; combines with `search_query`.
+    show_only_selected: bool,
+    /// Keyboard-focused entry of the source list, as a position among the sources currently
+    /// listed (after the search filter and "Selected only").
+    source_list_focus: Option<usize>,
+    /// Sources matching `search_query`, kept between frames; `None` after the list changes.
+    source_list_cache: Option<SourceListCache>,
+    /// Rows of the source list laid out last frame, to scroll a keyboard focus into view.
+    source_list_shown_rows: std::ops::Range<usize>,
+    night_search: NightSearch,
+    station_comparison: StationComparison,
+    station_pair: StationPairPlot,
+    /// Compute peak elevation and up time for every loaded source, not just the selected ones.
+    source_preview_enabled: bool,
+    source_previews: HashMap<PreviewKey, HashMap<String, SourcePreview>>,
+    preview_job: Option<PreviewJob>,
     selected_tab: AppTab,
     uptime_plot_rect: Option<egui::Rect>,
+    /// UT hour under the pointer on any time plot (az/el, HA, LST, polar), shared so every
+    /// view marks the same instant. Plots write `cursor_ut_next` while hovered; it becomes
+    /// `cursor_ut` at the end of the frame, so the cursor clears once no plot is hovered.
+    cursor_ut: Option<f64>,
+    cursor_ut_next: Option<f64>,
     polar_plot_rect: Option<egui::Rect>,
     lst_plot_rect: Option<egui::Rect>,
     output_capture: Option<OutputCaptureState>,
+    elevation_floor_deg: f64,
+    /// Raise the floor to where the line-of-sight opacity `tau0 * airmass` reaches
+    /// `max_line_opacity`.
+    opacity_floor_enabled: bool,
+    tau0: f64,
+    max_line_opacity: f64,
+    /// Allowed azimuth sectors as typed, e.g. "30-150, 200-330"; empty allows all.
+    az_sectors_text: String,
+    /// Parsed `az_sectors_text`; elevation samples outside every sector are masked.
+    az_sectors: Vec<(f64, f64)>,
+    polar_below_floor_color: egui::Color32,
+    polar_horizon_color: egui::Color32,
+    /// Fill the visible-sky disk of the polar plot with a theme-dependent tint.
+    polar_sky_fill: bool,
+    /// Shade the polar annulus between the horizon and the science floor as high airmass.
+    polar_low_el_shading: bool,
+    /// Show the selected sources where they are now instead of their tracks over the day.
+    polar_live: bool,
+    /// Let the polar plot be panned and zoomed; the aspect stays locked so rings stay round.
+    polar_zoom_enabled: bool,
+    /// Zoomed polar view as `[min_x, min_y, max_x, max_y]`; `None` is the whole sky.
+    polar_view: Option<[f64; 4]>,
+    /// Mark the Sun and Moon on the polar plot at the overlay time (or now, when live).
+    show_sun_moon: bool,
+    show_preferences: bool,
+    /// Fixed pixel size of exported plot images; `None` captures at the window size.
+    image_size: Option<[u32; 2]>,
+    /// Window size to restore once a fixed-size capture has finished.
+    restore_window_size: Option<egui::Vec2>,
+    /// Area of the active tab's view, with its crop margin, waiting for a screenshot to copy
+    /// to the clipboard.
+    clipboard_capture: Option<(egui::Rect, f32)>,
+    central_panel_rect: Option<egui::Rect>,
+    /// `--export-azel` target and whether the export has been started.
+    cli_export: Option<PathBuf>,
+    cli_export_started: bool,
+    /// Set when the `--export-azel` export fails, so the process exits non-zero.
+    cli_export_failed: Arc<AtomicBool>,
+    elevation_color_map: ElevationColorMap,
+    zenith_distance_axis: bool,
+    grid_alignment: GridAlignment,
+    session_enabled: bool,
+    session_start_time: String,
+    session_end_time: String,
+    suggested_order: Option<SuggestedOrder>,
+    never_visible: Vec<NeverVisible>,
+    max_az_rate_deg_per_sec: f64,
+    fast_track_warnings: Vec<FastTrackWarning>,
+    /// Antenna beam (arcmin) below which two selected sources are flagged as unresolved.
+    beam_arcmin: f64,
+    /// Selected source pairs closer than `beam_arcmin` at the last plot, with the separation
+    /// in arcmin, closest first.
+    close_pairs: Vec<(String, String, f64)>,
+    source_editor: Option<SourceEditor>,
+    adhoc_form: SourceEditor,
+    manual_station_form: ManualStationForm,
+    /// Stations entered in the manual form; in memory only, kept across station file reloads.
+    manual_stations: Vec<Station>,
+    /// Names of sources entered in the ad-hoc form; in memory only, kept across source file
+    /// reloads.
+    adhoc_sources: HashSet<String>,
+    mosaic: MosaicSettings,
+    countdown: Option<CountdownCache>,
+    ellipsoid: utils::Ellipsoid,
+    batch_start_date: String,
+    batch_days: u32,
+    batch_output_dir: String,
+    line_width: f32,
+    polar_point_radius: f32,
+    /// Font size (points) of the polar plot's ring and azimuth labels.
+    polar_label_size: f32,
+    /// Draw spline-interpolated tracks; the computed samples and exports stay raw.
+    smooth_curves: bool,
+    time_display: TimeDisplay,
+    csv_options: CsvExportOptions,
+    calendar_windows: CalendarWindows,
+    tracking_export: TrackingExport,
+    preferences_path: Option<PathBuf>,
+    band_file_path: String,
+    band_templates: Vec<BandTemplate>,
+    session_path: Option<PathBuf>,
+    /// Reopen the last catalogues, selection, date and settings on startup.
+    auto_restore_session: bool,
+    frequency_enabled: bool,
+    observing_frequency_ghz: f64,
+    time_zoom_enabled: bool,
+    time_view: (f64, f64),
+    /// Plotted source (e.g. a phase-reference calibrator) whose coverage gaps are shaded on the
+    /// other sources' elevation lines.
+    reference_source: Option<String>,
+    uptime_layout: UptimeLayout,
+    /// Share of the Uptime Plotters height given to the azimuth plot.
+    az_plot_fraction: f32,
+    /// `station_idx` of the panel shown in per-station layout.
+    uptime_panel_station: usize,
+    /// Legend names ("SOURCE:STATION") of plotted series hidden from the plots without
+    /// dropping them from `plot_data`.
+    hidden_series: HashSet<String>,
+    /// Polar-plot marker shapes chosen for sources by name; others take one from
+    /// `default_source_markers`.
+    source_markers: HashMap<String, SourceMarker>,
+    /// Hours of the next date sampled after 24:00 UT, so late tracks continue past midnight.
+    overnight_hours: f64,
+    /// UT1 - UTC in seconds, applied before sidereal time in the plots and LST readouts.
+    dut1_seconds: f64,
+    sidereal_mode: utils::SiderealMode,
+    /// How catalogue positions are read; ICRS positions are reduced to apparent place.
+    reference_frame: utils::ReferenceFrame,
+    /// UT hour shown in the time diagnostics panel.
+    diagnostic_ut_hour: f64,
+    /// `source:station` rows of the strip chart whose elevation line is shown below it.
+    strip_chart_expanded: HashSet<String>,
+    /// Overlay the scans that fit each visibility window on the strip chart.
+    scan_coverage_enabled: bool,
+    /// Highlight on the strip chart the spans where no plotted source is above the floor.
+    down_time_enabled: bool,
+    scan_length_min: f64,
+    /// Start-to-start interval of the modeled scans.
+    scan_cadence_min: f64,
+    polar_orientation: PolarOrientation,
+    /// Label and space the polar elevation rings by plane-parallel airmass instead of degrees.
+    polar_airmass_rings: bool,
+    show_galactic_plane: bool,
+    show_ecliptic: bool,
+    /// UT hour at which the Galactic plane and ecliptic overlays are drawn on the polar plot,
+    /// and the time the polar animation has reached.
+    sky_overlay_hour: f64,
+    polar_animation: PolarAnimation,
+    auto_reload_catalogs: bool,
+    station_watch: CatalogWatch,
+    source_watch: CatalogWatch,
+    station_file_changed: bool,
+    source_file_changed: bool,
+    last_catalog_poll: Instant,
+    sun_avoid_deg: f64,
+    moon_avoid_deg: f64,
+    /// Rise/set at the apparent (refracted) horizon instead of the geometric el = 0°; rise/set
+    /// markers are refracted onto the elevation floor likewise.
+    rise_set_refracted: bool,
+    /// Visibility windows separated by at most this many minutes are reported as one.
+    merge_gap_min: f64,
+    /// Append a line describing each "Plot Selected" run to `plot_log_path`.
+    plot_log_enabled: bool,
+    plot_log_path: String,
+    plot_log_format: PlotLogFormat,
+    /// Sort the visibility summary by the fraction of the session above the floor.
+    rank_by_session_fraction: bool,
+    separation_data: Vec<SeparationSeries>,
+    /// Station/source files found missing or empty at startup, with the reason shown in the
+    /// first-run window. Cleared per kind once a usable file is loaded.
+    startup_file_issues: Vec<(CatalogKind, String)>,
 }
 
 impl UptimePlotApp {
     fn new(cli_args: CliArgs) -> Self {
+        let print_config = cli_args.print_config;
+        let restore_session = !cli_args.fresh;
+        let catalog_paths_given = (
+            cli_args.station_path.is_some(),
+            cli_args.source_path.is_some(),
+        );
         let app_dir = runtime_app_dir();
         let user_data_dir = uptimeplot_data_dir().unwrap_or_else(|| app_dir.clone());
         let default_source_path =
@@ -381,6 +1957,9 @@ impl UptimePlotApp {
         let default_station_path =
             ensure_user_data_file(&user_data_dir, "station.txt", DEFAULT_STATION_TXT)
                 .unwrap_or_else(|| app_dir.join("station.txt"));
+        let band_file_path =
+            ensure_user_data_file(&user_data_dir, "bands.toml", DEFAULT_BANDS_TOML)
+                .unwrap_or_else(|| app_dir.join("bands.toml"));
 
         // Determine station_file_path
         let station_file_path = cli_args.station_path.unwrap_or(default_station_path);
@@ -388,25 +1967,20 @@ impl UptimePlotApp {
         // Determine source_file_path
         let source_file_path = cli_args.source_path.unwrap_or(default_source_path);
 
-        let stations: Vec<Station> = {
+        let stations: Vec<(Station, bool)> = {
             let mut stations_vec = Vec::new();
-            if let Ok(file) = fs::File::open(&station_file_path) {
-                let reader = BufReader::new(file);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        let parts: Vec<&str> = line.trim().split_whitespace().collect();
-                        if parts.len() == 4 {
-                            if let (Ok(pos_x), Ok(pos_y), Ok(pos_z)) = (
-                                parts[1].parse::<f64>(),
-                                parts[2].parse::<f64>(),
-                                parts[3].parse::<f64>(),
-                            ) {
-                                stations_vec.push(Station {
-                                    name: parts[0].to_string(),
-                                    pos: [pos_x, pos_y, pos_z],
-                                    selected: parts[0] == "YAMAGU32",
-                                });
-                            }
+            if let Ok(content) = fs::read_to_string(&station_file_path) {
+                if is_vex_file(&station_file_path, &content) {
+                    stations_vec.extend(
+                        parse_vex_sites(&content)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(with_default_selection),
+                    );
+                } else {
+                    for line in content.lines() {
+                        if let Ok(station) = parse_station_line(line.trim()) {
+                            stations_vec.push(with_default_selection(station));
                         }
                     }
                 }
@@ -462,27 +2036,176 @@ impl UptimePlotApp {
             plot_data: Vec::new(),
             lst_plot_data: Vec::new(),
             polar_plot_data: Vec::new(),
-            error_msg: None,
+            log_entries: VecDeque::new(),
             show_calendar: false,
             show_new_skd_calendar: false,
             search_query: String::new(),
+            source_list_cache: None,
+            source_list_shown_rows: 0..0,
+            show_only_selected: false,
+            source_list_focus: None,
+            night_search: NightSearch::new(Utc::now().date_naive()),
+            station_comparison: StationComparison::default(),
+            station_pair: StationPairPlot::default(),
+            source_preview_enabled: false,
+            source_previews: HashMap::new(),
+            preview_job: None,
             selected_tab: AppTab::UptimePlotters,
             uptime_plot_rect: None,
+            cursor_ut: None,
+            cursor_ut_next: None,
             polar_plot_rect: None,
             lst_plot_rect: None,
             output_capture: None,
+            elevation_floor_deg: 20.0,
+            opacity_floor_enabled: false,
+            tau0: 0.1,
+            max_line_opacity: 0.3,
+            az_sectors_text: String::new(),
+            az_sectors: Vec::new(),
+            polar_below_floor_color: egui::Color32::from_rgb(255, 140, 0),
+            polar_horizon_color: egui::Color32::from_rgb(70, 130, 200),
+            polar_sky_fill: false,
+            polar_low_el_shading: true,
+            polar_live: false,
+            polar_zoom_enabled: false,
+            polar_view: None,
+            show_sun_moon: false,
+            show_preferences: false,
+            image_size: None,
+            restore_window_size: None,
+            clipboard_capture: None,
+            central_panel_rect: None,
+            cli_export: cli_args.export_azel,
+            cli_export_started: false,
+            cli_export_failed: Arc::new(AtomicBool::new(false)),
+            elevation_color_map: ElevationColorMap::Flat,
+            zenith_distance_axis: false,
+            grid_alignment: GridAlignment::Ut,
+            session_enabled: false,
+            session_start_time: "12:00:00".to_string(),
+            session_end_time: "20:00:00".to_string(),
+            suggested_order: None,
+            never_visible: Vec::new(),
+            max_az_rate_deg_per_sec: 0.5,
+            fast_track_warnings: Vec::new(),
+            beam_arcmin: 5.0,
+            close_pairs: Vec::new(),
+            source_editor: None,
+            manual_station_form: ManualStationForm::default(),
+            manual_stations: Vec::new(),
+            adhoc_sources: HashSet::new(),
+            adhoc_form: SourceEditor {
+                name: "ADHOC".to_string(),
+                ..SourceEditor::new_source()
+            },
+            mosaic: MosaicSettings::default(),
+            countdown: None,
+            ellipsoid: utils::Ellipsoid::default(),
+            batch_start_date: Utc::now().date_naive().format("%Y-%m-%d").to_string(),
+            batch_days: 7,
+            batch_output_dir: std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join("uptime_batch")
+                .to_string_lossy()
+                .to_string(),
+            line_width: 2.0,
+            polar_point_radius: 3.5,
+            polar_label_size: 14.0,
+            smooth_curves: false,
+            time_display: TimeDisplay {
+                twelve_hour: false,
+                seconds: true,
+            },
+            csv_options: CsvExportOptions::default(),
+            calendar_windows: CalendarWindows::AboveFloor,
+            tracking_export: TrackingExport::default(),
+            preferences_path: uptimeplot_data_dir().map(|dir| dir.join(PREFERENCES_FILE)),
+            band_file_path: band_file_path.to_str().unwrap_or_default().to_string(),
+            band_templates: Vec::new(),
+            session_path: uptimeplot_data_dir().map(|dir| dir.join(SESSION_FILE)),
+            auto_restore_session: false,
+            frequency_enabled: false,
+            observing_frequency_ghz: 8.4,
+            time_zoom_enabled: false,
+            reference_source: None,
+            uptime_layout: UptimeLayout::Overlay,
+            az_plot_fraction: 0.5,
+            uptime_panel_station: 0,
+            hidden_series: HashSet::new(),
+            source_markers: HashMap::new(),
+            time_view: (0.0, 24.0 + UT_PLOT_RIGHT_MARGIN_HOURS),
+            overnight_hours: 0.0,
+            dut1_seconds: 0.0,
+            sidereal_mode: utils::SiderealMode::default(),
+            reference_frame: utils::ReferenceFrame::default(),
+            diagnostic_ut_hour: 0.0,
+            strip_chart_expanded: HashSet::new(),
+            scan_coverage_enabled: false,
+            down_time_enabled: false,
+            scan_length_min: 10.0,
+            scan_cadence_min: 30.0,
+            polar_orientation: PolarOrientation::NorthCw,
+            polar_airmass_rings: false,
+            show_galactic_plane: false,
+            show_ecliptic: false,
+            sky_overlay_hour: 12.0,
+            polar_animation: PolarAnimation::default(),
+            auto_reload_catalogs: false,
+            station_watch: CatalogWatch::default(),
+            source_watch: CatalogWatch::default(),
+            station_file_changed: false,
+            source_file_changed: false,
+            last_catalog_poll: Instant::now(),
+            sun_avoid_deg: 10.0,
+            moon_avoid_deg: 5.0,
+            rise_set_refracted: false,
+            merge_gap_min: 0.0,
+            plot_log_enabled: false,
+            plot_log_path: uptimeplot_data_dir()
+                .map(|dir| dir.join("plot_log.csv").to_string_lossy().to_string())
+                .unwrap_or_default(),
+            plot_log_format: PlotLogFormat::Csv,
+            rank_by_session_fraction: false,
+            separation_data: Vec::new(),
+            startup_file_issues: Vec::new(),
         };
+        // The stations were read above without `load_stations`, so start watching them here.
+        app.station_watch.mark_loaded(&app.station_file_path);
         let _ = app.load_sources();
+        if let Err(e) = app.load_band_templates() {
+            app.log(LogLevel::Warn, e);
+        }
         let _ = app.load_antennas();
+        app.load_preferences();
+        if restore_session && app.auto_restore_session {
+            app.restore_session(catalog_paths_given);
+        }
+        if cli_args.image_size.is_some() {
+            app.image_size = cli_args.image_size;
+        }
+        app.check_startup_files();
+        if print_config {
+            println!("{}", app.effective_config_json());
+        }
         app
     }
 }
 
 impl eframe::App for UptimePlotApp {
-    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+    fn ui(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
         let ctx = ui.ctx().clone();
+        self.show_startup_files_window(&ctx);
         self.show_calendar_window(&ctx);
         self.show_new_skd_calendar_window(&ctx);
+        self.show_preferences_window(&ctx);
+        self.show_source_editor_window(&ctx);
+        self.poll_catalog_files(&ctx);
+        if self.auto_restore_session && ctx.input(|i| i.viewport().close_requested()) {
+            if let Err(e) = self.save_session() {
+                eprintln!("{}", e);
+            }
+        }
 
         if let Some(image) = ctx.input(|i| {
             i.events.iter().find_map(|e| {
@@ -493,7 +2216,11 @@ impl eframe::App for UptimePlotApp {
                 }
             })
         }) {
-            self.handle_output_screenshot(&image, ctx.pixels_per_point());
+            if let Some((rect, pad_points)) = self.clipboard_capture.take() {
+                self.copy_screenshot_to_clipboard(&ctx, &image, rect, pad_points);
+            } else {
+                self.handle_output_screenshot(&image, ctx.pixels_per_point());
+            }
         }
 
         egui::Panel::top("top_panel").show_inside(ui, |ui| {
@@ -506,1777 +2233,8325 @@ impl eframe::App for UptimePlotApp {
                 );
                 ui.selectable_value(&mut self.selected_tab, AppTab::PolarPlot, "Polar Plot");
                 ui.selectable_value(&mut self.selected_tab, AppTab::LstPlot, "LST Plot");
+                ui.selectable_value(&mut self.selected_tab, AppTab::HaPlot, "HA Plot");
+                ui.selectable_value(
+                    &mut self.selected_tab,
+                    AppTab::CoVisibility,
+                    "Co-visibility",
+                );
+                ui.selectable_value(&mut self.selected_tab, AppTab::StripChart, "Strip Chart");
+                ui.selectable_value(
+                    &mut self.selected_tab,
+                    AppTab::Separation,
+                    "Sun/Moon Separation",
+                );
+                ui.selectable_value(
+                    &mut self.selected_tab,
+                    AppTab::StationComparison,
+                    "All Stations",
+                );
+                ui.selectable_value(&mut self.selected_tab, AppTab::StationPair, "Station Pair");
                 ui.selectable_value(&mut self.selected_tab, AppTab::SkdTable, "SKD Table");
+                ui.separator();
+                if ui.button("⚙ Preferences").clicked() {
+                    self.show_preferences = !self.show_preferences;
+                }
+                if ui
+                    .add_enabled(
+                        self.copy_view_rect().is_some() && self.ensure_capture_idle().is_ok(),
+                        egui::Button::new("📋 Copy Image"),
+                    )
+                    .on_hover_text("Copy the plot in the current tab to the clipboard as an image")
+                    .clicked()
+                {
+                    if let Err(e) = self.request_clipboard_capture(&ctx) {
+                        self.log(LogLevel::Error, e);
+                    }
+                }
             });
+            self.ui_catalog_change_notice(ui);
         });
 
-        egui::CentralPanel::default().show_inside(ui, |ui| match self.selected_tab {
+        egui::Panel::bottom("log_panel").show_inside(ui, |ui| self.ui_log_panel(ui));
+
+        let central = egui::CentralPanel::default()
+            .show_inside(ui, |ui| self.ui_tab_contents(ui, self.selected_tab));
+        self.central_panel_rect = Some(central.response.rect);
+
+        if self.cursor_ut != self.cursor_ut_next {
+            ctx.request_repaint();
+        }
+        self.cursor_ut = self.cursor_ut_next.take();
+
+        self.drive_output_capture(&ctx, frame);
+        if self.output_capture.is_none() {
+            if let Some(size) = self.restore_window_size.take() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+            }
+        }
+        self.drive_cli_export(&ctx);
+    }
+}
+
+impl UptimePlotApp {
+    fn ui_tab_contents(&mut self, ui: &mut egui::Ui, tab: AppTab) {
+        match tab {
             AppTab::UptimePlotters => self.ui_uptime_plotters_tab(ui),
             AppTab::Parameters => self.ui_parameters_tab(ui),
             AppTab::PolarPlot => self.ui_polar_plot_tab(ui),
             AppTab::LstPlot => self.ui_lst_plot_tab(ui),
+            AppTab::HaPlot => self.ui_ha_plot_tab(ui),
+            AppTab::CoVisibility => self.ui_co_visibility_tab(ui),
+            AppTab::StripChart => self.ui_strip_chart_tab(ui),
+            AppTab::Separation => self.ui_separation_tab(ui),
+            AppTab::StationComparison => self.ui_station_comparison_tab(ui),
+            AppTab::StationPair => self.ui_station_pair_tab(ui),
             AppTab::SkdTable => self.ui_skd_table_tab(ui),
-        });
+        }
+    }
 
-        self.drive_output_capture(&ctx);
+    fn check_catalog_paths_distinct(&self) -> Result<(), String> {
+        // An empty path is reported by the loader that needs it, not as "same file".
+        if self.station_file_path.trim().is_empty() || self.source_file_path.trim().is_empty() {
+            return Ok(());
+        }
+        let same = match (
+            fs::canonicalize(&self.station_file_path),
+            fs::canonicalize(&self.source_file_path),
+        ) {
+            (Ok(station), Ok(source)) => station == source,
+            _ => self.station_file_path == self.source_file_path,
+        };
+        if same {
+            return Err("The station file and source file are the same file.".to_string());
+        }
+        Ok(())
     }
-}
 
-impl UptimePlotApp {
     fn load_sources(&mut self) -> Result<(), String> {
-        let source_content = fs::read_to_string(&self.source_file_path)
-            .map_err(|e| format!("Failed to read source file: {}", e))?;
-
-        let mut sources = Vec::new();
-        for line in source_content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('*') {
-                continue;
-            }
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 7 {
-                continue;
+        require_catalog_path(&self.source_file_path, "source")?;
+        self.check_catalog_paths_distinct()?;
+        // Nothing is replaced until the whole file has been read and parsed, so a deleted or
+        // broken file leaves the loaded catalogue and plots as they were.
+        let parsed = read_source_file(&self.source_file_path);
+        self.source_watch.mark_loaded(&self.source_file_path);
+        self.source_file_changed = false;
+        let (sources, invalid) =
+            parsed.map_err(|e| kept_catalog_message(e, self.sources.len(), "sources"))?;
+        let mut sources: Vec<(Source, bool)> =
+            sources.into_iter().map(|source| (source, false)).collect();
+        if !invalid.is_empty() {
+            for message in &invalid {
+                self.log(LogLevel::Warn, message.clone());
             }
-
-            let source = parse_source_tokens(&parts, 0, 1, line)?;
-            sources.push((source, false));
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Skipped {} invalid source line(s) in {}; {} loaded.",
+                    invalid.len(),
+                    self.source_file_path,
+                    sources.len()
+                ),
+            );
         }
+        // A catalogue entry of the same name replaces the ad-hoc position.
+        self.adhoc_sources
+            .retain(|name| !sources.iter().any(|(s, _)| &s.name == name));
+        let adhoc: Vec<(Source, bool)> = self
+            .sources
+            .drain(..)
+            .filter(|(source, _)| self.adhoc_sources.contains(&source.name))
+            .collect();
+        sources.extend(adhoc);
         self.sources = sources;
+        self.source_list_cache = None;
+        self.source_previews.clear();
+        self.preview_job = None;
         self.clear_plot_data();
         self.mark_skd_status_dirty();
         Ok(())
     }
 
-    fn load_antennas(&mut self) -> Result<(), String> {
-        let content = fs::read_to_string(&self.antenna_file_path)
-            .map_err(|e| format!("Failed to read antenna.sch: {}", e))?;
+    /// Writes `edited` back to the source file, replacing the line of `original_name` (or
+    /// appending a new line), then reloads the catalogue keeping the current selection.
+    fn save_source_edit(
+        &mut self,
+        original_name: Option<&str>,
+        edited: &Source,
+    ) -> Result<(), String> {
+        let duplicate = self.sources.iter().any(|(source, _)| {
+            source.name == edited.name && Some(source.name.as_str()) != original_name
+        });
+        if duplicate {
+            return Err(format!("Source '{}' already exists.", edited.name));
+        }
 
-        let mut antennas = Vec::new();
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('*') || line.starts_with("LTRCODE") {
-                continue;
-            }
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 15 {
-                continue;
-            }
-            let parsed = (
-                parts[2].parse::<f64>(),
-                parts[3].parse::<f64>(),
-                parts[4].parse::<f64>(),
-                parts[7].parse::<f64>(),
-                parts[9].parse::<f64>(),
-                parts[10].parse::<f64>(),
-                parts[11].parse::<f64>(),
-                parts[13].parse::<f64>(),
-                parts[14].parse::<f64>(),
-            );
-            if let (
-                Ok(x),
-                Ok(y),
-                Ok(z),
-                Ok(az_rate),
-                Ok(az_min),
-                Ok(az_max),
-                Ok(el_rate),
-                Ok(el_min),
-                Ok(el_max),
-            ) = parsed
-            {
-                antennas.push(Antenna {
-                    code: parts[0].to_string(),
-                    name: parts[1].to_string(),
-                    pos: [x, y, z],
-                    az_rate_deg_per_min: az_rate,
-                    az_min_deg: az_min,
-                    az_max_deg: az_max,
-                    el_rate_deg_per_min: el_rate,
-                    el_min_deg: el_min,
-                    el_max_deg: el_max,
-                });
+        let content = fs::read_to_string(&self.source_file_path)
+            .map_err(|e| format!("Failed to read source file: {}", e))?;
+        if is_csv_catalog(Path::new(&self.source_file_path), &content) {
+            return Err(format!(
+                "{} is a CSV catalogue; edit it in a spreadsheet and reload.",
+                self.source_file_path
+            ));
+        }
+        let new_line = format_source_catalog_line(edited);
+        let mut replaced = false;
+        let mut lines: Vec<String> = content
+            .lines()
+            .map(|line| {
+                let name = line.split_whitespace().next();
+                if !replaced
+                    && !line.trim_start().starts_with('*')
+                    && name.is_some()
+                    && name == original_name
+                {
+                    replaced = true;
+                    new_line.clone()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        if !replaced {
+            lines.push(new_line);
+        }
+        let mut output = lines.join("\n");
+        output.push('\n');
+        fs::write(&self.source_file_path, output)
+            .map_err(|e| format!("Failed to write source file: {}", e))?;
+
+        let mut selected_names: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(source, _)| source.name.clone())
+            .collect();
+        if let Some(original_name) = original_name {
+            if let Some(name) = selected_names
+                .iter_mut()
+                .find(|name| *name == original_name)
+            {
+                *name = edited.name.clone();
             }
         }
-
-        if antennas.is_empty() {
-            return Err("No antennas loaded from antenna.sch.".to_string());
+        self.load_sources()?;
+        for (source, selected) in &mut self.sources {
+            *selected = selected_names.contains(&source.name);
         }
-        self.antennas = antennas;
-        self.selected_antenna = self
-            .antennas
-            .iter()
-            .position(|antenna| antenna.name == "YAMAGU32")
-            .unwrap_or(0);
-        self.selected_antenna_2 = self
-            .antennas
-            .iter()
-            .position(|antenna| antenna.name == "YAMAGU34")
-            .unwrap_or_else(|| {
-                if self.antennas.len() > 1 {
-                    (self.selected_antenna + 1) % self.antennas.len()
-                } else {
-                    self.selected_antenna
-                }
-            });
-        self.mark_skd_status_dirty();
         Ok(())
     }
 
-    fn clear_plot_data(&mut self) {
-        self.plot_data.clear();
-        self.lst_plot_data.clear();
-        self.polar_plot_data.clear();
+    fn log(&mut self, level: LogLevel, message: impl Into<String>) {
+        if self.log_entries.len() >= LOG_CAPACITY {
+            self.log_entries.pop_front();
+        }
+        self.log_entries.push_back(LogEntry {
+            time: Utc::now(),
+            level,
+            message: message.into(),
+        });
     }
 
-    fn load_stations(&mut self) -> Result<(), String> {
-        let station_content = fs::read_to_string(&self.station_file_path)
-            .map_err(|e| format!("Failed to read station file: {}", e))?;
-
-        let mut stations_vec = Vec::new();
-        for line in station_content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('*') {
-                continue;
-            }
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() == 4 {
-                if let (Ok(pos_x), Ok(pos_y), Ok(pos_z)) = (
-                    parts[1].parse::<f64>(),
-                    parts[2].parse::<f64>(),
-                    parts[3].parse::<f64>(),
-                ) {
-                    stations_vec.push(Station {
-                        name: parts[0].to_string(),
-                        pos: [pos_x, pos_y, pos_z],
-                        selected: parts[0] == "YAMAGU32",
-                    });
-                } else {
-                    return Err(format!("Invalid number format in station file: {}", line));
+    /// One-line view of the latest message with a collapsible history below it.
+    fn ui_log_panel(&mut self, ui: &mut egui::Ui) {
+        let Some((level, message)) = self
+            .log_entries
+            .back()
+            .map(|entry| (entry.level, entry.message.clone()))
+        else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.colored_label(level.color(), message);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("Clear").clicked() {
+                    self.log_entries.clear();
                 }
-            } else {
-                return Err(format!("Invalid line format in station file: {}", line));
-            }
+            });
+        });
+        if self.log_entries.is_empty() {
+            return;
         }
-        self.stations = stations_vec;
-        Ok(())
+        egui::CollapsingHeader::new(format!("Log ({})", self.log_entries.len()))
+            .id_salt("log_panel")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(150.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for entry in &self.log_entries {
+                            ui.colored_label(
+                                entry.level.color(),
+                                format!(
+                                    "{} UT [{}] {}",
+                                    entry.time.format("%H:%M:%S"),
+                                    entry.level.label(),
+                                    entry.message
+                                ),
+                            );
+                        }
+                    });
+            });
     }
 
-    fn calculate_plots(&mut self) {
-        if self.stations.is_empty() {
-            self.error_msg = Some("No stations loaded. Please check station.txt".to_string());
-            return;
+    /// Reloads the source file, keeping the selection of sources whose names are still present.
+    fn reload_sources_keeping_selection(&mut self) -> Result<(), String> {
+        let selected_names: HashSet<String> = self
+            .sources
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(source, _)| source.name.clone())
+            .collect();
+        self.load_sources()?;
+        for (source, selected) in &mut self.sources {
+            *selected = selected_names.contains(&source.name);
         }
+        Ok(())
+    }
 
-        let mut new_plot_data = Vec::new();
-        let selected_stations: Vec<(usize, &Station)> = self
+    /// Reloads the station file, keeping the selection of stations whose names are still
+    /// present.
+    fn reload_stations_keeping_selection(&mut self) -> Result<(), String> {
+        let selected_names: HashSet<String> = self
             .stations
             .iter()
-            .enumerate()
-            .filter(|(_, s)| s.selected)
+            .filter(|(_, selected)| *selected)
+            .map(|(station, _)| station.name.clone())
             .collect();
+        self.load_stations()?;
+        if !selected_names.is_empty() {
+            for (station, selected) in &mut self.stations {
+                *selected = selected_names.contains(&station.name);
+            }
+        }
+        Ok(())
+    }
 
-        if selected_stations.is_empty() {
-            self.error_msg = Some("No stations selected.".to_string());
+    /// Checks the loaded catalogue files for external edits, reloading them straight away in
+    /// auto-reload mode or flagging them for the reload prompt otherwise.
+    fn poll_catalog_files(&mut self, ctx: &egui::Context) {
+        if !self.station_watch.is_active() && !self.source_watch.is_active() {
             return;
         }
+        ctx.request_repaint_after(CATALOG_POLL_INTERVAL);
+        let now = Instant::now();
+        if now.duration_since(self.last_catalog_poll) < CATALOG_POLL_INTERVAL {
+            return;
+        }
+        self.last_catalog_poll = now;
 
-        for (station_idx, station) in selected_stations {
-            let ant_pos = station.pos;
-            for (source, selected) in &self.sources {
-                if !*selected {
-                    continue;
-                }
-
-                let mut full_day_points = Vec::new();
-                for i in (0..=(24 * 60)).step_by(3) {
-                    let hour_float = (i as f64) / 60.0;
-                    let h = (i / 60) as u32;
-                    let m = (i % 60) as u32;
+        if self.station_watch.poll(&self.station_file_path, now) {
+            self.station_file_changed = true;
+        }
+        if self.source_watch.poll(&self.source_file_path, now) {
+            self.source_file_changed = true;
+        }
+        if self.auto_reload_catalogs {
+            self.reload_changed_catalogs();
+        }
+    }
 
-                    if let Some(time) = self.selected_date.and_hms_opt(h, m, 0) {
-                        let datetime_utc = Utc.from_utc_datetime(&time);
-                        let (az, el, _) =
-                            utils::radec2azalt(ant_pos, datetime_utc, source.ra_rad, source.dec_rad);
-                        full_day_points.push((hour_float, az, el));
-                    }
+    fn reload_changed_catalogs(&mut self) {
+        let mut reloaded = Vec::new();
+        if self.station_file_changed {
+            match self.reload_stations_keeping_selection() {
+                Ok(()) => reloaded.push(self.station_file_path.clone()),
+                Err(e) => {
+                    self.station_file_changed = false;
+                    self.log(LogLevel::Error, e);
                 }
-
-                let mut az_points = Vec::new();
-                let mut el_points = Vec::new();
-
-                if let Some(last_point) = full_day_points.get(0) {
-                    if last_point.2 >= 0.0 {
-                        az_points.push([last_point.0, last_point.1]);
-                        el_points.push([last_point.0, last_point.2]);
-                    }
-
-                    for &point in full_day_points.iter().skip(1) {
-                        let (hour, az, el) = point;
-                        az_points.push([hour, az]);
-                        if el >= 0.0 {
-                            el_points.push([hour, el]);
-                        } else {
-                            el_points.push([hour, f64::NAN]);
-                        }
-                    }
+            }
+        }
+        if self.source_file_changed {
+            match self.reload_sources_keeping_selection() {
+                Ok(()) => reloaded.push(self.source_file_path.clone()),
+                Err(e) => {
+                    self.source_file_changed = false;
+                    self.log(LogLevel::Error, e);
                 }
-                new_plot_data.push((
-                    source.name.clone(),
-                    station.name.clone(),
-                    az_points,
-                    el_points,
-                    station_idx,
-                ));
             }
         }
-        self.plot_data = new_plot_data;
-        self.lst_plot_data = self.build_lst_plot_data();
-        self.polar_plot_data = self.build_polar_plot_data();
+        if !reloaded.is_empty() {
+            self.log(LogLevel::Info, format!("Reloaded {}", reloaded.join(", ")));
+        }
     }
 
-    fn station_position(&self) -> Option<[f64; 3]> {
-        self.stations
-            .iter()
-            .find(|s| s.selected)
-            .map(|station| station.pos)
+    /// Re-reads the station and source files, keeping both selections by name, and replots.
+    /// A file that fails to load keeps its catalogue; the failures come back as one message.
+    fn reload_all_and_replot(&mut self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.reload_stations_keeping_selection() {
+            errors.push(e);
+        }
+        if let Err(e) = self.reload_sources_keeping_selection() {
+            errors.push(e);
+        }
+        self.calculate_plots();
+        if errors.is_empty() {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Reloaded {} and {}",
+                    self.station_file_path, self.source_file_path
+                ),
+            );
+            Ok(())
+        } else {
+            Err(format!("Reload & Replot: {}", errors.join("; ")))
+        }
     }
 
-    fn lst_from_ut_hour(&self, station_pos: [f64; 3], ut_hour: f64) -> Option<f64> {
-        let datetime = utc_datetime_from_hour(self.selected_date, ut_hour)?;
-        Some(utils::utc_to_lst_hours(station_pos, datetime))
+    fn ui_catalog_change_notice(&mut self, ui: &mut egui::Ui) {
+        if !self.station_file_changed && !self.source_file_changed {
+            return;
+        }
+        let mut changed = Vec::new();
+        if self.station_file_changed {
+            changed.push(self.station_file_path.as_str());
+        }
+        if self.source_file_changed {
+            changed.push(self.source_file_path.as_str());
+        }
+        let notice = format!("Changed on disk: {}", changed.join(", "));
+        ui.horizontal(|ui| {
+            ui.colored_label(egui::Color32::YELLOW, notice);
+            if ui.button("Reload").clicked() {
+                self.reload_changed_catalogs();
+            }
+            if ui.button("Dismiss").clicked() {
+                self.station_file_changed = false;
+                self.source_file_changed = false;
+            }
+        });
     }
 
-    fn build_lst_plot_data(&self) -> Vec<(String, String, Vec<[f64; 2]>, Vec<[f64; 2]>, usize)> {
-        let mut lst_plot_data = Vec::new();
-
-        for (source_name, station_name, az_points, el_points, station_idx) in &self.plot_data {
-            let station_pos = self
-                .stations
-                .iter()
-                .find(|s| &s.name == station_name)
-                .map(|s| s.pos)
-                .unwrap_or([0.0, 0.0, 0.0]);
-
-            let mut lst_az_points = Vec::new();
-            let mut lst_el_points = Vec::new();
-            let mut prev_lst: Option<f64> = None;
+    fn is_adhoc(&self, source: &Source) -> bool {
+        self.adhoc_sources.contains(&source.name)
+    }
 
-            for i in 0..az_points.len() {
-                let ut_hour = az_points[i][0];
-                let az = az_points[i][1];
-                let el = el_points[i][1];
+    /// Adds the ad-hoc form's position as a selected, in-memory source and replots.
+    fn add_adhoc_source(&mut self) -> Result<(), String> {
+        let mut source = self.adhoc_form.to_source()?;
+        if self.find_source(&source.name).is_some() {
+            return Err(format!("Source '{}' already exists.", source.name));
+        }
+        source.group = Some(ADHOC_GROUP.to_string());
+        self.adhoc_sources.insert(source.name.clone());
+        self.sources.push((source, true));
+        self.mark_skd_status_dirty();
+        self.calculate_plots();
+        Ok(())
+    }
 
-                if let Some(lst_hour) = self.lst_from_ut_hour(station_pos, ut_hour) {
-                    if let Some(prev) = prev_lst {
-                        if lst_hour + 12.0 < prev {
-                            lst_az_points.push([f64::NAN, f64::NAN]);
-                            lst_el_points.push([f64::NAN, f64::NAN]);
-                        }
+    fn ui_adhoc_sources(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Ad-hoc position").show(ui, |ui| {
+            egui::Grid::new("adhoc_source_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut self.adhoc_form.name);
+                    ui.end_row();
+                    ui.label("RA (HH MM SS.sss):");
+                    ui.text_edit_singleline(&mut self.adhoc_form.ra);
+                    ui.end_row();
+                    ui.label("Dec (±DD MM SS.ss):");
+                    ui.text_edit_singleline(&mut self.adhoc_form.dec);
+                    ui.end_row();
+                });
+            if ui.button("Add & Plot").clicked() {
+                match self.add_adhoc_source() {
+                    Ok(()) => {}
+                    Err(e) => self.log(LogLevel::Error, e),
+                }
+            }
+            let mut remove = None;
+            for (idx, (source, _)) in self.sources.iter().enumerate() {
+                if !self.is_adhoc(source) {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} {:02} {:02} {:06.3} {}{:02} {:02} {:05.2}",
+                        source.name,
+                        source.ra_h,
+                        source.ra_m,
+                        source.ra_s,
+                        source.dec_sign,
+                        source.dec_d,
+                        source.dec_m,
+                        source.dec_s
+                    ));
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(idx);
                     }
-
-                    lst_az_points.push([lst_hour, az]);
-                    lst_el_points.push([lst_hour, el]);
-                    prev_lst = Some(lst_hour);
+                });
+            }
+            if let Some(idx) = remove {
+                let (source, _) = self.sources.remove(idx);
+                self.adhoc_sources.remove(&source.name);
+                self.source_list_cache = None;
+                self.mark_skd_status_dirty();
+                if !self.plot_data.is_empty() {
+                    self.calculate_plots();
                 }
             }
-            lst_plot_data.push((
-                source_name.clone(),
-                station_name.clone(),
-                lst_az_points,
-                lst_el_points,
-                *station_idx,
-            ));
-        }
-        lst_plot_data
+        });
     }
 
-    fn build_polar_plot_data(
-        &self,
-    ) -> Vec<(
-        String,
-        String,
-        Vec<[f64; 2]>,
-        Vec<[f64; 2]>,
-        Vec<(f64, f64, String)>,
-        usize,
-    )> {
-        let mut polar_plot_data = Vec::new();
-
-        for (source_name, station_name, az_points, el_points, station_idx) in &self.plot_data {
-            let mut polar_points = Vec::new();
-            let mut hour_marker_points = Vec::new();
-            let mut hour_labels = Vec::new();
-
-            for i in 0..az_points.len() {
-                let hour = az_points[i][0];
-                let az = az_points[i][1];
-                let el = el_points[i][1];
+    /// Next rise and set after `now` for each selected source at the first selected station,
+    /// interpolated from one-minute samples over `COUNTDOWN_HORIZON_HOURS`. The horizon follows
+    /// the Rise/Set Horizon setting.
+    fn compute_countdown(&self, now: chrono::DateTime<Utc>) -> Option<CountdownCache> {
+        let station = self.current_station()?;
+        let horizon_deg = if self.rise_set_refracted {
+            utils::APPARENT_HORIZON_DEG
+        } else {
+            0.0
+        };
+        let last_minute = COUNTDOWN_HORIZON_HOURS * 60;
+        let mut entries = Vec::new();
+        for (source, _) in self.sources.iter().filter(|(_, selected)| *selected) {
+            let samples: Vec<(f64, f64)> = (0..=last_minute)
+                .map(|minute| {
+                    let time =
+                        utils::ut1_from_utc(now + Duration::minutes(minute), self.dut1_seconds);
+                    let el = utils::radec2azalt_with(
+                        self.ellipsoid,
+                        self.sidereal_mode,
+                        self.reference_frame,
+                        station.pos,
+                        time,
+                        source.ra_rad,
+                        source.dec_rad,
+                    )
+                    .el_deg;
+                    (minute as f64 / 60.0, el - horizon_deg)
+                })
+                .collect();
+            let windows = utils::visibility_windows_from_margin(&samples);
+            let end_hour = last_minute as f64 / 60.0;
+            let at = |hour: f64| now + Duration::seconds((hour * 3600.0).round() as i64);
+            entries.push(CountdownEntry {
+                source_name: source.name.clone(),
+                up_now: samples.first().is_some_and(|&(_, margin)| margin >= 0.0),
+                next_rise: windows.iter().map(|w| w.0).find(|&h| h > 0.0).map(at),
+                next_set: windows.iter().map(|w| w.1).find(|&h| h < end_hour).map(at),
+            });
+        }
+        Some(CountdownCache {
+            computed_at: now,
+            station_name: station.name.clone(),
+            sources: entries
+                .iter()
+                .map(|entry| entry.source_name.clone())
+                .collect(),
+            entries,
+        })
+    }
 
-                if !el.is_nan() && el >= 0.0 {
-                    let angle_rad = (90.0f64 - az).to_radians();
-                    let radius = (90.0 - el) / 90.0;
-                    let x = radius * angle_rad.cos();
-                    let y = radius * angle_rad.sin();
-                    polar_points.push([x, y]);
+    /// Indices of the sources whose names contain the search filter (case-insensitive), per
+    /// group in list order. Cached until the filter or the number of sources changes, so a
+    /// large catalogue is not rescanned every frame.
+    fn source_filter_matches(&mut self) -> &[(Option<String>, Vec<usize>)] {
+        let query = self.search_query.trim().to_lowercase();
+        let stale = self
+            .source_list_cache
+            .as_ref()
+            .is_none_or(|cache| cache.query != query || cache.source_count != self.sources.len());
+        if stale {
+            let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+            for (idx, (source, _)) in self.sources.iter().enumerate() {
+                if !query.is_empty() && !source.name.to_lowercase().contains(&query) {
+                    continue;
+                }
+                match groups.iter_mut().find(|(group, _)| *group == source.group) {
+                    Some((_, indices)) => indices.push(idx),
+                    None => groups.push((source.group.clone(), vec![idx])),
+                }
+            }
+            groups.sort_by_key(|(group, _)| group_sort_key(group.as_deref()));
+            self.source_list_cache = Some(SourceListCache {
+                query,
+                source_count: self.sources.len(),
+                groups,
+            });
+        }
+        self.source_list_cache
+            .as_ref()
+            .map(|cache| cache.groups.as_slice())
+            .unwrap_or_default()
+    }
 
-                    if (hour - hour.round()).abs() < 1e-6 {
-                        hour_marker_points.push([x, y]);
-                        let label_hour = hour.round() as i32;
-                        let label_offset = 0.04;
-                        hour_labels.push((
-                            x + angle_rad.cos() * label_offset,
-                            y + angle_rad.sin() * label_offset,
-                            format!("{:02}h", label_hour),
-                        ));
+    /// The checkbox list of sources within the search filter and "Selected only". Only the
+    /// rows in view are laid out.
+    fn ui_source_list(&mut self, ui: &mut egui::Ui) {
+        let only_selected = self.show_only_selected;
+        self.source_filter_matches();
+        let matches = self
+            .source_list_cache
+            .as_ref()
+            .map(|cache| cache.groups.as_slice())
+            .unwrap_or_default();
+        let listed_groups: Vec<(Option<String>, Vec<usize>)> = matches
+            .iter()
+            .map(|(group, indices)| {
+                let indices: Vec<usize> = indices
+                    .iter()
+                    .copied()
+                    .filter(|&idx| !only_selected || self.sources[idx].1)
+                    .collect();
+                (group.clone(), indices)
+            })
+            .filter(|(_, indices)| !indices.is_empty())
+            .collect();
+        let listed: Vec<usize> = listed_groups
+            .iter()
+            .flat_map(|(_, indices)| indices.iter().copied())
+            .collect();
+        if listed.is_empty() {
+            ui.label(if only_selected {
+                "(No sources selected)"
+            } else {
+                "(No sources match the filter)"
+            });
+        }
+        let focus_moved = self.handle_source_list_keys(ui.ctx(), &listed);
+        let focus = self.source_list_focus;
+        let sectioned = listed_groups.iter().any(|(group, _)| group.is_some());
+        let rows = source_list_rows(&listed_groups, sectioned);
+
+        let row_height = ui.spacing().interact_size.y;
+        let mut scroll_area = egui::ScrollArea::vertical().max_height(200.0);
+        let focus_row = focus.and_then(|pos| {
+            rows.iter().position(|row| match row {
+                SourceListRow::Sources {
+                    first_pos, indices, ..
+                } => (*first_pos..first_pos + indices.len()).contains(&pos),
+                SourceListRow::Header(_) => false,
+            })
+        });
+        if let Some(row) =
+            focus_row.filter(|row| focus_moved && !self.source_list_shown_rows.contains(row))
+        {
+            scroll_area = scroll_area
+                .vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+        let previews = self
+            .source_preview_enabled
+            .then(|| self.preview_key())
+            .flatten()
+            .and_then(|key| self.source_previews.get(&key));
+        let sources = &mut self.sources;
+        let mut clicked_pos = None;
+        let mut set_group = None;
+        let shown = scroll_area.show_rows(ui, row_height, rows.len(), |ui, range| {
+            let mut row = range.start;
+            while row < range.end {
+                let group = match &rows[row] {
+                    SourceListRow::Header(group) => {
+                        ui.horizontal(|ui| {
+                            match group {
+                                Some(name) => ui.colored_label(
+                                    group_color(name),
+                                    egui::RichText::new(name).strong(),
+                                ),
+                                None => ui.strong("(ungrouped)"),
+                            };
+                            if ui.small_button("Select group").clicked() {
+                                set_group = Some((group.clone(), true));
+                            }
+                            if ui.small_button("Deselect group").clicked() {
+                                set_group = Some((group.clone(), false));
+                            }
+                        });
+                        row += 1;
+                        continue;
+                    }
+                    SourceListRow::Sources { group, .. } => group,
+                };
+                let end = (row..range.end)
+                    .find(|&r| matches!(rows[r], SourceListRow::Header(_)))
+                    .unwrap_or(range.end);
+                egui::Grid::new(("source_grid", group.as_deref())).show(ui, |ui| {
+                    for source_row in &rows[row..end] {
+                        let SourceListRow::Sources {
+                            first_pos, indices, ..
+                        } = source_row
+                        else {
+                            continue;
+                        };
+                        for (offset, &idx) in indices.iter().enumerate() {
+                            let list_pos = first_pos + offset;
+                            let (source, selected) = &mut sources[idx];
+                            let response =
+                                match previews.and_then(|previews| previews.get(&source.name)) {
+                                    Some(preview) => ui
+                                        .checkbox(
+                                            selected,
+                                            format!(
+                                                "{} {:.0}°/{:.1}h",
+                                                source_list_name(source),
+                                                preview.max_el_deg,
+                                                preview.up_hours
+                                            ),
+                                        )
+                                        .on_hover_text(format!(
+                                            "Peak elevation {:.1}°, {:.1} h above the floor",
+                                            preview.max_el_deg, preview.up_hours
+                                        )),
+                                    None => ui.checkbox(selected, source_list_name(source)),
+                                };
+                            if response.clicked() {
+                                clicked_pos = Some(list_pos);
+                            }
+                            if focus == Some(list_pos) {
+                                ui.painter().rect_stroke(
+                                    response.rect.expand(2.0),
+                                    2.0,
+                                    ui.visuals().selection.stroke,
+                                    egui::StrokeKind::Outside,
+                                );
+                                if focus_moved {
+                                    response.scroll_to_me(None);
+                                }
+                            }
+                        }
+                        ui.end_row();
                     }
+                });
+                row = end;
+            }
+            range
+        });
+        self.source_list_shown_rows = shown.inner;
+        if let Some((group, value)) = set_group {
+            for (source, selected) in &mut self.sources {
+                if source.group == group {
+                    *selected = value;
                 }
             }
-            polar_plot_data.push((
-                source_name.clone(),
-                station_name.clone(),
-                polar_points,
-                hour_marker_points,
-                hour_labels,
-                *station_idx,
-            ));
         }
-        polar_plot_data
+        if clicked_pos.is_some() {
+            self.source_list_focus = clicked_pos;
+        }
     }
 
-    fn output_target_tab(target: OutputTarget) -> AppTab {
-        match target {
-            OutputTarget::UtAzel => AppTab::UptimePlotters,
-            OutputTarget::Polar => AppTab::PolarPlot,
-            OutputTarget::Lst => AppTab::LstPlot,
+    /// Moves the source-list focus with the arrow keys and toggles the focused source with
+    /// space, unless a text field has the keyboard. `listed` holds `self.sources` indices in
+    /// display order. Returns true when the focus moved, so the list can scroll to it.
+    fn handle_source_list_keys(&mut self, ctx: &egui::Context, listed: &[usize]) -> bool {
+        let before = self.source_list_focus;
+        let Some(last) = listed.len().checked_sub(1) else {
+            self.source_list_focus = None;
+            return false;
+        };
+        self.source_list_focus = self.source_list_focus.map(|pos| pos.min(last));
+        if ctx.egui_wants_keyboard_input() {
+            return false;
+        }
+        // Space and Escape are only taken while an entry is focused, so they still reach
+        // buttons and windows otherwise.
+        let none = egui::Modifiers::NONE;
+        let focused = self.source_list_focus.is_some();
+        let (next, previous, toggle, clear) = ctx.input_mut(|input| {
+            (
+                input.consume_key(none, egui::Key::ArrowDown)
+                    | input.consume_key(none, egui::Key::ArrowRight),
+                input.consume_key(none, egui::Key::ArrowUp)
+                    | input.consume_key(none, egui::Key::ArrowLeft),
+                focused && input.consume_key(none, egui::Key::Space),
+                focused && input.consume_key(none, egui::Key::Escape),
+            )
+        });
+        if clear {
+            self.source_list_focus = None;
+        }
+        if next {
+            self.source_list_focus =
+                Some(self.source_list_focus.map_or(0, |pos| (pos + 1).min(last)));
+        }
+        if previous {
+            self.source_list_focus = Some(
+                self.source_list_focus
+                    .map_or(last, |pos| pos.saturating_sub(1)),
+            );
+        }
+        if toggle {
+            if let Some(pos) = self.source_list_focus {
+                let selected = &mut self.sources[listed[pos]].1;
+                *selected = !*selected;
+            }
         }
+        self.source_list_focus != before
     }
 
-    fn output_target_path(target: OutputTarget) -> PathBuf {
-        let mut path = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        match target {
-            OutputTarget::UtAzel => path.push("upt_azel.png"),
-            OutputTarget::Polar => path.push("upt_polar.png"),
-            OutputTarget::Lst => path.push("upt_lst.png"),
+    /// Selects every source above the floor, inside the HA limits and the allowed azimuth
+    /// sectors at the current station right now. Returns how many are up.
+    fn select_visible_now(&mut self) -> Result<usize, String> {
+        let station = self
+            .current_station()
+            .cloned()
+            .ok_or_else(|| "No station selected.".to_string())?;
+        let now = utils::ut1_from_utc(Utc::now(), self.dut1_seconds);
+        let floor = self.effective_floor_deg();
+        let mut count = 0;
+        for (source, selected) in &mut self.sources {
+            let pointing = utils::radec2azalt_with(
+                self.ellipsoid,
+                self.sidereal_mode,
+                self.reference_frame,
+                station.pos,
+                now,
+                source.ra_rad,
+                source.dec_rad,
+            );
+            if pointing.el_deg >= floor
+                && station.ha_allows(pointing.hour_angle)
+                && utils::az_sector_margin(pointing.az_deg, &self.az_sectors) >= 0.0
+            {
+                *selected = true;
+                count += 1;
+            }
         }
-        path
+        Ok(count)
     }
 
-    fn output_target_rect(&self, target: OutputTarget) -> Option<egui::Rect> {
-        match target {
-            OutputTarget::UtAzel => self.uptime_plot_rect,
-            OutputTarget::Polar => self.polar_plot_rect,
-            OutputTarget::Lst => self.lst_plot_rect,
-        }
-    }
-
-    fn start_output_capture(&mut self, ctx: &egui::Context) -> Result<(), String> {
-        if self.plot_data.is_empty() {
-            return Err("No plot data to output. Please run Plot Selected first.".to_string());
-        }
-        if self.station_position().is_none() {
-            return Err("No station selected.".to_string());
-        }
-        if self.output_capture.is_some() {
-            return Err("Output is already running.".to_string());
+    fn run_night_search(&mut self) -> Result<(), String> {
+        let station = self
+            .current_station()
+            .cloned()
+            .ok_or_else(|| "No station selected.".to_string())?;
+        let search = &self.night_search;
+        let source = self
+            .sources
+            .iter()
+            .map(|(source, _)| source)
+            .find(|source| source.name == search.source_name)
+            .ok_or_else(|| "Pick a source to search for.".to_string())?;
+        let start = NaiveDate::parse_from_str(search.start_date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid start date: {}", search.start_date))?;
+        let nights = utils::best_night_dates(
+            &station,
+            source,
+            start,
+            search.days,
+            search.max_sun_el_deg,
+            self.effective_floor_deg(),
+        );
+        if nights.is_empty() {
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "{} is never above the floor at night from {} in {} days.",
+                    search.source_name, station.name, search.days
+                ),
+            );
         }
-
-        let previous_tab = self.selected_tab;
-        self.output_capture = Some(OutputCaptureState {
-            targets: vec![OutputTarget::UtAzel, OutputTarget::Polar, OutputTarget::Lst],
-            index: 0,
-            previous_tab,
-            screenshot_requested: false,
-        });
-        self.uptime_plot_rect = None;
-        self.polar_plot_rect = None;
-        self.lst_plot_rect = None;
-        self.selected_tab = AppTab::UptimePlotters;
-        ctx.request_repaint();
+        self.night_search.result = Some((station.name, nights));
         Ok(())
     }
 
-    fn drive_output_capture(&mut self, ctx: &egui::Context) {
-        let (target, screenshot_requested) = match self.output_capture.as_ref() {
-            Some(state) => match state.targets.get(state.index).copied() {
-                Some(target) => (target, state.screenshot_requested),
-                None => return,
-            },
-            None => return,
-        };
-
-        if screenshot_requested {
-            return;
+    /// Finds the dates on which a source is best placed at night for the current station.
+    fn ui_night_search(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("night_search_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Source:");
+                egui::ComboBox::from_id_salt("night_search_source")
+                    .selected_text(if self.night_search.source_name.is_empty() {
+                        "(pick)"
+                    } else {
+                        &self.night_search.source_name
+                    })
+                    .show_ui(ui, |ui| {
+                        for (source, _) in self.sources.iter().filter(|(_, selected)| *selected) {
+                            ui.selectable_value(
+                                &mut self.night_search.source_name,
+                                source.name.clone(),
+                                &source.name,
+                            );
+                        }
+                    });
+                ui.end_row();
+                ui.label("From (YYYY-MM-DD):");
+                ui.text_edit_singleline(&mut self.night_search.start_date);
+                ui.end_row();
+                ui.label("Days:");
+                ui.add(egui::DragValue::new(&mut self.night_search.days).range(1..=366));
+                ui.end_row();
+                ui.label("Night when Sun below:");
+                ui.add(
+                    egui::DragValue::new(&mut self.night_search.max_sun_el_deg)
+                        .range(-18.0..=0.0)
+                        .speed(0.5)
+                        .suffix("°"),
+                )
+                .on_hover_text("0° sunset, -6° civil, -12° nautical, -18° astronomical twilight");
+                ui.end_row();
+            });
+        if ui.button("Find best nights").clicked() {
+            if let Err(e) = self.run_night_search() {
+                self.log(LogLevel::Error, e);
+            }
         }
-
-        let target_tab = Self::output_target_tab(target);
-        if self.selected_tab != target_tab {
-            self.selected_tab = target_tab;
-            ctx.request_repaint();
+        let Some((station_name, nights)) = &self.night_search.result else {
             return;
-        }
-
-        if self.output_target_rect(target).is_none() {
-            ctx.request_repaint();
+        };
+        if nights.is_empty() {
             return;
         }
-
-        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
-        if let Some(state) = self.output_capture.as_mut() {
-            state.screenshot_requested = true;
+        ui.label(format!("At {}, best first:", station_name));
+        let mut use_date = None;
+        egui::Grid::new("night_search_results")
+            .num_columns(7)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Date");
+                ui.strong("Highest (UT)");
+                ui.strong("El");
+                ui.strong("HA");
+                ui.strong("Sun el");
+                ui.strong("Up in dark");
+                ui.label("");
+                ui.end_row();
+                for night in nights.iter().take(NIGHT_SEARCH_SHOWN) {
+                    ui.label(night.date.format("%Y-%m-%d").to_string());
+                    ui.label(night.best_time.format("%m-%d %H:%M").to_string());
+                    ui.label(format!("{:.1}°", night.el_deg));
+                    ui.label(format!("{:+.1} h", night.hour_angle));
+                    ui.label(format!("{:.0}°", night.sun_el_deg));
+                    ui.label(format!("{:.1} h", night.dark_up_hours));
+                    if ui
+                        .small_button("Use")
+                        .on_hover_text("Set the observation date")
+                        .clicked()
+                    {
+                        use_date = Some(night.date);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(date) = use_date {
+            self.selected_date = date;
+            if !self.plot_data.is_empty() {
+                self.calculate_plots();
+            }
         }
-        ctx.request_repaint();
     }
 
-    fn handle_output_screenshot(&mut self, image: &egui::ColorImage, pixels_per_point: f32) {
-        let (target, screenshot_requested, previous_tab) = match self.output_capture.as_ref() {
-            Some(state) => match state.targets.get(state.index).copied() {
-                Some(target) => (target, state.screenshot_requested, state.previous_tab),
-                None => return,
-            },
-            None => return,
+    fn ui_countdown(&mut self, ui: &mut egui::Ui) {
+        let now = Utc::now();
+        let selected: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(source, _)| source.name.clone())
+            .collect();
+        let station_name = self.current_station().map(|station| station.name.clone());
+        let stale = self.countdown.as_ref().is_none_or(|cache| {
+            Some(&cache.station_name) != station_name.as_ref()
+                || cache.sources != selected
+                || (now - cache.computed_at).num_seconds() >= COUNTDOWN_REFRESH_SECONDS
+                || cache.entries.iter().any(|entry| {
+                    entry.next_rise.is_some_and(|t| t <= now)
+                        || entry.next_set.is_some_and(|t| t <= now)
+                })
+        });
+        if stale {
+            self.countdown = self.compute_countdown(now);
+        }
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_secs(1));
+        let Some(cache) = &self.countdown else {
+            ui.label("No station selected.");
+            return;
         };
-
-        if !screenshot_requested {
+        if cache.entries.is_empty() {
+            ui.label("No sources selected.");
             return;
         }
-
-        let rect = match self.output_target_rect(target) {
-            Some(rect) => rect,
-            None => {
-                self.error_msg = Some("Failed to capture plot area.".to_string());
-                self.selected_tab = previous_tab;
-                self.output_capture = None;
-                return;
+        ui.label(format!(
+            "{} UTC at {}",
+            now.format("%Y-%m-%d %H:%M:%S"),
+            cache.station_name
+        ));
+        // The event that changes each source's state next: set while up, rise while down.
+        let next_event = |entry: &CountdownEntry| {
+            if entry.up_now {
+                entry.next_set.map(|t| ("sets", t))
+            } else {
+                entry.next_rise.map(|t| ("rises", t))
             }
         };
+        let mut rows: Vec<&CountdownEntry> = cache.entries.iter().collect();
+        rows.sort_by_key(|entry| next_event(entry).map_or(i64::MAX, |(_, t)| t.timestamp()));
+        egui::Grid::new("countdown_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Source");
+                ui.strong("Now");
+                ui.strong("Next event (UT)");
+                ui.strong("In");
+                ui.end_row();
+                for entry in rows {
+                    ui.label(&entry.source_name);
+                    ui.label(if entry.up_now { "up" } else { "down" });
+                    match next_event(entry) {
+                        Some((kind, time)) => {
+                            ui.label(format!("{} {}", kind, time.format("%m-%d %H:%M:%S")));
+                            let seconds = (time - now).num_seconds().max(0);
+                            ui.monospace(format!(
+                                "{:02}:{:02}:{:02}",
+                                seconds / 3600,
+                                (seconds % 3600) / 60,
+                                seconds % 60
+                            ));
+                        }
+                        None if entry.up_now => {
+                            ui.label(format!(
+                                "no set within {} h (circumpolar)",
+                                COUNTDOWN_HORIZON_HOURS
+                            ));
+                            ui.label("-");
+                        }
+                        None => {
+                            ui.label(format!(
+                                "no rise within {} h (never up)",
+                                COUNTDOWN_HORIZON_HOURS
+                            ));
+                            ui.label("-");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
 
-        let output_path = Self::output_target_path(target);
-        if let Err(e) = save_plot_region_png(image, rect, pixels_per_point, &output_path) {
-            self.error_msg = Some(e);
-            self.selected_tab = previous_tab;
-            self.output_capture = None;
+    fn ui_mosaic(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Mosaic pattern").show(ui, |ui| {
+            let mut changed = ui
+                .checkbox(&mut self.mosaic.enabled, "Plot a mosaic envelope")
+                .on_hover_text("One track per station with the lowest elevation across the pattern")
+                .changed();
+            egui::Grid::new("mosaic_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Centre source:");
+                    let previous = self.mosaic.center.clone();
+                    egui::ComboBox::from_id_salt("mosaic_center")
+                        .selected_text(self.mosaic.center.as_deref().unwrap_or("(none)"))
+                        .show_ui(ui, |ui| {
+                            for (source, _) in &self.sources {
+                                ui.selectable_value(
+                                    &mut self.mosaic.center,
+                                    Some(source.name.clone()),
+                                    &source.name,
+                                );
+                            }
+                        });
+                    changed |= self.mosaic.center != previous;
+                    ui.end_row();
+                    ui.label("Grid (columns x rows):");
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.mosaic.columns).range(1..=9))
+                            .changed();
+                        ui.label("x");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.mosaic.rows).range(1..=9))
+                            .changed();
+                    });
+                    ui.end_row();
+                    ui.label("Spacing:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.mosaic.spacing_arcmin)
+                                .range(0.1..=600.0)
+                                .speed(0.5)
+                                .suffix("′"),
+                        )
+                        .changed();
+                    ui.end_row();
+                });
+            ui.label(format!(
+                "{} pointings spanning {:.1}′ x {:.1}′",
+                self.mosaic.columns * self.mosaic.rows,
+                (self.mosaic.columns - 1) as f64 * self.mosaic.spacing_arcmin,
+                (self.mosaic.rows - 1) as f64 * self.mosaic.spacing_arcmin
+            ));
+            if changed && !self.plot_data.is_empty() {
+                self.calculate_plots();
+            }
+        });
+    }
+
+    fn show_source_editor_window(&mut self, ctx: &egui::Context) {
+        let Some(editor) = self.source_editor.as_mut() else {
             return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut pick: Option<SourceEditor> = None;
+        egui::Window::new("Edit Source")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("source_editor_pick")
+                    .selected_text(editor.original_name.as_deref().unwrap_or("(new source)"))
+                    .show_ui(ui, |ui| {
+                        if ui
+                            .selectable_label(editor.original_name.is_none(), "(new source)")
+                            .clicked()
+                        {
+                            pick = Some(SourceEditor::new_source());
+                        }
+                        for (source, _) in self.sources.iter().filter(|(s, _)| !self.adhoc_sources.contains(&s.name)) {
+                            let current =
+                                editor.original_name.as_deref() == Some(source.name.as_str());
+                            if ui.selectable_label(current, &source.name).clicked() {
+                                pick = Some(SourceEditor::from_source(source));
+                            }
+                        }
+                    });
+                egui::Grid::new("source_editor_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut editor.name);
+                        ui.end_row();
+                        ui.label("RA (HH MM SS.sss):");
+                        ui.text_edit_singleline(&mut editor.ra);
+                        ui.end_row();
+                        ui.label("Dec (±DD MM SS.ss):");
+                        ui.text_edit_singleline(&mut editor.dec);
+                        ui.end_row();
+                        ui.label("Epoch:");
+                        ui.text_edit_singleline(&mut editor.epoch);
+                        ui.end_row();
+                        ui.label("Group (optional):");
+                        ui.text_edit_singleline(&mut editor.group);
+                        ui.end_row();
+                        ui.label("Priority:");
+                        ui.add(egui::DragValue::new(&mut editor.priority).range(-9..=9))
+                            .on_hover_text("Higher priorities are favoured in the visibility ranking and the suggested order.");
+                        ui.end_row();
+                    });
+                save = ui.button("Save to source file").clicked();
+            });
+        if let Some(picked) = pick {
+            *editor = picked;
         }
-
-        let mut next_target: Option<OutputTarget> = None;
-        let mut done = false;
-        if let Some(state) = self.output_capture.as_mut() {
-            state.index += 1;
-            state.screenshot_requested = false;
-            if state.index >= state.targets.len() {
-                done = true;
-            } else {
-                next_target = state.targets.get(state.index).copied();
+        if save {
+            let original_name = editor.original_name.clone();
+            let result = editor
+                .to_source()
+                .and_then(|source| self.save_source_edit(original_name.as_deref(), &source));
+            match result {
+                Ok(()) => {
+                    self.log(LogLevel::Info, format!("Saved {}", self.source_file_path));
+                    self.source_editor = None;
+                }
+                Err(e) => self.log(LogLevel::Error, e),
             }
+        } else if !open {
+            self.source_editor = None;
         }
+    }
 
-        if done {
-            self.selected_tab = previous_tab;
-            self.output_capture = None;
-            self.error_msg =
-                Some("Output complete: upt_azel.png, upt_polar.png, upt_lst.png".to_string());
-        } else if let Some(next) = next_target {
-            match next {
-                OutputTarget::UtAzel => self.uptime_plot_rect = None,
-                OutputTarget::Polar => self.polar_plot_rect = None,
-                OutputTarget::Lst => self.lst_plot_rect = None,
+    /// Reads `key value` lines from the preferences file; unknown keys and bad values are
+    /// ignored so older or hand-edited files still load.
+    fn load_preferences(&mut self) {
+        let content = self
+            .preferences_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let mut migrated_keys = vec!["sidereal_mode", "rise_set_refracted"];
+        for line in content.lines() {
+            let Some((key, value)) = line.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            let value = value.trim();
+            migrated_keys.retain(|migrated| *migrated != key);
+            match key {
+                "line_width" => {
+                    if let Ok(width) = value.parse::<f32>() {
+                        self.line_width = width.clamp(0.5, 8.0);
+                    }
+                }
+                "polar_point_radius" => {
+                    if let Ok(radius) = value.parse::<f32>() {
+                        self.polar_point_radius = radius.clamp(0.5, 10.0);
+                    }
+                }
+                "polar_label_size" => {
+                    if let Ok(size) = value.parse::<f32>() {
+                        self.polar_label_size = size.clamp(8.0, 32.0);
+                    }
+                }
+                "time_format" => match value {
+                    "12h" => self.time_display.twelve_hour = true,
+                    "24h" => self.time_display.twelve_hour = false,
+                    _ => {}
+                },
+                "time_seconds" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.time_display.seconds = enabled;
+                    }
+                }
+                "elevation_colors" => {
+                    if let Some(color_map) = ElevationColorMap::from_key(value) {
+                        self.elevation_color_map = color_map;
+                    }
+                }
+                "grid_alignment" => {
+                    if let Some(alignment) = GridAlignment::from_key(value) {
+                        self.grid_alignment = alignment;
+                    }
+                }
+                "zenith_distance_axis" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.zenith_distance_axis = enabled;
+                    }
+                }
+                "polar_below_floor_color" => {
+                    if let Ok(color) = egui::Color32::from_hex(value) {
+                        self.polar_below_floor_color = color;
+                    }
+                }
+                "polar_horizon_color" => {
+                    if let Ok(color) = egui::Color32::from_hex(value) {
+                        self.polar_horizon_color = color;
+                    }
+                }
+                "ellipsoid" => {
+                    if let Some(ellipsoid) = utils::Ellipsoid::ALL
+                        .into_iter()
+                        .find(|ellipsoid| ellipsoid.label() == value)
+                    {
+                        self.ellipsoid = ellipsoid;
+                    }
+                }
+                "sidereal_mode" => {
+                    self.set_sidereal_key(value);
+                }
+                "reference_frame" => {
+                    if let Some(frame) = utils::ReferenceFrame::from_key(value) {
+                        self.reference_frame = frame;
+                    }
+                }
+                "rise_set_refracted" => {
+                    if let Ok(refracted) = value.parse::<bool>() {
+                        self.rise_set_refracted = refracted;
+                    }
+                }
+                "plot_log_enabled" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.plot_log_enabled = enabled;
+                    }
+                }
+                "plot_log_path" => self.plot_log_path = value.to_string(),
+                "plot_log_format" => {
+                    if let Some(format) = PlotLogFormat::from_key(value) {
+                        self.plot_log_format = format;
+                    }
+                }
+                "merge_gap_min" => {
+                    if let Ok(minutes) = value.parse::<f64>() {
+                        self.merge_gap_min = minutes.clamp(0.0, MAX_MERGE_GAP_MIN);
+                    }
+                }
+                "image_size" => {
+                    if value == "window" {
+                        self.image_size = None;
+                    } else if let Ok(size) = parse_image_size(value) {
+                        self.image_size = Some(size);
+                    }
+                }
+                "az_plot_fraction" => {
+                    if let Ok(fraction) = value.parse::<f32>() {
+                        self.az_plot_fraction =
+                            fraction.clamp(MIN_PLOT_FRACTION, 1.0 - MIN_PLOT_FRACTION);
+                    }
+                }
+                "smooth_curves" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.smooth_curves = enabled;
+                    }
+                }
+                "csv_delimiter" => {
+                    if let Some(delimiter) = CsvDelimiter::from_key(value) {
+                        self.csv_options.delimiter = delimiter;
+                    }
+                }
+                "csv_decimals" => {
+                    let decimals: Vec<usize> = value
+                        .split(',')
+                        .filter_map(|v| v.trim().parse().ok())
+                        .collect();
+                    if let [time, az, el] = decimals[..] {
+                        self.csv_options.time_decimals = time.min(MAX_CSV_DECIMALS);
+                        self.csv_options.az_decimals = az.min(MAX_CSV_DECIMALS);
+                        self.csv_options.el_decimals = el.min(MAX_CSV_DECIMALS);
+                    }
+                }
+                "auto_restore_session" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.auto_restore_session = enabled;
+                    }
+                }
+                "auto_reload_catalogs" => {
+                    if let Ok(enabled) = value.parse::<bool>() {
+                        self.auto_reload_catalogs = enabled;
+                    }
+                }
+                "polar_orientation" => {
+                    if let Some(orientation) = PolarOrientation::from_key(value) {
+                        self.polar_orientation = orientation;
+                    }
+                }
+                "observing_frequency_ghz" => {
+                    if let Ok(frequency) = value.parse::<f64>() {
+                        self.frequency_enabled = frequency > 0.0;
+                        if frequency > 0.0 {
+                            self.observing_frequency_ghz = frequency;
+                        }
+                    }
+                }
+                _ => {}
             }
-            self.selected_tab = Self::output_target_tab(next);
+        }
+        if !migrated_keys.is_empty() {
+            self.migrate_session_settings(&migrated_keys);
         }
     }
 
-    fn show_calendar_window(&mut self, ctx: &egui::Context) {
-        if self.show_calendar {
-            let previous_date = self.selected_date;
-            let mut open = true;
-            egui::Window::new("Select Date")
-                .open(&mut open)
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    if calendar_ui(ui, &mut self.selected_date) {
-                        self.show_calendar = false;
+    /// Takes the settings in `keys` from the session file, which held them before they moved
+    /// to the preferences, and saves them there so the session is only read for them once.
+    fn migrate_session_settings(&mut self, keys: &[&str]) {
+        let Some(content) = self
+            .session_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return;
+        };
+        let mut migrated = false;
+        for line in content.lines() {
+            let Some((key, value)) = line.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            if !keys.contains(&key) {
+                continue;
+            }
+            let value = value.trim();
+            match key {
+                "sidereal_mode" => {
+                    migrated |= self.set_sidereal_key(value);
+                }
+                "rise_set_refracted" => {
+                    if let Ok(refracted) = value.parse::<bool>() {
+                        self.rise_set_refracted = refracted;
+                        migrated = true;
                     }
-                });
-            if self.selected_tab == AppTab::SkdTable && self.selected_date != previous_date {
-                self.shift_skd_rows_by_days((self.selected_date - previous_date).num_days());
-                self.new_skd_start_date = self.selected_date;
+                }
+                _ => {}
             }
-            if !open {
-                self.show_calendar = false;
+        }
+        if migrated {
+            if let Err(e) = self.save_preferences() {
+                self.log(LogLevel::Warn, e);
             }
         }
     }
 
-    fn show_new_skd_calendar_window(&mut self, ctx: &egui::Context) {
-        if self.show_new_skd_calendar {
-            let mut open = true;
-            egui::Window::new("Select Date for New Row")
-                .open(&mut open)
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    if calendar_ui(ui, &mut self.new_skd_start_date) {
-                        self.show_new_skd_calendar = false;
-                    }
-                });
-            if !open {
-                self.show_new_skd_calendar = false;
-            }
+    /// Sets the sidereal mode from its key. Older files stored the ICRS frame as the
+    /// `icrs` sidereal mode; that now selects the ICRS frame with apparent sidereal time.
+    fn set_sidereal_key(&mut self, value: &str) -> bool {
+        if value == utils::ReferenceFrame::Icrs.key() {
+            self.reference_frame = utils::ReferenceFrame::Icrs;
+            self.sidereal_mode = utils::SiderealMode::Apparent;
+            return true;
         }
+        utils::SiderealMode::from_key(value)
+            .map(|mode| self.sidereal_mode = mode)
+            .is_some()
     }
 
-    #[allow(dead_code)]
-    fn save_plot_data_to_csv(&self) -> Result<(), String> {
-        if self.plot_data.is_empty() {
-            return Err("No plot data to save.".to_string());
-        }
+    /// The sidereal time the plots use: the chosen one, or apparent under the ICRS frame.
+    fn effective_sidereal_mode(&self) -> utils::SiderealMode {
+        self.reference_frame.sidereal_mode(self.sidereal_mode)
+    }
 
-        let mut csv_content = String::new();
-        let mut header = "Time".to_string();
-        let mut time_points: Vec<f64> = Vec::new();
+    fn save_preferences(&self) -> Result<(), String> {
+        let Some(path) = &self.preferences_path else {
+            return Ok(());
+        };
+        let content = format!(
+            "line_width {}\npolar_point_radius {}\nsmooth_curves {}\ntime_format {}\ntime_seconds {}\npolar_orientation {}\nobserving_frequency_ghz {}\nauto_reload_catalogs {}\nauto_restore_session {}\ncsv_delimiter {}\ncsv_decimals {},{},{}\naz_plot_fraction {}\nimage_size {}\nelevation_colors {}\nzenith_distance_axis {}\ngrid_alignment {}\npolar_below_floor_color {}\npolar_horizon_color {}\nellipsoid {}\nsidereal_mode {}\nreference_frame {}\nrise_set_refracted {}\npolar_label_size {}\nmerge_gap_min {}\nplot_log_enabled {}\nplot_log_path {}\nplot_log_format {}\n",
+            self.line_width,
+            self.polar_point_radius,
+            self.smooth_curves,
+            if self.time_display.twelve_hour { "12h" } else { "24h" },
+            self.time_display.seconds,
+            self.polar_orientation.key(),
+            self.observing_frequency().unwrap_or(0.0),
+            self.auto_reload_catalogs,
+            self.auto_restore_session,
+            self.csv_options.delimiter.key(),
+            self.csv_options.time_decimals,
+            self.csv_options.az_decimals,
+            self.csv_options.el_decimals,
+            self.az_plot_fraction,
+            self.image_size
+                .map(|[width, height]| format!("{}x{}", width, height))
+                .unwrap_or_else(|| "window".to_string()),
+            self.elevation_color_map.key(),
+            self.zenith_distance_axis,
+            self.grid_alignment.key(),
+            self.polar_below_floor_color.to_hex(),
+            self.polar_horizon_color.to_hex(),
+            self.ellipsoid.label(),
+            self.sidereal_mode.key(),
+            self.reference_frame.key(),
+            self.rise_set_refracted,
+            self.polar_label_size,
+            self.merge_gap_min,
+            self.plot_log_enabled,
+            self.plot_log_path,
+            self.plot_log_format.key()
+        );
+        fs::write(path, content).map_err(|e| format!("Failed to save preferences: {}", e))
+    }
 
-        for (i, (source_name, station_name, az_points, _, _)) in self.plot_data.iter().enumerate() {
-            let label = format!("{}_{}", source_name, station_name);
-            header.push_str(&format!(",{},{}", label, label));
-            if i == 0 {
-                time_points = az_points.iter().map(|p| p[0]).collect();
-            }
-        }
-        csv_content.push_str(&header);
-        csv_content.push_str("\n");
+    /// Writes the catalogue paths, selection, date and observation settings to the session
+    /// file in the same `key value` format as the preferences.
+    fn save_session(&self) -> Result<(), String> {
+        let Some(path) = &self.session_path else {
+            return Ok(());
+        };
+        let selected = |names: Vec<&str>| names.join(" ");
+        let mut hidden_series: Vec<&str> = self.hidden_series.iter().map(String::as_str).collect();
+        hidden_series.sort_unstable();
+        let mut source_markers: Vec<String> = self
+            .source_markers
+            .iter()
+            .map(|(name, marker)| format!("{}={}", name, marker.key()))
+            .collect();
+        source_markers.sort_unstable();
+        let stations = selected(
+            self.stations
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(station, _)| station.name.as_str())
+                .collect(),
+        );
+        let sources = selected(
+            self.sources
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(source, _)| source.name.as_str())
+                .collect(),
+        );
+        let content = format!(
+            concat!(
+                "station_file {}\n",
+                "source_file {}\n",
+                "antenna_file {}\n",
+                "selected_stations {}\n",
+                "selected_sources {}\n",
+                "date {}\n",
+                "elevation_floor_deg {}\n",
+                "az_sectors {}\n",
+                "opacity_floor_enabled {}\n",
+                "tau0 {}\n",
+                "max_line_opacity {}\n",
+                "session_enabled {}\n",
+                "session_start {}\n",
+                "session_end {}\n",
+                "sun_avoid_deg {}\n",
+                "moon_avoid_deg {}\n",
+                "max_az_rate_deg_per_sec {}\n",
+                "beam_arcmin {}\n",
+                "dut1_seconds {}\n",
+                "overnight_hours {}\n",
+                "hidden_series {}\n",
+                "source_markers {}\n",
+                "plotted {}\n"
+            ),
+            self.station_file_path,
+            self.source_file_path,
+            self.antenna_file_path,
+            stations,
+            sources,
+            self.selected_date.format("%Y-%m-%d"),
+            self.elevation_floor_deg,
+            self.az_sectors_text.trim(),
+            self.opacity_floor_enabled,
+            self.tau0,
+            self.max_line_opacity,
+            self.session_enabled,
+            self.session_start_time,
+            self.session_end_time,
+            self.sun_avoid_deg,
+            self.moon_avoid_deg,
+            self.max_az_rate_deg_per_sec,
+            self.beam_arcmin,
+            self.dut1_seconds,
+            self.overnight_hours,
+            hidden_series.join(" "),
+            source_markers.join(" "),
+            !self.plot_data.is_empty()
+        );
+        fs::write(path, content).map_err(|e| format!("Failed to save session: {}", e))
+    }
 
-        for &time in &time_points {
-            let mut row = format!("{:.2}", time);
-            for (_, _, az_points, el_points, _) in &self.plot_data {
-                let az_val = az_points
-                    .iter()
-                    .find(|p| (p[0] - time).abs() < 1e-6)
-                    .map_or("".to_string(), |p| format!("{:.1}", p[1]));
-                let el_val = el_points
-                    .iter()
-                    .find(|p| (p[0] - time).abs() < 1e-6)
-                    .map_or("".to_string(), |p| format!("{:.1}", p[1]));
-                row.push_str(&format!(",{},{}", az_val, el_val));
+    /// Applies the saved session. Catalogue paths given on the command line
+    /// (`catalog_paths_given` = station, source) take precedence over the saved ones; like the
+    /// preferences, unknown keys and bad values are ignored.
+    fn restore_session(&mut self, catalog_paths_given: (bool, bool)) {
+        let Some(content) = self
+            .session_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return;
+        };
+        let values: HashMap<&str, &str> = content
+            .lines()
+            .filter_map(|line| line.trim().split_once(char::is_whitespace))
+            .map(|(key, value)| (key, value.trim()))
+            .collect();
+        let flag = |key: &str| values.get(key).and_then(|v| v.parse::<bool>().ok());
+        let number = |key: &str| values.get(key).and_then(|v| v.parse::<f64>().ok());
+
+        if let (false, Some(path)) = (catalog_paths_given.0, values.get("station_file")) {
+            if *path != self.station_file_path {
+                self.station_file_path = path.to_string();
+                if let Err(e) = self.load_stations() {
+                    self.log(LogLevel::Warn, format!("Last session: {}", e));
+                }
             }
-            csv_content.push_str(&row);
-            csv_content.push_str("\n");
         }
-
-        let mut path = runtime_app_dir();
-        path.push("plot_data.csv");
-        fs::write(&path, csv_content).map_err(|e| format!("Failed to save CSV file: {}", e))?;
-        Ok(())
-    }
-
-    fn load_drg_file(&mut self) -> Result<(), String> {
-        let path = output_drg_path(&self.input_drg_file_path)?;
-        let content = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read DRG file {}: {}", path.display(), e))?;
-
-        if let Some(obs_code) = parse_exper_code(&content) {
-            self.obs_code = obs_code;
+        if let (false, Some(path)) = (catalog_paths_given.1, values.get("source_file")) {
+            if *path != self.source_file_path {
+                self.source_file_path = path.to_string();
+                if let Err(e) = self.load_sources() {
+                    self.log(LogLevel::Warn, format!("Last session: {}", e));
+                }
+            }
         }
-        if let Some(pi_name) = parse_pi_name(&content) {
-            self.pi_name = pi_name;
+        if let Some(path) = values.get("antenna_file") {
+            if *path != self.antenna_file_path {
+                self.antenna_file_path = path.to_string();
+                if let Err(e) = self.load_antennas() {
+                    self.log(LogLevel::Warn, format!("Last session: {}", e));
+                }
+            }
         }
-
-        let mut sources = Vec::new();
-        for line in section_lines(&content, "$SOURCES")? {
-            let trimmed = line.trim();
-            if trimmed.is_empty() || trimmed.starts_with('*') {
-                continue;
+        if let Some(names) = values.get("selected_stations") {
+            let names: HashSet<&str> = names.split_whitespace().collect();
+            if self
+                .stations
+                .iter()
+                .any(|(station, _)| names.contains(station.name.as_str()))
+            {
+                for (station, selected) in &mut self.stations {
+                    *selected = names.contains(station.name.as_str());
+                }
             }
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if parts.len() >= 9 {
-                sources.push((parse_source_tokens(&parts, 0, 2, trimmed)?, false));
+        }
+        if let Some(names) = values.get("selected_sources") {
+            let names: HashSet<&str> = names.split_whitespace().collect();
+            for (source, selected) in &mut self.sources {
+                *selected = names.contains(source.name.as_str());
             }
         }
-        if !sources.is_empty() {
-            let mut added_source = false;
-            for (drg_source, _) in sources {
-                if !self
-                    .sources
+        if let Some(date) = values
+            .get("date")
+            .and_then(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok())
+        {
+            self.selected_date = date;
+        }
+        if let Some(floor) = number("elevation_floor_deg") {
+            self.elevation_floor_deg = floor.clamp(0.0, 60.0);
+        }
+        if let Some(enabled) = flag("opacity_floor_enabled") {
+            self.opacity_floor_enabled = enabled;
+        }
+        if let Some(tau0) = number("tau0") {
+            self.tau0 = tau0.clamp(0.001, 5.0);
+        }
+        if let Some(limit) = number("max_line_opacity") {
+            self.max_line_opacity = limit.clamp(0.01, 10.0);
+        }
+        if let Some(sectors) = values
+            .get("az_sectors")
+            .and_then(|text| utils::parse_az_sectors(text).ok())
+        {
+            self.az_sectors_text = values["az_sectors"].to_string();
+            self.az_sectors = sectors;
+        }
+        if let Some(enabled) = flag("session_enabled") {
+            self.session_enabled = enabled;
+        }
+        if let Some(start) = values.get("session_start") {
+            self.session_start_time = start.to_string();
+        }
+        if let Some(end) = values.get("session_end") {
+            self.session_end_time = end.to_string();
+        }
+        if let Some(limit) = number("sun_avoid_deg") {
+            self.sun_avoid_deg = limit.clamp(0.0, 90.0);
+        }
+        if let Some(limit) = number("moon_avoid_deg") {
+            self.moon_avoid_deg = limit.clamp(0.0, 90.0);
+        }
+        if let Some(rate) = number("max_az_rate_deg_per_sec") {
+            self.max_az_rate_deg_per_sec = rate;
+        }
+        if let Some(beam) = number("beam_arcmin") {
+            self.beam_arcmin = beam.clamp(0.01, 600.0);
+        }
+        if let Some(dut1) = number("dut1_seconds") {
+            self.dut1_seconds = dut1.clamp(-MAX_DUT1_SECONDS, MAX_DUT1_SECONDS);
+        }
+        if let Some(hours) = number("overnight_hours") {
+            self.overnight_hours = hours.clamp(0.0, MAX_OVERNIGHT_HOURS);
+            self.time_view = self.full_time_view();
+        }
+        if let Some(names) = values.get("hidden_series") {
+            self.hidden_series = names.split_whitespace().map(str::to_string).collect();
+        }
+        if let Some(markers) = values.get("source_markers") {
+            self.source_markers = markers
+                .split_whitespace()
+                .filter_map(|entry| {
+                    let (name, key) = entry.rsplit_once('=')?;
+                    Some((name.to_string(), SourceMarker::from_key(key)?))
+                })
+                .collect();
+        }
+        if flag("plotted") == Some(true) {
+            self.calculate_plots();
+        }
+    }
+
+    /// Appends one line for the current plot to the plot log: a CSV row (with a header when the
+    /// file is new) or the resolved configuration as a single JSON line.
+    fn append_plot_log(&self) -> Result<(), String> {
+        use std::io::Write as _;
+        let path = self.plot_log_path.trim();
+        if path.is_empty() {
+            return Err("Set a plot log file in Preferences.".to_string());
+        }
+        let logged_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let line = match self.plot_log_format {
+            PlotLogFormat::Jsonl => {
+                // `json_string` escapes newlines, so joining the pretty-printed lines is safe.
+                let config = self
+                    .effective_config_json()
+                    .lines()
+                    .map(str::trim)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!(
+                    "{{\"logged_at\": {}, \"config\": {}}}\n",
+                    json_string(&logged_at),
+                    config
+                )
+            }
+            PlotLogFormat::Csv => {
+                let names = |names: Vec<&str>| names.join(" ");
+                let fields = [
+                    logged_at,
+                    self.selected_date.format("%Y-%m-%d").to_string(),
+                    names(
+                        self.stations
+                            .iter()
+                            .filter(|(_, selected)| *selected)
+                            .map(|(station, _)| station.name.as_str())
+                            .collect(),
+                    ),
+                    names(
+                        self.sources
+                            .iter()
+                            .filter(|(_, selected)| *selected)
+                            .map(|(source, _)| source.name.as_str())
+                            .collect(),
+                    ),
+                    self.elevation_floor_deg.to_string(),
+                    self.effective_floor_deg().to_string(),
+                    if self.session_enabled {
+                        format!("{}-{}", self.session_start_time, self.session_end_time)
+                    } else {
+                        String::new()
+                    },
+                    self.observing_frequency()
+                        .map_or(String::new(), |f| f.to_string()),
+                    self.station_file_path.clone(),
+                    self.source_file_path.clone(),
+                ];
+                let row = fields
                     .iter()
-                    .any(|(source, _)| source.name == drg_source.name)
-                {
-                    self.sources.push((drg_source, false));
-                    added_source = true;
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let is_new = fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+                if is_new {
+                    format!("{}\n{}\n", PLOT_LOG_CSV_HEADER, row)
+                } else {
+                    format!("{}\n", row)
                 }
             }
-            if added_source {
-                self.clear_plot_data();
-                self.mark_skd_status_dirty();
-            }
-            self.interleave_target_index = self.interleave_target_index.min(self.sources.len() - 1);
-            self.interleave_cal_index = self.interleave_cal_index.min(self.sources.len() - 1);
-            self.new_skd_source_index = self.new_skd_source_index.min(self.sources.len() - 1);
-            self.five_point_cal_index = self.five_point_cal_index.min(self.sources.len() - 1);
-        }
+        };
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("Failed to append to plot log {}: {}", path, e))
+    }
 
-        let mut rows = Vec::new();
-        for line in section_lines(&content, "$SKED")? {
-            let trimmed = line.trim();
-            if trimmed.is_empty() || trimmed.starts_with('*') {
+    /// The resolved run settings as a JSON object, for bug reports and reproducing a plot.
+    fn effective_config_json(&self) -> String {
+        let names = |names: Vec<&str>| {
+            names
+                .into_iter()
+                .map(json_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let stations = names(
+            self.stations
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(station, _)| station.name.as_str())
+                .collect(),
+        );
+        let sources = names(
+            self.sources
+                .iter()
+                .filter(|(_, selected)| *selected)
+                .map(|(source, _)| source.name.as_str())
+                .collect(),
+        );
+        let session = if self.session_enabled {
+            format!(
+                "{{\"start\": {}, \"end\": {}}}",
+                json_string(&self.session_start_time),
+                json_string(&self.session_end_time)
+            )
+        } else {
+            "null".to_string()
+        };
+        let frequency = self
+            .observing_frequency()
+            .map_or("null".to_string(), |f| f.to_string());
+        format!(
+            concat!(
+                "{{\n",
+                "  \"version\": {},\n",
+                "  \"station_file\": {},\n",
+                "  \"source_file\": {},\n",
+                "  \"antenna_file\": {},\n",
+                "  \"selected_stations\": [{}],\n",
+                "  \"selected_sources\": [{}],\n",
+                "  \"date\": {},\n",
+                "  \"sample_interval_min\": 1,\n",
+                "  \"overnight_hours\": {},\n",
+                "  \"session\": {},\n",
+                "  \"elevation_floor_deg\": {},\n",
+                "  \"opacity_floor\": {},\n",
+                "  \"effective_floor_deg\": {},\n",
+                "  \"az_sectors_deg\": [{}],\n",
+                "  \"sun_avoid_deg\": {},\n",
+                "  \"moon_avoid_deg\": {},\n",
+                "  \"max_az_rate_deg_per_sec\": {},\n",
+                "  \"observing_frequency_ghz\": {},\n",
+                "  \"dut1_seconds\": {},\n",
+                "  \"sidereal_time\": {},\n",
+                "  \"coordinate_frame\": {},\n",
+                "  \"ellipsoid\": {},\n",
+                "  \"azimuth_convention\": \"N=0 E=90\",\n",
+                "  \"polar_orientation\": {}\n",
+                "}}"
+            ),
+            json_string(env!("CARGO_PKG_VERSION")),
+            json_string(&self.station_file_path),
+            json_string(&self.source_file_path),
+            json_string(&self.antenna_file_path),
+            stations,
+            sources,
+            json_string(&self.selected_date.format("%Y-%m-%d").to_string()),
+            self.overnight_hours,
+            session,
+            self.elevation_floor_deg,
+            if self.opacity_floor_enabled {
+                format!(
+                    "{{\"tau0\": {}, \"max_line_opacity\": {}}}",
+                    self.tau0, self.max_line_opacity
+                )
+            } else {
+                "null".to_string()
+            },
+            self.effective_floor_deg(),
+            self.az_sectors
+                .iter()
+                .map(|(min, max)| format!("[{}, {}]", min, max.rem_euclid(360.0)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.sun_avoid_deg,
+            self.moon_avoid_deg,
+            self.max_az_rate_deg_per_sec,
+            frequency,
+            self.dut1_seconds,
+            json_string(self.effective_sidereal_mode().key()),
+            json_string(self.reference_frame.key()),
+            json_string(self.ellipsoid.label()),
+            json_string(self.polar_orientation.key()),
+        )
+    }
+
+    fn load_antennas(&mut self) -> Result<(), String> {
+        let content = fs::read_to_string(&self.antenna_file_path)
+            .map_err(|e| format!("Failed to read antenna.sch: {}", e))?;
+
+        let mut antennas = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('*') || line.starts_with("LTRCODE") {
                 continue;
             }
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-            if let Some(start_idx) = parts
-                .iter()
-                .position(|part| part.len() == 11 && part.chars().all(|c| c.is_ascii_digit()))
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 15 {
+                continue;
+            }
+            let parsed = (
+                parts[2].parse::<f64>(),
+                parts[3].parse::<f64>(),
+                parts[4].parse::<f64>(),
+                parts[7].parse::<f64>(),
+                parts[9].parse::<f64>(),
+                parts[10].parse::<f64>(),
+                parts[11].parse::<f64>(),
+                parts[13].parse::<f64>(),
+                parts[14].parse::<f64>(),
+            );
+            if let (
+                Ok(x),
+                Ok(y),
+                Ok(z),
+                Ok(az_rate),
+                Ok(az_min),
+                Ok(az_max),
+                Ok(el_rate),
+                Ok(el_min),
+                Ok(el_max),
+            ) = parsed
             {
-                let (start_date, start_time) = parse_drg_timestamp(parts[start_idx])?;
-                let duration_sec = parts
-                    .get(start_idx + 1)
-                    .and_then(|part| part.parse::<u32>().ok())
-                    .ok_or_else(|| format!("Invalid SKED duration: {}", trimmed))?;
-                let az_offset_deg = parts
-                    .get(start_idx + 2)
-                    .and_then(|part| part.parse::<f64>().ok())
-                    .unwrap_or(0.0);
-                let el_offset_deg = parts
-                    .get(start_idx + 3)
-                    .and_then(|part| part.parse::<f64>().ok())
-                    .unwrap_or(0.0);
-                let ra_offset_deg = parts
-                    .get(start_idx + 4)
-                    .and_then(|part| part.parse::<f64>().ok())
-                    .unwrap_or(0.0);
-                let dec_offset_deg = parts
-                    .get(start_idx + 5)
-                    .and_then(|part| part.parse::<f64>().ok())
-                    .unwrap_or(0.0);
-                rows.push(SkdRow {
-                    source_name: parts[0].to_string(),
-                    start_date,
-                    start_time,
-                    duration_sec,
-                    az_offset_deg,
-                    el_offset_deg,
-                    ra_offset_deg,
-                    dec_offset_deg,
-                    include_station_offsets: false,
+                antennas.push(Antenna {
+                    code: parts[0].to_string(),
+                    name: parts[1].to_string(),
+                    pos: [x, y, z],
+                    az_rate_deg_per_min: az_rate,
+                    az_min_deg: az_min,
+                    az_max_deg: az_max,
+                    el_rate_deg_per_min: el_rate,
+                    el_min_deg: el_min,
+                    el_max_deg: el_max,
                 });
             }
         }
-        self.skd_rows = rows;
-        self.sort_skd_rows_by_start_time();
-        if let Some(first_row) = self.skd_rows.first() {
-            self.selected_date = first_row.start_date;
+
+        if antennas.is_empty() {
+            return Err("No antennas loaded from antenna.sch.".to_string());
         }
+        self.antennas = antennas;
+        self.selected_antenna = self
+            .antennas
+            .iter()
+            .position(|antenna| antenna.name == "YAMAGU32")
+            .unwrap_or(0);
+        self.selected_antenna_2 = self
+            .antennas
+            .iter()
+            .position(|antenna| antenna.name == "YAMAGU34")
+            .unwrap_or_else(|| {
+                if self.antennas.len() > 1 {
+                    (self.selected_antenna + 1) % self.antennas.len()
+                } else {
+                    self.selected_antenna
+                }
+            });
+        self.mark_skd_status_dirty();
         Ok(())
     }
 
-    fn output_directory_from_input_drg(&self) -> Option<PathBuf> {
-        let input_path = output_drg_path(&self.input_drg_file_path).ok()?;
-        input_path
-            .parent()
-            .filter(|parent| !parent.as_os_str().is_empty())
-            .map(Path::to_path_buf)
+    fn clear_plot_data(&mut self) {
+        self.plot_data.clear();
+        self.lst_plot_data.clear();
+        self.polar_plot_data.clear();
+        self.never_visible.clear();
+        self.fast_track_warnings.clear();
+        self.close_pairs.clear();
+        self.separation_data.clear();
     }
 
-    fn path_in_output_directory(&self, path: PathBuf) -> PathBuf {
-        let Some(output_dir) = self.output_directory_from_input_drg() else {
-            return path;
-        };
-        let Some(file_name) = path.file_name() else {
-            return path;
-        };
-        output_dir.join(file_name)
+    /// Selected sources closer together than the beam, which one pointing cannot tell apart.
+    fn find_close_pairs(&self) -> Vec<(String, String, f64)> {
+        let selected: Vec<&Source> = self
+            .sources
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(source, _)| source)
+            .collect();
+        let positions: Vec<(f64, f64)> = selected
+            .iter()
+            .map(|source| (source.ra_rad, source.dec_rad))
+            .collect();
+        utils::close_pairs(&positions, self.beam_arcmin / 60.0)
+            .into_iter()
+            .map(|(i, j, separation)| {
+                (
+                    selected[i].name.clone(),
+                    selected[j].name.clone(),
+                    separation * 60.0,
+                )
+            })
+            .collect()
     }
 
-    fn obs_code_output_path(&self, obs_code: &str) -> Result<PathBuf, String> {
-        obs_code_output_path(obs_code).map(|path| self.path_in_output_directory(path))
+    fn load_stations(&mut self) -> Result<(), String> {
+        require_catalog_path(&self.station_file_path, "station")?;
+        self.check_catalog_paths_distinct()?;
+        let parsed = read_station_file(&self.station_file_path);
+        self.station_watch.mark_loaded(&self.station_file_path);
+        self.station_file_changed = false;
+        let mut stations =
+            parsed.map_err(|e| kept_catalog_message(e, self.stations.len(), "stations"))?;
+        self.manual_stations
+            .retain(|manual| !stations.iter().any(|station| station.name == manual.name));
+        stations.extend(self.manual_stations.iter().cloned());
+        self.stations = stations.into_iter().map(with_default_selection).collect();
+        Ok(())
     }
 
-    fn obs_code_station_skd_output_path(
-        &self,
-        obs_code: &str,
-        station_suffix: &str,
-    ) -> Result<PathBuf, String> {
-        obs_code_station_skd_output_path(obs_code, station_suffix)
-            .map(|path| self.path_in_output_directory(path))
+    /// Adds the manual form's position as a selected, in-memory station.
+    fn add_manual_station(&mut self) -> Result<(), String> {
+        let station = self.manual_station_form.to_station(self.ellipsoid)?;
+        if self.stations.iter().any(|(s, _)| s.name == station.name) {
+            return Err(format!("Station '{}' already exists.", station.name));
+        }
+        self.manual_stations.push(station.clone());
+        self.stations.push((station, true));
+        Ok(())
     }
 
-    fn write_skd_to_drg(&self) -> Result<Vec<PathBuf>, String> {
-        if self.skd_rows.is_empty() {
-            return Err("No SKED rows to write.".to_string());
+    fn remove_manual_station(&mut self, name: &str) {
+        self.manual_stations.retain(|station| station.name != name);
+        self.stations.retain(|(station, _)| station.name != name);
+        // Plotted series refer to stations by index.
+        if !self.plot_data.is_empty() {
+            self.calculate_plots();
         }
-        let obs_code = self.obs_code.trim();
-        if obs_code.is_empty() || obs_code.split_whitespace().count() != 1 {
-            return Err("Obscode must be one non-empty word.".to_string());
+    }
+
+    /// Appends a manual station to the station file as `NAME X Y Z`, after which it is an
+    /// ordinary loaded station.
+    fn append_manual_station(&mut self, name: &str) -> Result<(), String> {
+        let station = self
+            .manual_stations
+            .iter()
+            .find(|station| station.name == name)
+            .ok_or_else(|| format!("Station '{}' is not a manual station.", name))?;
+        let content = fs::read_to_string(&self.station_file_path)
+            .map_err(|e| format!("Failed to read station file: {}", e))?;
+        if is_vex_file(Path::new(&self.station_file_path), &content) {
+            return Err(format!(
+                "{} is a VEX schedule; add the station to a station.txt instead.",
+                self.station_file_path
+            ));
         }
-        let drg_output_path = self.obs_code_output_path(obs_code)?;
-        let skd32_output_path = self.obs_code_station_skd_output_path(obs_code, "32")?;
-        let skd34_output_path = self.obs_code_station_skd_output_path(obs_code, "34")?;
+        let mut output = content;
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "{} {:.3} {:.3} {:.3}\n",
+            station.name, station.pos[0], station.pos[1], station.pos[2]
+        ));
+        fs::write(&self.station_file_path, output)
+            .map_err(|e| format!("Failed to write station file: {}", e))?;
+        self.station_watch.mark_loaded(&self.station_file_path);
+        self.manual_stations.retain(|station| station.name != name);
+        Ok(())
+    }
 
-        let mut source_names: Vec<String> = Vec::new();
-        for row in &self.skd_rows {
-            parse_time_string(&row.start_time)?;
-            if self.find_source(&row.source_name).is_none() {
-                return Err(format!(
-                    "Source '{}' is not loaded in Source Settings.",
-                    row.source_name
-                ));
+    fn ui_manual_station(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Manual station").show(ui, |ui| {
+            let form = &mut self.manual_station_form;
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut form.geodetic, true, "Lat/Lon/Height");
+                ui.radio_value(&mut form.geodetic, false, "ECEF");
+            });
+            egui::Grid::new("manual_station_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut form.name);
+                    ui.end_row();
+                    let fields = if form.geodetic {
+                        [
+                            ("Latitude (deg):", &mut form.latitude),
+                            ("Longitude (deg, east):", &mut form.longitude),
+                            ("Height (m):", &mut form.height),
+                        ]
+                    } else {
+                        [
+                            ("X (m):", &mut form.x),
+                            ("Y (m):", &mut form.y),
+                            ("Z (m):", &mut form.z),
+                        ]
+                    };
+                    for (label, value) in fields {
+                        ui.label(label);
+                        ui.text_edit_singleline(value);
+                        ui.end_row();
+                    }
+                });
+            if ui
+                .button("Add station")
+                .on_hover_text(format!(
+                    "Kept for this session only; heights are {} ellipsoidal",
+                    self.ellipsoid.label()
+                ))
+                .clicked()
+            {
+                if let Err(e) = self.add_manual_station() {
+                    self.log(LogLevel::Error, e);
+                }
             }
-            if !source_names.iter().any(|name| name == &row.source_name) {
-                source_names.push(row.source_name.clone());
+            let mut remove = None;
+            let mut append = None;
+            for station in &self.manual_stations {
+                ui.horizontal(|ui| {
+                    let (lat, lon, height) = self.ellipsoid.geodetic(station.pos);
+                    ui.label(format!(
+                        "{}: lat {:.6}°, lon {:.6}°, h {:.1} m",
+                        station.name,
+                        lat.to_degrees(),
+                        lon.to_degrees(),
+                        height
+                    ));
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(station.name.clone());
+                    }
+                    if ui
+                        .small_button("Append to file")
+                        .on_hover_text("Write it to the station file as NAME X Y Z")
+                        .clicked()
+                    {
+                        append = Some(station.name.clone());
+                    }
+                });
             }
-        }
+            if let Some(name) = remove {
+                self.remove_manual_station(&name);
+            }
+            if let Some(name) = append {
+                match self.append_manual_station(&name) {
+                    Ok(()) => self.log(
+                        LogLevel::Info,
+                        format!("Appended {} to {}", name, self.station_file_path),
+                    ),
+                    Err(e) => self.log(LogLevel::Error, e),
+                }
+            }
+        });
+    }
 
-        let mut sources_section = String::from("$SOURCES\n");
-        for source_name in &source_names {
-            let source = self.find_source(source_name).unwrap();
-            sources_section.push_str(&format_source_drg_line(source));
-            sources_section.push('\n');
+    fn calculate_plots(&mut self) {
+        if self.stations.is_empty() {
+            self.log(
+                LogLevel::Warn,
+                "No stations loaded. Please check station.txt",
+            );
+            return;
         }
-        sources_section.push_str("*\n");
 
-        let mut drg_sked_section = String::from("$SKED\n");
-        drg_sked_section
-            .push_str("*SOURCES CAL FR          START     DUR       IDLE       STATIONS  TAPE\n");
-        for row in &self.skd_rows {
-            let start = format_drg_timestamp(row.start_date, &row.start_time)?;
-            drg_sked_section.push_str(&format!(
-                "{:<8}  10 S2  PREOB {} {:>4}  MIDOB 0 POSTOB K-L- 1F00000 1F00000\n",
-                row.source_name, start, row.duration_sec
-            ));
+        let mut new_plot_data = Vec::new();
+        let mut never_visible = Vec::new();
+        let mut fast_track_warnings = Vec::new();
+        let selected_stations: Vec<(usize, &Station)> = self
+            .stations
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, selected))| *selected)
+            .map(|(idx, (station, _))| (idx, station))
+            .collect();
+
+        if selected_stations.is_empty() {
+            self.log(LogLevel::Warn, "No stations selected.");
+            return;
         }
-        drg_sked_section.push_str("*\n");
 
-        let drg_content =
-            build_new_drg_content(obs_code, &self.pi_name, &sources_section, &drg_sked_section);
-        let skd32_content =
-            build_simple_skd_content(obs_code, &sources_section, &self.skd_rows, Some("32"))?;
-        let skd34_content =
-            build_simple_skd_content(obs_code, &sources_section, &self.skd_rows, Some("34"))?;
-        fs::write(&drg_output_path, drg_content)
-            .map_err(|e| format!("Failed to write output DRG file: {}", e))?;
-        fs::write(&skd32_output_path, skd32_content)
-            .map_err(|e| format!("Failed to write output SKD32 file: {}", e))?;
-        fs::write(&skd34_output_path, skd34_content)
-            .map_err(|e| format!("Failed to write output SKD34 file: {}", e))?;
-        Ok(vec![drg_output_path, skd32_output_path, skd34_output_path])
-    }
+        for (station_idx, station) in selected_stations {
+            let ant_pos = station.pos;
+            for (source, selected) in &self.sources {
+                if !*selected {
+                    continue;
+                }
 
-    fn generate_five_point_skd_rows(&mut self) -> Result<(), String> {
-        if self.sources.is_empty() {
-            return Err("Load source.txt first.".to_string());
-        }
-        self.five_point_cal_index = self.five_point_cal_index.min(self.sources.len() - 1);
-        parse_time_string(&self.five_point_start_time)?;
-        if self.five_point_obstime_sec == 0 {
-            return Err("Obs Time must be at least 1 second.".to_string());
-        }
+                let mut full_day_points = Vec::new();
+                let last_minute = (self.ut_sample_end() * 60.0).round() as usize;
+                for i in (0..=last_minute).step_by(PLOT_STEP_MIN) {
+                    let hour_float = (i as f64) / 60.0;
 
-        let cal_name = self.sources[self.five_point_cal_index].0.name.clone();
-        let mut generated = Vec::new();
-        let mut offset_sec = 0_i64;
-        let offset_pattern = five_point_offset_pattern(self.five_point_offset_deg);
-        for &(az_offset_deg, el_offset_deg) in &offset_pattern {
-            let (start_date, start_time) =
-                offset_schedule_time(self.selected_date, &self.five_point_start_time, offset_sec)?;
-            generated.push(SkdRow {
-                source_name: cal_name.clone(),
-                start_date,
-                start_time,
-                duration_sec: self.five_point_obstime_sec,
-                az_offset_deg,
-                el_offset_deg,
-                ra_offset_deg: 0.0,
-                dec_offset_deg: 0.0,
-                include_station_offsets: self.five_point_include_station_offsets,
-            });
-            offset_sec += self.five_point_obstime_sec as i64 + self.five_point_slew_sec as i64;
-        }
+                    if let Some(datetime_utc) =
+                        utc_datetime_from_hour(self.selected_date, hour_float)
+                    {
+                        let pointing = utils::radec2azalt_with(
+                            self.ellipsoid,
+                            self.sidereal_mode,
+                            self.reference_frame,
+                            ant_pos,
+                            utils::ut1_from_utc(datetime_utc, self.dut1_seconds),
+                            source.ra_rad,
+                            source.dec_rad,
+                        );
+                        full_day_points.push((hour_float, pointing));
+                    }
+                }
 
-        if self.five_point_clear_existing {
-            self.skd_rows = generated;
-        } else {
-            self.skd_rows.extend(generated);
-        }
-        self.sort_skd_rows_by_start_time();
-        Ok(())
-    }
+                let max_el_deg = full_day_points
+                    .iter()
+                    .map(|(_, pointing)| pointing.el_deg)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                if max_el_deg < 0.0 {
+                    never_visible.push(NeverVisible {
+                        source_name: source.name.clone(),
+                        station_name: station.name.clone(),
+                        max_el_deg,
+                        flipped_max_el_deg: None,
+                    });
+                    continue;
+                }
 
-    fn generate_interleaved_skd_rows(&mut self) -> Result<(), String> {
-        if self.sources.is_empty() {
-            return Err("Load source.txt first.".to_string());
-        }
-        self.interleave_target_index = self.interleave_target_index.min(self.sources.len() - 1);
-        self.interleave_cal_index = self.interleave_cal_index.min(self.sources.len() - 1);
-        parse_time_string(&self.interleave_start_time)?;
-        if self.interleave_cycles == 0 {
-            return Err("Cycle count must be at least 1.".to_string());
-        }
-        if self.interleave_target_duration_sec == 0 || self.interleave_cal_duration_sec == 0 {
-            return Err("Durations must be at least 1 second.".to_string());
-        }
+                let mut az_points = Vec::new();
+                let mut el_points = Vec::new();
+                let mut ha_points = Vec::new();
+                let mut lst_hours = Vec::new();
+
+                let floor_deg = self.effective_floor_deg();
+                let az_allowed =
+                    |az_deg: f64| utils::az_sector_margin(az_deg, &self.az_sectors) >= 0.0;
+                // The limits see the true sky position; the plots and exports show the
+                // commanded angles, pointing model included.
+                let commanded = |pointing: &utils::PointingResult| {
+                    station.commanded_az_el(pointing.az_deg, pointing.el_deg)
+                };
+                if let Some(&(first_hour, first)) = full_day_points.first() {
+                    if first.el_deg >= 0.0
+                        && station.ha_allows(first.hour_angle)
+                        && az_allowed(first.az_deg)
+                    {
+                        let (az, el) = commanded(&first);
+                        az_points.push([first_hour, az]);
+                        el_points.push([first_hour, el]);
+                        ha_points.push([first_hour, first.hour_angle]);
+                        lst_hours.push(first.lst);
+                    }
 
-        let target_name = self.sources[self.interleave_target_index].0.name.clone();
-        let cal_name = self.sources[self.interleave_cal_index].0.name.clone();
-        let mut generated = Vec::new();
-        let mut offset_sec = 0_i64;
+                    for &(hour, pointing) in full_day_points.iter().skip(1) {
+                        let (az, el) = commanded(&pointing);
+                        az_points.push([hour, az]);
+                        lst_hours.push(pointing.lst);
+                        if pointing.el_deg >= 0.0
+                            && station.ha_allows(pointing.hour_angle)
+                            && az_allowed(pointing.az_deg)
+                        {
+                            el_points.push([hour, el]);
+                            ha_points.push([hour, pointing.hour_angle]);
+                        } else {
+                            el_points.push([hour, f64::NAN]);
+                            ha_points.push([hour, f64::NAN]);
+                        }
+                    }
+                }
+                let margins: Vec<(f64, f64)> = full_day_points
+                    .iter()
+                    .map(|(hour, pointing)| {
+                        (
+                            *hour,
+                            (pointing.el_deg - floor_deg)
+                                .min(station.ha_margin(pointing.hour_angle))
+                                .min(utils::az_sector_margin(pointing.az_deg, &self.az_sectors)),
+                        )
+                    })
+                    .collect();
+                // Angular rates between successive visible samples, placed at their midpoint.
+                let mut peak_az_rate: f64 = 0.0;
+                let mut peak_el_rate: f64 = 0.0;
+                let az_rate_margins: Vec<(f64, f64)> = full_day_points
+                    .windows(2)
+                    .map(|pair| {
+                        let ((h0, p0), (h1, p1)) = (pair[0], pair[1]);
+                        let dt_sec = (h1 - h0) * 3600.0;
+                        if p0.el_deg < 0.0 || p1.el_deg < 0.0 || dt_sec <= 0.0 {
+                            return ((h0 + h1) / 2.0, f64::NAN);
+                        }
+                        let d_az = (p1.az_deg - p0.az_deg + 540.0).rem_euclid(360.0) - 180.0;
+                        let az_rate = d_az.abs() / dt_sec;
+                        peak_az_rate = peak_az_rate.max(az_rate);
+                        peak_el_rate = peak_el_rate.max((p1.el_deg - p0.el_deg).abs() / dt_sec);
+                        ((h0 + h1) / 2.0, az_rate - self.max_az_rate_deg_per_sec)
+                    })
+                    .collect();
+                let fast_windows = utils::visibility_windows_from_margin(&az_rate_margins);
+                if !fast_windows.is_empty() {
+                    fast_track_warnings.push(FastTrackWarning {
+                        source_name: source.name.clone(),
+                        station_name: station.name.clone(),
+                        windows: fast_windows,
+                        peak_az_rate,
+                        peak_el_rate,
+                    });
+                }
 
-        for cycle_idx in 0..self.interleave_cycles {
-            let (cal_date, cal_time) =
-                offset_schedule_time(self.selected_date, &self.interleave_start_time, offset_sec)?;
-            generated.push(SkdRow {
-                source_name: cal_name.clone(),
-                start_date: cal_date,
-                start_time: cal_time,
-                duration_sec: self.interleave_cal_duration_sec,
-                az_offset_deg: 0.0,
-                el_offset_deg: 0.0,
-                ra_offset_deg: 0.0,
-                dec_offset_deg: 0.0,
-                include_station_offsets: false,
-            });
-            offset_sec += self.interleave_cal_duration_sec as i64 + self.interleave_slew_sec as i64;
+                let crossing_deg = self.rise_set_crossing_deg();
+                let horizon_samples: Vec<(f64, f64)> = full_day_points
+                    .iter()
+                    .map(|(hour, pointing)| (*hour, pointing.el_deg - crossing_deg))
+                    .collect();
+                let horizon_windows = utils::visibility_windows_from_margin(&horizon_samples);
+                let (first_hour, last_hour) = (
+                    horizon_samples.first().map_or(0.0, |s| s.0),
+                    horizon_samples.last().map_or(24.0, |s| s.0),
+                );
+                let rise_hours = horizon_windows
+                    .iter()
+                    .map(|w| w.0)
+                    .filter(|&hour| hour > first_hour)
+                    .collect();
+                let set_hours = horizon_windows
+                    .iter()
+                    .map(|w| w.1)
+                    .filter(|&hour| hour < last_hour)
+                    .collect();
 
-            let (target_date, target_time) =
-                offset_schedule_time(self.selected_date, &self.interleave_start_time, offset_sec)?;
-            generated.push(SkdRow {
-                source_name: target_name.clone(),
-                start_date: target_date,
-                start_time: target_time,
-                duration_sec: self.interleave_target_duration_sec,
-                az_offset_deg: 0.0,
-                el_offset_deg: 0.0,
-                ra_offset_deg: 0.0,
-                dec_offset_deg: 0.0,
-                include_station_offsets: false,
-            });
-            offset_sec += self.interleave_target_duration_sec as i64;
-            if cycle_idx + 1 < self.interleave_cycles {
-                offset_sec += self.interleave_slew_sec as i64;
+                new_plot_data.push(PlotSeries {
+                    source_name: source.name.clone(),
+                    station_name: station.name.clone(),
+                    az_points,
+                    el_points,
+                    ha_points,
+                    lst_hours,
+                    windows: utils::merge_close_windows(
+                        &utils::visibility_windows_from_margin(&margins),
+                        self.merge_gap_min / 60.0,
+                    ),
+                    rise_hours,
+                    set_hours,
+                    station_idx,
+                    group: source.group.clone(),
+                });
             }
         }
-
-        if self.interleave_clear_existing {
-            self.skd_rows = generated;
-        } else {
-            self.skd_rows.extend(generated);
+        for (station_idx, (station, selected)) in self.stations.iter().enumerate() {
+            if *selected {
+                new_plot_data.extend(self.mosaic_series(station_idx, station));
+            }
         }
-        self.sort_skd_rows_by_start_time();
-        Ok(())
+        // Stable sort keeps the station/catalogue order inside each group section.
+        new_plot_data.sort_by_key(|series| group_sort_key(series.group.as_deref()));
+        self.plot_data = new_plot_data;
+        self.never_visible = never_visible;
+        self.fast_track_warnings = fast_track_warnings;
+        self.close_pairs = self.find_close_pairs();
+        self.lst_plot_data = self.build_lst_plot_data();
+        self.polar_plot_data = self.build_polar_plot_data();
+        self.separation_data = self.build_separation_data();
     }
 
-    fn shift_skd_rows_by_days(&mut self, days: i64) {
-        if days == 0 {
-            return;
-        }
-        for row in &mut self.skd_rows {
-            row.start_date = row.start_date + Duration::days(days);
-        }
-        self.sort_skd_rows_by_start_time();
+    /// Peak elevation over the plotted UT range of `entry`'s source with its declination
+    /// negated, sampled like `calculate_plots`.
+    fn flipped_dec_max_el(&self, entry: &NeverVisible) -> Option<f64> {
+        let source = self.find_source(&entry.source_name)?;
+        let (station, _) = self
+            .stations
+            .iter()
+            .find(|(station, _)| station.name == entry.station_name)?;
+        let last_minute = (self.ut_sample_end() * 60.0).round() as usize;
+        let max_el = (0..=last_minute)
+            .step_by(PLOT_STEP_MIN)
+            .filter_map(|minute| utc_datetime_from_hour(self.selected_date, minute as f64 / 60.0))
+            .map(|time| {
+                utils::radec2azalt_with(
+                    self.ellipsoid,
+                    self.sidereal_mode,
+                    self.reference_frame,
+                    station.pos,
+                    utils::ut1_from_utc(time, self.dut1_seconds),
+                    source.ra_rad,
+                    -source.dec_rad,
+                )
+                .el_deg
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        Some(max_el)
     }
 
-    fn apply_schedule_time_shift(&mut self) -> Result<(), String> {
-        if self.skd_rows.is_empty() {
-            return Err("No SKED rows to shift.".to_string());
+    /// The mosaic envelope at `station`: centre-pointing az/HA with the lowest elevation of
+    /// any pattern position, masked and windowed like the per-source tracks.
+    fn mosaic_series(&self, station_idx: usize, station: &Station) -> Option<PlotSeries> {
+        if !self.mosaic.enabled {
+            return None;
         }
-        let shift_sec = self.schedule_time_shift_sec as i64;
-        for row in &mut self.skd_rows {
-            let shifted =
-                schedule_datetime(row.start_date, &row.start_time)? + Duration::seconds(shift_sec);
-            row.start_date = shifted.date();
-            row.start_time = format!(
-                "{:02}:{:02}:{:02}",
-                shifted.time().hour(),
-                shifted.time().minute(),
-                shifted.time().second()
+        let center = self.find_source(self.mosaic.center.as_deref()?)?;
+        let pointings = utils::mosaic_offsets(
+            center.ra_rad,
+            center.dec_rad,
+            self.mosaic.columns,
+            self.mosaic.rows,
+            self.mosaic.spacing_arcmin,
+        );
+        let floor_deg = self.effective_floor_deg();
+        let mut series = PlotSeries {
+            source_name: format!("{}-mosaic", center.name),
+            station_name: station.name.clone(),
+            az_points: Vec::new(),
+            el_points: Vec::new(),
+            ha_points: Vec::new(),
+            lst_hours: Vec::new(),
+            windows: Vec::new(),
+            rise_hours: Vec::new(),
+            set_hours: Vec::new(),
+            station_idx,
+            group: center.group.clone(),
+        };
+        let mut margins = Vec::new();
+        let last_minute = (self.ut_sample_end() * 60.0).round() as usize;
+        for minute in (0..=last_minute).step_by(PLOT_STEP_MIN) {
+            let hour = minute as f64 / 60.0;
+            let Some(time) = utc_datetime_from_hour(self.selected_date, hour) else {
+                continue;
+            };
+            let time = utils::ut1_from_utc(time, self.dut1_seconds);
+            let pointing = utils::radec2azalt_with(
+                self.ellipsoid,
+                self.sidereal_mode,
+                self.reference_frame,
+                station.pos,
+                time,
+                center.ra_rad,
+                center.dec_rad,
             );
+            let min_el = pointings
+                .iter()
+                .map(|&(ra, dec)| {
+                    utils::radec2azalt_with(
+                        self.ellipsoid,
+                        self.sidereal_mode,
+                        self.reference_frame,
+                        station.pos,
+                        time,
+                        ra,
+                        dec,
+                    )
+                    .el_deg
+                })
+                .fold(f64::INFINITY, f64::min);
+            let az_margin = utils::az_sector_margin(pointing.az_deg, &self.az_sectors);
+            let usable =
+                min_el >= 0.0 && station.ha_allows(pointing.hour_angle) && az_margin >= 0.0;
+            series.az_points.push([hour, pointing.az_deg]);
+            series.lst_hours.push(pointing.lst);
+            series
+                .el_points
+                .push([hour, if usable { min_el } else { f64::NAN }]);
+            series.ha_points.push([
+                hour,
+                if usable {
+                    pointing.hour_angle
+                } else {
+                    f64::NAN
+                },
+            ]);
+            margins.push((
+                hour,
+                (min_el - floor_deg)
+                    .min(station.ha_margin(pointing.hour_angle))
+                    .min(az_margin),
+            ));
         }
-        self.sort_skd_rows_by_start_time();
-        Ok(())
+        series.windows = utils::merge_close_windows(
+            &utils::visibility_windows_from_margin(&margins),
+            self.merge_gap_min / 60.0,
+        );
+        Some(series)
     }
 
-    fn sort_skd_rows_by_start_time(&mut self) {
-        self.skd_rows.sort_by(|a, b| {
-            let a_dt = schedule_datetime(a.start_date, &a.start_time).ok();
-            let b_dt = schedule_datetime(b.start_date, &b.start_time).ok();
-            a_dt.cmp(&b_dt)
-        });
-        self.mark_skd_status_dirty();
+    /// Sun and Moon separation curves for each distinct source in `plot_data`.
+    fn build_separation_data(&self) -> Vec<SeparationSeries> {
+        // (UT hour, Sun RA/Dec, Moon RA/Dec)
+        let bodies: Vec<_> = (0..=(self.ut_sample_end() * 60.0).round() as usize)
+            .step_by(SEPARATION_STEP_MIN)
+            .filter_map(|minute| {
+                let hour = minute as f64 / 60.0;
+                let time = utc_datetime_from_hour(self.selected_date, hour)?;
+                Some((hour, utils::sun_radec(time), utils::moon_radec(time)))
+            })
+            .collect();
+
+        let mut result: Vec<SeparationSeries> = Vec::new();
+        for series in &self.plot_data {
+            if result
+                .iter()
+                .any(|entry| entry.source_name == series.source_name)
+            {
+                continue;
+            }
+            let Some(source) = self.find_source(&series.source_name) else {
+                continue;
+            };
+            let separation = |(ra, dec): (f64, f64)| {
+                utils::angular_separation_deg(source.ra_rad, source.dec_rad, ra, dec)
+            };
+            result.push(SeparationSeries {
+                source_name: source.name.clone(),
+                sun: bodies
+                    .iter()
+                    .map(|&(hour, sun, _)| [hour, separation(sun)])
+                    .collect(),
+                moon: bodies
+                    .iter()
+                    .map(|&(hour, _, moon)| [hour, separation(moon)])
+                    .collect(),
+            });
+        }
+        result
     }
 
-    fn mark_skd_status_dirty(&mut self) {
-        self.skd_status_dirty = true;
+    /// The station used for single-station readouts (LST, pointer formatters, SKD fallback):
+    /// the first selected one, or `None` when nothing is selected.
+    fn current_station(&self) -> Option<&Station> {
+        self.stations
+            .iter()
+            .find(|(_, selected)| *selected)
+            .map(|(station, _)| station)
     }
 
-    fn rebuild_skd_status_cache(&mut self) {
-        if !self.skd_status_dirty && self.skd_status_cache.len() == self.skd_rows.len() {
-            return;
+    /// Vertical grid for the UT plots, aligned to LST hours at `station_pos` when chosen in
+    /// Preferences (UT hours without a station).
+    fn time_grid_for(&self, station_pos: Option<[f64; 3]>) -> TimeGrid {
+        TimeGrid {
+            last_hour: self.ut_sample_end(),
+            lst_at_midnight: station_pos
+                .filter(|_| self.grid_alignment == GridAlignment::Lst)
+                .and_then(|pos| self.lst_from_ut_hour(pos, 0.0)),
         }
+    }
 
-        let mut selected_antenna_indices = Vec::new();
-        for index in [self.selected_antenna, self.selected_antenna_2] {
-            if index < self.antennas.len() && !selected_antenna_indices.contains(&index) {
-                selected_antenna_indices.push(index);
+    fn time_grid(&self) -> TimeGrid {
+        self.time_grid_for(self.current_station().map(|station| station.pos))
+    }
+
+    fn lst_from_ut_hour(&self, station_pos: [f64; 3], ut_hour: f64) -> Option<f64> {
+        let datetime = utc_datetime_from_hour(self.selected_date, ut_hour)?;
+        Some(
+            utils::sidereal_time(
+                self.ellipsoid,
+                station_pos,
+                utils::ut1_from_utc(datetime, self.dut1_seconds),
+            )
+            .local_hours(self.effective_sidereal_mode()),
+        )
+    }
+
+    fn build_lst_plot_data(&self) -> Vec<PlotSeries> {
+        let mut lst_plot_data = Vec::new();
+
+        for series in &self.plot_data {
+            let mut lst_az_points = Vec::new();
+            let mut lst_el_points = Vec::new();
+            let mut prev_lst: Option<f64> = None;
+
+            for ((az_point, el_point), &lst_hour) in series
+                .az_points
+                .iter()
+                .zip(&series.el_points)
+                .zip(&series.lst_hours)
+            {
+                let az = az_point[1];
+                let el = el_point[1];
+
+                if let Some(prev) = prev_lst {
+                    if lst_hour + 12.0 < prev {
+                        lst_az_points.push([f64::NAN, f64::NAN]);
+                        lst_el_points.push([f64::NAN, f64::NAN]);
+                    }
+                }
+
+                lst_az_points.push([lst_hour, az]);
+                lst_el_points.push([lst_hour, el]);
+                prev_lst = Some(lst_hour);
             }
+            lst_plot_data.push(PlotSeries {
+                source_name: series.source_name.clone(),
+                station_name: series.station_name.clone(),
+                az_points: lst_az_points,
+                el_points: lst_el_points,
+                ha_points: Vec::new(),
+                lst_hours: Vec::new(),
+                windows: series.windows.clone(),
+                rise_hours: Vec::new(),
+                set_hours: Vec::new(),
+                station_idx: series.station_idx,
+                group: series.group.clone(),
+            });
         }
-        let selected_antennas: Vec<Antenna> = selected_antenna_indices
-            .iter()
-            .filter_map(|&index| self.antennas.get(index).cloned())
-            .collect();
-        let ant_pos = selected_antennas
-            .first()
-            .map(|antenna| antenna.pos)
-            .or_else(|| self.station_position());
-        let source_map: HashMap<&str, &Source> = self
-            .sources
-            .iter()
-            .map(|(source, _)| (source.name.as_str(), source))
-            .collect();
-        let mut cache = Vec::with_capacity(self.skd_rows.len());
-        let mut prev_ends: Vec<Option<ScanEnd>> = vec![None; selected_antennas.len()];
+        lst_plot_data
+    }
 
-        for row in &self.skd_rows {
-            let Some(source) = source_map.get(row.source_name.as_str()).copied() else {
-                cache.push(SkdRowStatus {
-                    start_geometry: "NO".to_string(),
-                    end_geometry: "NO".to_string(),
-                    motion_1: "NO".to_string(),
-                    motion_2: "NO".to_string(),
-                });
-                prev_ends.fill(None);
-                continue;
-            };
+    /// `points` as drawn: raw, or spline-interpolated when smoothing is on. `period` is 360 for
+    /// azimuth tracks.
+    fn display_track(&self, points: &[[f64; 2]], period: Option<f64>) -> Vec<[f64; 2]> {
+        if self.smooth_curves {
+            utils::catmull_rom_track(points, SMOOTH_SUBDIVISIONS, period)
+        } else {
+            points.to_vec()
+        }
+    }
 
-            let (start_geometry, end_geometry) = match ant_pos {
-                Some(pos) => match (
-                    scan_az_el_for(row, source, pos, false),
-                    scan_az_el_for(row, source, pos, true),
-                ) {
-                    (Some((_, start_az, start_el)), Some((_, end_az, end_el))) => (
-                        format!("{:5.1}/{:5.1}", start_az, start_el),
-                        format!("{:5.1}/{:5.1}", end_az, end_el),
-                    ),
-                    _ => ("NO".to_string(), "NO".to_string()),
-                },
-                None => ("No antenna".to_string(), "No antenna".to_string()),
-            };
+    fn build_polar_plot_data(&self) -> Vec<PolarSeries> {
+        let mut polar_plot_data = Vec::new();
 
-            let mut motion_values = vec![String::new(), String::new()];
-            if selected_antennas.is_empty() {
-                motion_values[0] = "Load ant".to_string();
-            } else {
-                for (ant_idx, antenna) in selected_antennas.iter().take(2).enumerate() {
-                    let (antenna_motion, current_end) =
-                        antenna_motion_status(row, source, antenna, prev_ends[ant_idx]);
-                    prev_ends[ant_idx] = current_end;
-                    motion_values[ant_idx] = antenna_motion;
+        for series in &self.plot_data {
+            let mut polar_points = Vec::new();
+            let mut polar_el = Vec::new();
+            let mut polar_hours = Vec::new();
+            let mut hour_marker_points = Vec::new();
+            let mut hour_labels = Vec::new();
+
+            // Azimuth takes the elevation gaps so both tracks interpolate over the same runs.
+            let masked_az: Vec<[f64; 2]> = series
+                .az_points
+                .iter()
+                .zip(&series.el_points)
+                .map(|(az, el)| [az[0], if el[1].is_nan() { f64::NAN } else { az[1] }])
+                .collect();
+            let az_track = self.display_track(&masked_az, Some(360.0));
+            let el_track = self.display_track(&series.el_points, None);
+            // The tracks hold commanded angles; the floor colouring uses the true elevation.
+            let el_offset_deg = self
+                .stations
+                .get(series.station_idx)
+                .map_or(0.0, |(station, _)| station.el_offset_arcmin / 60.0);
+
+            for (az_point, el_point) in az_track.iter().zip(&el_track) {
+                let hour = az_point[0];
+                let az = az_point[1];
+                let el = el_point[1];
+
+                if !el.is_nan() && !az.is_nan() && el - el_offset_deg >= 0.0 {
+                    let angle_rad = self.polar_orientation.screen_angle_rad(az);
+                    let [x, y] = self.polar_orientation.polar_xy(az, el);
+                    polar_points.push([x, y]);
+                    polar_el.push(el - el_offset_deg);
+                    polar_hours.push(hour);
+
+                    if (hour - hour.round()).abs() < 1e-6 {
+                        hour_marker_points.push([x, y]);
+                        let label_hour = (hour.round() as i32).rem_euclid(24);
+                        let label_offset = 0.04;
+                        hour_labels.push((
+                            x + angle_rad.cos() * label_offset,
+                            y + angle_rad.sin() * label_offset,
+                            format!("{:02}h", label_hour),
+                        ));
+                    }
                 }
             }
-
-            cache.push(SkdRowStatus {
-                start_geometry,
-                end_geometry,
-                motion_1: motion_values[0].clone(),
-                motion_2: motion_values[1].clone(),
+            polar_plot_data.push(PolarSeries {
+                source_name: series.source_name.clone(),
+                station_name: series.station_name.clone(),
+                polar_points,
+                polar_el,
+                polar_hours,
+                hour_marker_points,
+                hour_labels,
+                station_idx: series.station_idx,
             });
         }
+        polar_plot_data
+    }
 
-        self.skd_status_cache = cache;
-        self.skd_status_dirty = false;
+    fn output_target_tab(target: OutputTarget) -> AppTab {
+        match target {
+            OutputTarget::UtAzel => AppTab::UptimePlotters,
+            OutputTarget::Polar => AppTab::PolarPlot,
+            OutputTarget::Lst => AppTab::LstPlot,
+        }
     }
 
-    fn find_source(&self, name: &str) -> Option<&Source> {
-        self.sources
-            .iter()
-            .map(|(source, _)| source)
-            .find(|source| source.name == name)
+    fn output_target_path(target: OutputTarget) -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(target.file_name())
     }
 
-    fn ui_skd_table_tab(&mut self, ui: &mut egui::Ui) {
-        let available = ui.available_size();
-        let left_width = (available.x / 3.0).max(300.0);
-        let right_width = (available.x - left_width - 12.0).max(500.0);
-        let parameter_panel_width = (left_width - 28.0).max(280.0);
+    fn output_target_rect(&self, target: OutputTarget) -> Option<egui::Rect> {
+        match target {
+            OutputTarget::UtAzel => self.uptime_plot_rect,
+            OutputTarget::Polar => self.polar_plot_rect,
+            OutputTarget::Lst => self.lst_plot_rect,
+        }
+    }
 
-        ui.horizontal(|ui| {
-            ui.allocate_ui_with_layout(
-                egui::vec2(left_width, available.y),
-                egui::Layout::top_down(egui::Align::Min),
-                |ui| {
-                    egui::ScrollArea::vertical()
-                        .id_salt("skd_left_parameters")
-                        .auto_shrink([false, false])
-                        .show(ui, |ui| {
-                            ui.group(|ui| {
-                                ui.set_min_width(parameter_panel_width);
-                                ui.set_max_width(parameter_panel_width);
-                                ui.label("Input DRG");
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui.button("Load DRG").clicked() {
-                                        match pick_file_dialog("Select DRG file") {
-                                            Ok(Some(path)) => {
-                                                self.input_drg_file_path =
-                                                    path.to_string_lossy().to_string();
-                                                match self.load_drg_file() {
-                                                    Ok(_) => {
-                                                        self.error_msg =
-                                                            Some("Loaded DRG.".to_string())
-                                                    }
-                                                    Err(e) => self.error_msg = Some(e),
-                                                }
-                                            }
-                                            Ok(None) => {}
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    if ui.button("Open Input").clicked() {
-                                        let input_path = output_drg_path(&self.input_drg_file_path)
-                                            .map(|path| path.to_string_lossy().to_string());
-                                        match input_path.and_then(|path| {
-                                            utils::open_file_in_external_editor(&path)
-                                        }) {
-                                            Ok(_) => self.error_msg = None,
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    let input_drg_path_text = if self.input_drg_file_path.is_empty()
-                                    {
-                                        "<DRG path>"
-                                    } else {
-                                        self.input_drg_file_path.as_str()
-                                    };
-                                    ui.add_sized(
-                                        [parameter_panel_width, 20.0],
-                                        egui::Label::new(
-                                            egui::RichText::new(input_drg_path_text).monospace(),
-                                        )
-                                        .truncate(),
-                                    )
-                                    .on_hover_text(input_drg_path_text);
-                                });
-                            });
+    /// The area copied by "Copy Image" for the active tab and the margin kept around it for
+    /// axis labels, or `None` when the tab has no plot.
+    fn copy_view_rect(&self) -> Option<(egui::Rect, f32)> {
+        let plot_rect = match self.selected_tab {
+            AppTab::Parameters | AppTab::SkdTable => return None,
+            AppTab::UptimePlotters => self.uptime_plot_rect,
+            AppTab::PolarPlot => self.polar_plot_rect,
+            AppTab::LstPlot => self.lst_plot_rect,
+            _ => None,
+        };
+        match plot_rect {
+            Some(rect) => Some((rect, PLOT_CROP_MARGIN)),
+            None => self.central_panel_rect.map(|rect| (rect, 0.0)),
+        }
+    }
 
-                            ui.group(|ui| {
-                                ui.set_min_width(parameter_panel_width);
-                                ui.set_max_width(parameter_panel_width);
-                                ui.label("Output");
-                                ui.horizontal_wrapped(|ui| {
-                                    ui.label("Obscode:");
-                                    ui.add_sized(
-                                        [120.0, 20.0],
-                                        egui::TextEdit::singleline(&mut self.obs_code),
-                                    );
-                                    ui.label("PI:");
-                                    ui.add_sized(
-                                        [120.0, 20.0],
-                                        egui::TextEdit::singleline(&mut self.pi_name),
-                                    );
-                                });
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui.button("Create DRG").clicked() {
-                                        match self.write_skd_to_drg() {
-                                            Ok(paths) => {
-                                                self.error_msg = Some(format!(
-                                                    "Created {}",
-                                                    paths
-                                                        .iter()
-                                                        .map(|path| path.display().to_string())
-                                                        .collect::<Vec<_>>()
-                                                        .join(", ")
-                                                ))
+    /// Errors when an output run or clipboard copy is already waiting on screenshots, which
+    /// would otherwise take each other's frames.
+    fn ensure_capture_idle(&self) -> Result<(), String> {
+        if self.output_capture.is_some() {
+            return Err("Output is already running.".to_string());
+        }
+        if self.clipboard_capture.is_some() {
+            return Err("A plot image copy is still pending.".to_string());
+        }
+        Ok(())
+    }
+
+    fn request_clipboard_capture(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        self.ensure_capture_idle()?;
+        let target = self
+            .copy_view_rect()
+            .ok_or_else(|| "The current tab has no plot to copy.".to_string())?;
+        self.clipboard_capture = Some(target);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+        ctx.request_repaint();
+        Ok(())
+    }
+
+    fn copy_screenshot_to_clipboard(
+        &mut self,
+        ctx: &egui::Context,
+        image: &egui::ColorImage,
+        rect: egui::Rect,
+        pad_points: f32,
+    ) {
+        match crop_plot_region(image, rect, ctx.pixels_per_point(), pad_points) {
+            Ok(cropped) => {
+                let (width, height) = cropped.dimensions();
+                ctx.copy_image(egui::ColorImage::from_rgba_unmultiplied(
+                    [width as usize, height as usize],
+                    cropped.as_raw(),
+                ));
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Requested copy of the plot image ({}×{} px) to the clipboard.",
+                        width, height
+                    ),
+                );
+            }
+            Err(e) => self.log(LogLevel::Error, format!("Failed to copy plot image: {}", e)),
+        }
+    }
+
+    fn start_output_capture(
+        &mut self,
+        ctx: &egui::Context,
+        report_path: Option<PathBuf>,
+    ) -> Result<(), String> {
+        if self.plot_data.is_empty() {
+            return Err("No plot data to output. Please run Plot Selected first.".to_string());
+        }
+        if self.current_station().is_none() {
+            return Err("No station selected. Tick one in Station Settings first.".to_string());
+        }
+        self.ensure_capture_idle()?;
+
+        let previous_tab = self.selected_tab;
+        let targets = if report_path.is_some() {
+            vec![OutputTarget::UtAzel, OutputTarget::Polar]
+        } else {
+            OutputTarget::ALL.to_vec()
+        };
+        self.output_capture = Some(OutputCaptureState {
+            targets,
+            index: 0,
+            previous_tab,
+            screenshot_requested: false,
+            report_path,
+            report_images: Vec::new(),
+            titled_path: None,
+            batch: None,
+            image_size: self.image_size,
+            resize_frames: 0,
+        });
+        self.uptime_plot_rect = None;
+        self.polar_plot_rect = None;
+        self.lst_plot_rect = None;
+        self.selected_tab = AppTab::UptimePlotters;
+        ctx.request_repaint();
+        Ok(())
+    }
+
+    /// Captures the az/el plots with a title block (stations, date, sources) into one PNG.
+    fn start_titled_azel_output(
+        &mut self,
+        ctx: &egui::Context,
+        path: PathBuf,
+    ) -> Result<(), String> {
+        if self.plot_data.is_empty() {
+            return Err("No plot data to output. Please run Plot Selected first.".to_string());
+        }
+        self.ensure_capture_idle()?;
+        self.output_capture = Some(OutputCaptureState {
+            targets: vec![OutputTarget::UtAzel],
+            index: 0,
+            previous_tab: self.selected_tab,
+            screenshot_requested: false,
+            report_path: None,
+            report_images: Vec::new(),
+            titled_path: Some(path),
+            batch: None,
+            image_size: self.image_size,
+            resize_frames: 0,
+        });
+        self.uptime_plot_rect = None;
+        self.selected_tab = AppTab::UptimePlotters;
+        ctx.request_repaint();
+        Ok(())
+    }
+
+    /// Runs the `--export-azel` image export on the first frame and closes the window once
+    /// it has finished, printing the outcome.
+    fn drive_cli_export(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.cli_export.clone() else {
+            return;
+        };
+        if !self.cli_export_started {
+            self.cli_export_started = true;
+            if self.plot_data.is_empty() {
+                self.calculate_plots();
+            }
+            if let Err(e) = self.start_titled_azel_output(ctx, path) {
+                eprintln!("{}", e);
+                self.cli_export = None;
+                self.cli_export_failed.store(true, Ordering::Relaxed);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            return;
+        }
+        if self.output_capture.is_some() {
+            return;
+        }
+        // The capture logs its outcome last: "Image saved" or the error that stopped it.
+        match self.log_entries.back() {
+            Some(entry) => {
+                eprintln!("{}", entry.message);
+                if entry.level == LogLevel::Error {
+                    self.cli_export_failed.store(true, Ordering::Relaxed);
+                }
+            }
+            None => self.cli_export_failed.store(true, Ordering::Relaxed),
+        }
+        self.cli_export = None;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+
+    fn capturing_titled_azel(&self) -> bool {
+        self.output_capture
+            .as_ref()
+            .is_some_and(|state| state.titled_path.is_some())
+    }
+
+    /// Caption drawn above the az/el plots for a titled image capture.
+    fn ui_azel_title_block(&self, ui: &mut egui::Ui) -> egui::Rect {
+        let mut stations: Vec<&str> = Vec::new();
+        let mut sources: Vec<&str> = Vec::new();
+        for series in &self.plot_data {
+            if !stations.contains(&series.station_name.as_str()) {
+                stations.push(&series.station_name);
+            }
+            if !sources.contains(&series.source_name.as_str()) {
+                sources.push(&series.source_name);
+            }
+        }
+        ui.vertical(|ui| {
+            ui.heading(format!(
+                "{}  {}",
+                stations.join(", "),
+                self.selected_date.format("%Y-%m-%d")
+            ));
+            ui.label(format!("Sources: {}", sources.join(", ")));
+            ui.label(format!(
+                "Elevation floor {:.1}°    Generated {}",
+                self.effective_floor_deg(),
+                Utc::now().format("%Y-%m-%d %H:%M UTC")
+            ));
+        })
+        .response
+        .rect
+    }
+
+    fn abort_output_capture(&mut self, previous_tab: AppTab) {
+        self.selected_tab = previous_tab;
+        if let Some(batch) = self.output_capture.take().and_then(|state| state.batch) {
+            self.selected_date = batch.original_date;
+            self.calculate_plots();
+        }
+    }
+
+    fn start_batch_output(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        self.ensure_capture_idle()?;
+        let start = NaiveDate::parse_from_str(self.batch_start_date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid start date: {}", self.batch_start_date))?;
+        let output_dir = PathBuf::from(self.batch_output_dir.trim());
+        fs::create_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to create {:?}: {}", output_dir, e))?;
+        let dates: Vec<NaiveDate> = start.iter_days().take(self.batch_days as usize).collect();
+
+        let original_date = self.selected_date;
+        self.selected_date = dates[0];
+        self.calculate_plots();
+        if self.plot_data.is_empty() {
+            self.selected_date = original_date;
+            return Err("No plot data to output. Please select stations and sources.".to_string());
+        }
+
+        self.output_capture = Some(OutputCaptureState {
+            targets: vec![OutputTarget::UtAzel; dates.len()],
+            index: 0,
+            previous_tab: self.selected_tab,
+            screenshot_requested: false,
+            report_path: None,
+            report_images: Vec::new(),
+            titled_path: None,
+            batch: Some(BatchOutput {
+                dates,
+                output_dir,
+                original_date,
+            }),
+            image_size: self.image_size,
+            resize_frames: 0,
+        });
+        self.uptime_plot_rect = None;
+        self.selected_tab = AppTab::UptimePlotters;
+        ctx.request_repaint();
+        Ok(())
+    }
+
+    fn drive_output_capture(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let (target, screenshot_requested) = match self.output_capture.as_ref() {
+            Some(state) => match state.targets.get(state.index).copied() {
+                Some(target) => (target, state.screenshot_requested),
+                None => return,
+            },
+            None => return,
+        };
+
+        if screenshot_requested {
+            return;
+        }
+
+        // A fixed size is rendered offscreen when the GPU renderer is at hand; otherwise the
+        // window is resized and captured.
+        let offscreen = self
+            .output_capture
+            .as_ref()
+            .and_then(|state| state.image_size)
+            .zip(frame.wgpu_render_state());
+        if offscreen.is_none() && self.resize_for_capture(ctx) {
+            return;
+        }
+
+        let batch_date = self.output_capture.as_ref().and_then(|state| {
+            let batch = state.batch.as_ref()?;
+            batch.dates.get(state.index).copied()
+        });
+        if let Some(date) = batch_date {
+            if self.selected_date != date {
+                // Replacing the plot data drops the previous day's series.
+                self.selected_date = date;
+                self.calculate_plots();
+                self.uptime_plot_rect = None;
+                ctx.request_repaint();
+                return;
+            }
+        }
+
+        if let Some((size, render_state)) = offscreen {
+            let previous_tab = self
+                .output_capture
+                .as_ref()
+                .map_or(self.selected_tab, |state| state.previous_tab);
+            let image = self.render_offscreen(ctx, render_state, target, size);
+            self.finish_output_image(target, previous_tab, image);
+            ctx.request_repaint();
+            return;
+        }
+
+        let target_tab = Self::output_target_tab(target);
+        if self.selected_tab != target_tab {
+            self.selected_tab = target_tab;
+            ctx.request_repaint();
+            return;
+        }
+
+        if self.output_target_rect(target).is_none() {
+            ctx.request_repaint();
+            return;
+        }
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+        if let Some(state) = self.output_capture.as_mut() {
+            state.screenshot_requested = true;
+        }
+        ctx.request_repaint();
+    }
+
+    /// Resizes the window so its framebuffer matches the capture's fixed image size. Returns
+    /// true while still waiting for the new size to take effect.
+    fn resize_for_capture(&mut self, ctx: &egui::Context) -> bool {
+        let Some(state) = self.output_capture.as_mut() else {
+            return false;
+        };
+        let Some([width, height]) = state.image_size else {
+            return false;
+        };
+        let pixels_per_point = ctx.pixels_per_point();
+        let wanted = egui::vec2(width as f32, height as f32) / pixels_per_point;
+        let current = ctx.input(|i| i.viewport().inner_rect.map(|rect| rect.size()));
+        let reached = current.is_some_and(|size| (size - wanted).abs().max_elem() < 1.0);
+        if reached || state.resize_frames >= MAX_RESIZE_FRAMES {
+            return false;
+        }
+        if state.resize_frames == 0 {
+            if let Some(size) = current {
+                self.restore_window_size.get_or_insert(size);
+            }
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(wanted));
+        }
+        state.resize_frames += 1;
+        ctx.request_repaint();
+        true
+    }
+
+    /// Draws the tab of `target` in a separate egui context and renders it on the GPU at a
+    /// canvas sized so the plot's crop is exactly `size` pixels, whatever the window size.
+    fn render_offscreen(
+        &mut self,
+        ctx: &egui::Context,
+        render_state: &eframe::egui_wgpu::RenderState,
+        target: OutputTarget,
+        size: [u32; 2],
+    ) -> Result<image::RgbaImage, String> {
+        let max_side = render_state.device.limits().max_texture_dimension_2d;
+        let offscreen = egui::Context::default();
+        let style = ctx.global_style();
+        offscreen.set_global_style(style.clone());
+        let background = style.visuals.window_fill;
+        let pixels_per_point = ctx.pixels_per_point();
+        let tab = Self::output_target_tab(target);
+        // The window's plot areas stay in window coordinates for "Copy Image".
+        let window_rects = (
+            self.uptime_plot_rect,
+            self.polar_plot_rect,
+            self.lst_plot_rect,
+        );
+
+        let mut canvas = size;
+        let mut textures = Vec::new();
+        let mut result = Err("Failed to capture plot area.".to_string());
+        // The plot takes the space the tab's controls leave, so the canvas grows by the
+        // shortfall of each attempt until the crop comes out at `size`.
+        for _ in 0..OFFSCREEN_LAYOUT_ATTEMPTS {
+            if canvas.iter().any(|&side| side > max_side) {
+                result = Err(format!(
+                    "Image size {}x{} exceeds the GPU texture limit of {} pixels.",
+                    size[0], size[1], max_side
+                ));
+                break;
+            }
+            let raw_input = |ppp: f32| {
+                let mut input = egui::RawInput {
+                    screen_rect: Some(egui::Rect::from_min_size(
+                        egui::Pos2::ZERO,
+                        egui::vec2(canvas[0] as f32, canvas[1] as f32) / ppp,
+                    )),
+                    max_texture_side: Some(max_side as usize),
+                    ..Default::default()
+                };
+                input
+                    .viewports
+                    .entry(egui::ViewportId::ROOT)
+                    .or_default()
+                    .native_pixels_per_point = Some(ppp);
+                input
+            };
+            // A second pass lets sizes measured in the first settle.
+            let mut output = None;
+            for _ in 0..2 {
+                let full_output = offscreen.run_ui(raw_input(pixels_per_point), |ui| {
+                    egui::CentralPanel::default()
+                        .show_inside(ui, |ui| self.ui_tab_contents(ui, tab));
+                });
+                textures.push(full_output.textures_delta.clone());
+                output = Some(full_output);
+            }
+            let Some(output) = output else {
+                break;
+            };
+            let Some(rect) = self.output_target_rect(target) else {
+                break;
+            };
+            let ppp = output.pixels_per_point;
+            let primitives = offscreen.tessellate(output.shapes, ppp);
+            result = paint_offscreen(
+                render_state,
+                &primitives,
+                &textures,
+                canvas,
+                ppp,
+                background,
+            )
+            .and_then(|image| crop_plot_region(&image, rect, ppp, PLOT_CROP_MARGIN));
+            let Ok(cropped) = result.as_ref() else {
+                break;
+            };
+            let (width, height) = cropped.dimensions();
+            if [width, height] == size {
+                break;
+            }
+            canvas = [
+                (canvas[0] + size[0]).saturating_sub(width).max(size[0]),
+                (canvas[1] + size[1]).saturating_sub(height).max(size[1]),
+            ];
+        }
+        (
+            self.uptime_plot_rect,
+            self.polar_plot_rect,
+            self.lst_plot_rect,
+        ) = window_rects;
+        // Any pixel left over after the last attempt is taken up by the fit.
+        result.map(|cropped| fit_image_to_size(&cropped, size))
+    }
+
+    fn handle_output_screenshot(&mut self, image: &egui::ColorImage, pixels_per_point: f32) {
+        let (target, screenshot_requested, previous_tab) = match self.output_capture.as_ref() {
+            Some(state) => match state.targets.get(state.index).copied() {
+                Some(target) => (target, state.screenshot_requested, state.previous_tab),
+                None => return,
+            },
+            None => return,
+        };
+
+        if !screenshot_requested {
+            return;
+        }
+
+        let rect = match self.output_target_rect(target) {
+            Some(rect) => rect,
+            None => {
+                self.log(LogLevel::Error, "Failed to capture plot area.");
+                self.abort_output_capture(previous_tab);
+                return;
+            }
+        };
+
+        let image_size = self
+            .output_capture
+            .as_ref()
+            .and_then(|state| state.image_size);
+        let cropped =
+            crop_plot_region(image, rect, pixels_per_point, PLOT_CROP_MARGIN).map(|cropped| {
+                match image_size {
+                    Some(size) => fit_image_to_size(&cropped, size),
+                    None => cropped,
+                }
+            });
+        self.finish_output_image(target, previous_tab, cropped);
+    }
+
+    /// Stores one captured image of `target` (saving it, or keeping it for the report) and
+    /// moves the capture on to the next target, finishing it after the last.
+    fn finish_output_image(
+        &mut self,
+        target: OutputTarget,
+        previous_tab: AppTab,
+        image: Result<image::RgbaImage, String>,
+    ) {
+        let saved = image.and_then(|cropped| match self.output_capture.as_mut() {
+            Some(state) if state.report_path.is_some() => {
+                state.report_images.push(cropped);
+                Ok(())
+            }
+            Some(OutputCaptureState {
+                titled_path: Some(path),
+                ..
+            }) => cropped
+                .save(&*path)
+                .map_err(|e| format!("Failed to save {:?}: {}", path, e)),
+            Some(OutputCaptureState {
+                index,
+                batch: Some(batch),
+                ..
+            }) => {
+                let output_path = batch.output_dir.join(format!(
+                    "upt_azel_{}.png",
+                    batch.dates[*index].format("%Y%m%d")
+                ));
+                cropped
+                    .save(&output_path)
+                    .map_err(|e| format!("Failed to save {:?}: {}", output_path, e))
+            }
+            _ => {
+                let output_path = Self::output_target_path(target);
+                cropped
+                    .save(&output_path)
+                    .map_err(|e| format!("Failed to save {:?}: {}", output_path, e))
+            }
+        });
+        if let Err(e) = saved {
+            self.log(LogLevel::Error, e);
+            self.abort_output_capture(previous_tab);
+            return;
+        }
+
+        let mut next_target: Option<OutputTarget> = None;
+        let mut done = false;
+        if let Some(state) = self.output_capture.as_mut() {
+            state.index += 1;
+            state.screenshot_requested = false;
+            if state.index >= state.targets.len() {
+                done = true;
+            } else {
+                next_target = state.targets.get(state.index).copied();
+            }
+        }
+
+        if done {
+            self.selected_tab = previous_tab;
+            let state = self.output_capture.take();
+            let outcome = match state {
+                Some(OutputCaptureState {
+                    report_path: Some(path),
+                    report_images,
+                    ..
+                }) => self
+                    .write_report(&path, report_images)
+                    .map(|()| format!("Report saved: {}", path.display())),
+                Some(OutputCaptureState {
+                    titled_path: Some(path),
+                    ..
+                }) => Ok(format!("Image saved: {}", path.display())),
+                Some(OutputCaptureState {
+                    batch: Some(batch), ..
+                }) => {
+                    self.selected_date = batch.original_date;
+                    self.calculate_plots();
+                    Ok(format!(
+                        "Batch output complete: {} files in {}",
+                        batch.dates.len(),
+                        batch.output_dir.display()
+                    ))
+                }
+                _ => Ok("Output complete: upt_azel.png, upt_polar.png, upt_lst.png".to_string()),
+            };
+            match outcome {
+                Ok(message) => self.log(LogLevel::Info, message),
+                Err(e) => self.log(LogLevel::Error, e),
+            }
+        } else if let Some(next) = next_target {
+            match next {
+                OutputTarget::UtAzel => self.uptime_plot_rect = None,
+                OutputTarget::Polar => self.polar_plot_rect = None,
+                OutputTarget::Lst => self.lst_plot_rect = None,
+            }
+            self.selected_tab = Self::output_target_tab(next);
+        }
+    }
+
+    fn write_report(&self, path: &Path, images: Vec<image::RgbaImage>) -> Result<(), String> {
+        const MARGIN: f64 = 30.0;
+        const ROW_HEIGHT: f64 = 10.0;
+        let mut page = PdfPage::a4_landscape();
+        let top = page.height() - MARGIN;
+
+        page.text(
+            MARGIN,
+            top - 14.0,
+            16.0,
+            PdfFont::HelveticaBold,
+            &format!("Uptime plan for {}", self.selected_date.format("%Y-%m-%d")),
+        );
+        let stations: Vec<&str> = self
+            .stations
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(station, _)| station.name.as_str())
+            .collect();
+        let has_session = self.session_windows().is_some();
+        let session_text = if has_session {
+            format!("{} - {} UT", self.session_start_time, self.session_end_time)
+        } else {
+            "none".to_string()
+        };
+        page.text(
+            MARGIN,
+            top - 30.0,
+            9.0,
+            PdfFont::Helvetica,
+            &format!(
+                "Stations: {}    Elevation floor: {:.1}°    Session: {}",
+                stations.join(", "),
+                self.effective_floor_deg(),
+                session_text
+            ),
+        );
+        if !self.never_visible.is_empty() {
+            let entries: Vec<String> = self
+                .never_visible
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{}@{} (max el {:.1}°)",
+                        entry.source_name, entry.station_name, entry.max_el_deg
+                    )
+                })
+                .collect();
+            page.text(
+                MARGIN,
+                top - 42.0,
+                8.0,
+                PdfFont::Helvetica,
+                &format!("Never visible: {}", entries.join(", ")),
+            );
+        }
+
+        // Az/el plot on the left, polar plot on the right.
+        let image_top = top - 50.0;
+        let image_bottom = 220.0;
+        let az_el_width = 520.0;
+        let mut images = images.into_iter().map(|rgba| {
+            let rgb = image::DynamicImage::ImageRgba8(rgba).into_rgb8();
+            PdfImage {
+                width: rgb.width(),
+                height: rgb.height(),
+                rgb: rgb.into_raw(),
+            }
+        });
+        if let Some(az_el) = images.next() {
+            page.image(
+                az_el,
+                MARGIN,
+                image_bottom,
+                az_el_width,
+                image_top - image_bottom,
+            );
+        }
+        if let Some(polar) = images.next() {
+            let x = MARGIN + az_el_width + 10.0;
+            page.image(
+                polar,
+                x,
+                image_bottom,
+                page.width() - MARGIN - x,
+                image_top - image_bottom,
+            );
+        }
+
+        // Visibility summary in two columns underneath, warnings first.
+        let mut rows = self.visibility_summary_rows();
+        rows.sort_by_key(|(_, status, _)| *status == VisibilityStatus::Ok);
+        let header = format!(
+            "{:<12} {:<10} {:>7}  {}",
+            "Source",
+            "Station",
+            if has_session { "Sess(h)" } else { "Up(h)" },
+            "Status"
+        );
+        let rows_per_column = ((image_bottom - 10.0 - MARGIN) / ROW_HEIGHT) as usize - 1;
+        let column_width = (page.width() - 2.0 * MARGIN) / 2.0;
+        let capacity = rows_per_column * 2;
+        for column in 0..2 {
+            let x = MARGIN + column as f64 * column_width;
+            let mut y = image_bottom - 10.0;
+            page.text(x, y, 8.0, PdfFont::Courier, &header);
+            for (i, (series, status, up_hours)) in rows
+                .iter()
+                .enumerate()
+                .skip(column * rows_per_column)
+                .take(rows_per_column)
+            {
+                y -= ROW_HEIGHT;
+                let line = if i + 1 == capacity && rows.len() > capacity {
+                    format!("... {} more rows not shown", rows.len() - capacity + 1)
+                } else {
+                    format!(
+                        "{:<12} {:<10} {:>7.2}  {}",
+                        series.source_name,
+                        series.station_name,
+                        up_hours,
+                        status.label()
+                    )
+                };
+                page.text(x, y, 8.0, PdfFont::Courier, &line);
+            }
+        }
+
+        page.save(path)
+    }
+
+    /// Records which of the station and source files could not give any entries, so a first
+    /// start with a missing data directory or an emptied file explains itself instead of
+    /// showing blank lists.
+    fn check_startup_files(&mut self) {
+        self.startup_file_issues.clear();
+        if let Some(problem) =
+            catalog_file_problem(&self.station_file_path, self.stations.len(), "station")
+        {
+            self.log(LogLevel::Warn, problem.clone());
+            self.startup_file_issues
+                .push((CatalogKind::Station, problem));
+        }
+        if let Some(problem) =
+            catalog_file_problem(&self.source_file_path, self.sources.len(), "source")
+        {
+            self.log(LogLevel::Warn, problem.clone());
+            self.startup_file_issues
+                .push((CatalogKind::Source, problem));
+        }
+    }
+
+    /// Loads the station or source file from `path`, dropping the startup issue for that kind
+    /// once it gives at least one entry.
+    fn load_startup_file(&mut self, kind: CatalogKind, path: String) {
+        let result = match kind {
+            CatalogKind::Station => {
+                self.station_file_path = path;
+                self.load_stations()
+            }
+            CatalogKind::Source => {
+                self.source_file_path = path;
+                self.load_sources()
+            }
+        };
+        if let Err(e) = result {
+            self.log(LogLevel::Error, e);
+        }
+        let (path, count, what) = match kind {
+            CatalogKind::Station => (&self.station_file_path, self.stations.len(), "station"),
+            CatalogKind::Source => (&self.source_file_path, self.sources.len(), "source"),
+        };
+        let problem = catalog_file_problem(path, count, what);
+        self.startup_file_issues.retain(|(k, _)| *k != kind);
+        if let Some(problem) = problem {
+            self.startup_file_issues.push((kind, problem));
+        }
+    }
+
+    /// Writes the embedded default station or source file into the user data directory,
+    /// replacing a missing or emptied copy, and loads it.
+    fn restore_default_file(&mut self, kind: CatalogKind) {
+        let (filename, content) = match kind {
+            CatalogKind::Station => ("station.txt", DEFAULT_STATION_TXT),
+            CatalogKind::Source => ("source.txt", DEFAULT_SOURCE_TXT),
+        };
+        let Some(dir) = uptimeplot_data_dir() else {
+            self.log(LogLevel::Error, "Home directory not found.".to_string());
+            return;
+        };
+        let path = dir.join(filename);
+        if let Err(e) = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, content)) {
+            self.log(
+                LogLevel::Error,
+                format!("Failed to write {}: {}", path.display(), e),
+            );
+            return;
+        }
+        self.log(
+            LogLevel::Info,
+            format!("Restored the default {}", path.display()),
+        );
+        self.load_startup_file(kind, path.to_string_lossy().to_string());
+    }
+
+    fn show_startup_files_window(&mut self, ctx: &egui::Context) {
+        if self.startup_file_issues.is_empty() {
+            return;
+        }
+        let mut open = true;
+        let mut action: Option<(CatalogKind, bool)> = None;
+        egui::Window::new("Data Files")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Some data files could not be used:");
+                ui.add_space(5.0);
+                for (kind, problem) in &self.startup_file_issues {
+                    ui.colored_label(egui::Color32::from_rgb(220, 140, 0), problem);
+                    ui.horizontal(|ui| {
+                        let what = match kind {
+                            CatalogKind::Station => "Station",
+                            CatalogKind::Source => "Source",
+                        };
+                        if ui.button(format!("Load {} File…", what)).clicked() {
+                            action = Some((*kind, false));
+                        }
+                        if ui
+                            .button("Restore Default")
+                            .on_hover_text("Write the bundled example file to the data directory")
+                            .clicked()
+                        {
+                            action = Some((*kind, true));
+                        }
+                    });
+                    ui.add_space(5.0);
+                }
+                ui.label("Files can also be chosen later in Station Settings and Source Settings.");
+            });
+        match action {
+            Some((kind, true)) => self.restore_default_file(kind),
+            Some((kind, false)) => {
+                let title = match kind {
+                    CatalogKind::Station => "Select station.txt",
+                    CatalogKind::Source => "Select source.txt",
+                };
+                match pick_file_dialog(title) {
+                    Ok(Some(path)) => {
+                        self.load_startup_file(kind, path.to_string_lossy().to_string())
+                    }
+                    Ok(None) => {}
+                    Err(e) => self.log(LogLevel::Error, e),
+                }
+            }
+            None => {}
+        }
+        if !open {
+            self.startup_file_issues.clear();
+        }
+    }
+
+    fn show_calendar_window(&mut self, ctx: &egui::Context) {
+        if self.show_calendar {
+            let previous_date = self.selected_date;
+            let mut open = true;
+            egui::Window::new("Select Date")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if calendar_ui(ui, &mut self.selected_date) {
+                        self.show_calendar = false;
+                    }
+                });
+            if self.selected_tab == AppTab::SkdTable && self.selected_date != previous_date {
+                self.shift_skd_rows_by_days((self.selected_date - previous_date).num_days());
+                self.new_skd_start_date = self.selected_date;
+            }
+            if !open {
+                self.show_calendar = false;
+            }
+        }
+    }
+
+    /// Display and computation options, kept out of the Parameters tab so it stays focused on
+    /// files and selection. Every option here is saved to the preferences file.
+    fn show_preferences_window(&mut self, ctx: &egui::Context) {
+        if !self.show_preferences {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Preferences")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Display");
+                egui::Grid::new("preferences_display_grid")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Elevation Colors:");
+                        let previous_color_map = self.elevation_color_map;
+                        egui::ComboBox::from_id_salt("elevation_color_map")
+                            .selected_text(self.elevation_color_map.label())
+                            .show_ui(ui, |ui| {
+                                for color_map in ElevationColorMap::ALL {
+                                    ui.selectable_value(
+                                        &mut self.elevation_color_map,
+                                        color_map,
+                                        color_map.label(),
+                                    );
+                                }
+                            });
+                        if self.elevation_color_map != previous_color_map {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Time Grid:");
+                        ui.horizontal(|ui| {
+                            let mut changed = false;
+                            for alignment in GridAlignment::ALL {
+                                changed |= ui.radio_value(&mut self.grid_alignment, alignment, alignment.label()).changed();
+                            }
+                            if changed {
+                                if let Err(e) = self.save_preferences() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("LST hours puts the vertical lines of the UT plots at whole sidereal hours of the plotted station, labelled in LST");
+                        ui.end_row();
+
+                        ui.label("Lower Plot Axis:");
+                        ui.horizontal(|ui| {
+                            let elevation = ui.radio_value(&mut self.zenith_distance_axis, false, "Elevation");
+                            let zenith = ui.radio_value(&mut self.zenith_distance_axis, true, "Zenith distance");
+                            if elevation.changed() || zenith.changed() {
+                                if let Err(e) = self.save_preferences() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                            }
+                    });
+                    ui.end_row();
+
+                    ui.label("Line Width (az/el):");
+                    let line_width_response =
+                        ui.add(egui::Slider::new(&mut self.line_width, 0.5..=8.0).suffix(" px"));
+                    ui.end_row();
+
+                    ui.label("Polar Point Radius:");
+                    let point_radius_response = ui.add(
+                        egui::Slider::new(&mut self.polar_point_radius, 0.5..=10.0).suffix(" px"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Polar Label Size:");
+                    let label_size_response = ui.add(
+                        egui::Slider::new(&mut self.polar_label_size, 8.0..=32.0).suffix(" pt"),
+                    );
+                    ui.end_row();
+                    if [line_width_response, point_radius_response, label_size_response]
+                        .iter()
+                        .any(|r| r.drag_stopped() || (r.changed() && !r.dragged()))
+                    {
+                        if let Err(e) = self.save_preferences() {
+                            self.log(LogLevel::Error, e);
+                        }
+                    }
+
+                    ui.label("Time Display:");
+                    ui.horizontal(|ui| {
+                        let twelve_hour = ui.checkbox(&mut self.time_display.twelve_hour, "12-hour (AM/PM)");
+                        let seconds = ui.checkbox(&mut self.time_display.seconds, "Seconds");
+                        if twelve_hour.changed() || seconds.changed() {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Curve Drawing:");
+                    if ui
+                        .checkbox(&mut self.smooth_curves, "Smooth curves (Catmull-Rom)")
+                        .on_hover_text("Interpolate the az/el and polar tracks between samples. Computed data and exports are unchanged.")
+                        .changed()
+                    {
+                        self.polar_plot_data = self.build_polar_plot_data();
+                        if let Err(e) = self.save_preferences() {
+                            self.log(LogLevel::Error, e);
+                        }
+                    }
+                    ui.end_row();
+
+                    ui.label("Image Size:");
+                    ui.horizontal(|ui| {
+                        let previous_size = self.image_size;
+                        egui::ComboBox::from_id_salt("image_size")
+                            .selected_text(image_size_label(self.image_size))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.image_size, None, "Window");
+                                for size in IMAGE_SIZE_PRESETS {
+                                    ui.selectable_value(&mut self.image_size, Some(size), image_size_label(Some(size)));
+                                }
+                            });
+                        let preset_changed = self.image_size != previous_size;
+                        let mut side_edited = false;
+                        if let Some([width, height]) = self.image_size.as_mut() {
+                            let width = ui.add(egui::DragValue::new(width).range(MIN_IMAGE_SIDE..=MAX_IMAGE_SIDE));
+                            ui.label("×");
+                            let height = ui.add(egui::DragValue::new(height).range(MIN_IMAGE_SIDE..=MAX_IMAGE_SIDE).suffix(" px"));
+                            side_edited = [width, height].iter().any(|r| r.drag_stopped() || r.lost_focus());
+                        }
+                        // Typed or dragged sides are saved once the edit ends, not every frame.
+                        if preset_changed || side_edited {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Exported plot images are rendered offscreen at this size instead of the window size.");
+                    ui.end_row();
+
+                    ui.label("Polar Orientation:");
+                    let previous_orientation = self.polar_orientation;
+                    egui::ComboBox::from_id_salt("polar_orientation")
+                        .selected_text(self.polar_orientation.label())
+                        .show_ui(ui, |ui| {
+                            for orientation in PolarOrientation::ALL {
+                                ui.selectable_value(
+                                    &mut self.polar_orientation,
+                                    orientation,
+                                    orientation.label(),
+                                );
+                            }
+                        });
+                    if self.polar_orientation != previous_orientation {
+                        self.polar_plot_data = self.build_polar_plot_data();
+                        if let Err(e) = self.save_preferences() {
+                            self.log(LogLevel::Error, e);
+                        }
+                    }
+                    ui.end_row();
+
+                    ui.label("Polar Below-floor Color:");
+                    let below_floor = ui.color_edit_button_srgba(&mut self.polar_below_floor_color);
+                    ui.end_row();
+
+                    ui.label("Polar Horizon Color:");
+                    let horizon = ui.color_edit_button_srgba(&mut self.polar_horizon_color);
+                    ui.end_row();
+                    if below_floor.changed() || horizon.changed() {
+                        if let Err(e) = self.save_preferences() {
+                            self.log(LogLevel::Error, e);
+                        }
+                    }
+
+                    });
+                ui.separator();
+                ui.heading("Computation");
+                egui::Grid::new("preferences_computation_grid")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Reference Ellipsoid:");
+                        let previous_ellipsoid = self.ellipsoid;
+                        egui::ComboBox::from_id_salt("reference_ellipsoid")
+                            .selected_text(self.ellipsoid.label())
+                            .show_ui(ui, |ui| {
+                                for ellipsoid in utils::Ellipsoid::ALL {
+                                    ui.selectable_value(&mut self.ellipsoid, ellipsoid, ellipsoid.label());
+                                }
+                            });
+                        if self.ellipsoid != previous_ellipsoid {
+                            self.mark_skd_status_dirty();
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                            if !self.plot_data.is_empty() {
+                                self.calculate_plots();
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("dUT1 (UT1-UTC):");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut self.dut1_seconds)
+                                    .range(-MAX_DUT1_SECONDS..=MAX_DUT1_SECONDS)
+                                    .speed(0.001)
+                                    .fixed_decimals(4)
+                                    .suffix(" s"),
+                            )
+                            .on_hover_text("From IERS Bulletin A. 1 s of dUT1 shifts sidereal time by ~1 s (~15″ of hour angle); negligible for visibility planning.")
+                            .changed()
+                            && !self.plot_data.is_empty()
+                        {
+                            self.calculate_plots();
+                        }
+                        ui.end_row();
+
+                        ui.label("Coordinates:");
+                        ui.horizontal(|ui| {
+                            let previous_frame = self.reference_frame;
+                            for frame in utils::ReferenceFrame::ALL {
+                                ui.radio_value(&mut self.reference_frame, frame, frame.label());
+                            }
+                            if self.reference_frame != previous_frame {
+                                self.mark_skd_status_dirty();
+                                if let Err(e) = self.save_preferences() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                                if !self.plot_data.is_empty() {
+                                    self.calculate_plots();
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Catalogue takes RA/Dec as given. ICRS reduces them to apparent place of date (precession, nutation, aberration) and uses apparent sidereal time.");
+                        ui.end_row();
+
+                        ui.label("Sidereal Time:");
+                        let icrs = self.reference_frame == utils::ReferenceFrame::Icrs;
+                        ui.add_enabled_ui(!icrs, |ui| {
+                            ui.horizontal(|ui| {
+                                let previous_mode = self.sidereal_mode;
+                                for mode in utils::SiderealMode::ALL {
+                                    ui.radio_value(&mut self.sidereal_mode, mode, mode.label());
+                                }
+                                if self.sidereal_mode != previous_mode {
+                                    self.mark_skd_status_dirty();
+                                    if let Err(e) = self.save_preferences() {
+                                        self.log(LogLevel::Error, e);
+                                    }
+                                    if !self.plot_data.is_empty() {
+                                        self.calculate_plots();
+                                    }
+                                }
+                            })
+                        })
+                        .response
+                        .on_hover_text("Mean for catalogue (J2000) positions; apparent with apparent RA of date. They differ by the equation of the equinoxes, at most ~1.2 s.")
+                        .on_disabled_hover_text("The ICRS frame always uses apparent sidereal time.");
+                        ui.end_row();
+
+                        ui.label("Rise/Set Horizon:");
+                        ui.horizontal(|ui| {
+                            let geometric = ui.radio_value(&mut self.rise_set_refracted, false, "Geometric (el 0°)");
+                            let apparent = ui.radio_value(&mut self.rise_set_refracted, true, "Apparent (-34′)");
+                            if geometric.changed() || apparent.changed() {
+                                if let Err(e) = self.save_preferences() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                                if !self.plot_data.is_empty() {
+                                    self.calculate_plots();
+                                }
+                            }
+                    });
+                    ui.end_row();
+
+                    ui.label("Merge Windows:");
+                    let merge_response = ui
+                        .add(
+                            egui::DragValue::new(&mut self.merge_gap_min)
+                                .range(0.0..=MAX_MERGE_GAP_MIN)
+                                .speed(0.5)
+                                .prefix("gaps ≤ ")
+                                .suffix(" min"),
+                        )
+                        .on_hover_text("Report windows split by a short dip below the floor (e.g. a horizon-mask notch) as one. 0 disables merging.");
+                    if merge_response.drag_stopped() || (merge_response.changed() && !merge_response.dragged()) {
+                        if let Err(e) = self.save_preferences() {
+                            self.log(LogLevel::Error, e);
+                        }
+                        if !self.plot_data.is_empty() {
+                            self.calculate_plots();
+                        }
+                    }
+                    ui.end_row();
+
+                    ui.label("Time Diagnostics:");
+                    ui.horizontal(|ui| {
+                        ui.label("UT");
+                        ui.add(
+                            egui::DragValue::new(&mut self.diagnostic_ut_hour)
+                                .range(0.0..=24.0)
+                                .speed(0.05)
+                                .fixed_decimals(3)
+                                .suffix(" h"),
+                        );
+                    });
+                    ui.end_row();
+                    self.ui_time_diagnostics(ui);
+
+                    });
+                ui.separator();
+                ui.heading("Plot Log");
+                egui::Grid::new("preferences_log_grid")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let mut changed = false;
+                        ui.label("Logging:");
+                        changed |= ui
+                            .checkbox(&mut self.plot_log_enabled, "Append each \"Plot Selected\" run")
+                            .changed();
+                        ui.end_row();
+
+                        ui.label("Log File:");
+                        ui.horizontal(|ui| {
+                            changed |= ui.text_edit_singleline(&mut self.plot_log_path).lost_focus();
+                            if ui.button("Choose").clicked() {
+                                match save_file_dialog("Plot log file", &format!("plot_log.{}", self.plot_log_format.key())) {
+                                    Ok(Some(path)) => {
+                                        self.plot_log_path = path.to_string_lossy().to_string();
+                                        changed = true;
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Format:");
+                        ui.horizontal(|ui| {
+                            for format in PlotLogFormat::ALL {
+                                changed |= ui.radio_value(&mut self.plot_log_format, format, format.label()).changed();
+                            }
+                        });
+                        ui.end_row();
+                        if changed {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                    });
+            });
+        if !open {
+            self.show_preferences = false;
+        }
+    }
+
+    fn show_new_skd_calendar_window(&mut self, ctx: &egui::Context) {
+        if self.show_new_skd_calendar {
+            let mut open = true;
+            egui::Window::new("Select Date for New Row")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if calendar_ui(ui, &mut self.new_skd_start_date) {
+                        self.show_new_skd_calendar = false;
+                    }
+                });
+            if !open {
+                self.show_new_skd_calendar = false;
+            }
+        }
+    }
+
+    /// Azimuth and elevation of a series at its highest sample within `window`, as
+    /// (UT hour, az, el).
+    fn transit_in_window(series: &PlotSeries, window: (f64, f64)) -> Option<(f64, f64, f64)> {
+        series
+            .el_points
+            .iter()
+            .zip(&series.az_points)
+            .filter(|(el, _)| el[0] >= window.0 && el[0] <= window.1 && el[1].is_finite())
+            .max_by(|(a, _), (b, _)| a[1].total_cmp(&b[1]))
+            .map(|(el, az)| (el[0], az[1], el[1]))
+    }
+
+    /// The plotted windows chosen by `calendar_windows` as calendar events, with the station
+    /// and the az/el at transit in each description.
+    fn calendar_events(&self) -> Vec<CalendarEvent> {
+        let time_display = self.time_display;
+        let transit_text =
+            |series: &PlotSeries, window: (f64, f64)| match Self::transit_in_window(series, window)
+            {
+                Some((hour, az, el)) => format!(
+                    "{}: transit {} UT at az {:.1}°, el {:.1}°",
+                    series.station_name,
+                    time_display.format(hour),
+                    az,
+                    el
+                ),
+                None => format!("{}: no samples in the window", series.station_name),
+            };
+        let mut windows: Vec<(String, Vec<String>, (f64, f64))> = Vec::new();
+        match self.calendar_windows {
+            CalendarWindows::AboveFloor | CalendarWindows::AboveHorizon => {
+                for series in &self.plot_data {
+                    let spans = if self.calendar_windows == CalendarWindows::AboveFloor {
+                        series.windows.clone()
+                    } else {
+                        let margins: Vec<(f64, f64)> =
+                            series.el_points.iter().map(|p| (p[0], p[1])).collect();
+                        utils::visibility_windows_from_margin(&margins)
+                    };
+                    for window in spans {
+                        windows.push((
+                            format!("{} at {}", series.source_name, series.station_name),
+                            vec![transit_text(series, window)],
+                            window,
+                        ));
+                    }
+                }
+            }
+            CalendarWindows::CoVisibility => {
+                for entry in self.build_co_visibility() {
+                    let (a, b) = (
+                        &self.plot_data[entry.series.0],
+                        &self.plot_data[entry.series.1],
+                    );
+                    for &window in &entry.windows {
+                        windows.push((
+                            format!("{} on {}", entry.source_name, entry.baseline),
+                            vec![transit_text(a, window), transit_text(b, window)],
+                            window,
+                        ));
+                    }
+                }
+            }
+        }
+        let floor = match self.calendar_windows {
+            CalendarWindows::AboveHorizon => "the horizon".to_string(),
+            _ => format!("the {:.1}° floor", self.effective_floor_deg()),
+        };
+        windows
+            .into_iter()
+            .filter_map(|(summary, transits, (start, end))| {
+                let description = format!(
+                    "{}\nAbove {} from {} to {} UT on {}.\n{}",
+                    summary,
+                    floor,
+                    time_display.format(start),
+                    time_display.format(end),
+                    self.selected_date.format("%Y-%m-%d"),
+                    transits.join("\n")
+                );
+                Some(CalendarEvent {
+                    summary,
+                    description,
+                    start: utc_datetime_from_hour(self.selected_date, start)?,
+                    end: utc_datetime_from_hour(self.selected_date, end)?,
+                })
+            })
+            .collect()
+    }
+
+    fn ui_calendar_export(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Calendar export (.ics)").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Windows:");
+                egui::ComboBox::from_id_salt("calendar_windows")
+                    .selected_text(self.calendar_windows.label())
+                    .show_ui(ui, |ui| {
+                        for windows in CalendarWindows::ALL {
+                            ui.selectable_value(
+                                &mut self.calendar_windows,
+                                windows,
+                                windows.label(),
+                            );
+                        }
+                    });
+            });
+            if ui
+                .button("Save .ics")
+                .on_hover_text("One UTC event per window of the plotted sources")
+                .clicked()
+            {
+                if self.plot_data.is_empty() {
+                    self.log(
+                        LogLevel::Error,
+                        "No plot data. Please run Plot Selected first.",
+                    );
+                    return;
+                }
+                let events = self.calendar_events();
+                if events.is_empty() {
+                    self.log(LogLevel::Warn, "No windows to export.");
+                    return;
+                }
+                let default_name = format!("uptime_{}.ics", self.selected_date.format("%Y%m%d"));
+                match save_file_dialog("Save calendar as", &default_name) {
+                    Ok(Some(path)) => match fs::write(&path, ics_calendar(&events, Utc::now())) {
+                        Ok(_) => self.log(
+                            LogLevel::Info,
+                            format!("{} events saved to {}", events.len(), path.display()),
+                        ),
+                        Err(e) => {
+                            self.log(LogLevel::Error, format!("Failed to save {:?}: {}", path, e))
+                        }
+                    },
+                    Ok(None) => {}
+                    Err(e) => self.log(LogLevel::Error, e),
+                }
+            }
+        });
+    }
+
+    fn save_plot_data_to_csv(&self, path: &Path) -> Result<(), String> {
+        let csv_content = self.plot_data_table(self.csv_options.delimiter.as_char())?;
+        fs::write(path, csv_content)
+            .map_err(|e| format!("Failed to save CSV file {}: {}", path.display(), e))
+    }
+
+    /// The plotted az/el samples as delimited text, one row per UT sample and two columns per
+    /// series, with the CSV export's decimals. Lines starting with `#` describe the date,
+    /// stations and units; masked elevations are left empty.
+    fn plot_data_table(&self, delimiter: char) -> Result<String, String> {
+        if self.plot_data.is_empty() {
+            return Err("No plot data. Please run Plot Selected first.".to_string());
+        }
+        let options = &self.csv_options;
+
+        let mut stations: Vec<&str> = Vec::new();
+        for series in &self.plot_data {
+            if !stations.contains(&series.station_name.as_str()) {
+                stations.push(&series.station_name);
+            }
+        }
+        let mut csv_content = format!(
+            "# uptimeplot az/el export\n# date: {}\n# stations: {}\n# units: time = UT hours from 00:00 of the date, az/el = degrees (az N=0 E=90)\n",
+            self.selected_date.format("%Y-%m-%d"),
+            stations.join(" ")
+        );
+        let mut header = "Time".to_string();
+        let mut time_points: Vec<f64> = Vec::new();
+
+        for (i, series) in self.plot_data.iter().enumerate() {
+            let label = format!("{}_{}", series.source_name, series.station_name);
+            header.push_str(&format!("{delimiter}{label}_az{delimiter}{label}_el"));
+            if i == 0 {
+                time_points = series.az_points.iter().map(|p| p[0]).collect();
+            }
+        }
+        csv_content.push_str(&header);
+        csv_content.push('\n');
+
+        let value = |points: &[[f64; 2]], time: f64, decimals: usize| {
+            points
+                .iter()
+                .find(|p| (p[0] - time).abs() < 1e-6)
+                .filter(|p| p[1].is_finite())
+                .map_or(String::new(), |p| format!("{:.*}", decimals, p[1]))
+        };
+        for &time in &time_points {
+            let mut row = format!("{:.*}", options.time_decimals, time);
+            for series in &self.plot_data {
+                row.push(delimiter);
+                row.push_str(&value(&series.az_points, time, options.az_decimals));
+                row.push(delimiter);
+                row.push_str(&value(&series.el_points, time, options.el_decimals));
+            }
+            csv_content.push_str(&row);
+            csv_content.push('\n');
+        }
+        Ok(csv_content)
+    }
+
+    fn ui_csv_export(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("CSV export (az/el samples)").show(ui, |ui| {
+            let mut changed = false;
+            egui::Grid::new("csv_export_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Delimiter:");
+                    egui::ComboBox::from_id_salt("csv_delimiter")
+                        .selected_text(self.csv_options.delimiter.label())
+                        .show_ui(ui, |ui| {
+                            for delimiter in CsvDelimiter::ALL {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut self.csv_options.delimiter,
+                                        delimiter,
+                                        delimiter.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                    ui.end_row();
+                    for (label, decimals) in [
+                        ("Time decimals:", &mut self.csv_options.time_decimals),
+                        ("Az decimals:", &mut self.csv_options.az_decimals),
+                        ("El decimals:", &mut self.csv_options.el_decimals),
+                    ] {
+                        ui.label(label);
+                        changed |= ui
+                            .add(egui::DragValue::new(decimals).range(0..=MAX_CSV_DECIMALS))
+                            .changed();
+                        ui.end_row();
+                    }
+                });
+            if changed {
+                if let Err(e) = self.save_preferences() {
+                    self.log(LogLevel::Error, e);
+                }
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save CSV").clicked() {
+                    let default_name =
+                        format!("uptime_{}.csv", self.selected_date.format("%Y%m%d"));
+                    match save_file_dialog("Save az/el samples as", &default_name) {
+                        Ok(Some(path)) => match self.save_plot_data_to_csv(&path) {
+                            Ok(_) => {
+                                self.log(LogLevel::Info, format!("CSV saved to {}", path.display()))
+                            }
+                            Err(e) => self.log(LogLevel::Error, e),
+                        },
+                        Ok(None) => {}
+                        Err(e) => self.log(LogLevel::Error, e),
+                    }
+                }
+                if ui
+                    .button("Copy to Clipboard")
+                    .on_hover_text("Tab-separated, for pasting into a spreadsheet")
+                    .clicked()
+                {
+                    match self.plot_data_table('\t') {
+                        Ok(text) => {
+                            let rows = text.lines().filter(|l| !l.starts_with('#')).count();
+                            ui.ctx().copy_text(text);
+                            self.log(
+                                LogLevel::Info,
+                                format!("Copied {} rows of plot data to the clipboard.", rows),
+                            );
+                        }
+                        Err(e) => self.log(LogLevel::Error, e),
+                    }
+                }
+            });
+        });
+    }
+
+    /// The tracking table for `self.tracking_export`: one `ISO-8601 UTC, az, el` row per
+    /// cadence step while the source is inside the antenna's elevation limits, with the
+    /// azimuth unwrapped into the antenna's cable-wrap range. Each visible stretch is unwrapped
+    /// on its own, since the antenna may change wrap while the source is out of its limits.
+    /// Returns the text and row count.
+    fn tracking_table(&self) -> Result<(String, usize), String> {
+        let export = &self.tracking_export;
+        let antenna = self
+            .antennas
+            .get(export.antenna)
+            .ok_or_else(|| "No antenna loaded. Please check antenna.sch.".to_string())?;
+        let source = self
+            .sources
+            .iter()
+            .map(|(source, _)| source)
+            .find(|source| source.name == export.source_name)
+            .ok_or_else(|| "Pick a source for the tracking table.".to_string())?;
+        if export.cadence_sec == 0 {
+            return Err("The cadence must be at least 1 s.".to_string());
+        }
+        let start = schedule_datetime(self.selected_date, &export.start_time)?;
+        let mut end = schedule_datetime(self.selected_date, &export.end_time)?;
+        if end <= start {
+            // An end before the start runs past midnight into the next date.
+            end += Duration::days(1);
+        }
+
+        // The station of the same name supplies the pointing-model offsets, if loaded.
+        let station = self
+            .stations
+            .iter()
+            .map(|(station, _)| station)
+            .find(|station| station.name == antenna.name);
+        let mut times = Vec::new();
+        let mut az = Vec::new();
+        let mut el = Vec::new();
+        // Index in `times` where each visible stretch starts.
+        let mut segment_starts = Vec::new();
+        let mut previous_visible = false;
+        let mut time = start;
+        while time <= end {
+            let pointing = utils::radec2azalt_with(
+                self.ellipsoid,
+                self.sidereal_mode,
+                self.reference_frame,
+                antenna.pos,
+                utils::ut1_from_utc(Utc.from_utc_datetime(&time), self.dut1_seconds),
+                source.ra_rad,
+                source.dec_rad,
+            );
+            let (pointing_az, pointing_el) = station
+                .map_or((pointing.az_deg, pointing.el_deg), |station| {
+                    station.commanded_az_el(pointing.az_deg, pointing.el_deg)
+                });
+            let visible = pointing_el >= antenna.el_min_deg && pointing_el <= antenna.el_max_deg;
+            if visible {
+                if !previous_visible {
+                    segment_starts.push(times.len());
+                }
+                times.push(time);
+                az.push(pointing_az);
+                el.push(pointing_el);
+            }
+            previous_visible = visible;
+            time += Duration::seconds(export.cadence_sec as i64);
+        }
+        if times.is_empty() {
+            return Err(format!(
+                "{} is outside the elevation limits of {} for the whole span.",
+                source.name, antenna.name
+            ));
+        }
+        let mut wrapped = Vec::with_capacity(az.len());
+        for (index, &segment_start) in segment_starts.iter().enumerate() {
+            let segment_end = segment_starts.get(index + 1).copied().unwrap_or(az.len());
+            let segment = utils::unwrap_azimuths(
+                &az[segment_start..segment_end],
+                antenna.az_min_deg,
+                antenna.az_max_deg,
+            )
+            .ok_or_else(|| {
+                format!(
+                    "The track of {} from {} UT runs into the azimuth limits of {} ({:.1}° to {:.1}°).",
+                    source.name,
+                    times[segment_start].format("%H:%M:%S"),
+                    antenna.name,
+                    antenna.az_min_deg,
+                    antenna.az_max_deg
+                )
+            })?;
+            wrapped.extend(segment);
+        }
+
+        let mut table = format!(
+            "# uptimeplot tracking table\n# source: {} RA {} Dec {}\n# antenna: {} az range {:.1} to {:.1} deg, el range {:.1} to {:.1} deg\n# pointing offsets: az {:+.2} el {:+.2} arcmin\n# cadence: {} s\n# columns: time (UTC), azimuth (deg, in the antenna wrap range), elevation (deg)\n",
+            source.name,
+            utils::format_ra(source.ra_rad),
+            utils::format_dec(source.dec_rad),
+            antenna.name,
+            antenna.az_min_deg,
+            antenna.az_max_deg,
+            antenna.el_min_deg,
+            antenna.el_max_deg,
+            station.map_or(0.0, |station| station.az_offset_arcmin),
+            station.map_or(0.0, |station| station.el_offset_arcmin),
+            export.cadence_sec
+        );
+        for ((time, az), el) in times.iter().zip(&wrapped).zip(&el) {
+            table.push_str(&format!(
+                "{} {:9.4} {:8.4}\n",
+                time.format("%Y-%m-%dT%H:%M:%SZ"),
+                az,
+                el
+            ));
+        }
+        Ok((table, times.len()))
+    }
+
+    fn ui_tracking_export(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Tracking table (antenna control)").show(ui, |ui| {
+            let export = &mut self.tracking_export;
+            egui::Grid::new("tracking_export_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Source:");
+                    egui::ComboBox::from_id_salt("tracking_export_source")
+                        .selected_text(if export.source_name.is_empty() {
+                            "(pick)"
+                        } else {
+                            &export.source_name
+                        })
+                        .show_ui(ui, |ui| {
+                            for (source, _) in &self.sources {
+                                ui.selectable_value(
+                                    &mut export.source_name,
+                                    source.name.clone(),
+                                    &source.name,
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Antenna:");
+                    egui::ComboBox::from_id_salt("tracking_export_antenna")
+                        .selected_text(
+                            self.antennas
+                                .get(export.antenna)
+                                .map_or("(none in antenna.sch)", |antenna| &antenna.name),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (i, antenna) in self.antennas.iter().enumerate() {
+                                ui.selectable_value(&mut export.antenna, i, &antenna.name);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Start (UT):");
+                    ui.text_edit_singleline(&mut export.start_time);
+                    ui.end_row();
+                    ui.label("End (UT):");
+                    ui.text_edit_singleline(&mut export.end_time);
+                    ui.end_row();
+                    ui.label("Cadence:");
+                    ui.add(
+                        egui::DragValue::new(&mut export.cadence_sec)
+                            .range(1..=3600)
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+                });
+            ui.label(format!(
+                "On {}; samples outside the antenna's elevation limits are left out.",
+                self.selected_date.format("%Y-%m-%d")
+            ));
+            if ui.button("Save Tracking Table").clicked() {
+                let default_name = format!(
+                    "track_{}_{}.txt",
+                    self.tracking_export.source_name,
+                    self.selected_date.format("%Y%m%d")
+                );
+                let result =
+                    self.tracking_table().and_then(|(table, rows)| {
+                        match save_file_dialog("Save tracking table as", &default_name)? {
+                            Some(path) => match fs::write(&path, table) {
+                                Ok(_) => Ok(Some((path, rows))),
+                                Err(e) => Err(format!("Failed to save {}: {}", path.display(), e)),
+                            },
+                            None => Ok(None),
+                        }
+                    });
+                match result {
+                    Ok(Some((path, rows))) => self.log(
+                        LogLevel::Info,
+                        format!("Tracking table ({} rows) saved to {}", rows, path.display()),
+                    ),
+                    Ok(None) => {}
+                    Err(e) => self.log(LogLevel::Error, e),
+                }
+            }
+        });
+    }
+
+    fn load_drg_file(&mut self) -> Result<(), String> {
+        let path = output_drg_path(&self.input_drg_file_path)?;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read DRG file {}: {}", path.display(), e))?;
+
+        if let Some(obs_code) = parse_exper_code(&content) {
+            self.obs_code = obs_code;
+        }
+        if let Some(pi_name) = parse_pi_name(&content) {
+            self.pi_name = pi_name;
+        }
+
+        let mut sources = Vec::new();
+        for line in section_lines(&content, "$SOURCES")? {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('*') {
+                continue;
+            }
+            let parts: Vec<&str> = trimmed.split_whitespace().collect();
+            if parts.len() >= 9 {
+                sources.push((parse_source_tokens(&parts, 0, 2, trimmed)?, false));
+            }
+        }
+        if !sources.is_empty() {
+            let mut added_source = false;
+            for (drg_source, _) in sources {
+                if !self
+                    .sources
+                    .iter()
+                    .any(|(source, _)| source.name == drg_source.name)
+                {
+                    self.sources.push((drg_source, false));
+                    added_source = true;
+                }
+            }
+            if added_source {
+                self.clear_plot_data();
+                self.mark_skd_status_dirty();
+            }
+            self.interleave_target_index = self.interleave_target_index.min(self.sources.len() - 1);
+            self.interleave_cal_index = self.interleave_cal_index.min(self.sources.len() - 1);
+            self.new_skd_source_index = self.new_skd_source_index.min(self.sources.len() - 1);
+            self.five_point_cal_index = self.five_point_cal_index.min(self.sources.len() - 1);
+        }
+
+        let mut rows = Vec::new();
+        for line in section_lines(&content, "$SKED")? {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('*') {
+                continue;
+            }
+            let parts: Vec<&str> = trimmed.split_whitespace().collect();
+            if let Some(start_idx) = parts
+                .iter()
+                .position(|part| part.len() == 11 && part.chars().all(|c| c.is_ascii_digit()))
+            {
+                let (start_date, start_time) = parse_drg_timestamp(parts[start_idx])?;
+                let duration_sec = parts
+                    .get(start_idx + 1)
+                    .and_then(|part| part.parse::<u32>().ok())
+                    .ok_or_else(|| format!("Invalid SKED duration: {}", trimmed))?;
+                let az_offset_deg = parts
+                    .get(start_idx + 2)
+                    .and_then(|part| part.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                let el_offset_deg = parts
+                    .get(start_idx + 3)
+                    .and_then(|part| part.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                let ra_offset_deg = parts
+                    .get(start_idx + 4)
+                    .and_then(|part| part.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                let dec_offset_deg = parts
+                    .get(start_idx + 5)
+                    .and_then(|part| part.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                rows.push(SkdRow {
+                    source_name: parts[0].to_string(),
+                    start_date,
+                    start_time,
+                    duration_sec,
+                    az_offset_deg,
+                    el_offset_deg,
+                    ra_offset_deg,
+                    dec_offset_deg,
+                    include_station_offsets: false,
+                });
+            }
+        }
+        self.skd_rows = rows;
+        self.sort_skd_rows_by_start_time();
+        if let Some(first_row) = self.skd_rows.first() {
+            self.selected_date = first_row.start_date;
+        }
+        Ok(())
+    }
+
+    fn output_directory_from_input_drg(&self) -> Option<PathBuf> {
+        let input_path = output_drg_path(&self.input_drg_file_path).ok()?;
+        input_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    }
+
+    fn path_in_output_directory(&self, path: PathBuf) -> PathBuf {
+        let Some(output_dir) = self.output_directory_from_input_drg() else {
+            return path;
+        };
+        let Some(file_name) = path.file_name() else {
+            return path;
+        };
+        output_dir.join(file_name)
+    }
+
+    fn obs_code_output_path(&self, obs_code: &str) -> Result<PathBuf, String> {
+        obs_code_output_path(obs_code).map(|path| self.path_in_output_directory(path))
+    }
+
+    fn obs_code_station_skd_output_path(
+        &self,
+        obs_code: &str,
+        station_suffix: &str,
+    ) -> Result<PathBuf, String> {
+        obs_code_station_skd_output_path(obs_code, station_suffix)
+            .map(|path| self.path_in_output_directory(path))
+    }
+
+    fn write_skd_to_drg(&self) -> Result<Vec<PathBuf>, String> {
+        if self.skd_rows.is_empty() {
+            return Err("No SKED rows to write.".to_string());
+        }
+        let obs_code = self.obs_code.trim();
+        if obs_code.is_empty() || obs_code.split_whitespace().count() != 1 {
+            return Err("Obscode must be one non-empty word.".to_string());
+        }
+        let drg_output_path = self.obs_code_output_path(obs_code)?;
+        let skd32_output_path = self.obs_code_station_skd_output_path(obs_code, "32")?;
+        let skd34_output_path = self.obs_code_station_skd_output_path(obs_code, "34")?;
+
+        let mut source_names: Vec<String> = Vec::new();
+        for row in &self.skd_rows {
+            parse_time_string(&row.start_time)?;
+            if self.find_source(&row.source_name).is_none() {
+                return Err(format!(
+                    "Source '{}' is not loaded in Source Settings.",
+                    row.source_name
+                ));
+            }
+            if !source_names.iter().any(|name| name == &row.source_name) {
+                source_names.push(row.source_name.clone());
+            }
+        }
+
+        let mut sources_section = String::from("$SOURCES\n");
+        for source_name in &source_names {
+            let source = self.find_source(source_name).unwrap();
+            sources_section.push_str(&format_source_drg_line(source));
+            sources_section.push('\n');
+        }
+        sources_section.push_str("*\n");
+
+        let mut drg_sked_section = String::from("$SKED\n");
+        drg_sked_section
+            .push_str("*SOURCES CAL FR          START     DUR       IDLE       STATIONS  TAPE\n");
+        for row in &self.skd_rows {
+            let start = format_drg_timestamp(row.start_date, &row.start_time)?;
+            drg_sked_section.push_str(&format!(
+                "{:<8}  10 S2  PREOB {} {:>4}  MIDOB 0 POSTOB K-L- 1F00000 1F00000\n",
+                row.source_name, start, row.duration_sec
+            ));
+        }
+        drg_sked_section.push_str("*\n");
+
+        let drg_content =
+            build_new_drg_content(obs_code, &self.pi_name, &sources_section, &drg_sked_section);
+        let skd32_content =
+            build_simple_skd_content(obs_code, &sources_section, &self.skd_rows, Some("32"))?;
+        let skd34_content =
+            build_simple_skd_content(obs_code, &sources_section, &self.skd_rows, Some("34"))?;
+        fs::write(&drg_output_path, drg_content)
+            .map_err(|e| format!("Failed to write output DRG file: {}", e))?;
+        fs::write(&skd32_output_path, skd32_content)
+            .map_err(|e| format!("Failed to write output SKD32 file: {}", e))?;
+        fs::write(&skd34_output_path, skd34_content)
+            .map_err(|e| format!("Failed to write output SKD34 file: {}", e))?;
+        Ok(vec![drg_output_path, skd32_output_path, skd34_output_path])
+    }
+
+    fn generate_five_point_skd_rows(&mut self) -> Result<(), String> {
+        if self.sources.is_empty() {
+            return Err("Load source.txt first.".to_string());
+        }
+        self.five_point_cal_index = self.five_point_cal_index.min(self.sources.len() - 1);
+        parse_time_string(&self.five_point_start_time)?;
+        if self.five_point_obstime_sec == 0 {
+            return Err("Obs Time must be at least 1 second.".to_string());
+        }
+
+        let cal_name = self.sources[self.five_point_cal_index].0.name.clone();
+        let mut generated = Vec::new();
+        let mut offset_sec = 0_i64;
+        let offset_pattern = five_point_offset_pattern(self.five_point_offset_deg);
+        for &(az_offset_deg, el_offset_deg) in &offset_pattern {
+            let (start_date, start_time) =
+                offset_schedule_time(self.selected_date, &self.five_point_start_time, offset_sec)?;
+            generated.push(SkdRow {
+                source_name: cal_name.clone(),
+                start_date,
+                start_time,
+                duration_sec: self.five_point_obstime_sec,
+                az_offset_deg,
+                el_offset_deg,
+                ra_offset_deg: 0.0,
+                dec_offset_deg: 0.0,
+                include_station_offsets: self.five_point_include_station_offsets,
+            });
+            offset_sec += self.five_point_obstime_sec as i64 + self.five_point_slew_sec as i64;
+        }
+
+        if self.five_point_clear_existing {
+            self.skd_rows = generated;
+        } else {
+            self.skd_rows.extend(generated);
+        }
+        self.sort_skd_rows_by_start_time();
+        Ok(())
+    }
+
+    fn generate_interleaved_skd_rows(&mut self) -> Result<(), String> {
+        if self.sources.is_empty() {
+            return Err("Load source.txt first.".to_string());
+        }
+        self.interleave_target_index = self.interleave_target_index.min(self.sources.len() - 1);
+        self.interleave_cal_index = self.interleave_cal_index.min(self.sources.len() - 1);
+        parse_time_string(&self.interleave_start_time)?;
+        if self.interleave_cycles == 0 {
+            return Err("Cycle count must be at least 1.".to_string());
+        }
+        if self.interleave_target_duration_sec == 0 || self.interleave_cal_duration_sec == 0 {
+            return Err("Durations must be at least 1 second.".to_string());
+        }
+
+        let target_name = self.sources[self.interleave_target_index].0.name.clone();
+        let cal_name = self.sources[self.interleave_cal_index].0.name.clone();
+        let mut generated = Vec::new();
+        let mut offset_sec = 0_i64;
+
+        for cycle_idx in 0..self.interleave_cycles {
+            let (cal_date, cal_time) =
+                offset_schedule_time(self.selected_date, &self.interleave_start_time, offset_sec)?;
+            generated.push(SkdRow {
+                source_name: cal_name.clone(),
+                start_date: cal_date,
+                start_time: cal_time,
+                duration_sec: self.interleave_cal_duration_sec,
+                az_offset_deg: 0.0,
+                el_offset_deg: 0.0,
+                ra_offset_deg: 0.0,
+                dec_offset_deg: 0.0,
+                include_station_offsets: false,
+            });
+            offset_sec += self.interleave_cal_duration_sec as i64 + self.interleave_slew_sec as i64;
+
+            let (target_date, target_time) =
+                offset_schedule_time(self.selected_date, &self.interleave_start_time, offset_sec)?;
+            generated.push(SkdRow {
+                source_name: target_name.clone(),
+                start_date: target_date,
+                start_time: target_time,
+                duration_sec: self.interleave_target_duration_sec,
+                az_offset_deg: 0.0,
+                el_offset_deg: 0.0,
+                ra_offset_deg: 0.0,
+                dec_offset_deg: 0.0,
+                include_station_offsets: false,
+            });
+            offset_sec += self.interleave_target_duration_sec as i64;
+            if cycle_idx + 1 < self.interleave_cycles {
+                offset_sec += self.interleave_slew_sec as i64;
+            }
+        }
+
+        if self.interleave_clear_existing {
+            self.skd_rows = generated;
+        } else {
+            self.skd_rows.extend(generated);
+        }
+        self.sort_skd_rows_by_start_time();
+        Ok(())
+    }
+
+    fn shift_skd_rows_by_days(&mut self, days: i64) {
+        if days == 0 {
+            return;
+        }
+        for row in &mut self.skd_rows {
+            row.start_date += Duration::days(days);
+        }
+        self.sort_skd_rows_by_start_time();
+    }
+
+    fn apply_schedule_time_shift(&mut self) -> Result<(), String> {
+        if self.skd_rows.is_empty() {
+            return Err("No SKED rows to shift.".to_string());
+        }
+        let shift_sec = self.schedule_time_shift_sec as i64;
+        for row in &mut self.skd_rows {
+            let shifted =
+                schedule_datetime(row.start_date, &row.start_time)? + Duration::seconds(shift_sec);
+            row.start_date = shifted.date();
+            row.start_time = format!(
+                "{:02}:{:02}:{:02}",
+                shifted.time().hour(),
+                shifted.time().minute(),
+                shifted.time().second()
+            );
+        }
+        self.sort_skd_rows_by_start_time();
+        Ok(())
+    }
+
+    fn sort_skd_rows_by_start_time(&mut self) {
+        self.skd_rows.sort_by(|a, b| {
+            let a_dt = schedule_datetime(a.start_date, &a.start_time).ok();
+            let b_dt = schedule_datetime(b.start_date, &b.start_time).ok();
+            a_dt.cmp(&b_dt)
+        });
+        self.mark_skd_status_dirty();
+    }
+
+    fn mark_skd_status_dirty(&mut self) {
+        self.skd_status_dirty = true;
+    }
+
+    fn rebuild_skd_status_cache(&mut self) {
+        if !self.skd_status_dirty && self.skd_status_cache.len() == self.skd_rows.len() {
+            return;
+        }
+
+        let mut selected_antenna_indices = Vec::new();
+        for index in [self.selected_antenna, self.selected_antenna_2] {
+            if index < self.antennas.len() && !selected_antenna_indices.contains(&index) {
+                selected_antenna_indices.push(index);
+            }
+        }
+        let selected_antennas: Vec<Antenna> = selected_antenna_indices
+            .iter()
+            .filter_map(|&index| self.antennas.get(index).cloned())
+            .collect();
+        let ant_pos = selected_antennas
+            .first()
+            .map(|antenna| antenna.pos)
+            .or_else(|| self.current_station().map(|station| station.pos));
+        let source_map: HashMap<&str, &Source> = self
+            .sources
+            .iter()
+            .map(|(source, _)| (source.name.as_str(), source))
+            .collect();
+        let mut cache = Vec::with_capacity(self.skd_rows.len());
+        let mut prev_ends: Vec<Option<ScanEnd>> = vec![None; selected_antennas.len()];
+
+        for row in &self.skd_rows {
+            let Some(source) = source_map.get(row.source_name.as_str()).copied() else {
+                cache.push(SkdRowStatus {
+                    start_geometry: "NO".to_string(),
+                    end_geometry: "NO".to_string(),
+                    motion_1: "NO".to_string(),
+                    motion_2: "NO".to_string(),
+                });
+                prev_ends.fill(None);
+                continue;
+            };
+
+            let (start_geometry, end_geometry) = match ant_pos {
+                Some(pos) => match (
+                    scan_az_el_for(
+                        row,
+                        source,
+                        pos,
+                        false,
+                        self.ellipsoid,
+                        self.sidereal_mode,
+                        self.reference_frame,
+                    ),
+                    scan_az_el_for(
+                        row,
+                        source,
+                        pos,
+                        true,
+                        self.ellipsoid,
+                        self.sidereal_mode,
+                        self.reference_frame,
+                    ),
+                ) {
+                    (Some((_, start_az, start_el)), Some((_, end_az, end_el))) => (
+                        format!("{:5.1}/{:5.1}", start_az, start_el),
+                        format!("{:5.1}/{:5.1}", end_az, end_el),
+                    ),
+                    _ => ("NO".to_string(), "NO".to_string()),
+                },
+                None => ("No antenna".to_string(), "No antenna".to_string()),
+            };
+
+            let mut motion_values = [String::new(), String::new()];
+            if selected_antennas.is_empty() {
+                motion_values[0] = "Load ant".to_string();
+            } else {
+                for (ant_idx, antenna) in selected_antennas.iter().take(2).enumerate() {
+                    let (antenna_motion, current_end) = antenna_motion_status(
+                        row,
+                        source,
+                        antenna,
+                        prev_ends[ant_idx],
+                        self.ellipsoid,
+                        self.sidereal_mode,
+                        self.reference_frame,
+                    );
+                    prev_ends[ant_idx] = current_end;
+                    motion_values[ant_idx] = antenna_motion;
+                }
+            }
+
+            cache.push(SkdRowStatus {
+                start_geometry,
+                end_geometry,
+                motion_1: motion_values[0].clone(),
+                motion_2: motion_values[1].clone(),
+            });
+        }
+
+        self.skd_status_cache = cache;
+        self.skd_status_dirty = false;
+    }
+
+    /// Session start and end in UT hours; the end may be earlier than the start when the
+    /// session runs past 24:00 UT.
+    fn session_bounds(&self) -> Option<(f64, f64)> {
+        if !self.session_enabled {
+            return None;
+        }
+        let (start_h, start_m, start_s) = parse_time_string(&self.session_start_time).ok()?;
+        let (end_h, end_m, end_s) = parse_time_string(&self.session_end_time).ok()?;
+        Some((
+            start_h as f64 + start_m as f64 / 60.0 + start_s as f64 / 3600.0,
+            end_h as f64 + end_m as f64 / 60.0 + end_s as f64 / 3600.0,
+        ))
+    }
+
+    /// UT hour intervals of the observing session; `None` when no session is set.
+    fn session_windows(&self) -> Option<Vec<(f64, f64)>> {
+        let (start, end) = self.session_bounds()?;
+        Some(if start < end {
+            vec![(start, end)]
+        } else if start > end {
+            // The session runs past 24:00 UT.
+            vec![(0.0, end), (start, 24.0)]
+        } else {
+            vec![(0.0, 24.0)]
+        })
+    }
+
+    /// Orders the selected sources to minimise slew time for the first SKD antenna, with every
+    /// source pointed at the session midpoint (12:00 UT without a session).
+    fn suggest_observing_order(&self) -> Result<SuggestedOrder, String> {
+        let antenna = self
+            .antennas
+            .get(self.selected_antenna)
+            .ok_or_else(|| "Load antenna.sch to use its slew model.".to_string())?;
+        let reference_hour = match self.session_bounds() {
+            Some((start, end)) if start <= end => (start + end) / 2.0,
+            Some((start, end)) => ((start + end + 24.0) / 2.0).rem_euclid(24.0),
+            None => 12.0,
+        };
+        let time = utc_datetime_from_hour(self.selected_date, reference_hour)
+            .ok_or_else(|| "Invalid reference time.".to_string())?;
+
+        let mut candidates = Vec::new();
+        let mut skipped = Vec::new();
+        for (source, _) in self.sources.iter().filter(|(_, selected)| *selected) {
+            let pointing = utils::radec2azalt_with(
+                self.ellipsoid,
+                self.sidereal_mode,
+                self.reference_frame,
+                antenna.pos,
+                utils::ut1_from_utc(time, self.dut1_seconds),
+                source.ra_rad,
+                source.dec_rad,
+            );
+            let az = pointing.az_deg.rem_euclid(360.0);
+            if pointing.el_deg < antenna.el_min_deg
+                || pointing.el_deg > antenna.el_max_deg
+                || antenna.az_in_limit(az).is_none()
+            {
+                skipped.push(source.name.clone());
+            } else {
+                candidates.push((source.name.clone(), az, pointing.el_deg, source.priority));
+            }
+        }
+        if candidates.len() < 2 {
+            return Err(format!(
+                "Need at least two selected sources within {}'s limits at {} UT.",
+                antenna.name,
+                self.time_display.format(reference_hour)
+            ));
+        }
+
+        let cost: Vec<Vec<f64>> = candidates
+            .iter()
+            .map(|(_, from_az, from_el, _)| {
+                candidates
+                    .iter()
+                    .map(|(_, to_az, to_el, _)| {
+                        antenna
+                            .slew_seconds(*from_az, *from_el, *to_az, *to_el)
+                            .unwrap_or(f64::INFINITY)
+                    })
+                    .collect()
+            })
+            .collect();
+        // Each priority level above the default makes a source's wait for every earlier
+        // visit cost 10% of a typical slew (and each level below makes waiting cheaper), so
+        // the path reaches higher-priority sources sooner; the reported total is still the
+        // real slew time.
+        let finite: Vec<f64> = cost
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+            .collect();
+        let typical_slew = if finite.is_empty() {
+            1.0
+        } else {
+            finite.iter().sum::<f64>() / finite.len() as f64
+        };
+        let visit_penalty: Vec<f64> = candidates
+            .iter()
+            .map(|candidate| typical_slew * (utils::priority_weight(candidate.3) - 1.0))
+            .collect();
+        let order = utils::order_minimizing_cost(&cost, &visit_penalty);
+        Ok(SuggestedOrder {
+            total_slew_sec: utils::path_cost(&cost, &order),
+            source_names: order.iter().map(|&i| candidates[i].0.clone()).collect(),
+            reference_hour,
+            antenna_name: antenna.name.clone(),
+            skipped,
+        })
+    }
+
+    /// Moves the suggested sources to the top of the source list in the suggested order.
+    fn accept_suggested_order(&mut self) {
+        let Some(suggestion) = self.suggested_order.take() else {
+            return;
+        };
+        let mut remaining = std::mem::take(&mut self.sources);
+        for name in &suggestion.source_names {
+            if let Some(index) = remaining
+                .iter()
+                .position(|(source, _)| &source.name == name)
+            {
+                self.sources.push(remaining.remove(index));
+            }
+        }
+        self.sources.extend(remaining);
+        self.source_list_cache = None;
+        if !self.plot_data.is_empty() {
+            self.calculate_plots();
+        }
+    }
+
+    fn ui_suggested_order(&mut self, ui: &mut egui::Ui) {
+        let Some(suggestion) = &self.suggested_order else {
+            return;
+        };
+        let mut accept = false;
+        let mut dismiss = false;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.label(format!(
+                "Suggested order for {} at {} UT:",
+                suggestion.antenna_name,
+                self.time_display.format(suggestion.reference_hour)
+            ));
+            ui.label(suggestion.source_names.join(" → "));
+            if suggestion.total_slew_sec.is_finite() {
+                ui.label(format!(
+                    "Total slew overhead: {:.0} s",
+                    suggestion.total_slew_sec
+                ));
+            } else {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Some steps cannot be slewed within the antenna limits.",
+                );
+            }
+            if !suggestion.skipped.is_empty() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Outside limits: {}", suggestion.skipped.join(", ")),
+                );
+            }
+            ui.horizontal(|ui| {
+                accept = ui.button("Accept").clicked();
+                dismiss = ui.button("Dismiss").clicked();
+            });
+        });
+        if accept {
+            self.accept_suggested_order();
+        } else if dismiss {
+            self.suggested_order = None;
+        }
+    }
+
+    fn shade_fast_az_spans(&self, plot_ui: &mut egui_plot::PlotUi, panel_station: Option<usize>) {
+        let panel_name = panel_station.and_then(|panel_idx| {
+            self.plotted_stations()
+                .into_iter()
+                .find(|&(idx, _)| idx == panel_idx)
+                .map(|(_, name)| name)
+        });
+        for warning in &self.fast_track_warnings {
+            if panel_name.is_some_and(|name| name != warning.station_name) {
+                continue;
+            }
+            for &(start, end) in &warning.windows {
+                plot_ui.span(
+                    egui_plot::Span::new(
+                        format!("Az rate > {:.2}°/s", self.max_az_rate_deg_per_sec),
+                        start..=end,
+                    )
+                    .fill(egui::Color32::from_rgba_unmultiplied(255, 0, 0, 40))
+                    .border_width(0.0),
+                );
+            }
+        }
+    }
+
+    fn shade_outside_session(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let Some(session) = self.session_windows() else {
+            return;
+        };
+        for (start, end) in utils::complement_windows(&session, 0.0, self.ut_plot_end()) {
+            plot_ui.span(
+                egui_plot::Span::new("", start..=end)
+                    .fill(egui::Color32::from_rgba_unmultiplied(128, 128, 128, 48))
+                    .border_width(0.0),
+            );
+        }
+    }
+
+    fn find_source(&self, name: &str) -> Option<&Source> {
+        self.sources
+            .iter()
+            .map(|(source, _)| source)
+            .find(|source| source.name == name)
+    }
+
+    /// The source behind the plotted series `name`; a mosaic envelope `X-mosaic` resolves to
+    /// its centre `X`.
+    fn series_source(&self, name: &str) -> Option<&Source> {
+        self.find_source(name).or_else(|| {
+            name.strip_suffix("-mosaic")
+                .and_then(|center| self.find_source(center))
+        })
+    }
+
+    fn ui_skd_table_tab(&mut self, ui: &mut egui::Ui) {
+        let available = ui.available_size();
+        let left_width = (available.x / 3.0).max(300.0);
+        let right_width = (available.x - left_width - 12.0).max(500.0);
+        let parameter_panel_width = (left_width - 28.0).max(280.0);
+
+        ui.horizontal(|ui| {
+            ui.allocate_ui_with_layout(
+                egui::vec2(left_width, available.y),
+                egui::Layout::top_down(egui::Align::Min),
+                |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("skd_left_parameters")
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            ui.group(|ui| {
+                                ui.set_min_width(parameter_panel_width);
+                                ui.set_max_width(parameter_panel_width);
+                                ui.label("Input DRG");
+                                ui.horizontal_wrapped(|ui| {
+                                    if ui.button("Load DRG").clicked() {
+                                        match pick_file_dialog("Select DRG file") {
+                                            Ok(Some(path)) => {
+                                                self.input_drg_file_path =
+                                                    path.to_string_lossy().to_string();
+                                                match self.load_drg_file() {
+                                                    Ok(_) => {
+                                                        self.log(LogLevel::Info, "Loaded DRG.")
+                                                    }
+                                                    Err(e) => self.log(LogLevel::Error, e),
+                                                }
+                                            }
+                                            Ok(None) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    if ui.button("Open Input").clicked() {
+                                        let input_path = output_drg_path(&self.input_drg_file_path)
+                                            .map(|path| path.to_string_lossy().to_string());
+                                        match input_path.and_then(|path| {
+                                            utils::open_file_in_external_editor(&path)
+                                        }) {
+                                            Ok(_) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    let input_drg_path_text = if self.input_drg_file_path.is_empty()
+                                    {
+                                        "<DRG path>"
+                                    } else {
+                                        self.input_drg_file_path.as_str()
+                                    };
+                                    ui.add_sized(
+                                        [parameter_panel_width, 20.0],
+                                        egui::Label::new(
+                                            egui::RichText::new(input_drg_path_text).monospace(),
+                                        )
+                                        .truncate(),
+                                    )
+                                    .on_hover_text(input_drg_path_text);
+                                });
+                            });
+
+                            ui.group(|ui| {
+                                ui.set_min_width(parameter_panel_width);
+                                ui.set_max_width(parameter_panel_width);
+                                ui.label("Output");
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("Obscode:");
+                                    ui.add_sized(
+                                        [120.0, 20.0],
+                                        egui::TextEdit::singleline(&mut self.obs_code),
+                                    );
+                                    ui.label("PI:");
+                                    ui.add_sized(
+                                        [120.0, 20.0],
+                                        egui::TextEdit::singleline(&mut self.pi_name),
+                                    );
+                                });
+                                ui.horizontal_wrapped(|ui| {
+                                    if ui.button("Create DRG").clicked() {
+                                        match self.write_skd_to_drg() {
+                                            Ok(paths) => self.log(
+                                                LogLevel::Info,
+                                                format!(
+                                                    "Created {}",
+                                                    paths
+                                                        .iter()
+                                                        .map(|path| path.display().to_string())
+                                                        .collect::<Vec<_>>()
+                                                        .join(", ")
+                                                ),
+                                            ),
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    if ui.button("Open DRG").clicked() {
+                                        let output_path = self
+                                            .obs_code_output_path(self.obs_code.trim())
+                                            .map(|path| path.to_string_lossy().to_string());
+                                        match output_path.and_then(|path| {
+                                            utils::open_file_in_external_editor(&path)
+                                        }) {
+                                            Ok(_) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    match (
+                                        self.obs_code_output_path(self.obs_code.trim()),
+                                        self.obs_code_station_skd_output_path(
+                                            self.obs_code.trim(),
+                                            "32",
+                                        ),
+                                        self.obs_code_station_skd_output_path(
+                                            self.obs_code.trim(),
+                                            "34",
+                                        ),
+                                    ) {
+                                        (Ok(drg_path), Ok(skd32_path), Ok(skd34_path)) => {
+                                            let output_path_text = format!(
+                                                "{} | {} | {}",
+                                                drg_path.display(),
+                                                skd32_path.display(),
+                                                skd34_path.display()
+                                            );
+                                            ui.add_sized(
+                                                [parameter_panel_width, 20.0],
+                                                egui::Label::new(
+                                                    egui::RichText::new(output_path_text.as_str())
+                                                        .monospace(),
+                                                )
+                                                .truncate(),
+                                            )
+                                            .on_hover_text(output_path_text);
+                                        }
+                                        _ => {
+                                            ui.add_sized(
+                                                [parameter_panel_width, 20.0],
+                                                egui::Label::new(
+                                                    egui::RichText::new("<DRG/SKD>").monospace(),
+                                                )
+                                                .truncate(),
+                                            );
+                                        }
+                                    }
+                                });
+                            });
+
+                            ui.group(|ui| {
+                                ui.set_min_width(parameter_panel_width);
+                                ui.set_max_width(parameter_panel_width);
+                                ui.label("Source List");
+                                ui.horizontal_wrapped(|ui| {
+                                    if ui.button("Load Sources").clicked() {
+                                        match pick_file_dialog("Select source.txt") {
+                                            Ok(Some(path)) => {
+                                                self.source_file_path =
+                                                    path.to_string_lossy().to_string();
+                                                match self.load_sources() {
+                                                    Ok(_) => {}
+                                                    Err(e) => self.log(LogLevel::Error, e),
+                                                }
+                                            }
+                                            Ok(None) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    if ui.button("Open Sources").clicked() {
+                                        match utils::open_file_in_external_editor(
+                                            &self.source_file_path,
+                                        ) {
+                                            Ok(_) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    ui.label(format!("{} sources", self.sources.len()));
+                                    ui.add_sized(
+                                        [parameter_panel_width, 20.0],
+                                        egui::Label::new(
+                                            egui::RichText::new(self.source_file_path.as_str())
+                                                .monospace(),
+                                        )
+                                        .truncate(),
+                                    )
+                                    .on_hover_text(self.source_file_path.as_str());
+                                });
+                            });
+
+                            ui.group(|ui| {
+                                ui.set_min_width(parameter_panel_width);
+                                ui.set_max_width(parameter_panel_width);
+                                ui.label("Antenna SCH");
+                                ui.horizontal_wrapped(|ui| {
+                                    if ui.button("Load Antennas").clicked() {
+                                        match pick_file_dialog("Select antenna.sch") {
+                                            Ok(Some(path)) => {
+                                                self.antenna_file_path =
+                                                    path.to_string_lossy().to_string();
+                                                match self.load_antennas() {
+                                                    Ok(_) => {}
+                                                    Err(e) => self.log(LogLevel::Error, e),
+                                                }
+                                            }
+                                            Ok(None) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    if ui.button("Open Antennas").clicked() {
+                                        match utils::open_file_in_external_editor(
+                                            &self.antenna_file_path,
+                                        ) {
+                                            Ok(_) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                    ui.add_sized(
+                                        [parameter_panel_width, 20.0],
+                                        egui::Label::new(
+                                            egui::RichText::new(self.antenna_file_path.as_str())
+                                                .monospace(),
+                                        )
+                                        .truncate(),
+                                    )
+                                    .on_hover_text(self.antenna_file_path.as_str());
+                                });
+                                if self.antennas.is_empty() {
+                                    ui.label("No antenna loaded");
+                                } else {
+                                    self.selected_antenna =
+                                        self.selected_antenna.min(self.antennas.len() - 1);
+                                    self.selected_antenna_2 =
+                                        self.selected_antenna_2.min(self.antennas.len() - 1);
+                                    let old_selected_antenna = self.selected_antenna;
+                                    let old_selected_antenna_2 = self.selected_antenna_2;
+                                    for (selected, combo_id) in [
+                                        (&mut self.selected_antenna, "skd_antenna_1"),
+                                        (&mut self.selected_antenna_2, "skd_antenna_2"),
+                                    ] {
+                                        ui.horizontal_wrapped(|ui| {
+                                            egui::ComboBox::from_id_salt(combo_id)
+                                                .selected_text(&self.antennas[*selected].name)
+                                                .show_ui(ui, |ui| {
+                                                    for (i, antenna) in
+                                                        self.antennas.iter().enumerate()
+                                                    {
+                                                        ui.selectable_value(
+                                                            selected,
+                                                            i,
+                                                            format!(
+                                                                "{} {}",
+                                                                antenna.code, antenna.name
+                                                            ),
+                                                        );
+                                                    }
+                                                });
+                                            let antenna = &self.antennas[*selected];
+                                            ui.label(format!(
+                                        "AZ {:.1}-{:.1} {:.1}/min, EL {:.1}-{:.1} {:.1}/min",
+                                        antenna.az_min_deg,
+                                        antenna.az_max_deg,
+                                        antenna.az_rate_deg_per_min,
+                                        antenna.el_min_deg,
+                                        antenna.el_max_deg,
+                                        antenna.el_rate_deg_per_min
+                                    ));
+                                        });
+                                    }
+                                    if self.selected_antenna != old_selected_antenna
+                                        || self.selected_antenna_2 != old_selected_antenna_2
+                                    {
+                                        self.mark_skd_status_dirty();
+                                    }
+                                }
+                            });
+
+                            ui.group(|ui| {
+                                ui.set_min_width(parameter_panel_width);
+                                ui.set_max_width(parameter_panel_width);
+                                ui.label("Schedule Date / Time Shift");
+                                ui.horizontal_wrapped(|ui| {
+                                    if ui
+                                        .button(self.selected_date.format("%Y-%m-%d").to_string())
+                                        .clicked()
+                                    {
+                                        self.show_calendar = !self.show_calendar;
+                                    }
+                                    ui.label("Time Shift:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.schedule_time_shift_sec)
+                                            .speed(1)
+                                            .suffix(" s"),
+                                    );
+                                    if ui.button("Apply to All").clicked() {
+                                        match self.apply_schedule_time_shift() {
+                                            Ok(_) => self.log(
+                                                LogLevel::Info,
+                                                format!(
+                                                    "Shifted all scans by {} seconds.",
+                                                    self.schedule_time_shift_sec
+                                                ),
+                                            ),
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
+                                    }
+                                });
+                            });
+
+                            if self.sources.is_empty() {
+                                ui.label("Load source.txt first");
+                            } else {
+                                ui.group(|ui| {
+                                    ui.set_min_width(parameter_panel_width);
+                                    ui.set_max_width(parameter_panel_width);
+                                    self.interleave_target_index =
+                                        self.interleave_target_index.min(self.sources.len() - 1);
+                                    self.interleave_cal_index =
+                                        self.interleave_cal_index.min(self.sources.len() - 1);
+                                    ui.label("Target / Gain Calibrator");
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("Target:");
+                                        ui.monospace(
+                                            &self.sources[self.interleave_target_index].0.name,
+                                        );
+                                        if ui.button("Select Target").clicked() {
+                                            self.target_picker_open = true;
+                                        }
+                                        ui.label("Target Dur:");
+                                        ui.add(
+                                            egui::DragValue::new(
+                                                &mut self.interleave_target_duration_sec,
+                                            )
+                                            .speed(10)
+                                            .range(1..=86400)
+                                            .suffix(" s"),
+                                        );
+                                    });
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("Gain Cal:");
+                                        ui.monospace(
+                                            &self.sources[self.interleave_cal_index].0.name,
+                                        );
+                                        if ui.button("Select Gain Cal").clicked() {
+                                            self.cal_picker_open = true;
+                                        }
+                                        ui.label("Calib Dur:");
+                                        ui.add(
+                                            egui::DragValue::new(
+                                                &mut self.interleave_cal_duration_sec,
+                                            )
+                                            .speed(10)
+                                            .range(1..=86400)
+                                            .suffix(" s"),
+                                        );
+                                    });
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("Start UT:");
+                                        ui.add_sized(
+                                            [86.0, 20.0],
+                                            egui::TextEdit::singleline(
+                                                &mut self.interleave_start_time,
+                                            ),
+                                        );
+                                        ui.label("Slew:");
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.interleave_slew_sec)
+                                                .speed(10)
+                                                .range(0..=86400)
+                                                .suffix(" s"),
+                                        );
+                                        ui.label("Cycle:");
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.interleave_cycles)
+                                                .speed(1)
+                                                .range(1..=1000),
+                                        );
+                                        ui.checkbox(
+                                            &mut self.interleave_clear_existing,
+                                            "Replace table",
+                                        );
+                                        if ui.button("Generate").clicked() {
+                                            match self.generate_interleaved_skd_rows() {
+                                                Ok(_) => {}
+                                                Err(e) => self.log(LogLevel::Error, e),
+                                            }
+                                        }
+                                    });
+                                });
+
+                                ui.group(|ui| {
+                                    ui.set_min_width(parameter_panel_width);
+                                    ui.set_max_width(parameter_panel_width);
+                                    self.five_point_cal_index =
+                                        self.five_point_cal_index.min(self.sources.len() - 1);
+                                    ui.label("Five-point Observation");
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("Gain Cal:");
+                                        ui.monospace(
+                                            &self.sources[self.five_point_cal_index].0.name,
+                                        );
+                                        if ui.button("Select Five-point Cal").clicked() {
+                                            self.five_point_picker_open = true;
+                                        }
+                                        ui.label("Start UT:");
+                                        ui.add_sized(
+                                            [86.0, 20.0],
+                                            egui::TextEdit::singleline(
+                                                &mut self.five_point_start_time,
+                                            ),
+                                        );
+                                        ui.label("Obs Time:");
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.five_point_obstime_sec)
+                                                .speed(10)
+                                                .range(1..=86400)
+                                                .suffix(" s"),
+                                        );
+                                        ui.label("Slew:");
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.five_point_slew_sec)
+                                                .speed(10)
+                                                .range(0..=86400)
+                                                .suffix(" s"),
+                                        );
+                                        ui.label("Offset:");
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.five_point_offset_deg)
+                                                .speed(0.1)
+                                                .suffix(" arcmin"),
+                                        );
+                                        ui.checkbox(
+                                            &mut self.five_point_include_station_offsets,
+                                            "Include 32/34 +/-2 arcmin",
+                                        );
+                                        ui.checkbox(
+                                            &mut self.five_point_clear_existing,
+                                            "Replace table",
+                                        );
+                                        if ui.button("Generate 10 Scans").clicked() {
+                                            match self.generate_five_point_skd_rows() {
+                                                Ok(_) => {}
+                                                Err(e) => self.log(LogLevel::Error, e),
+                                            }
+                                        }
+                                    });
+                                });
+
+                                ui.group(|ui| {
+                                    ui.set_min_width(parameter_panel_width);
+                                    ui.set_max_width(parameter_panel_width);
+                                    ui.label("New Row");
+                                    self.new_skd_source_index =
+                                        self.new_skd_source_index.min(self.sources.len() - 1);
+                                    ui.horizontal_wrapped(|ui| {
+                                        egui::ComboBox::from_id_salt("new_skd_source")
+                                            .selected_text(
+                                                &self.sources[self.new_skd_source_index].0.name,
+                                            )
+                                            .show_ui(ui, |ui| {
+                                                for (i, (source, _)) in
+                                                    self.sources.iter().enumerate()
+                                                {
+                                                    ui.selectable_value(
+                                                        &mut self.new_skd_source_index,
+                                                        i,
+                                                        &source.name,
+                                                    );
+                                                }
+                                            });
+                                        if ui
+                                            .button(
+                                                self.new_skd_start_date
+                                                    .format("%Y-%m-%d")
+                                                    .to_string(),
+                                            )
+                                            .clicked()
+                                        {
+                                            self.show_new_skd_calendar =
+                                                !self.show_new_skd_calendar;
+                                        }
+                                        ui.add_sized(
+                                            [86.0, 20.0],
+                                            egui::TextEdit::singleline(
+                                                &mut self.new_skd_start_time,
+                                            ),
+                                        );
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.new_skd_duration_sec)
+                                                .speed(10)
+                                                .range(1..=86400)
+                                                .suffix(" s"),
+                                        );
+                                        if ui.button("Add").clicked() {
+                                            if parse_time_string(&self.new_skd_start_time).is_ok() {
+                                                self.skd_rows.push(SkdRow {
+                                                    source_name: self.sources
+                                                        [self.new_skd_source_index]
+                                                        .0
+                                                        .name
+                                                        .clone(),
+                                                    start_date: self.new_skd_start_date,
+                                                    start_time: normalize_time_string(
+                                                        &self.new_skd_start_time,
+                                                    )
+                                                    .unwrap_or_else(|| {
+                                                        self.new_skd_start_time.clone()
+                                                    }),
+                                                    duration_sec: self.new_skd_duration_sec,
+                                                    az_offset_deg: 0.0,
+                                                    el_offset_deg: 0.0,
+                                                    ra_offset_deg: 0.0,
+                                                    dec_offset_deg: 0.0,
+                                                    include_station_offsets: false,
+                                                });
+                                                self.sort_skd_rows_by_start_time();
+                                            } else {
+                                                self.log(
+                                                    LogLevel::Error,
+                                                    "Start time must be HH:MM:SS or HHMMSS.",
+                                                );
+                                            }
+                                        }
+                                    });
+                                });
+                            }
+                        });
+                },
+            );
+
+            ui.separator();
+            ui.allocate_ui_with_layout(
+                egui::vec2(right_width, available.y),
+                egui::Layout::top_down(egui::Align::Min),
+                |ui| {
+                    self.rebuild_skd_status_cache();
+                    let mut remove_idx = None;
+                    let mut table_changed = false;
+                    egui::ScrollArea::both()
+                        .id_salt("skd_schedule_scroll")
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            ui.set_min_width(SKD_TABLE_MIN_WIDTH);
+                            ui.horizontal(|ui| {
+                                show_table_text_cell(ui, SKD_COL_NUM, 18.0, "#");
+                                show_table_text_cell(ui, SKD_COL_SOURCE, 18.0, "Source");
+                                show_table_text_cell(ui, SKD_COL_DATE, 18.0, "Date");
+                                show_table_text_cell(ui, SKD_COL_TIME, 18.0, "Start UT");
+                                show_table_text_cell(ui, SKD_COL_DURATION, 18.0, "Duration");
+                                show_table_text_cell(ui, SKD_COL_AZEL, 18.0, "Start Az/El");
+                                show_table_text_cell(ui, SKD_COL_AZEL, 18.0, "End Az/El");
+                                show_table_text_cell(ui, SKD_COL_ANTENNA, 18.0, "Antenna 1");
+                                show_table_text_cell(ui, SKD_COL_ANTENNA, 18.0, "Antenna 2");
+                                show_table_text_cell(ui, SKD_COL_DELETE, 18.0, "");
+                            });
+                            ui.horizontal(|ui| {
+                                show_table_text_cell(ui, SKD_COL_NUM, 16.0, "");
+                                show_table_text_cell(ui, SKD_COL_SOURCE, 16.0, "");
+                                show_table_text_cell(ui, SKD_COL_DATE, 16.0, "YYYY-MM-DD");
+                                show_table_text_cell(ui, SKD_COL_TIME, 16.0, "hh:mm:ss");
+                                show_table_text_cell(ui, SKD_COL_DURATION, 16.0, "s");
+                                show_table_text_cell(ui, SKD_COL_AZEL, 16.0, "deg");
+                                show_table_text_cell(ui, SKD_COL_AZEL, 16.0, "deg");
+                                show_table_text_cell(ui, SKD_COL_ANTENNA, 16.0, "limits / slew");
+                                show_table_text_cell(ui, SKD_COL_ANTENNA, 16.0, "limits / slew");
+                                show_table_text_cell(ui, SKD_COL_DELETE, 16.0, "");
+                            });
+                            ui.separator();
+
+                            for i in 0..self.skd_rows.len() {
+                                let status = self.skd_status_cache.get(i);
+                                let (start_geometry, end_geometry, motion_check_1, motion_check_2) =
+                                    if let Some(s) = status {
+                                        (
+                                            s.start_geometry.as_str(),
+                                            s.end_geometry.as_str(),
+                                            s.motion_1.as_str(),
+                                            s.motion_2.as_str(),
+                                        )
+                                    } else {
+                                        ("...", "...", "...", "...")
+                                    };
+                                ui.horizontal(|ui| {
+                                    ui.add_sized(
+                                        [SKD_COL_NUM, 20.0],
+                                        egui::Label::new((i + 1).to_string()),
+                                    );
+                                    let selected_source = self.skd_rows[i].source_name.as_str();
+                                    egui::ComboBox::from_id_salt(format!("skd_source_{}", i))
+                                        .width(SKD_COL_SOURCE)
+                                        .selected_text(source_table_text(selected_source))
+                                        .show_ui(ui, |ui| {
+                                            for (source, _) in &self.sources {
+                                                if ui
+                                                    .selectable_value(
+                                                        &mut self.skd_rows[i].source_name,
+                                                        source.name.clone(),
+                                                        &source.name,
+                                                    )
+                                                    .changed()
+                                                {
+                                                    table_changed = true;
+                                                }
                                             }
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    if ui.button("Open DRG").clicked() {
-                                        let output_path = self
-                                            .obs_code_output_path(self.obs_code.trim())
-                                            .map(|path| path.to_string_lossy().to_string());
-                                        match output_path.and_then(|path| {
-                                            utils::open_file_in_external_editor(&path)
-                                        }) {
-                                            Ok(_) => self.error_msg = None,
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    match (
-                                        self.obs_code_output_path(self.obs_code.trim()),
-                                        self.obs_code_station_skd_output_path(
-                                            self.obs_code.trim(),
-                                            "32",
-                                        ),
-                                        self.obs_code_station_skd_output_path(
-                                            self.obs_code.trim(),
-                                            "34",
+                                        });
+                                    ui.add_sized(
+                                        [SKD_COL_DATE, 20.0],
+                                        egui::Label::new(
+                                            self.skd_rows[i]
+                                                .start_date
+                                                .format("%Y-%m-%d")
+                                                .to_string(),
                                         ),
-                                    ) {
-                                        (Ok(drg_path), Ok(skd32_path), Ok(skd34_path)) => {
-                                            let output_path_text = format!(
-                                                "{} | {} | {}",
-                                                drg_path.display(),
-                                                skd32_path.display(),
-                                                skd34_path.display()
-                                            );
-                                            ui.add_sized(
-                                                [parameter_panel_width, 20.0],
-                                                egui::Label::new(
-                                                    egui::RichText::new(output_path_text.as_str())
-                                                        .monospace(),
-                                                )
-                                                .truncate(),
+                                    );
+                                    if ui
+                                        .add_sized(
+                                            [SKD_COL_TIME, 20.0],
+                                            egui::TextEdit::singleline(
+                                                &mut self.skd_rows[i].start_time,
+                                            ),
+                                        )
+                                        .changed()
+                                    {
+                                        table_changed = true;
+                                    }
+                                    if ui
+                                        .add_sized(
+                                            [SKD_COL_DURATION, 20.0],
+                                            egui::DragValue::new(
+                                                &mut self.skd_rows[i].duration_sec,
                                             )
-                                            .on_hover_text(output_path_text);
-                                        }
-                                        _ => {
-                                            ui.add_sized(
-                                                [parameter_panel_width, 20.0],
-                                                egui::Label::new(
-                                                    egui::RichText::new("<DRG/SKD>").monospace(),
-                                                )
-                                                .truncate(),
-                                            );
-                                        }
+                                            .speed(10)
+                                            .range(1..=86400),
+                                        )
+                                        .changed()
+                                    {
+                                        table_changed = true;
+                                    }
+                                    ui.add_sized(
+                                        [SKD_COL_AZEL, 20.0],
+                                        egui::Label::new(start_geometry),
+                                    )
+                                    .on_hover_text("Start AZ/EL");
+                                    ui.add_sized(
+                                        [SKD_COL_AZEL, 20.0],
+                                        egui::Label::new(end_geometry),
+                                    )
+                                    .on_hover_text("End AZ/EL");
+                                    for motion_check in [motion_check_1, motion_check_2] {
+                                        show_motion_status_cell(ui, motion_check).on_hover_text(
+                                            "Checks start/end AZ/EL limits and required slew time.",
+                                        );
+                                    }
+                                    if ui
+                                        .add_sized([SKD_COL_DELETE, 20.0], egui::Button::new("Del"))
+                                        .clicked()
+                                    {
+                                        remove_idx = Some(i);
                                     }
                                 });
-                            });
+                            }
+                        });
+                    if let Some(i) = remove_idx {
+                        self.skd_rows.remove(i);
+                        self.mark_skd_status_dirty();
+                    } else if table_changed {
+                        self.mark_skd_status_dirty();
+                    }
+                },
+            );
+        });
+
+        self.show_source_picker_windows(ui.ctx());
+    }
+
+    fn show_source_picker_windows(&mut self, ctx: &egui::Context) {
+        self.show_source_picker_window(ctx, 0);
+        self.show_source_picker_window(ctx, 1);
+        self.show_source_picker_window(ctx, 2);
+    }
+
+    fn show_source_picker_window(&mut self, ctx: &egui::Context, picker_kind: usize) {
+        let open = match picker_kind {
+            0 => &mut self.target_picker_open,
+            1 => &mut self.cal_picker_open,
+            _ => &mut self.five_point_picker_open,
+        };
+        if !*open {
+            return;
+        }
+
+        let title = match picker_kind {
+            0 => "Select Target",
+            1 => "Select Gain Cal",
+            _ => "Select Five-point Cal",
+        };
+        let filter_text = match picker_kind {
+            0 => &mut self.target_picker_filter,
+            1 => &mut self.cal_picker_filter,
+            _ => &mut self.five_point_picker_filter,
+        };
+        let mut selected_index = None;
+        let mut should_close = false;
+
+        egui::Window::new(title)
+            .open(open)
+            .default_width(520.0)
+            .default_height(620.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.text_edit_singleline(filter_text);
+                    if ui.button("Clear").clicked() {
+                        filter_text.clear();
+                    }
+                });
+                ui.separator();
+
+                let filter = filter_text.to_lowercase();
+                egui::ScrollArea::vertical()
+                    .max_height(520.0)
+                    .show(ui, |ui| {
+                        for i in 0..self.sources.len() {
+                            let source_name = &self.sources[i].0.name;
+                            if !filter.is_empty() && !source_name.to_lowercase().contains(&filter) {
+                                continue;
+                            }
+                            let selected = match picker_kind {
+                                0 => i == self.interleave_target_index,
+                                1 => i == self.interleave_cal_index,
+                                _ => i == self.five_point_cal_index,
+                            };
+                            if ui.selectable_label(selected, source_name).clicked() {
+                                selected_index = Some(i);
+                                should_close = true;
+                            }
+                        }
+                    });
+            });
+
+        if let Some(i) = selected_index {
+            match picker_kind {
+                0 => self.interleave_target_index = i,
+                1 => self.interleave_cal_index = i,
+                _ => self.five_point_cal_index = i,
+            }
+        }
+        if should_close {
+            match picker_kind {
+                0 => self.target_picker_open = false,
+                1 => self.cal_picker_open = false,
+                _ => self.five_point_picker_open = false,
+            }
+        }
+    }
+
+    fn ui_uptime_plotters_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        // Grouped catalogues list the legend in plot_data order so group sections stay together.
+        let grouped_legend = self.plot_data.iter().any(|series| series.group.is_some());
+        let title_rect = self
+            .capturing_titled_azel()
+            .then(|| self.ui_azel_title_block(ui));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.time_zoom_enabled, "Time zoom")
+                .on_hover_text("Drag or scroll to pan and zoom the UT axis; elevation stays fixed");
+            if ui
+                .add_enabled(
+                    self.time_view != self.full_time_view(),
+                    egui::Button::new("Reset view"),
+                )
+                .clicked()
+            {
+                self.time_view = self.full_time_view();
+            }
+            ui.separator();
+            self.ui_reference_source_picker(ui);
+            ui.separator();
+            for layout in UptimeLayout::ALL {
+                ui.radio_value(&mut self.uptime_layout, layout, layout.label());
+            }
+            ui.separator();
+            self.ui_shown_sources_menu(ui);
+        });
+        self.ui_uptime_panel_tabs(ui);
+        let panel_station = self.uptime_panel_station();
+        let station_pos = match panel_station {
+            Some(idx) => self.stations.get(idx).map(|(station, _)| station.pos),
+            None => self.current_station().map(|station| station.pos),
+        };
+        let time_grid = self.time_grid_for(station_pos);
+        let selected_date = self.selected_date;
+        let dut1_seconds = self.dut1_seconds;
+        let (ellipsoid, sidereal) = (self.ellipsoid, self.effective_sidereal_mode());
+
+        let az_pointer_formatter = move |x: f64, y: f64| {
+            let ut_text = time_display.format(x);
+            let lst_text = station_pos
+                .and_then(|pos| {
+                    utc_datetime_from_hour(selected_date, x).map(|dt| {
+                        utils::sidereal_time(ellipsoid, pos, utils::ut1_from_utc(dt, dut1_seconds))
+                            .local_hours(sidereal)
+                    })
+                })
+                .map(format_hour_hms)
+                .unwrap_or_else(|| "N/A".to_string());
+            format!("UT: {}\nLST: {}\nAz: {:.1}°", ut_text, lst_text, y)
+        };
+        let zenith_distance = self.zenith_distance_axis;
+        let el_axis_name = if zenith_distance { "ZD" } else { "El" };
+        let el_pointer_formatter = move |x: f64, y: f64| {
+            let ut_text = time_display.format(x);
+            let lst_text = station_pos
+                .and_then(|pos| {
+                    utc_datetime_from_hour(selected_date, x).map(|dt| {
+                        utils::sidereal_time(ellipsoid, pos, utils::ut1_from_utc(dt, dut1_seconds))
+                            .local_hours(sidereal)
+                    })
+                })
+                .map(format_hour_hms)
+                .unwrap_or_else(|| "N/A".to_string());
+            format!(
+                "UT: {}\nLST: {}\n{}: {:.1}°",
+                ut_text, lst_text, el_axis_name, y
+            )
+        };
+
+        let time_zoom = self.time_zoom_enabled;
+        let (view_min, view_max) = self.time_view;
+        // The splitter sits between two item spacings.
+        let plots_height =
+            (ui.available_height() - PLOT_SPLITTER_HEIGHT - 2.0 * ui.spacing().item_spacing.y)
+                .max(0.0);
+        let az_height = plots_height * self.az_plot_fraction;
+        let el_height = plots_height - az_height;
+        let plot_az = Plot::new("az_plot")
+            .id(egui::Id::new("az_plot"))
+            .width(ui.available_width())
+            .height(az_height)
+            .y_axis_label("Azimuth (deg, N=0 E=90)")
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
+            .include_x(0.0)
+            .include_x(24.0)
+            .include_y(-5.0)
+            .include_y(365.0)
+            .allow_drag([time_zoom, false])
+            .allow_zoom([time_zoom, false])
+            .allow_scroll([time_zoom, false])
+            .x_axis_label("") // Re-added
+            .x_axis_formatter(|_, _| "".to_string()) // Re-added
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .y_grid_spacer(|_input| {
+                [
+                    0.0, 30.0, 60.0, 90.0, 120.0, 150.0, 180.0, 210.0, 240.0, 270.0, 300.0, 330.0,
+                    360.0,
+                ]
+                .into_iter()
+                .map(|v| GridMark {
+                    value: v,
+                    step_size: 30.0,
+                })
+                .collect::<Vec<_>>()
+            })
+            .y_axis_formatter(|m, _| format!("{:.0}", m.value))
+            .show_y(true)
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(move |plot_point, _plot_bounds| {
+                    az_pointer_formatter(plot_point.x, plot_point.y)
+                }),
+            )
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("az_plot"),
+                Legend::default().follow_insertion_order(grouped_legend),
+            ));
+
+        let plot_el = Plot::new("el_plot")
+            .id(egui::Id::new("el_plot"))
+            .width(ui.available_width())
+            .height(el_height)
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label(if zenith_distance {
+                "Zenith Distance (deg)"
+            } else {
+                "Elevation (deg)"
+            })
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
+            .include_x(0.0)
+            .include_x(24.0)
+            .include_y(if zenith_distance { -1.0 } else { 0.0 })
+            .include_y(if zenith_distance { 90.0 } else { 91.0 })
+            .invert_y(zenith_distance)
+            .allow_drag([time_zoom, false])
+            .allow_zoom([time_zoom, false])
+            .allow_scroll([time_zoom, false])
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .y_grid_spacer(|_input| {
+                [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0]
+                    .into_iter()
+                    .map(|v| GridMark {
+                        value: v,
+                        step_size: 10.0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .y_axis_formatter(|m, _| format!("{:.0}", m.value))
+            .show_x(true)
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(move |plot_point, _plot_bounds| {
+                    el_pointer_formatter(plot_point.x, plot_point.y)
+                }),
+            )
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("el_plot"),
+                Legend::default().follow_insertion_order(grouped_legend),
+            ));
+
+        let az_response = plot_az.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [view_min, -5.0],
+                [view_max, 365.0],
+            ));
+            self.shade_outside_session(plot_ui);
+            self.shade_fast_az_spans(plot_ui, panel_station);
+            self.draw_cursor_line(plot_ui, |ut| ut);
+            let hovered = nearest_plot_sample(
+                plot_ui,
+                self.plot_data.iter().map(|series| {
+                    if self.in_uptime_panel(series, panel_station) && !self.is_hidden(series) {
+                        series.az_points.as_slice()
+                    } else {
+                        &[]
+                    }
+                }),
+            );
+            for (series_idx, series) in self.plot_data.iter().enumerate() {
+                if !self.in_uptime_panel(series, panel_station) {
+                    continue;
+                }
+                draw_group_legend_header(plot_ui, &self.plot_data, series_idx);
+                let mut line = Line::new(
+                    format!("{}:{}", series.source_name, series.station_name),
+                    PlotPoints::from(self.display_track(&series.az_points, Some(360.0))),
+                );
+                line = apply_station_line_style(line, series.station_idx);
+                if hovered.is_some_and(|(idx, _)| idx == series_idx) {
+                    line = line.width(self.line_width + HOVER_HIGHLIGHT_EXTRA_WIDTH);
+                } else {
+                    line = line.width(self.line_width);
+                }
+                plot_ui.line(line);
+            }
+            hovered
+        });
+        self.show_sample_tooltip(&az_response.response, az_response.inner, "Az");
+        if let Some(ut) = hovered_plot_x(&az_response) {
+            self.cursor_ut_next = Some(ut);
+        }
+        self.sync_hidden_series(
+            ui.ctx(),
+            egui::Id::new("az_plot"),
+            self.plot_data_legend_names(),
+        );
+        // Pan/zoom from either plot moves both, since the next frame sets both to `time_view`.
+        let az_bounds = az_response.transform.bounds();
+        if time_zoom && (az_bounds.min()[0], az_bounds.max()[0]) != (view_min, view_max) {
+            self.time_view = clamp_time_view(
+                az_bounds.min()[0],
+                az_bounds.max()[0],
+                self.full_time_view(),
+            );
+        }
+
+        self.ui_plot_splitter(ui, plots_height);
+
+        // Zenith distance mode plots 90° - el on an inverted axis, so the zenith stays on top.
+        let el_tracks: Vec<Vec<[f64; 2]>> = self
+            .plot_data
+            .iter()
+            .map(|series| {
+                if !self.in_uptime_panel(series, panel_station) {
+                    return Vec::new();
+                }
+                series
+                    .el_points
+                    .iter()
+                    .map(|&[x, el]| [x, self.el_axis_value(el)])
+                    .collect()
+            })
+            .collect();
+        let el_response = plot_el.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(if zenith_distance {
+                egui_plot::PlotBounds::from_min_max([view_min, -1.0], [view_max, 90.0])
+            } else {
+                egui_plot::PlotBounds::from_min_max([view_min, 0.0], [view_max, 91.0])
+            });
+            self.shade_outside_session(plot_ui);
+            self.draw_cursor_line(plot_ui, |ut| ut);
+            let hovered = nearest_plot_sample(
+                plot_ui,
+                self.plot_data
+                    .iter()
+                    .zip(&el_tracks)
+                    .map(|(series, track)| {
+                        if self.is_hidden(series) {
+                            &[]
+                        } else {
+                            track.as_slice()
+                        }
+                    }),
+            );
+            for (series_idx, (series, track)) in self.plot_data.iter().zip(&el_tracks).enumerate() {
+                if !self.in_uptime_panel(series, panel_station) {
+                    continue;
+                }
+                draw_group_legend_header(plot_ui, &self.plot_data, series_idx);
+                let track = &self.display_track(track, None);
+                let name = format!("{}:{}", series.source_name, series.station_name);
+                let width = if hovered.is_some_and(|(idx, _)| idx == series_idx) {
+                    self.line_width + HOVER_HIGHLIGHT_EXTRA_WIDTH
+                } else {
+                    self.line_width
+                };
+                if self.elevation_color_map == ElevationColorMap::Flat {
+                    for line in track_lines(&name, track) {
+                        plot_ui
+                            .line(apply_station_line_style(line, series.station_idx).width(width));
+                    }
+                    continue;
+                }
+                // Runs share the series name, so the legend still toggles the whole track.
+                for (bin_value, run) in split_by_elevation_bin(track) {
+                    let color = self
+                        .elevation_color_map
+                        .color_at(self.el_axis_value(bin_value))
+                        .unwrap_or(egui::Color32::GRAY);
+                    plot_ui.line(
+                        Line::new(name.clone(), PlotPoints::from(run))
+                            .stroke(egui::Stroke::new(width, color)),
+                    );
+                }
+            }
+            self.draw_reference_gaps(plot_ui, panel_station);
+            self.draw_elevation_colorbar(plot_ui);
+            self.draw_rise_set_markers(plot_ui, panel_station);
+            self.draw_opacity_advisory(plot_ui);
+            self.draw_opacity_floor(plot_ui);
+            hovered
+        });
+        self.show_sample_tooltip(&el_response.response, el_response.inner, el_axis_name);
+        if let Some(ut) = hovered_plot_x(&el_response) {
+            self.cursor_ut_next = Some(ut);
+        }
+        self.sync_hidden_series(
+            ui.ctx(),
+            egui::Id::new("el_plot"),
+            self.plot_data_legend_names(),
+        );
+        let el_bounds = el_response.transform.bounds();
+        if time_zoom && (el_bounds.min()[0], el_bounds.max()[0]) != (view_min, view_max) {
+            self.time_view = clamp_time_view(
+                el_bounds.min()[0],
+                el_bounds.max()[0],
+                self.full_time_view(),
+            );
+        }
+
+        let plots_rect = az_response.response.rect.union(el_response.response.rect);
+        self.uptime_plot_rect = Some(title_rect.map_or(plots_rect, |rect| rect.union(plots_rect)));
+    }
+
+    /// Vertical line at the shared cursor; `to_x` maps the UT hour onto the plot's x axis.
+    fn draw_cursor_line(&self, plot_ui: &mut egui_plot::PlotUi, to_x: impl Fn(f64) -> f64) {
+        if let Some(ut) = self.cursor_ut {
+            plot_ui.vline(
+                egui_plot::VLine::new("", to_x(ut))
+                    .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(150)))
+                    .allow_hover(false),
+            );
+        }
+    }
+
+    /// Marks where each polar track is at the shared cursor time, if it is up then.
+    fn draw_polar_cursor(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let Some(ut) = self.cursor_ut else {
+            return;
+        };
+        let color = plot_ui.ctx().global_style().visuals.strong_text_color();
+        let mut marked = false;
+        for series in &self.polar_plot_data {
+            let after = series.polar_hours.partition_point(|&hour| hour < ut);
+            let nearest = [after.checked_sub(1), Some(after)]
+                .into_iter()
+                .flatten()
+                .filter(|&idx| idx < series.polar_hours.len())
+                .min_by(|&a, &b| {
+                    (series.polar_hours[a] - ut)
+                        .abs()
+                        .total_cmp(&(series.polar_hours[b] - ut).abs())
+                });
+            let Some(idx) =
+                nearest.filter(|&idx| (series.polar_hours[idx] - ut).abs() <= CURSOR_SNAP_HOURS)
+            else {
+                continue;
+            };
+            plot_ui.points(
+                Points::new("", PlotPoints::from(vec![series.polar_points[idx]]))
+                    .shape(egui_plot::MarkerShape::Circle)
+                    .filled(false)
+                    .radius(self.polar_point_radius * 2.5)
+                    .color(color)
+                    .allow_hover(false),
+            );
+            marked = true;
+        }
+        if marked {
+            let bounds = plot_ui.plot_bounds();
+            plot_ui.text(
+                egui_plot::Text::new(
+                    "",
+                    egui_plot::PlotPoint::new(bounds.min()[0], bounds.min()[1]),
+                    format!("{} UT", self.time_display.format(ut)),
+                )
+                .anchor(egui::Align2::LEFT_BOTTOM)
+                .color(color),
+            );
+        }
+    }
+
+    /// LST at 00:00 UT at the current station, for placing the shared cursor on the LST plot.
+    fn lst_at_midnight(&self) -> Option<f64> {
+        self.lst_from_ut_hour(self.current_station()?.pos, 0.0)
+    }
+
+    /// Last sampled UT hour: the end of the selected date plus any overnight extension.
+    fn ut_sample_end(&self) -> f64 {
+        24.0 + self.overnight_hours
+    }
+
+    /// Right edge of the UT plots.
+    fn ut_plot_end(&self) -> f64 {
+        self.ut_sample_end() + UT_PLOT_RIGHT_MARGIN_HOURS
+    }
+
+    fn full_time_view(&self) -> (f64, f64) {
+        (0.0, self.ut_plot_end())
+    }
+
+    fn observing_frequency(&self) -> Option<f64> {
+        (self.frequency_enabled && self.observing_frequency_ghz > 0.0)
+            .then_some(self.observing_frequency_ghz)
+    }
+
+    fn load_band_templates(&mut self) -> Result<(), String> {
+        let content = fs::read_to_string(&self.band_file_path)
+            .map_err(|e| format!("Failed to read bands.toml: {}", e))?;
+        self.band_templates = parse_band_templates(&content)?;
+        Ok(())
+    }
+
+    /// Sets the frequency, floors and avoidance limits given by `band`, then replots so the
+    /// new floors show.
+    fn apply_band_template(&mut self, band: &BandTemplate) {
+        if let Some(frequency) = band.frequency_ghz {
+            self.frequency_enabled = frequency > 0.0;
+            if frequency > 0.0 {
+                self.observing_frequency_ghz = frequency.clamp(0.1, 1000.0);
+            }
+        }
+        if let Some(floor) = band.elevation_floor_deg {
+            self.elevation_floor_deg = floor.clamp(0.0, 60.0);
+        }
+        if let Some(limit) = band.sun_avoid_deg {
+            self.sun_avoid_deg = limit.clamp(0.0, 90.0);
+        }
+        if let Some(limit) = band.moon_avoid_deg {
+            self.moon_avoid_deg = limit.clamp(0.0, 90.0);
+        }
+        if let Some(tau0) = band.tau0 {
+            self.opacity_floor_enabled = tau0 > 0.0;
+            if tau0 > 0.0 {
+                self.tau0 = tau0.clamp(0.001, 5.0);
+            }
+        }
+        if let Some(max_opacity) = band.max_line_opacity {
+            self.max_line_opacity = max_opacity.clamp(0.01, 10.0);
+        }
+        if let Err(e) = self.save_preferences() {
+            self.log(LogLevel::Error, e);
+        }
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Band {}: floor {:.1}°, Sun {:.0}°, Moon {:.0}°",
+                band.name,
+                self.effective_floor_deg(),
+                self.sun_avoid_deg,
+                self.moon_avoid_deg
+            ),
+        );
+        if !self.plot_data.is_empty() {
+            self.calculate_plots();
+        }
+    }
+
+    /// Name of the first band template whose every given field matches the current
+    /// settings, or `None` once any of them has been changed by hand.
+    fn active_band(&self) -> Option<&str> {
+        let matches =
+            |value: Option<f64>, current: f64| value.is_none_or(|v| (v - current).abs() < 1e-9);
+        self.band_templates
+            .iter()
+            .find(|band| {
+                band.frequency_ghz.is_none_or(|frequency| {
+                    (frequency > 0.0) == self.frequency_enabled
+                        && (frequency <= 0.0
+                            || (frequency - self.observing_frequency_ghz).abs() < 1e-9)
+                }) && matches(band.elevation_floor_deg, self.elevation_floor_deg)
+                    && matches(band.sun_avoid_deg, self.sun_avoid_deg)
+                    && matches(band.moon_avoid_deg, self.moon_avoid_deg)
+                    && band.tau0.is_none_or(|tau0| {
+                        (tau0 > 0.0) == self.opacity_floor_enabled
+                            && (tau0 <= 0.0 || (tau0 - self.tau0).abs() < 1e-9)
+                    })
+                    && (!self.opacity_floor_enabled
+                        || matches(band.max_line_opacity, self.max_line_opacity))
+            })
+            .map(|band| band.name.as_str())
+    }
+
+    /// The elevation floor in use: the fixed floor, raised by the opacity floor when enabled.
+    fn effective_floor_deg(&self) -> f64 {
+        self.opacity_floor()
+            .map_or(self.elevation_floor_deg, |floor| {
+                floor.max(self.elevation_floor_deg)
+            })
+    }
+
+    fn opacity_floor(&self) -> Option<f64> {
+        self.opacity_floor_enabled
+            .then(|| utils::opacity_floor_deg(self.tau0, self.max_line_opacity))
+            .flatten()
+    }
+
+    fn draw_opacity_floor(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let Some(floor) = self.opacity_floor() else {
+            return;
+        };
+        plot_ui.hline(
+            egui_plot::HLine::new(
+                format!("τ floor {:.1}° (τ0 {:.2})", floor, self.tau0),
+                self.el_axis_value(floor),
+            )
+            .color(egui::Color32::from_rgb(0, 170, 170))
+            .width(1.5),
+        );
+    }
+
+    fn draw_opacity_advisory(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let Some(limit_el) = self
+            .observing_frequency()
+            .and_then(utils::high_opacity_elevation_deg)
+        else {
+            return;
+        };
+        plot_ui.hline(
+            egui_plot::HLine::new(
+                format!("High opacity below {:.0}°", limit_el),
+                self.el_axis_value(limit_el),
+            )
+            .color(egui::Color32::from_rgb(160, 90, 255))
+            .style(egui_plot::LineStyle::Dashed { length: 8.0 }),
+        );
+    }
+
+    /// Maps an elevation to the lower plot's y value (and back): zenith distance when
+    /// `zenith_distance_axis` is set.
+    fn el_axis_value(&self, value: f64) -> f64 {
+        if self.zenith_distance_axis {
+            90.0 - value
+        } else {
+            value
+        }
+    }
+
+    /// A horizontal bar between the az and el plots; dragging it moves `az_plot_fraction`.
+    fn ui_plot_splitter(&mut self, ui: &mut egui::Ui, plots_height: f32) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), PLOT_SPLITTER_HEIGHT),
+            egui::Sense::drag(),
+        );
+        let response = response.on_hover_cursor(egui::CursorIcon::ResizeVertical);
+        if response.dragged() && plots_height > 0.0 {
+            self.az_plot_fraction = (self.az_plot_fraction
+                + response.drag_delta().y / plots_height)
+                .clamp(MIN_PLOT_FRACTION, 1.0 - MIN_PLOT_FRACTION);
+        }
+        if response.drag_stopped() {
+            if let Err(e) = self.save_preferences() {
+                self.log(LogLevel::Error, e);
+            }
+        }
+        let stroke = if response.hovered() || response.dragged() {
+            ui.visuals().widgets.active.fg_stroke
+        } else {
+            ui.visuals().widgets.noninteractive.bg_stroke
+        };
+        ui.painter().hline(rect.x_range(), rect.center().y, stroke);
+    }
+
+    /// Julian Day, GMST, LMST and longitude at `diagnostic_ut_hour` for the first selected
+    /// station, as grid rows.
+    fn ui_time_diagnostics(&self, ui: &mut egui::Ui) {
+        let (Some(station), Some(time)) = (
+            self.current_station(),
+            utc_datetime_from_hour(self.selected_date, self.diagnostic_ut_hour),
+        ) else {
+            return;
+        };
+        let st = utils::sidereal_time(
+            self.ellipsoid,
+            station.pos,
+            utils::ut1_from_utc(time, self.dut1_seconds),
+        );
+        let rows = [
+            ("  UTC", time.format("%Y-%m-%d %H:%M:%S").to_string()),
+            ("  Julian Day (UT1)", format!("{:.6}", st.julian_day)),
+            ("  MJD (UT1)", format!("{:.6}", st.julian_day - 2_400_000.5)),
+            ("  GMST", format_hour_hms(st.gmst_hours)),
+            (
+                "  LMST",
+                format!("{} ({})", format_hour_hms(st.lmst_hours), station.name),
+            ),
+            ("  GAST", format_hour_hms(st.gast_hours)),
+            (
+                "  LAST",
+                format!("{} ({})", format_hour_hms(st.last_hours), station.name),
+            ),
+            (
+                "  Eq. of Equinoxes",
+                format!("{:+.4} s", st.equation_of_equinoxes_seconds),
+            ),
+            (
+                "  Longitude",
+                format!("{:+.6}° ({})", st.longitude_deg, self.ellipsoid.label()),
+            ),
+        ];
+        for (label, value) in rows {
+            ui.label(label);
+            ui.label(value);
+            ui.end_row();
+        }
+    }
+
+    /// Plotted stations in `plot_data` order, as (`station_idx`, name).
+    fn plotted_stations(&self) -> Vec<(usize, &str)> {
+        let mut stations: Vec<(usize, &str)> = Vec::new();
+        for series in &self.plot_data {
+            if !stations.iter().any(|&(idx, _)| idx == series.station_idx) {
+                stations.push((series.station_idx, &series.station_name));
+            }
+        }
+        stations
+    }
+
+    /// The station whose panel is shown, or `None` in overlay layout. Falls back to the first
+    /// plotted station when the chosen one is no longer plotted.
+    fn uptime_panel_station(&self) -> Option<usize> {
+        if self.uptime_layout != UptimeLayout::PerStation {
+            return None;
+        }
+        let stations = self.plotted_stations();
+        stations
+            .iter()
+            .find(|&&(idx, _)| idx == self.uptime_panel_station)
+            .or(stations.first())
+            .map(|&(idx, _)| idx)
+    }
+
+    fn is_hidden(&self, series: &PlotSeries) -> bool {
+        self.hidden_series
+            .contains(&format!("{}:{}", series.source_name, series.station_name))
+    }
+
+    fn plot_data_legend_names(&self) -> Vec<String> {
+        self.plot_data
+            .iter()
+            .map(|series| format!("{}:{}", series.source_name, series.station_name))
+            .collect()
+    }
+
+    /// `legend` with the entries in `hidden_series` unchecked. Legend items are keyed by name,
+    /// so the same set drives every plot that names its series "SOURCE:STATION"; other entries
+    /// (group headers, limit lines) keep the plot's own toggles.
+    fn legend_with_hidden_series(
+        &self,
+        ctx: &egui::Context,
+        plot_id: egui::Id,
+        legend: Legend,
+    ) -> Legend {
+        let series_ids: HashSet<egui::Id> = self
+            .plot_data_legend_names()
+            .iter()
+            .map(egui::Id::new)
+            .collect();
+        let mut hidden: Vec<egui::Id> = egui_plot::PlotMemory::load(ctx, plot_id)
+            .map(|memory| {
+                memory
+                    .hidden_items
+                    .into_iter()
+                    .filter(|id| !series_ids.contains(id))
+                    .collect()
+            })
+            .unwrap_or_default();
+        hidden.extend(self.hidden_series.iter().map(egui::Id::new));
+        legend.hidden_items(hidden)
+    }
+
+    /// The marker shape of each source on the polar plot, in plotting order. Sources without a
+    /// chosen shape cycle through `SourceMarker::ALL` in the order they are plotted, so
+    /// neighbouring tracks differ.
+    fn default_source_markers(&self) -> Vec<(String, SourceMarker)> {
+        let mut markers: Vec<(String, SourceMarker)> = Vec::new();
+        for series in &self.polar_plot_data {
+            if markers.iter().any(|(name, _)| *name == series.source_name) {
+                continue;
+            }
+            let marker = self
+                .source_markers
+                .get(&series.source_name)
+                .copied()
+                .unwrap_or(SourceMarker::ALL[markers.len() % SourceMarker::ALL.len()]);
+            markers.push((series.source_name.clone(), marker));
+        }
+        markers
+    }
+
+    fn source_marker(&self, markers: &[(String, SourceMarker)], name: &str) -> SourceMarker {
+        markers
+            .iter()
+            .find(|(source, _)| source == name)
+            .map(|(_, marker)| *marker)
+            .or_else(|| self.source_markers.get(name).copied())
+            .unwrap_or(SourceMarker::Circle)
+    }
+
+    /// One row of shape-to-source entries above the polar plot; clicking a shape switches the
+    /// source to the next one.
+    fn ui_source_marker_legend(&mut self, ui: &mut egui::Ui, markers: &[(String, SourceMarker)]) {
+        if markers.is_empty() {
+            return;
+        }
+        let color = ui.visuals().strong_text_color();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Markers:");
+            for (name, marker) in markers {
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::click());
+                marker.paint(ui.painter(), rect.center(), 5.0, color);
+                let response = response.on_hover_text(format!(
+                    "{} for {}; click for the next shape",
+                    marker.label(),
+                    name
+                ));
+                if response.clicked() {
+                    self.source_markers.insert(name.clone(), marker.next());
+                }
+                ui.label(name);
+                ui.add_space(6.0);
+            }
+        });
+    }
+
+    /// Picks up legend clicks in plot `plot_id` for the series in `names`.
+    fn sync_hidden_series(&mut self, ctx: &egui::Context, plot_id: egui::Id, names: Vec<String>) {
+        let Some(memory) = egui_plot::PlotMemory::load(ctx, plot_id) else {
+            return;
+        };
+        for name in names {
+            if memory.hidden_items.contains(&egui::Id::new(&name)) {
+                self.hidden_series.insert(name);
+            } else {
+                self.hidden_series.remove(&name);
+            }
+        }
+    }
+
+    /// Per-source show/hide toggles for the plotted lines, independent of the source selection.
+    fn ui_shown_sources_menu(&mut self, ui: &mut egui::Ui) {
+        let mut sources: Vec<&str> = Vec::new();
+        for series in &self.plot_data {
+            if !sources.contains(&series.source_name.as_str()) {
+                sources.push(&series.source_name);
+            }
+        }
+        let hidden_count = self
+            .plot_data
+            .iter()
+            .filter(|series| self.is_hidden(series))
+            .count();
+        let mut changes: Vec<(String, bool)> = Vec::new();
+        let mut show_all = false;
+        ui.menu_button(format!("Shown ({} hidden)", hidden_count), |ui| {
+            show_all = ui.button("Show all").clicked();
+            ui.separator();
+            for source in sources {
+                let mut shown = self
+                    .plot_data
+                    .iter()
+                    .any(|series| series.source_name == source && !self.is_hidden(series));
+                if ui.checkbox(&mut shown, source).changed() {
+                    changes.push((source.to_string(), shown));
+                }
+            }
+        })
+        .response
+        .on_hover_text("Hide plotted lines without unselecting the sources; legend clicks do the same per station");
+        if show_all {
+            self.hidden_series.clear();
+        }
+        for (source, shown) in changes {
+            for name in self
+                .plot_data
+                .iter()
+                .filter(|series| series.source_name == source)
+                .map(|series| format!("{}:{}", series.source_name, series.station_name))
+                .collect::<Vec<_>>()
+            {
+                if shown {
+                    self.hidden_series.remove(&name);
+                } else {
+                    self.hidden_series.insert(name);
+                }
+            }
+        }
+    }
+
+    fn in_uptime_panel(&self, series: &PlotSeries, panel_station: Option<usize>) -> bool {
+        panel_station.is_none_or(|idx| series.station_idx == idx)
+    }
+
+    fn ui_uptime_panel_tabs(&mut self, ui: &mut egui::Ui) {
+        let Some(current) = self.uptime_panel_station() else {
+            return;
+        };
+        let mut chosen = current;
+        ui.horizontal(|ui| {
+            for (idx, name) in self.plotted_stations() {
+                ui.selectable_value(&mut chosen, idx, name);
+            }
+        });
+        self.uptime_panel_station = chosen;
+    }
+
+    fn preview_key(&self) -> Option<PreviewKey> {
+        let station = self.current_station()?;
+        let ha_limits = station
+            .ha_limits
+            .map_or([f64::NAN; 2], |(low, high)| [low, high]);
+        Some(PreviewKey {
+            station: station.name.clone(),
+            station_bits: station
+                .pos
+                .iter()
+                .chain(&ha_limits)
+                .map(|v| v.to_bits())
+                .collect(),
+            date: self.selected_date,
+            floor_bits: self.effective_floor_deg().to_bits(),
+            ellipsoid: self.ellipsoid,
+            sidereal_mode: self.sidereal_mode,
+            reference_frame: self.reference_frame,
+            dut1_bits: self.dut1_seconds.to_bits(),
+            az_sector_bits: self
+                .az_sectors
+                .iter()
+                .flat_map(|&(from, to)| [from.to_bits(), to.to_bits()])
+                .collect(),
+            source_count: self.sources.len(),
+        })
+    }
 
-                            ui.group(|ui| {
-                                ui.set_min_width(parameter_panel_width);
-                                ui.set_max_width(parameter_panel_width);
-                                ui.label("Source List");
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui.button("Load Sources").clicked() {
-                                        match pick_file_dialog("Select source.txt") {
-                                            Ok(Some(path)) => {
-                                                self.source_file_path =
-                                                    path.to_string_lossy().to_string();
-                                                match self.load_sources() {
-                                                    Ok(_) => self.error_msg = None,
-                                                    Err(e) => self.error_msg = Some(e),
-                                                }
-                                            }
-                                            Ok(None) => {}
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    if ui.button("Open Sources").clicked() {
-                                        match utils::open_file_in_external_editor(
-                                            &self.source_file_path,
-                                        ) {
-                                            Ok(_) => self.error_msg = None,
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    ui.label(format!("{} sources", self.sources.len()));
-                                    ui.add_sized(
-                                        [parameter_panel_width, 20.0],
-                                        egui::Label::new(
-                                            egui::RichText::new(self.source_file_path.as_str())
-                                                .monospace(),
-                                        )
-                                        .truncate(),
-                                    )
-                                    .on_hover_text(self.source_file_path.as_str());
-                                });
-                            });
+    /// Starts a background preview of every loaded source at the current station, unless
+    /// one is cached or already running for the same key.
+    fn start_source_preview(&mut self) {
+        let Some(key) = self.preview_key() else {
+            return;
+        };
+        if self.source_previews.contains_key(&key)
+            || self.preview_job.as_ref().is_some_and(|job| job.key == key)
+        {
+            return;
+        }
+        let Some(station) = self.current_station().cloned() else {
+            return;
+        };
+        let sources: Vec<Source> = self
+            .sources
+            .iter()
+            .map(|(source, _)| source.clone())
+            .collect();
+        let (sender, receiver) = mpsc::channel();
+        let (date, floor, ellipsoid, sidereal, frame, dut1) = (
+            self.selected_date,
+            self.effective_floor_deg(),
+            self.ellipsoid,
+            self.sidereal_mode,
+            self.reference_frame,
+            self.dut1_seconds,
+        );
+        let az_sectors = self.az_sectors.clone();
+        let total = sources.len();
+        std::thread::spawn(move || {
+            let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let times: Vec<(f64, chrono::DateTime<Utc>)> = (0..=24 * 60 / PREVIEW_STEP_MIN)
+                .map(|i| {
+                    let time = start + Duration::minutes(i * PREVIEW_STEP_MIN);
+                    (
+                        (i * PREVIEW_STEP_MIN) as f64 / 60.0,
+                        utils::ut1_from_utc(time, dut1),
+                    )
+                })
+                .collect();
+            for source in sources {
+                let mut max_el_deg = f64::NEG_INFINITY;
+                let margins: Vec<(f64, f64)> = times
+                    .iter()
+                    .map(|&(hour, time)| {
+                        let pointing = utils::radec2azalt_with(
+                            ellipsoid,
+                            sidereal,
+                            frame,
+                            station.pos,
+                            time,
+                            source.ra_rad,
+                            source.dec_rad,
+                        );
+                        max_el_deg = max_el_deg.max(pointing.el_deg);
+                        let margin = (pointing.el_deg - floor)
+                            .min(station.ha_margin(pointing.hour_angle))
+                            .min(utils::az_sector_margin(pointing.az_deg, &az_sectors));
+                        (hour, margin)
+                    })
+                    .collect();
+                let up_hours =
+                    utils::total_window_hours(&utils::visibility_windows_from_margin(&margins));
+                let preview = SourcePreview {
+                    max_el_deg,
+                    up_hours,
+                };
+                // The receiver is dropped when the key changes; stop early then.
+                if sender.send((source.name, preview)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.preview_job = Some(PreviewJob {
+            key,
+            receiver,
+            total,
+            results: HashMap::new(),
+        });
+    }
 
-                            ui.group(|ui| {
-                                ui.set_min_width(parameter_panel_width);
-                                ui.set_max_width(parameter_panel_width);
-                                ui.label("Antenna SCH");
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui.button("Load Antennas").clicked() {
-                                        match pick_file_dialog("Select antenna.sch") {
-                                            Ok(Some(path)) => {
-                                                self.antenna_file_path =
-                                                    path.to_string_lossy().to_string();
-                                                match self.load_antennas() {
-                                                    Ok(_) => self.error_msg = None,
-                                                    Err(e) => self.error_msg = Some(e),
-                                                }
-                                            }
-                                            Ok(None) => {}
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    if ui.button("Open Antennas").clicked() {
-                                        match utils::open_file_in_external_editor(
-                                            &self.antenna_file_path,
-                                        ) {
-                                            Ok(_) => self.error_msg = None,
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    ui.add_sized(
-                                        [parameter_panel_width, 20.0],
-                                        egui::Label::new(
-                                            egui::RichText::new(self.antenna_file_path.as_str())
-                                                .monospace(),
-                                        )
-                                        .truncate(),
-                                    )
-                                    .on_hover_text(self.antenna_file_path.as_str());
-                                });
-                                if self.antennas.is_empty() {
-                                    ui.label("No antenna loaded");
-                                } else {
-                                    self.selected_antenna =
-                                        self.selected_antenna.min(self.antennas.len() - 1);
-                                    self.selected_antenna_2 =
-                                        self.selected_antenna_2.min(self.antennas.len() - 1);
-                                    let old_selected_antenna = self.selected_antenna;
-                                    let old_selected_antenna_2 = self.selected_antenna_2;
-                                    for (selected, combo_id) in [
-                                        (&mut self.selected_antenna, "skd_antenna_1"),
-                                        (&mut self.selected_antenna_2, "skd_antenna_2"),
-                                    ] {
-                                        ui.horizontal_wrapped(|ui| {
-                                            egui::ComboBox::from_id_salt(combo_id)
-                                                .selected_text(&self.antennas[*selected].name)
-                                                .show_ui(ui, |ui| {
-                                                    for (i, antenna) in
-                                                        self.antennas.iter().enumerate()
-                                                    {
-                                                        ui.selectable_value(
-                                                            selected,
-                                                            i,
-                                                            format!(
-                                                                "{} {}",
-                                                                antenna.code, antenna.name
-                                                            ),
-                                                        );
-                                                    }
-                                                });
-                                            let antenna = &self.antennas[*selected];
-                                            ui.label(format!(
-                                        "AZ {:.1}-{:.1} {:.1}/min, EL {:.1}-{:.1} {:.1}/min",
-                                        antenna.az_min_deg,
-                                        antenna.az_max_deg,
-                                        antenna.az_rate_deg_per_min,
-                                        antenna.el_min_deg,
-                                        antenna.el_max_deg,
-                                        antenna.el_rate_deg_per_min
-                                    ));
-                                        });
-                                    }
-                                    if self.selected_antenna != old_selected_antenna
-                                        || self.selected_antenna_2 != old_selected_antenna_2
-                                    {
-                                        self.mark_skd_status_dirty();
-                                    }
-                                }
-                            });
+    /// Drains finished previews into the cache.
+    fn poll_source_preview(&mut self) {
+        let Some(job) = &mut self.preview_job else {
+            return;
+        };
+        loop {
+            match job.receiver.try_recv() {
+                Ok((name, preview)) => {
+                    job.results.insert(name, preview);
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        if let Some(job) = self.preview_job.take() {
+            self.source_previews.insert(job.key, job.results);
+        }
+    }
 
-                            ui.group(|ui| {
-                                ui.set_min_width(parameter_panel_width);
-                                ui.set_max_width(parameter_panel_width);
-                                ui.label("Schedule Date / Time Shift");
-                                ui.horizontal_wrapped(|ui| {
-                                    if ui
-                                        .button(self.selected_date.format("%Y-%m-%d").to_string())
-                                        .clicked()
-                                    {
-                                        self.show_calendar = !self.show_calendar;
-                                    }
-                                    ui.label("Time Shift:");
-                                    ui.add(
-                                        egui::DragValue::new(&mut self.schedule_time_shift_sec)
-                                            .speed(1)
-                                            .suffix(" s"),
-                                    );
-                                    if ui.button("Apply to All").clicked() {
-                                        match self.apply_schedule_time_shift() {
-                                            Ok(_) => {
-                                                self.error_msg = Some(format!(
-                                                    "Shifted all scans by {} seconds.",
-                                                    self.schedule_time_shift_sec
-                                                ))
-                                            }
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
+    fn ui_source_preview_control(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.source_preview_enabled, "Peak el / up time for all sources")
+                .on_hover_text("Computed in the background for the first selected station and cached per station and date");
+            if !self.source_preview_enabled {
+                self.preview_job = None;
+                return;
+            }
+            self.start_source_preview();
+            self.poll_source_preview();
+            match &self.preview_job {
+                Some(job) => {
+                    let fraction = job.results.len() as f32 / job.total.max(1) as f32;
+                    ui.add(
+                        egui::ProgressBar::new(fraction)
+                            .desired_width(160.0)
+                            .text(format!("{}/{}", job.results.len(), job.total)),
+                    );
+                    ui.ctx()
+                        .request_repaint_after(std::time::Duration::from_millis(100));
+                }
+                None if self.preview_key().is_none() => {
+                    ui.weak("(select a station)");
+                }
+                None => {}
+            }
+        });
+    }
+
+    fn ui_reference_source_picker(&mut self, ui: &mut egui::Ui) {
+        let mut names: Vec<&str> = Vec::new();
+        for series in &self.plot_data {
+            if !names.contains(&series.source_name.as_str()) {
+                names.push(&series.source_name);
+            }
+        }
+        let mut reference = self.reference_source.clone();
+        ui.label("Reference:");
+        egui::ComboBox::from_id_salt("reference_source")
+            .selected_text(reference.as_deref().unwrap_or("(none)"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut reference, None, "(none)");
+                for name in names {
+                    ui.selectable_value(&mut reference, Some(name.to_string()), name);
+                }
+            })
+            .response
+            .on_hover_text("Shade where the other sources are up but this one is not");
+        if ui
+            .add_enabled(reference.is_some(), egui::Button::new("Clear"))
+            .clicked()
+        {
+            reference = None;
+        }
+        self.reference_source = reference;
+    }
+
+    /// Per plot series, the UT intervals when it is up but the reference source is not above
+    /// the floor at the same station. Empty without a reference.
+    fn reference_gaps(&self) -> Vec<Vec<(f64, f64)>> {
+        let Some(reference) = &self.reference_source else {
+            return Vec::new();
+        };
+        self.plot_data
+            .iter()
+            .map(|series| {
+                if &series.source_name == reference {
+                    return Vec::new();
+                }
+                let reference_windows = self
+                    .plot_data
+                    .iter()
+                    .find(|other| {
+                        &other.source_name == reference && other.station_idx == series.station_idx
+                    })
+                    .map_or(&[][..], |other| other.windows.as_slice());
+                let reference_down =
+                    utils::complement_windows(reference_windows, 0.0, self.ut_plot_end());
+                utils::intersect_windows(&series.windows, &reference_down)
+            })
+            .collect()
+    }
+
+    /// Shades the parts of each elevation line that lack reference-source coverage.
+    fn draw_reference_gaps(&self, plot_ui: &mut egui_plot::PlotUi, panel_station: Option<usize>) {
+        let color = egui::Color32::from_rgba_unmultiplied(255, 60, 60, 110);
+        for (series, gaps) in self.plot_data.iter().zip(self.reference_gaps()) {
+            if !self.in_uptime_panel(series, panel_station) || self.is_hidden(series) {
+                continue;
+            }
+            for (start, end) in gaps {
+                let points: Vec<[f64; 2]> = series
+                    .el_points
+                    .iter()
+                    .filter(|p| p[0] >= start && p[0] <= end && p[1].is_finite())
+                    .map(|&[x, el]| [x, self.el_axis_value(el)])
+                    .collect();
+                plot_ui.line(
+                    Line::new("No reference", PlotPoints::from(points))
+                        .stroke(egui::Stroke::new(self.line_width + 6.0, color)),
+                );
+            }
+        }
+    }
+
+    /// True elevation at which the rise and set markers are placed: the elevation floor, or
+    /// where refraction lifts the source onto it when `rise_set_refracted` is set.
+    fn rise_set_crossing_deg(&self) -> f64 {
+        let floor = self.effective_floor_deg();
+        if self.rise_set_refracted {
+            floor - utils::refraction_deg(floor, None)
+        } else {
+            floor
+        }
+    }
+
+    fn draw_rise_set_markers(&self, plot_ui: &mut egui_plot::PlotUi, panel_station: Option<usize>) {
+        let horizon = self.el_axis_value(self.rise_set_crossing_deg());
+        let rises: Vec<[f64; 2]> = self
+            .plot_data
+            .iter()
+            .filter(|series| self.in_uptime_panel(series, panel_station) && !self.is_hidden(series))
+            .flat_map(|series| series.rise_hours.iter().map(|&hour| [hour, horizon]))
+            .collect();
+        let sets: Vec<[f64; 2]> = self
+            .plot_data
+            .iter()
+            .filter(|series| self.in_uptime_panel(series, panel_station) && !self.is_hidden(series))
+            .flat_map(|series| series.set_hours.iter().map(|&hour| [hour, horizon]))
+            .collect();
+        plot_ui.points(
+            Points::new("Rise", rises)
+                .shape(egui_plot::MarkerShape::Up)
+                .radius(5.0)
+                .filled(true)
+                .color(egui::Color32::from_rgb(0, 170, 255)),
+        );
+        plot_ui.points(
+            Points::new("Set", sets)
+                .shape(egui_plot::MarkerShape::Down)
+                .radius(5.0)
+                .filled(true)
+                .color(egui::Color32::from_rgb(255, 90, 90)),
+        );
+    }
+
+    /// Draws the colour map as a bar in the spare space right of 24h, aligned with the
+    /// elevation axis.
+    fn draw_elevation_colorbar(&self, plot_ui: &mut egui_plot::PlotUi) {
+        if self.elevation_color_map == ElevationColorMap::Flat {
+            return;
+        }
+        let end = self.ut_sample_end();
+        let (x0, x1) = (end + 0.25, end + 0.55);
+        let mut el = 0.0;
+        while el < 90.0 {
+            let top = (el + ELEVATION_COLOR_BIN_DEG).min(90.0);
+            let color = self
+                .elevation_color_map
+                .color_at(el + ELEVATION_COLOR_BIN_DEG / 2.0)
+                .unwrap_or(egui::Color32::GRAY);
+            let (y0, y1) = (self.el_axis_value(el), self.el_axis_value(top));
+            plot_ui.polygon(
+                egui_plot::Polygon::new(
+                    "",
+                    PlotPoints::from(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]),
+                )
+                .fill_color(color)
+                .stroke(egui::Stroke::NONE)
+                .allow_hover(false),
+            );
+            el = top;
+        }
+    }
+
+    fn show_sample_tooltip(
+        &self,
+        response: &egui::Response,
+        hovered: Option<(usize, [f64; 2])>,
+        axis_label: &str,
+    ) {
+        let Some((series_idx, [hour, value])) = hovered else {
+            return;
+        };
+        let Some(series) = self.plot_data.get(series_idx) else {
+            return;
+        };
+        response.clone().on_hover_ui_at_pointer(|ui| {
+            ui.strong(format!("{}:{}", series.source_name, series.station_name));
+            ui.label(format!("UT: {}", self.time_display.format(hour)));
+            ui.label(format!("{}: {:.2}°", axis_label, value));
+        });
+    }
+
+    fn ui_parameters_tab(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Parameters");
+        ui.add_space(10.0);
+
+        ui.columns(2, |columns| {
+            // --- Left Column: Settings and File Formats ---
+            egui::ScrollArea::vertical().show(&mut columns[0], |ui| {
+                // --- Station Settings ---
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.heading("📡 Station Settings");
+                    ui.add_space(5.0);
+                    ui.label("Stations:");
+                    egui::ScrollArea::vertical().id_salt("station_list_scroll").max_height(150.0).show(ui, |ui| {
+                        if self.stations.is_empty() {
+                            ui.label("Load stations from station.txt");
+                        } else {
+                            for (station, selected) in &mut self.stations {
+                                ui.checkbox(selected, &station.name);
+                            }
+                        }
+                    });
+                    if let Some(station) = self.current_station() {
+                        let (lat, lon, height) = self.ellipsoid.geodetic(station.pos);
+                        let (lat, lon) = (lat.to_degrees(), lon.to_degrees());
+                        ui.label(format!(
+                            "{} ({}): lat {:.6}° ({}), lon {:.6}° ({}), h {:.3} m",
+                            station.name,
+                            self.ellipsoid.label(),
+                            lat,
+                            format_dms(lat),
+                            lon,
+                            format_dms(lon),
+                            height
+                        ));
+                    }
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Station File:");
+                        ui.text_edit_singleline(&mut self.station_file_path);
+                        if ui.button("Load").clicked() {
+                            match pick_file_dialog("Select station.txt") {
+                                Ok(Some(path)) => {
+                                    self.station_file_path = path.to_string_lossy().to_string();
+                                    match self.load_stations() {
+                                        Ok(_) => {}
+                                        Err(e) => self.log(LogLevel::Error, e),
                                     }
-                                });
-                            });
+                                }
+                                Ok(None) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.button("Reload").clicked() {
+                            match self.load_stations() {
+                                Ok(_) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.button("Open").clicked() {
+                            match utils::open_file_in_external_editor(&self.station_file_path) {
+                                Ok(_) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                    });
+                    self.ui_manual_station(ui);
+                });
+                ui.add_space(10.0);
 
-                            if self.sources.is_empty() {
-                                ui.label("Load source.txt first");
-                            } else {
-                                ui.group(|ui| {
-                                    ui.set_min_width(parameter_panel_width);
-                                    ui.set_max_width(parameter_panel_width);
-                                    self.interleave_target_index =
-                                        self.interleave_target_index.min(self.sources.len() - 1);
-                                    self.interleave_cal_index =
-                                        self.interleave_cal_index.min(self.sources.len() - 1);
-                                    ui.label("Target / Gain Calibrator");
-                                    ui.horizontal_wrapped(|ui| {
-                                        ui.label("Target:");
-                                        ui.monospace(
-                                            &self.sources[self.interleave_target_index].0.name,
-                                        );
-                                        if ui.button("Select Target").clicked() {
-                                            self.target_picker_open = true;
-                                        }
-                                        ui.label("Target Dur:");
-                                        ui.add(
-                                            egui::DragValue::new(
-                                                &mut self.interleave_target_duration_sec,
-                                            )
-                                            .speed(10)
-                                            .range(1..=86400)
-                                            .suffix(" s"),
-                                        );
-                                    });
-                                    ui.horizontal_wrapped(|ui| {
-                                        ui.label("Gain Cal:");
-                                        ui.monospace(
-                                            &self.sources[self.interleave_cal_index].0.name,
-                                        );
-                                        if ui.button("Select Gain Cal").clicked() {
-                                            self.cal_picker_open = true;
-                                        }
-                                        ui.label("Calib Dur:");
-                                        ui.add(
-                                            egui::DragValue::new(
-                                                &mut self.interleave_cal_duration_sec,
-                                            )
-                                            .speed(10)
-                                            .range(1..=86400)
-                                            .suffix(" s"),
-                                        );
-                                    });
-                                    ui.horizontal_wrapped(|ui| {
-                                        ui.label("Start UT:");
-                                        ui.add_sized(
-                                            [86.0, 20.0],
-                                            egui::TextEdit::singleline(
-                                                &mut self.interleave_start_time,
-                                            ),
-                                        );
-                                        ui.label("Slew:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.interleave_slew_sec)
-                                                .speed(10)
-                                                .range(0..=86400)
-                                                .suffix(" s"),
-                                        );
-                                        ui.label("Cycle:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.interleave_cycles)
-                                                .speed(1)
-                                                .range(1..=1000),
-                                        );
-                                        ui.checkbox(
-                                            &mut self.interleave_clear_existing,
-                                            "Replace table",
-                                        );
-                                        if ui.button("Generate").clicked() {
-                                            match self.generate_interleaved_skd_rows() {
-                                                Ok(_) => self.error_msg = None,
-                                                Err(e) => self.error_msg = Some(e),
-                                            }
-                                        }
-                                    });
-                                });
+                // --- Observation Settings ---
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.heading("Observation Settings");
+                    ui.add_space(5.0);
+                    egui::Grid::new("obs_grid").num_columns(2).spacing([40.0, 4.0]).striped(true).show(ui, |ui| {
+                        ui.label("Observation Date:");
+                        if ui.button(self.selected_date.format("%Y-%m-%d").to_string()).clicked() {
+                            self.show_calendar = !self.show_calendar;
+                        }
+                        ui.end_row();
 
-                                ui.group(|ui| {
-                                    ui.set_min_width(parameter_panel_width);
-                                    ui.set_max_width(parameter_panel_width);
-                                    self.five_point_cal_index =
-                                        self.five_point_cal_index.min(self.sources.len() - 1);
-                                    ui.label("Five-point Observation");
-                                    ui.horizontal_wrapped(|ui| {
-                                        ui.label("Gain Cal:");
-                                        ui.monospace(
-                                            &self.sources[self.five_point_cal_index].0.name,
-                                        );
-                                        if ui.button("Select Five-point Cal").clicked() {
-                                            self.five_point_picker_open = true;
-                                        }
-                                        ui.label("Start UT:");
-                                        ui.add_sized(
-                                            [86.0, 20.0],
-                                            egui::TextEdit::singleline(
-                                                &mut self.five_point_start_time,
-                                            ),
-                                        );
-                                        ui.label("Obs Time:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.five_point_obstime_sec)
-                                                .speed(10)
-                                                .range(1..=86400)
-                                                .suffix(" s"),
-                                        );
-                                        ui.label("Slew:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.five_point_slew_sec)
-                                                .speed(10)
-                                                .range(0..=86400)
-                                                .suffix(" s"),
-                                        );
-                                        ui.label("Offset:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.five_point_offset_deg)
-                                                .speed(0.1)
-                                                .suffix(" arcmin"),
-                                        );
-                                        ui.checkbox(
-                                            &mut self.five_point_include_station_offsets,
-                                            "Include 32/34 +/-2 arcmin",
-                                        );
-                                        ui.checkbox(
-                                            &mut self.five_point_clear_existing,
-                                            "Replace table",
-                                        );
-                                        if ui.button("Generate 10 Scans").clicked() {
-                                            match self.generate_five_point_skd_rows() {
-                                                Ok(_) => self.error_msg = None,
-                                                Err(e) => self.error_msg = Some(e),
-                                            }
-                                        }
-                                    });
-                                });
+                        ui.label("Elevation Floor:");
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.elevation_floor_deg, 0.0..=60.0)
+                                    .suffix("°"),
+                            )
+                            .changed()
+                            && !self.plot_data.is_empty()
+                        {
+                            self.calculate_plots();
+                        }
+                        ui.end_row();
 
-                                ui.group(|ui| {
-                                    ui.set_min_width(parameter_panel_width);
-                                    ui.set_max_width(parameter_panel_width);
-                                    ui.label("New Row");
-                                    self.new_skd_source_index =
-                                        self.new_skd_source_index.min(self.sources.len() - 1);
-                                    ui.horizontal_wrapped(|ui| {
-                                        egui::ComboBox::from_id_salt("new_skd_source")
-                                            .selected_text(
-                                                &self.sources[self.new_skd_source_index].0.name,
-                                            )
-                                            .show_ui(ui, |ui| {
-                                                for (i, (source, _)) in
-                                                    self.sources.iter().enumerate()
-                                                {
-                                                    ui.selectable_value(
-                                                        &mut self.new_skd_source_index,
-                                                        i,
-                                                        &source.name,
-                                                    );
-                                                }
-                                            });
-                                        if ui
-                                            .button(
-                                                self.new_skd_start_date.format("%Y-%m-%d").to_string(),
-                                            )
-                                            .clicked()
-                                        {
-                                            self.show_new_skd_calendar = !self.show_new_skd_calendar;
-                                        }
-                                        ui.add_sized(
-                                            [86.0, 20.0],
-                                            egui::TextEdit::singleline(
-                                                &mut self.new_skd_start_time,
-                                            ),
-                                        );
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.new_skd_duration_sec)
-                                                .speed(10)
-                                                .range(1..=86400)
-                                                .suffix(" s"),
-                                        );
-                                        if ui.button("Add").clicked() {
-                                            if parse_time_string(&self.new_skd_start_time).is_ok() {
-                                                self.skd_rows.push(SkdRow {
-                                                    source_name: self.sources
-                                                        [self.new_skd_source_index]
-                                                        .0
-                                                        .name
-                                                        .clone(),
-                                                    start_date: self.new_skd_start_date,
-                                                    start_time: normalize_time_string(
-                                                        &self.new_skd_start_time,
-                                                    )
-                                                    .unwrap_or_else(|| {
-                                                        self.new_skd_start_time.clone()
-                                                    }),
-                                                    duration_sec: self.new_skd_duration_sec,
-                                                    az_offset_deg: 0.0,
-                                                    el_offset_deg: 0.0,
-                                                    ra_offset_deg: 0.0,
-                                                    dec_offset_deg: 0.0,
-                                                    include_station_offsets: false,
-                                                });
-                                                self.sort_skd_rows_by_start_time();
-                                                self.error_msg = None;
-                                            } else {
-                                                self.error_msg = Some(
-                                                    "Start time must be HH:MM:SS or HHMMSS."
-                                                        .to_string(),
-                                                );
-                                            }
+                        ui.label("Opacity Floor:");
+                        ui.horizontal(|ui| {
+                            let mut changed = ui.checkbox(&mut self.opacity_floor_enabled, "").changed();
+                            ui.add_enabled_ui(self.opacity_floor_enabled, |ui| {
+                                ui.label("τ0");
+                                changed |= ui
+                                    .add(egui::DragValue::new(&mut self.tau0).range(0.001..=5.0).speed(0.005).fixed_decimals(3))
+                                    .on_hover_text("Zenith opacity")
+                                    .changed();
+                                ui.label("max τ");
+                                changed |= ui
+                                    .add(egui::DragValue::new(&mut self.max_line_opacity).range(0.01..=10.0).speed(0.01).fixed_decimals(2))
+                                    .on_hover_text("Largest usable optical depth along the line of sight")
+                                    .changed();
+                            });
+                            if let Some(floor) = self.opacity_floor() {
+                                ui.label(format!("→ {:.1}° (in use {:.1}°)", floor, self.effective_floor_deg()));
+                            }
+                            if changed && !self.plot_data.is_empty() {
+                                self.calculate_plots();
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Allowed Azimuth:");
+                        let response = ui
+                            .add(
+                                egui::TextEdit::singleline(&mut self.az_sectors_text)
+                                    .hint_text("all (e.g. 30-150, 300-60)")
+                                    .desired_width(200.0),
+                            )
+                            .on_hover_text("Azimuth sectors (deg) usable by the feed; samples outside are masked. MIN > MAX wraps through north.");
+                        if response.lost_focus() {
+                            match utils::parse_az_sectors(&self.az_sectors_text) {
+                                Ok(sectors) if sectors != self.az_sectors => {
+                                    self.az_sectors = sectors;
+                                    self.source_previews.clear();
+                                    if !self.plot_data.is_empty() {
+                                        self.calculate_plots();
+                                    }
+                                }
+                                Ok(_) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Band:");
+                        ui.horizontal(|ui| {
+                            let mut picked = None;
+                            let active = self.active_band();
+                            egui::ComboBox::from_id_salt("band_template")
+                                .selected_text(active.unwrap_or("Custom"))
+                                .show_ui(ui, |ui| {
+                                    for band in &self.band_templates {
+                                        if ui.selectable_label(active == Some(band.name.as_str()), &band.name).clicked() {
+                                            picked = Some(band.clone());
                                         }
-                                    });
-                                });
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Set the frequency, elevation floor, opacity and Sun/Moon limits of a band from bands.toml");
+                            if let Some(band) = picked {
+                                self.apply_band_template(&band);
+                            }
+                            if ui.button("Open").clicked() {
+                                if let Err(e) = utils::open_file_in_external_editor(&self.band_file_path) {
+                                    self.log(LogLevel::Error, e);
+                                }
+                            }
+                            if ui.button("Reload").clicked() {
+                                match self.load_band_templates() {
+                                    Ok(()) => self.log(LogLevel::Info, format!("Loaded {} band templates.", self.band_templates.len())),
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                }
                             }
                         });
-                },
-            );
+                        ui.end_row();
 
-            ui.separator();
-            ui.allocate_ui_with_layout(
-                egui::vec2(right_width, available.y),
-                egui::Layout::top_down(egui::Align::Min),
-                |ui| {
-                    self.rebuild_skd_status_cache();
-                    let mut remove_idx = None;
-                    let mut table_changed = false;
-                    egui::ScrollArea::both()
-                        .id_salt("skd_schedule_scroll")
-                        .auto_shrink([false, false])
-                        .show(ui, |ui| {
-                            ui.set_min_width(SKD_TABLE_MIN_WIDTH);
-                            ui.horizontal(|ui| {
-                                show_table_text_cell(ui, SKD_COL_NUM, 18.0, "#");
-                                show_table_text_cell(ui, SKD_COL_SOURCE, 18.0, "Source");
-                                show_table_text_cell(ui, SKD_COL_DATE, 18.0, "Date");
-                                show_table_text_cell(ui, SKD_COL_TIME, 18.0, "Start UT");
-                                show_table_text_cell(ui, SKD_COL_DURATION, 18.0, "Duration");
-                                show_table_text_cell(ui, SKD_COL_AZEL, 18.0, "Start Az/El");
-                                show_table_text_cell(ui, SKD_COL_AZEL, 18.0, "End Az/El");
-                                show_table_text_cell(ui, SKD_COL_ANTENNA, 18.0, "Antenna 1");
-                                show_table_text_cell(ui, SKD_COL_ANTENNA, 18.0, "Antenna 2");
-                                show_table_text_cell(ui, SKD_COL_DELETE, 18.0, "");
-                            });
-                            ui.horizontal(|ui| {
-                                show_table_text_cell(ui, SKD_COL_NUM, 16.0, "");
-                                show_table_text_cell(ui, SKD_COL_SOURCE, 16.0, "");
-                                show_table_text_cell(ui, SKD_COL_DATE, 16.0, "YYYY-MM-DD");
-                                show_table_text_cell(ui, SKD_COL_TIME, 16.0, "hh:mm:ss");
-                                show_table_text_cell(ui, SKD_COL_DURATION, 16.0, "s");
-                                show_table_text_cell(ui, SKD_COL_AZEL, 16.0, "deg");
-                                show_table_text_cell(ui, SKD_COL_AZEL, 16.0, "deg");
-                                show_table_text_cell(ui, SKD_COL_ANTENNA, 16.0, "limits / slew");
-                                show_table_text_cell(ui, SKD_COL_ANTENNA, 16.0, "limits / slew");
-                                show_table_text_cell(ui, SKD_COL_DELETE, 16.0, "");
-                            });
-                            ui.separator();
+                        ui.label("Observing Frequency:");
+                        ui.horizontal(|ui| {
+                            let toggled = ui.checkbox(&mut self.frequency_enabled, "").changed();
+                            let edited = ui
+                                .add_enabled(
+                                    self.frequency_enabled,
+                                    egui::DragValue::new(&mut self.observing_frequency_ghz)
+                                        .range(0.1..=1000.0)
+                                        .speed(0.1)
+                                        .suffix(" GHz"),
+                                )
+                                .changed();
+                            if toggled || edited {
+                                if let Err(e) = self.save_preferences() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                            }
+                            ui.label(format!(
+                                "refraction at {:.0}°: {:.1}′",
+                                self.elevation_floor_deg,
+                                utils::refraction_deg(
+                                    self.elevation_floor_deg,
+                                    self.observing_frequency()
+                                ) * 60.0
+                            ));
+                        });
+                        ui.end_row();
 
-                            for i in 0..self.skd_rows.len() {
-                                let status = self.skd_status_cache.get(i);
-                                let (start_geometry, end_geometry, motion_check_1, motion_check_2) =
-                                    if let Some(s) = status {
-                                        (
-                                            s.start_geometry.as_str(),
-                                            s.end_geometry.as_str(),
-                                            s.motion_1.as_str(),
-                                            s.motion_2.as_str(),
-                                        )
-                                    } else {
-                                        ("...", "...", "...", "...")
-                                    };
-                                ui.horizontal(|ui| {
-                                    ui.add_sized(
-                                        [SKD_COL_NUM, 20.0],
-                                        egui::Label::new((i + 1).to_string()),
-                                    );
-                                    let selected_source = self.skd_rows[i].source_name.as_str();
-                                    egui::ComboBox::from_id_salt(format!("skd_source_{}", i))
-                                        .width(SKD_COL_SOURCE)
-                                        .selected_text(source_table_text(selected_source))
-                                        .show_ui(ui, |ui| {
-                                            for (source, _) in &self.sources {
-                                                if ui
-                                                    .selectable_value(
-                                                        &mut self.skd_rows[i].source_name,
-                                                        source.name.clone(),
-                                                        &source.name,
-                                                    )
-                                                    .changed()
-                                                {
-                                                    table_changed = true;
-                                                }
-                                            }
-                                        });
-                                    ui.add_sized(
-                                        [SKD_COL_DATE, 20.0],
-                                        egui::Label::new(
-                                            self.skd_rows[i]
-                                                .start_date
-                                                .format("%Y-%m-%d")
-                                                .to_string(),
-                                        ),
-                                    );
-                                    if ui
-                                        .add_sized(
-                                            [SKD_COL_TIME, 20.0],
-                                            egui::TextEdit::singleline(
-                                                &mut self.skd_rows[i].start_time,
-                                            ),
-                                        )
-                                        .changed()
-                                    {
-                                        table_changed = true;
-                                    }
-                                    if ui
-                                        .add_sized(
-                                            [SKD_COL_DURATION, 20.0],
-                                            egui::DragValue::new(
-                                                &mut self.skd_rows[i].duration_sec,
-                                            )
-                                            .speed(10)
-                                            .range(1..=86400),
-                                        )
-                                        .changed()
-                                    {
-                                        table_changed = true;
+                        ui.label("Extend Past Midnight:");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut self.overnight_hours)
+                                    .range(0.0..=MAX_OVERNIGHT_HOURS)
+                                    .speed(0.25)
+                                    .suffix(" h"),
+                            )
+                            .on_hover_text("Keep sampling into the next UT date so tracks rising late continue past 24:00")
+                            .changed()
+                        {
+                            self.time_view = self.full_time_view();
+                            if !self.plot_data.is_empty() {
+                                self.calculate_plots();
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Max Az Rate:");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut self.max_az_rate_deg_per_sec)
+                                    .range(0.01..=10.0)
+                                    .speed(0.01)
+                                    .suffix(" °/s"),
+                            )
+                            .changed()
+                            && !self.plot_data.is_empty()
+                        {
+                            self.calculate_plots();
+                        }
+                        ui.end_row();
+
+                        ui.label("Beam:");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut self.beam_arcmin)
+                                    .range(0.01..=600.0)
+                                    .speed(0.1)
+                                    .suffix("′"),
+                            )
+                            .on_hover_text("Selected sources closer than this are listed as unresolved pairs after plotting")
+                            .changed()
+                            && !self.plot_data.is_empty()
+                        {
+                            self.close_pairs = self.find_close_pairs();
+                        }
+                        ui.end_row();
+
+                        ui.label("Session (UT):");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.session_enabled, "");
+                            ui.add_enabled(
+                                self.session_enabled,
+                                egui::TextEdit::singleline(&mut self.session_start_time)
+                                    .desired_width(86.0),
+                            );
+                            ui.label("-");
+                            ui.add_enabled(
+                                self.session_enabled,
+                                egui::TextEdit::singleline(&mut self.session_end_time)
+                                    .desired_width(86.0),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("LST at 00:00 UT:");
+                        if let Some(station_pos) = self.current_station().map(|station| station.pos) {
+                            if let Some(lst_hours) = self.lst_from_ut_hour(station_pos, 0.0) {
+                                ui.label(format_hour_hms(lst_hours));
+                            } else {
+                                ui.label("N/A");
+                            }
+                        } else {
+                            ui.label("N/A (no station selected)");
+                        }
+                        ui.end_row();
+                    });
+                });
+                ui.add_space(10.0);
+
+                // --- Source Settings ---
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.heading("🔭 Source Settings");
+                    ui.add_space(5.0);
+                    egui::Grid::new("source_settings_grid").num_columns(2).spacing([40.0, 4.0]).striped(true).show(ui, |ui| {
+                        ui.label("Source List File:");
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.source_file_path);
+                            if ui.button("Load").clicked() {
+                                match pick_file_dialog("Select source.txt") {
+                                    Ok(Some(path)) => {
+                                        self.source_file_path = path.to_string_lossy().to_string();
+                                        match self.load_sources() {
+                                            Ok(_) => {}
+                                            Err(e) => self.log(LogLevel::Error, e),
+                                        }
                                     }
-                                    ui.add_sized(
-                                        [SKD_COL_AZEL, 20.0],
-                                        egui::Label::new(start_geometry),
-                                    )
-                                    .on_hover_text("Start AZ/EL");
-                                    ui.add_sized(
-                                        [SKD_COL_AZEL, 20.0],
-                                        egui::Label::new(end_geometry),
-                                    )
-                                    .on_hover_text("End AZ/EL");
-                                    for motion_check in [motion_check_1, motion_check_2] {
-                                        show_motion_status_cell(ui, motion_check).on_hover_text(
-                                            "Checks start/end AZ/EL limits and required slew time.",
-                                        );
+                                    Ok(None) => {}
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                }
+                            }
+                            if ui.button("Reload").clicked() {
+                                match self.load_sources() {
+                                    Ok(_) => {}
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                }
+                            }
+                            if ui.button("Open").clicked() {
+                                match utils::open_file_in_external_editor(&self.source_file_path) {
+                                    Ok(_) => {}
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                }
+                            }
+                            if ui.button("Edit").clicked() {
+                                self.source_editor = Some(
+                                    self.sources
+                                        .iter()
+                                        .find(|(source, selected)| {
+                                            *selected && !self.is_adhoc(source)
+                                        })
+                                        .map(|(source, _)| SourceEditor::from_source(source))
+                                        .unwrap_or_else(SourceEditor::new_source),
+                                );
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Catalog Changes:");
+                        if ui
+                            .checkbox(&mut self.auto_reload_catalogs, "Reload automatically")
+                            .on_hover_text("Reload station and source files edited outside the program, keeping the selection by name")
+                            .changed()
+                        {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Startup:");
+                        if ui
+                            .checkbox(&mut self.auto_restore_session, "Restore last session")
+                            .on_hover_text("Reopen the last catalogues, selection, date and settings. Start with --fresh to skip once.")
+                            .changed()
+                        {
+                            if let Err(e) = self.save_preferences() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Search Filter:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.search_query));
+                            let matches: usize = self.source_filter_matches().iter().map(|(_, indices)| indices.len()).sum();
+                            if matches == 0 && !self.sources.is_empty() {
+                                ui.colored_label(egui::Color32::YELLOW, "no matches");
+                            } else if !self.search_query.trim().is_empty() {
+                                ui.label(format!("{} of {} match", matches, self.sources.len()));
+                            }
+                            let selected_count = self.sources.iter().filter(|(_, selected)| *selected).count();
+                            ui.checkbox(&mut self.show_only_selected, format!("Selected only ({})", selected_count))
+                                .on_hover_text("List only the checked sources");
+                        });
+                        ui.end_row();
+
+                        ui.label("Preview:");
+                        self.ui_source_preview_control(ui);
+                        ui.end_row();
+                    });
+
+                    ui.separator();
+                    ui.label("Select Sources to Plot:");
+                    ui.horizontal(|ui|{
+                        if ui.button("Plot Selected").clicked() {
+                            self.calculate_plots();
+                            if self.plot_log_enabled {
+                                if let Err(e) = self.append_plot_log() {
+                                    self.log(LogLevel::Error, e);
+                                }
+                            }
+                        }
+                        if ui
+                            .button("Reload & Replot")
+                            .on_hover_text("Re-read the station and source files, keep the selection by name and plot again")
+                            .clicked()
+                        {
+                            if let Err(e) = self.reload_all_and_replot() {
+                                self.log(LogLevel::Error, e);
+                            }
+                        }
+                        let capture_idle = self.ensure_capture_idle().is_ok();
+                        if ui.add_enabled(capture_idle, egui::Button::new("output")).clicked() {
+                            match self.start_output_capture(ui.ctx(), None) {
+                                Ok(_) => self.log(LogLevel::Info, "Output started..."),
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.add_enabled(capture_idle, egui::Button::new("Save Az/El Image")).on_hover_text("One PNG of both UT plots with a title block").clicked() {
+                            let default_name = format!("uptime_azel_{}.png", Utc::now().format("%Y%m%d_%H%M%S"));
+                            match save_file_dialog("Save az/el image as", &default_name) {
+                                Ok(Some(path)) => match self.start_titled_azel_output(ui.ctx(), path) {
+                                    Ok(_) => self.log(LogLevel::Info, "Saving az/el image..."),
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                },
+                                Ok(None) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.add_enabled(capture_idle, egui::Button::new("Generate Report")).clicked() {
+                            let default_name = format!("uptime_{}.pdf", self.selected_date.format("%Y%m%d"));
+                            match save_file_dialog("Save report as", &default_name) {
+                                Ok(Some(path)) => match self.start_output_capture(ui.ctx(), Some(path)) {
+                                    Ok(_) => self.log(LogLevel::Info, "Generating report..."),
+                                    Err(e) => self.log(LogLevel::Error, e),
+                                },
+                                Ok(None) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.button("Export Config").on_hover_text("Save the resolved settings as JSON").clicked() {
+                            let default_name = format!("uptime_{}_config.json", self.selected_date.format("%Y%m%d"));
+                            match save_file_dialog("Save configuration as", &default_name) {
+                                Ok(Some(path)) => {
+                                    match fs::write(&path, self.effective_config_json() + "\n") {
+                                        Ok(_) => self.log(LogLevel::Info, format!("Configuration saved to {}", path.display())),
+                                        Err(e) => self.log(LogLevel::Error, format!("Failed to save {:?}: {}", path, e)),
                                     }
-                                    if ui
-                                        .add_sized([SKD_COL_DELETE, 20.0], egui::Button::new("Del"))
-                                        .clicked()
-                                    {
-                                        remove_idx = Some(i);
+                                }
+                                Ok(None) => {}
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.button("Reset Source Selection").clicked() {
+                            for (_, selected) in &mut self.sources {
+                                *selected = false;
+                            }
+                        }
+                        if ui.button("Invert Selection").clicked() {
+                            for (_, selected) in &mut self.sources {
+                                *selected = !*selected;
+                            }
+                        }
+                        if ui.button("Select Visible Now").on_hover_text("Also select every source above the floor right now at the first selected station").clicked() {
+                            match self.select_visible_now() {
+                                Ok(count) => self.log(LogLevel::Info, format!("{} sources are above the floor now.", count)),
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                        if ui.button("Suggest order").clicked() {
+                            match self.suggest_observing_order() {
+                                Ok(suggestion) => {
+                                    self.suggested_order = Some(suggestion);
+                                }
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                    });
+                    self.ui_suggested_order(ui);
+                    self.ui_adhoc_sources(ui);
+                    self.ui_mosaic(ui);
+                    self.ui_csv_export(ui);
+                    self.ui_calendar_export(ui);
+                    self.ui_tracking_export(ui);
+                    egui::CollapsingHeader::new("Batch output (one UT az/el PNG per day)").show(ui, |ui| {
+                        egui::Grid::new("batch_output_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Start date (YYYY-MM-DD):");
+                            ui.text_edit_singleline(&mut self.batch_start_date);
+                            ui.end_row();
+                            ui.label("Days:");
+                            ui.add(egui::DragValue::new(&mut self.batch_days).range(1..=366));
+                            ui.end_row();
+                            ui.label("Output directory:");
+                            ui.text_edit_singleline(&mut self.batch_output_dir);
+                            ui.end_row();
+                        });
+                        if ui
+                            .add_enabled(self.ensure_capture_idle().is_ok(), egui::Button::new("Run batch"))
+                            .clicked()
+                        {
+                            match self.start_batch_output(ui.ctx()) {
+                                Ok(_) => self.log(LogLevel::Info, "Batch output started..."),
+                                Err(e) => self.log(LogLevel::Error, e),
+                            }
+                        }
+                    });
+
+                    if self.sources.is_empty() {
+                        ui.label("(No sources loaded)");
+                    } else {
+                        self.ui_source_list(ui);
+                    }
+                });
+                ui.add_space(10.0);
+
+                // --- Visibility Summary ---
+                if !self.plot_data.is_empty() || !self.never_visible.is_empty() {
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.heading("Visibility Summary");
+                        ui.add_space(5.0);
+                        self.ui_visibility_summary(ui);
+                        if !self.fast_track_warnings.is_empty() {
+                            ui.add_space(5.0);
+                            ui.strong(format!(
+                                "Az rate above {:.2}°/s (keyhole)",
+                                self.max_az_rate_deg_per_sec
+                            ));
+                            for warning in &self.fast_track_warnings {
+                                let spans: Vec<String> = warning
+                                    .windows
+                                    .iter()
+                                    .map(|(start, end)| {
+                                        format!("{}-{}", self.time_display.format(*start), self.time_display.format(*end))
+                                    })
+                                    .collect();
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!(
+                                        "{}:{} {} UT (peak az {:.2}°/s, el {:.3}°/s)",
+                                        warning.source_name,
+                                        warning.station_name,
+                                        spans.join(", "),
+                                        warning.peak_az_rate,
+                                        warning.peak_el_rate
+                                    ),
+                                );
+                            }
+                        }
+                        if !self.close_pairs.is_empty() {
+                            ui.add_space(5.0);
+                            ui.strong(format!("Closer than the {:.1}′ beam (unresolved)", self.beam_arcmin));
+                            for (a, b, separation) in &self.close_pairs {
+                                ui.colored_label(egui::Color32::YELLOW, format!("{} and {}: {:.2}′ apart", a, b, separation));
+                            }
+                        }
+                        if !self.never_visible.is_empty() {
+                            ui.add_space(5.0);
+                            ui.strong("Never visible");
+                            let mut check = None;
+                            for (idx, entry) in self.never_visible.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        format!(
+                                            "{} at {} (max el {:.1}°)",
+                                            entry.source_name, entry.station_name, entry.max_el_deg
+                                        ),
+                                    );
+                                    match entry.flipped_max_el_deg {
+                                        None => {
+                                            if ui
+                                                .small_button("Check Dec sign")
+                                                .on_hover_text("Recompute with the declination sign flipped")
+                                                .clicked()
+                                            {
+                                                check = Some(idx);
+                                            }
+                                        }
+                                        Some(flipped) if flipped >= self.effective_floor_deg() => {
+                                            ui.colored_label(
+                                                egui::Color32::YELLOW,
+                                                format!("With the Dec sign flipped it would reach {:.1}°; check the catalogue entry.", flipped),
+                                            );
+                                        }
+                                        Some(flipped) => {
+                                            ui.weak(format!("Flipping the Dec sign does not help (max el {:.1}°).", flipped));
+                                        }
                                     }
                                 });
                             }
+                            if let Some(idx) = check {
+                                let flipped = self.flipped_dec_max_el(&self.never_visible[idx]);
+                                match flipped {
+                                    Some(max_el) => self.never_visible[idx].flipped_max_el_deg = Some(max_el),
+                                    None => self.log(LogLevel::Warn, "Source or station is no longer loaded."),
+                                }
+                            }
+                        }
+                    });
+                    ui.add_space(10.0);
+                }
+
+                egui::CollapsingHeader::new("Rise/Set Countdown").show(ui, |ui| {
+                    self.ui_countdown(ui);
+                });
+                egui::CollapsingHeader::new("Best Night Dates").show(ui, |ui| {
+                    self.ui_night_search(ui);
+                });
+                ui.add_space(10.0);
+
+                // --- File Formats (Moved here) ---
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.heading("📄 File Format Information");
+                    ui.add_space(5.0);
+                    ui.label("station.txt format (ECEF): NAME X_POS Y_POS Z_POS [HA_MIN HA_MAX]");
+                    ui.label("e.g. YAMAGU32 -3502544.587 3950966.235 3566381.192");
+                    ui.label("HA_MIN/HA_MAX (hours) mask samples outside an equatorial mount's hour-angle range.");
+                    ui.label("A .vex schedule can be loaded instead; stations come from its $SITE site_position entries.");
+                    ui.separator();
+                    ui.label("source.txt format: NAME  RA_H  RA_M  RA_S  DEC_D  DEC_M  DEC_S");
+                    ui.label("e.g. 3C273  12 29 06.7 +02 03 08.6");
+                    ui.label("An optional GROUP word after the epoch (e.g. target, fringe-finder) sections the source list and legend.");
+                });
+            });
+
+            // --- Right Column: Usage Only ---
+            egui::ScrollArea::vertical().show(&mut columns[1], |ui| {
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.heading("ℹProgram Usage");
+                    ui.add_space(5.0);
+                    let mut help_text = CliArgs::command().render_help().to_string();
+                    // Revert to ui.code() for now
+                    ui.add(egui::TextEdit::multiline(&mut help_text)
+                        .desired_width(f32::INFINITY)
+                        .interactive(false)
+                        .font(egui::TextStyle::Monospace));
+                    ui.label("1. Push the Load button in Source settings.");
+                    ui.label("2. Select some targets for drawing an uptime plot.");
+                    ui.label("3. Push the Plot Selected button in Source settings.");
+                    ui.label("4. Push the Uptime Plotters button in the upper left corner in this tab.");
+                    ui.label("");
+                    ui.label("To create a new uptime plot graph, first, push the \"Reset Source Selection\" button in Source Settings to clear previous selections. Then, you can repeat steps 1 to 4 to generate a new plot.");
+                    ui.label("");
+                    ui.label("If you want to edit the data files for sources or stations, please push the \"Open\" buttons in Station Settings and Source Settings.");
+                });
+            });
+        });
+    }
+
+    /// Catalogue priority of the source named `name`; mosaic and other derived series fall
+    /// back to the default.
+    fn source_priority(&self, name: &str) -> i32 {
+        self.sources
+            .iter()
+            .find(|(source, _)| source.name == name)
+            .map_or(DEFAULT_PRIORITY, |(source, _)| source.priority)
+    }
+
+    /// Per-series up time (within the session, if set) and its observability status.
+    fn visibility_summary_rows(&self) -> Vec<(&PlotSeries, VisibilityStatus, f64)> {
+        let session = self.session_windows();
+        self.plot_data
+            .iter()
+            .map(|series| {
+                let up_windows = match &session {
+                    Some(session) => utils::intersect_windows(&series.windows, session),
+                    None => series.windows.clone(),
+                };
+                let status = if series.windows.is_empty() {
+                    VisibilityStatus::NeverAboveFloor
+                } else if up_windows.is_empty() {
+                    VisibilityStatus::NotInSession
+                } else {
+                    VisibilityStatus::Ok
+                };
+                (series, status, utils::total_window_hours(&up_windows))
+            })
+            .collect()
+    }
+
+    fn ui_visibility_summary(&mut self, ui: &mut egui::Ui) {
+        let session_hours = self
+            .session_windows()
+            .map(|session| utils::total_window_hours(&session));
+        let mut rows = self.visibility_summary_rows();
+        if session_hours.is_some() && self.rank_by_session_fraction {
+            // Stable, so equally visible sources keep the plot order. Priority scales the up
+            // time, so a more important source ranks above a slightly better placed one.
+            let score = |row: &(&PlotSeries, VisibilityStatus, f64)| {
+                row.2 * utils::priority_weight(self.source_priority(&row.0.source_name))
+            };
+            rows.sort_by(|a, b| score(b).total_cmp(&score(a)));
+        }
+        let mut toggle_ranking = false;
+        let horizon = format!(
+            "{:.1}° {}",
+            self.effective_floor_deg(),
+            if self.rise_set_refracted {
+                "app."
+            } else {
+                "geom."
+            }
+        );
+        egui::Grid::new("visibility_summary_grid")
+            .num_columns(if session_hours.is_some() { 8 } else { 7 })
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Source");
+                ui.strong("Station");
+                ui.strong("Priority");
+                ui.strong(if session_hours.is_some() {
+                    "Up in session"
+                } else {
+                    "Up"
+                });
+                if session_hours.is_some() {
+                    let label = if self.rank_by_session_fraction {
+                        "Session % (ranked)"
+                    } else {
+                        "Session %"
+                    };
+                    toggle_ranking = ui
+                        .add(egui::Button::new(egui::RichText::new(label).strong()).frame(false))
+                        .on_hover_text(
+                            "Rank by the fraction of the session above the elevation floor",
+                        )
+                        .clicked();
+                }
+                ui.strong(format!("Rise ({})", horizon));
+                ui.strong(format!("Set ({})", horizon));
+                ui.strong("Status");
+                ui.end_row();
+
+                for (series, status, up_hours) in rows {
+                    ui.label(&series.source_name);
+                    ui.label(&series.station_name);
+                    ui.label(self.source_priority(&series.source_name).to_string());
+                    ui.label(format!("{:.2} h", up_hours));
+                    if let Some(session_hours) = session_hours {
+                        ui.label(format!(
+                            "{:.0}%",
+                            session_visible_percent(up_hours, session_hours)
+                        ));
+                    }
+                    for hours in [&series.rise_hours, &series.set_hours] {
+                        ui.label(if hours.is_empty() {
+                            "-".to_string()
+                        } else {
+                            hours
+                                .iter()
+                                .map(|&hour| self.time_display.format(hour))
+                                .collect::<Vec<_>>()
+                                .join(", ")
                         });
-                    if let Some(i) = remove_idx {
-                        self.skd_rows.remove(i);
-                        self.mark_skd_status_dirty();
-                    } else if table_changed {
-                        self.mark_skd_status_dirty();
                     }
-                },
+                    match status {
+                        VisibilityStatus::NeverAboveFloor => {
+                            ui.colored_label(egui::Color32::RED, status.label());
+                        }
+                        VisibilityStatus::NotInSession => {
+                            ui.colored_label(egui::Color32::YELLOW, status.label());
+                        }
+                        VisibilityStatus::Ok => {
+                            ui.label(status.label());
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        if toggle_ranking {
+            self.rank_by_session_fraction = !self.rank_by_session_fraction;
+        }
+    }
+
+    /// The station the polar plot's sky overlays and live positions are drawn for: that of
+    /// the first plotted series, else the first selected station.
+    fn polar_station(&self) -> Option<&Station> {
+        let station_idx = self
+            .plot_data
+            .first()
+            .map(|series| series.station_idx)
+            .or_else(|| self.stations.iter().position(|(_, selected)| *selected));
+        station_idx
+            .and_then(|idx| self.stations.get(idx))
+            .map(|(station, _)| station)
+    }
+
+    /// Advances the overlay time by the frames due since the last one, wrapping at the end of
+    /// the sampled day, and schedules the next frame. Observing time moves `step_minutes` per
+    /// frame however often the window repaints.
+    fn advance_polar_animation(&mut self, ctx: &egui::Context) {
+        let ut_end = self.ut_sample_end();
+        let animation = &mut self.polar_animation;
+        if !animation.enabled || !animation.playing || self.polar_live {
+            animation.last_frame = None;
+            return;
+        }
+        let frame = std::time::Duration::from_secs_f64(1.0 / animation.frames_per_second);
+        let now = Instant::now();
+        let last = *animation.last_frame.get_or_insert(now);
+        let frames = (now.duration_since(last).as_secs_f64() / frame.as_secs_f64()).floor();
+        if frames >= 1.0 {
+            // After a pause in repainting (another tab, a minimised window) resume from here
+            // rather than jumping ahead by the missed frames.
+            let frames = if frames > animation.frames_per_second {
+                1.0
+            } else {
+                frames
+            };
+            self.sky_overlay_hour =
+                (self.sky_overlay_hour + frames * animation.step_minutes / 60.0).rem_euclid(ut_end);
+            animation.last_frame = Some((last + frame.mul_f64(frames)).max(now - frame));
+        }
+        ctx.request_repaint_after(frame);
+    }
+
+    fn ui_polar_animation_controls(&mut self, ui: &mut egui::Ui) {
+        let ut_end = self.ut_sample_end();
+        let animation = &mut self.polar_animation;
+        ui.checkbox(&mut animation.enabled, "Animate")
+            .on_hover_text("Step through the day, ringing each track at the overlay time");
+        if !animation.enabled {
+            animation.playing = false;
+            return;
+        }
+        let step_hours = animation.step_minutes / 60.0;
+        if ui
+            .button(if animation.playing { "⏸" } else { "▶" })
+            .on_hover_text(if animation.playing { "Pause" } else { "Play" })
+            .clicked()
+        {
+            animation.playing = !animation.playing;
+        }
+        if ui.button("⏭").on_hover_text("Step one frame").clicked() {
+            animation.playing = false;
+            self.sky_overlay_hour = (self.sky_overlay_hour + step_hours).rem_euclid(ut_end);
+        }
+        if ui.button("⏮").on_hover_text("Back to 00:00 UT").clicked() {
+            self.sky_overlay_hour = 0.0;
+        }
+        ui.add(
+            egui::DragValue::new(&mut animation.step_minutes)
+                .range(0.5..=120.0)
+                .speed(0.5)
+                .suffix(" min/frame"),
+        )
+        .on_hover_text("Observing time advanced per frame");
+        ui.add(
+            egui::DragValue::new(&mut animation.frames_per_second)
+                .range(1.0..=60.0)
+                .speed(0.5)
+                .suffix(" fps"),
+        )
+        .on_hover_text("Frames shown per second of wall-clock time");
+    }
+
+    /// UTC time of the polar plot's overlays: now in live mode, else the chosen overlay hour.
+    fn sky_overlay_time(&self) -> Option<chrono::DateTime<Utc>> {
+        if self.polar_live {
+            Some(Utc::now())
+        } else {
+            utc_datetime_from_hour(self.selected_date, self.sky_overlay_hour)
+        }
+    }
+
+    /// Polar-plot position of `ra`/`dec` at `time` (UTC) from the polar station, or `None`
+    /// below the horizon.
+    fn polar_position_at(
+        &self,
+        time: chrono::DateTime<Utc>,
+        ra: f64,
+        dec: f64,
+        sidereal: utils::SiderealMode,
+        frame: utils::ReferenceFrame,
+    ) -> Option<(f64, f64)> {
+        let station = self.polar_station()?;
+        let pointing = utils::radec2azalt_with(
+            self.ellipsoid,
+            sidereal,
+            frame,
+            station.pos,
+            utils::ut1_from_utc(time, self.dut1_seconds),
+            ra,
+            dec,
+        );
+        (pointing.el_deg >= 0.0).then_some((pointing.az_deg, pointing.el_deg))
+    }
+
+    /// Above-horizon polar-plot segments of the great circle traced by `radec_at(angle)` for
+    /// `angle` in 0..360°, as seen from the first plotted (or selected) station at
+    /// `sky_overlay_hour`.
+    fn sky_circle_segments(&self, radec_at: impl Fn(f64) -> (f64, f64)) -> Vec<Vec<[f64; 2]>> {
+        let (Some(station), Some(time)) = (self.polar_station(), self.sky_overlay_time()) else {
+            return Vec::new();
+        };
+        let time = utils::ut1_from_utc(time, self.dut1_seconds);
+
+        let mut segments = Vec::new();
+        let mut current = Vec::new();
+        for step in 0..=360 {
+            let (ra, dec) = radec_at((step as f64).to_radians());
+            let pointing = utils::radec2azalt_with(
+                self.ellipsoid,
+                self.sidereal_mode,
+                self.reference_frame,
+                station.pos,
+                time,
+                ra,
+                dec,
             );
-        });
+            if pointing.el_deg >= 0.0 {
+                current.push(
+                    self.polar_orientation
+                        .polar_xy(pointing.az_deg, pointing.el_deg),
+                );
+            } else if current.len() > 1 {
+                segments.push(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+        }
+        if current.len() > 1 {
+            segments.push(current);
+        }
+        segments
+    }
 
-        self.show_source_picker_windows(ui.ctx());
+    /// Shades the annulus from the horizon up to the effective elevation floor, where
+    /// tracks are marginal, and labels it with the airmass at the floor.
+    fn draw_low_elevation_band(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let floor = self.effective_floor_deg();
+        if !self.polar_low_el_shading || floor <= 0.0 || floor >= 90.0 {
+            return;
+        }
+        let color = egui::Color32::from_rgba_unmultiplied(230, 150, 40, 45);
+        let inner = (90.0 - floor) / 90.0;
+        // The annulus is filled as convex 5° segments.
+        let step = 5.0_f64;
+        for i in 0..72 {
+            let (a0, a1) = (
+                (i as f64 * step).to_radians(),
+                ((i + 1) as f64 * step).to_radians(),
+            );
+            let points = vec![
+                [a0.cos(), a0.sin()],
+                [a1.cos(), a1.sin()],
+                [inner * a1.cos(), inner * a1.sin()],
+                [inner * a0.cos(), inner * a0.sin()],
+            ];
+            plot_ui.polygon(
+                egui_plot::Polygon::new("", PlotPoints::from(points))
+                    .fill_color(color)
+                    .stroke(egui::Stroke::NONE)
+                    .allow_hover(false),
+            );
+        }
+        let label_radius = (1.0 + inner) / 2.0;
+        let angle = 252.0_f64.to_radians();
+        let airmass = 1.0 / floor.to_radians().sin();
+        plot_ui.text(
+            egui_plot::Text::new(
+                "",
+                egui_plot::PlotPoint::new(label_radius * angle.cos(), label_radius * angle.sin()),
+                format!("High airmass (X > {:.1})", airmass),
+            )
+            .color(egui::Color32::from_rgb(200, 120, 30)),
+        );
+    }
+
+    /// Shades the azimuths outside `az_sectors` as wedges out to the horizon.
+    fn draw_forbidden_az_sectors(&self, plot_ui: &mut egui_plot::PlotUi) {
+        if self.az_sectors.is_empty() {
+            return;
+        }
+        let color = egui::Color32::from_rgba_unmultiplied(255, 60, 60, 50);
+        // Wedges of at most 10° stay convex, which the polygon fill needs.
+        let step = 2.0;
+        let mut az = 0.0;
+        while az < 360.0 {
+            let mid = az + step / 2.0;
+            if utils::az_sector_margin(mid, &self.az_sectors) < 0.0 {
+                let end = (az + 10.0).min(360.0);
+                let mut wedge_end = az;
+                while wedge_end < end
+                    && utils::az_sector_margin(wedge_end + step / 2.0, &self.az_sectors) < 0.0
+                {
+                    wedge_end += step;
+                }
+                let mut points = vec![[0.0, 0.0]];
+                let mut edge = az;
+                while edge <= wedge_end + 1e-9 {
+                    points.push(self.polar_orientation.polar_xy(edge, 0.0));
+                    edge += step;
+                }
+                plot_ui.polygon(
+                    egui_plot::Polygon::new("Forbidden azimuth", PlotPoints::from(points))
+                        .fill_color(color)
+                        .stroke(egui::Stroke::NONE)
+                        .allow_hover(false),
+                );
+                az = wedge_end;
+            } else {
+                az += step;
+            }
+        }
+    }
+
+    /// Sun and Moon markers at the overlay time, when above the horizon.
+    fn draw_sun_moon(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let Some(time) = self.sky_overlay_time() else {
+            return;
+        };
+        for (name, color, (ra, dec)) in [
+            (
+                "Sun",
+                egui::Color32::from_rgb(255, 200, 0),
+                utils::sun_radec(time),
+            ),
+            (
+                "Moon",
+                egui::Color32::from_rgb(200, 200, 210),
+                utils::moon_radec(time),
+            ),
+        ] {
+            let Some((az, el)) = self.polar_position_at(
+                time,
+                ra,
+                dec,
+                self.effective_sidereal_mode(),
+                utils::ReferenceFrame::Catalogue,
+            ) else {
+                continue;
+            };
+            let [x, y] = self.polar_orientation.polar_xy(az, el);
+            plot_ui.points(
+                Points::new(name, PlotPoints::from(vec![[x, y]]))
+                    .radius(self.polar_point_radius * 2.5)
+                    .color(color),
+            );
+            plot_ui.text(
+                egui_plot::Text::new("", egui_plot::PlotPoint::new(x, y), format!("  {}", name))
+                    .anchor(egui::Align2::LEFT_CENTER)
+                    .color(color),
+            );
+        }
+    }
+
+    /// Where each selected source is at this moment, for the live polar view. Sources below
+    /// the horizon are left out; those below the floor use the below-floor colour.
+    fn draw_live_positions(
+        &self,
+        plot_ui: &mut egui_plot::PlotUi,
+        markers: &[(String, SourceMarker)],
+    ) -> Vec<String> {
+        let Some(station) = self.polar_station() else {
+            return Vec::new();
+        };
+        let now = Utc::now();
+        let floor = self.effective_floor_deg();
+        let mut names = Vec::new();
+        for (source, _) in self.sources.iter().filter(|(_, selected)| *selected) {
+            let Some((az, el)) = self.polar_position_at(
+                now,
+                source.ra_rad,
+                source.dec_rad,
+                self.sidereal_mode,
+                self.reference_frame,
+            ) else {
+                continue;
+            };
+            let name = format!("{}:{}", source.name, station.name);
+            let [x, y] = self.polar_orientation.polar_xy(az, el);
+            let mut points = Points::new(name.clone(), PlotPoints::from(vec![[x, y]]))
+                .shape(self.source_marker(markers, &source.name).shape())
+                .radius(self.polar_point_radius * 1.5);
+            if el < floor {
+                points = points.color(self.polar_below_floor_color);
+            }
+            plot_ui.points(points);
+            plot_ui.text(
+                egui_plot::Text::new(
+                    "",
+                    egui_plot::PlotPoint::new(x, y),
+                    format!("  {}", source.name),
+                )
+                .anchor(egui::Align2::LEFT_CENTER)
+                .color(egui::Color32::LIGHT_GRAY),
+            );
+            names.push(name);
+        }
+        names
+    }
 
-        if let Some(err) = &self.error_msg {
-            ui.add_space(8.0);
-            ui.colored_label(egui::Color32::RED, err);
+    /// Faint dashed Galactic plane (b = 0) and ecliptic curves for the enabled overlays.
+    fn draw_sky_overlays(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let time = self.sky_overlay_time();
+        let mut overlays = Vec::new();
+        if self.show_galactic_plane {
+            overlays.push((
+                "Galactic plane",
+                egui::Color32::from_rgba_unmultiplied(190, 120, 255, 150),
+                self.sky_circle_segments(|l| utils::galactic_to_radec(l, 0.0)),
+            ));
+        }
+        if let (true, Some(time)) = (self.show_ecliptic, time) {
+            overlays.push((
+                "Ecliptic",
+                egui::Color32::from_rgba_unmultiplied(255, 220, 90, 150),
+                self.sky_circle_segments(|longitude| utils::ecliptic_radec(time, longitude)),
+            ));
+        }
+        for (name, color, segments) in overlays {
+            // Label the point nearest the zenith, where it is least likely to leave the plot.
+            let label_at = segments
+                .iter()
+                .flatten()
+                .min_by(|a, b| (a[0].hypot(a[1])).total_cmp(&b[0].hypot(b[1])))
+                .copied();
+            for segment in segments {
+                plot_ui.line(
+                    Line::new(name, PlotPoints::from(segment))
+                        .stroke(egui::Stroke::new(1.5, color))
+                        .style(egui_plot::LineStyle::Dashed { length: 8.0 }),
+                );
+            }
+            if let Some([x, y]) = label_at {
+                plot_ui.text(
+                    egui_plot::Text::new("", egui_plot::PlotPoint::new(x, y), name).color(color),
+                );
+            }
         }
     }
 
-    fn show_source_picker_windows(&mut self, ctx: &egui::Context) {
-        self.show_source_picker_window(ctx, 0);
-        self.show_source_picker_window(ctx, 1);
-        self.show_source_picker_window(ctx, 2);
-    }
+    fn ui_polar_plot_tab(&mut self, ui: &mut egui::Ui) {
+        //ui.heading("Polar Plot");
+        let ut_end = self.ut_sample_end();
+        ui.horizontal(|ui| {
+            ui.label("Rings:");
+            ui.radio_value(&mut self.polar_airmass_rings, false, "Elevation");
+            ui.radio_value(&mut self.polar_airmass_rings, true, "Airmass");
+            ui.separator();
+            ui.checkbox(&mut self.show_galactic_plane, "Galactic plane");
+            ui.checkbox(&mut self.show_ecliptic, "Ecliptic");
+            ui.separator();
+            ui.checkbox(&mut self.polar_sky_fill, "Sky disk");
+            ui.checkbox(&mut self.polar_low_el_shading, "Low-el shading")
+                .on_hover_text(
+                    "Shade the high-airmass band between the horizon and the elevation floor.",
+                );
+            ui.checkbox(&mut self.show_sun_moon, "Sun/Moon");
+            ui.separator();
+            ui.checkbox(&mut self.polar_live, "Live")
+                .on_hover_text("Show where the selected sources are now, updated every second");
+            ui.separator();
+            ui.add_enabled_ui(!self.polar_live, |ui| self.ui_polar_animation_controls(ui));
+            ui.separator();
+            if ui
+                .checkbox(&mut self.polar_zoom_enabled, "Zoom")
+                .on_hover_text("Drag to pan; Ctrl+scroll or right-drag a box to zoom")
+                .changed()
+                && !self.polar_zoom_enabled
+            {
+                self.polar_view = None;
+            }
+            if ui
+                .add_enabled(self.polar_view.is_some(), egui::Button::new("Reset view"))
+                .clicked()
+            {
+                self.polar_view = None;
+            }
+            if self.polar_live {
+                ui.label(format!("{} UTC", Utc::now().format("%Y-%m-%d %H:%M:%S")));
+            } else if self.show_galactic_plane
+                || self.show_ecliptic
+                || self.show_sun_moon
+                || self.polar_animation.enabled
+            {
+                ui.label("at");
+                ui.add(
+                    egui::DragValue::new(&mut self.sky_overlay_hour)
+                        .range(0.0..=ut_end)
+                        .speed(0.05)
+                        .fixed_decimals(2)
+                        .suffix(" h UT"),
+                );
+                ui.label(self.time_display.format(self.sky_overlay_hour));
+            }
+        });
+        let markers = self.default_source_markers();
+        self.ui_source_marker_legend(ui, &markers);
+        self.advance_polar_animation(ui.ctx());
+        if self.polar_animation.enabled && !self.polar_live {
+            self.cursor_ut_next = Some(self.sky_overlay_hour);
+        }
+
+        let orientation = self.polar_orientation;
+        let readout_color = ui.visuals().strong_text_color();
+        // A faint blue that stays below the tracks on either theme.
+        let sky_fill_color = if ui.visuals().dark_mode {
+            egui::Color32::from_rgba_unmultiplied(40, 80, 140, 70)
+        } else {
+            egui::Color32::from_rgba_unmultiplied(150, 200, 255, 90)
+        };
+        let label_size = self.polar_label_size;
+        let extent =
+            polar_label_extent(label_size, ui.available_width().min(ui.available_height()));
+        let plot = Plot::new("polar_plot")
+            .id(egui::Id::new("polar_plot"))
+            .width(ui.available_width()) // Added
+            .height(ui.available_height()) // Added
+            .data_aspect(1.0) // Ensure circular aspect ratio
+            .view_aspect(1.0) // Ensure circular aspect ratio
+            .include_x(-extent)
+            .include_x(extent) // Cartesian coordinates plus room for the azimuth labels
+            .include_y(-extent)
+            .include_y(extent)
+            .show_crosshair(false)
+            .center_x_axis(true)
+            .center_y_axis(true)
+            .show_x(false) // Hide Cartesian x-axis
+            .show_y(false) // Hide Cartesian y-axis
+            .x_grid_spacer(|_input| vec![]) // Disable x-grid
+            .y_grid_spacer(|_input| vec![]) // Disable y-grid
+            .allow_drag(self.polar_zoom_enabled)
+            .allow_zoom(self.polar_zoom_enabled)
+            .allow_scroll(self.polar_zoom_enabled)
+            .allow_boxed_zoom(self.polar_zoom_enabled)
+            .allow_double_click_reset(false)
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(move |point, _bounds| {
+                    orientation
+                        .az_el_at(point.x, point.y)
+                        .map(|(az, el)| format!("Az: {:.1}°  El: {:.1}°", az, el))
+                        .unwrap_or_default()
+                }),
+            )
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("polar_plot"),
+                Legend::default(),
+            ));
+
+        let view = self
+            .polar_view
+            .unwrap_or([-extent, -extent, extent, extent]);
+        let polar_response = plot.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [view[0], view[1]],
+                [view[2], view[3]],
+            ));
+            if self.polar_sky_fill {
+                let disk: Vec<[f64; 2]> = (0..100)
+                    .map(|i| {
+                        let angle = i as f64 * 2.0 * std::f64::consts::PI / 100.0;
+                        [angle.cos(), angle.sin()]
+                    })
+                    .collect();
+                plot_ui.polygon(
+                    egui_plot::Polygon::new("", PlotPoints::from(disk))
+                        .fill_color(sky_fill_color)
+                        .stroke(egui::Stroke::NONE)
+                        .allow_hover(false),
+                );
+            }
+
+            self.draw_low_elevation_band(plot_ui);
+            self.draw_forbidden_az_sectors(plot_ui);
+
+            // Draw circles for elevation levels (e.g., 0, 30, 60, 90)
+            // 90 deg el is center (radius 0), 0 deg el is outer edge (radius 1)
+            // So, radius = (90 - el) / 90
+            for (el_level, label_text) in polar_ring_levels(self.polar_airmass_rings) {
+                let radius = (90.0 - el_level) / 90.0;
+                if radius >= 0.0 {
+                    // Ensure radius is non-negative
+                    let num_segments = 100;
+                    let mut circle_points = Vec::new();
+                    for i in 0..=num_segments {
+                        let angle = i as f64 * 2.0 * std::f64::consts::PI / num_segments as f64;
+                        let x = radius * angle.cos();
+                        let y = radius * angle.sin();
+                        circle_points.push([x, y]);
+                    }
+                    // The horizon ring is drawn heavier and named, so the disk reads as the sky.
+                    let is_horizon = el_level == 0.0;
+                    let (width, color) = if is_horizon {
+                        (4.0, self.polar_horizon_color)
+                    } else {
+                        (2.0, egui::Color32::DARK_GRAY)
+                    };
+                    plot_ui.line(
+                        Line::new("", PlotPoints::from(circle_points))
+                            .stroke(egui::Stroke::new(width, color)),
+                    );
+
+                    // Add elevation labels
+                    let label_text = if is_horizon {
+                        Some("Horizon".to_string())
+                    } else {
+                        label_text
+                    };
+                    if let Some(label_text) = label_text {
+                        // Beside the upward radial line, with the text's lower-left corner on
+                        // the ring so it sits just outside the ring rather than across it.
+                        let label_x = radius * (72.0f64).to_radians().cos();
+                        let label_y = radius * (72.0f64).to_radians().sin();
+                        plot_ui.text(
+                            egui_plot::Text::new(
+                                "",
+                                egui_plot::PlotPoint::new(label_x, label_y),
+                                egui::RichText::new(label_text).size(label_size),
+                            )
+                            .anchor(egui::Align2::LEFT_BOTTOM)
+                            .color(if is_horizon {
+                                color
+                            } else {
+                                egui::Color32::DARK_GRAY
+                            }),
+                        );
+                    }
+                }
+            }
+
+            // Draw radial lines for azimuth (e.g., 0, 90, 180, 270)
+            for az_level in [0.0, 45.0, 90.0, 135.0, 180.0, 225.0, 270.0, 315.0] {
+                let [x, y] = self.polar_orientation.polar_xy(az_level, 0.0);
+                plot_ui.line(
+                    Line::new("", PlotPoints::from(vec![[0.0, 0.0], [x, y]]))
+                        .stroke(egui::Stroke::new(2.0, egui::Color32::DARK_GRAY)),
+                );
 
-    fn show_source_picker_window(&mut self, ctx: &egui::Context, picker_kind: usize) {
-        let open = match picker_kind {
-            0 => &mut self.target_picker_open,
-            1 => &mut self.cal_picker_open,
-            _ => &mut self.five_point_picker_open,
-        };
-        if !*open {
-            return;
-        }
+                // Add azimuth labels, with the cardinal point on the main axes
+                let cardinal = match az_level as u32 {
+                    0 => " N",
+                    90 => " E",
+                    180 => " S",
+                    270 => " W",
+                    _ => "",
+                };
+                let label_text = format!("{:.0}°{}", az_level, cardinal);
+                // Just outside the horizon, anchored on the side facing the centre so the
+                // text grows outwards into the margin kept by `polar_label_extent`.
+                plot_ui.text(
+                    egui_plot::Text::new(
+                        "",
+                        egui_plot::PlotPoint::new(x * 1.03, y * 1.03),
+                        egui::RichText::new(label_text).size(label_size),
+                    )
+                    .anchor(outward_anchor(x, y))
+                    .color(egui::Color32::DARK_GRAY),
+                );
+            }
 
-        let title = match picker_kind {
-            0 => "Select Target",
-            1 => "Select Gain Cal",
-            _ => "Select Five-point Cal",
-        };
-        let filter_text = match picker_kind {
-            0 => &mut self.target_picker_filter,
-            1 => &mut self.cal_picker_filter,
-            _ => &mut self.five_point_picker_filter,
-        };
-        let mut selected_index = None;
-        let mut should_close = false;
+            self.draw_sky_overlays(plot_ui);
+            if self.show_sun_moon {
+                self.draw_sun_moon(plot_ui);
+            }
 
-        egui::Window::new(title)
-            .open(open)
-            .default_width(520.0)
-            .default_height(620.0)
-            .resizable(true)
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Search:");
-                    ui.text_edit_singleline(filter_text);
-                    if ui.button("Clear").clicked() {
-                        filter_text.clear();
-                    }
-                });
-                ui.separator();
+            // Az/El readout just below the pointer; the hidden x/y axes mean nothing here.
+            if let Some(pointer) = plot_ui.pointer_coordinate() {
+                if let Some((az, el)) = orientation.az_el_at(pointer.x, pointer.y) {
+                    plot_ui.text(
+                        egui_plot::Text::new(
+                            "",
+                            egui_plot::PlotPoint::new(pointer.x, pointer.y - 0.06),
+                            format!("Az {:.1}°, El {:.1}°", az, el),
+                        )
+                        .color(readout_color),
+                    );
+                }
+            }
 
-                let filter = filter_text.to_lowercase();
-                egui::ScrollArea::vertical()
-                    .max_height(520.0)
-                    .show(ui, |ui| {
-                        for i in 0..self.sources.len() {
-                            let source_name = &self.sources[i].0.name;
-                            if !filter.is_empty() && !source_name.to_lowercase().contains(&filter) {
-                                continue;
-                            }
-                            let selected = match picker_kind {
-                                0 => i == self.interleave_target_index,
-                                1 => i == self.interleave_cal_index,
-                                _ => i == self.five_point_cal_index,
-                            };
-                            if ui.selectable_label(selected, source_name).clicked() {
-                                selected_index = Some(i);
-                                should_close = true;
-                            }
-                        }
-                    });
+            if self.polar_live {
+                return (self.draw_live_positions(plot_ui, &markers), None);
+            }
+            let hovered = nearest_plot_sample(
+                plot_ui,
+                self.polar_plot_data
+                    .iter()
+                    .map(|series| series.polar_points.as_slice()),
+            );
+            let hovered_ut = hovered.and_then(|(series_idx, point)| {
+                let series = &self.polar_plot_data[series_idx];
+                let idx = series.polar_points.iter().position(|p| *p == point)?;
+                series.polar_hours.get(idx).copied()
             });
-
-        if let Some(i) = selected_index {
-            match picker_kind {
-                0 => self.interleave_target_index = i,
-                1 => self.interleave_cal_index = i,
-                _ => self.five_point_cal_index = i,
+            for (idx, series) in self.polar_plot_data.iter().enumerate() {
+                let name = format!("{}:{}", series.source_name, series.station_name);
+                // One explicit colour per series, so its segments and markers match.
+                let color = series_color(idx);
+                for (below_floor, segment) in split_by_floor(
+                    &series.polar_points,
+                    &series.polar_el,
+                    self.effective_floor_deg(),
+                ) {
+                    let line = Line::new(name.clone(), PlotPoints::from(segment));
+                    if below_floor {
+                        plot_ui.line(
+                            line.stroke(egui::Stroke::new(2.0, self.polar_below_floor_color)),
+                        );
+                    } else {
+                        plot_ui.line(apply_station_line_style(
+                            line.color(color),
+                            series.station_idx,
+                        ));
+                    }
+                }
+                if !series.hour_marker_points.is_empty() {
+                    plot_ui.points(
+                        Points::new(
+                            name.clone(),
+                            PlotPoints::from(series.hour_marker_points.clone()),
+                        )
+                        .shape(self.source_marker(&markers, &series.source_name).shape())
+                        .color(color)
+                        .radius(self.polar_point_radius),
+                    );
+                    for (label_x, label_y, label_text) in &series.hour_labels {
+                        plot_ui.text(
+                            egui_plot::Text::new(
+                                "",
+                                egui_plot::PlotPoint::new(*label_x, *label_y),
+                                label_text.clone(),
+                            )
+                            .color(egui::Color32::LIGHT_GRAY),
+                        );
+                    }
+                }
             }
+            self.draw_polar_cursor(plot_ui);
+            let names = self
+                .polar_plot_data
+                .iter()
+                .map(|series| format!("{}:{}", series.source_name, series.station_name))
+                .collect();
+            (names, hovered_ut)
+        });
+        let (names, hovered_ut) = polar_response.inner;
+        if hovered_ut.is_some() {
+            self.cursor_ut_next = hovered_ut;
         }
-        if should_close {
-            match picker_kind {
-                0 => self.target_picker_open = false,
-                1 => self.cal_picker_open = false,
-                _ => self.five_point_picker_open = false,
-            }
+        self.polar_plot_rect = Some(polar_response.response.rect);
+        // Keep the user's pan/zoom from this frame for the next one.
+        let bounds = polar_response.transform.bounds();
+        let shown = [
+            bounds.min()[0],
+            bounds.min()[1],
+            bounds.max()[0],
+            bounds.max()[1],
+        ];
+        let interacted = polar_response.response.hovered() || polar_response.response.dragged();
+        if self.polar_zoom_enabled
+            && interacted
+            && shown.iter().zip(&view).any(|(a, b)| (a - b).abs() > 1e-9)
+        {
+            self.polar_view = Some(shown);
+        }
+        if self.polar_live {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_secs(1));
         }
+        self.sync_hidden_series(ui.ctx(), egui::Id::new("polar_plot"), names);
     }
 
-    fn ui_uptime_plotters_tab(&mut self, ui: &mut egui::Ui) {
-        let station_pos = self.station_position();
-        let selected_date = self.selected_date;
+    fn ui_lst_plot_tab(&mut self, ui: &mut egui::Ui) {
+        let grouped_legend = self
+            .lst_plot_data
+            .iter()
+            .any(|series| series.group.is_some());
+        if self.stations.is_empty() {
+            ui.label("No station selected.");
+            self.lst_plot_rect = None;
+            return;
+        }
 
-        let az_pointer_formatter = move |x: f64, y: f64| {
-            let ut_text = format_hour_hms(x);
-            let lst_text = station_pos
-                .and_then(|pos| {
-                    utc_datetime_from_hour(selected_date, x)
-                        .map(|dt| utils::utc_to_lst_hours(pos, dt))
-                })
-                .map(format_hour_hms)
-                .unwrap_or_else(|| "N/A".to_string());
-            format!("UT: {}\nLST: {}\nAz: {:.1}°", ut_text, lst_text, y)
-        };
-        let el_pointer_formatter = move |x: f64, y: f64| {
-            let ut_text = format_hour_hms(x);
-            let lst_text = station_pos
-                .and_then(|pos| {
-                    utc_datetime_from_hour(selected_date, x)
-                        .map(|dt| utils::utc_to_lst_hours(pos, dt))
-                })
-                .map(format_hour_hms)
-                .unwrap_or_else(|| "N/A".to_string());
-            format!("UT: {}\nLST: {}\nEl: {:.1}°", ut_text, lst_text, y)
-        };
+        let az_pointer_formatter =
+            |x: f64, y: f64| format!("LST: {}\nAz: {:.1}°", format_hour_hms(x), y);
+        let el_pointer_formatter =
+            |x: f64, y: f64| format!("LST: {}\nEl: {:.1}°", format_hour_hms(x), y);
 
-        let plot_az = Plot::new("az_plot")
+        let plot_az = Plot::new("lst_az_plot")
+            .id(egui::Id::new("lst_az_plot"))
             .width(ui.available_width())
             .height(ui.available_height() / 2.0)
-            .y_axis_label("Azimuth (deg)")
+            .y_axis_label("Azimuth (deg, N=0 E=90)")
             .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
             .include_x(0.0)
             .include_x(24.0)
@@ -2285,8 +10560,8 @@ impl UptimePlotApp {
             .allow_drag(false)
             .allow_zoom(false)
             .allow_scroll(false)
-            .x_axis_label("") // Re-added
-            .x_axis_formatter(|_, _| "".to_string()) // Re-added
+            .x_axis_label("")
+            .x_axis_formatter(|_, _| "".to_string())
             .x_grid_spacer(|_input| {
                 [
                     0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0,
@@ -2319,12 +10594,17 @@ impl UptimePlotApp {
                     az_pointer_formatter(plot_point.x, plot_point.y)
                 }),
             )
-            .legend(Legend::default());
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("lst_az_plot"),
+                Legend::default().follow_insertion_order(grouped_legend),
+            ));
 
-        let plot_el = Plot::new("el_plot")
+        let plot_el = Plot::new("lst_el_plot")
+            .id(egui::Id::new("lst_el_plot"))
             .width(ui.available_width())
             .height(ui.available_height() / 2.0)
-            .x_axis_label("Time (UT)")
+            .x_axis_label("Time (LST)")
             .y_axis_label("Elevation (deg)")
             .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
             .include_x(0.0)
@@ -2364,502 +10644,1036 @@ impl UptimePlotApp {
                     el_pointer_formatter(plot_point.x, plot_point.y)
                 }),
             )
-            .legend(Legend::default());
-
-        let az_response = plot_az.show(ui, |plot_ui| {
-            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
-                [0.0, -5.0],
-                [24.7, 365.0],
-            ));
-            for (source_name, station_name, az_points, _, station_idx) in &self.plot_data {
-                let mut line = Line::new(
-                    format!("{}:{}", source_name, station_name),
-                    PlotPoints::from_iter(az_points.iter().copied()),
-                );
-                line = apply_station_line_style(line, *station_idx);
-                plot_ui.line(line);
-            }
-        });
-
-        ui.add_space(-10.0);
-
-        let el_response = plot_el.show(ui, |plot_ui| {
-            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
-                [0.0, 0.0],
-                [24.7, 91.0],
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("lst_el_plot"),
+                Legend::default().follow_insertion_order(grouped_legend),
             ));
-            for (source_name, station_name, _, el_points, station_idx) in &self.plot_data {
-                let mut line = Line::new(
-                    format!("{}:{}", source_name, station_name),
-                    PlotPoints::from_iter(el_points.iter().copied()),
-                );
-                line = apply_station_line_style(line, *station_idx);
-                plot_ui.line(line);
-            }
-        });
-
-        self.uptime_plot_rect = Some(az_response.response.rect.union(el_response.response.rect));
-    }
-
-    fn ui_parameters_tab(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Parameters");
-        ui.add_space(10.0);
-
-        ui.columns(2, |columns| {
-            // --- Left Column: Settings and File Formats ---
-            egui::ScrollArea::vertical().show(&mut columns[0], |ui| {
-                // --- Station Settings ---
-                egui::Frame::group(ui.style()).show(ui, |ui| {
-                    ui.heading("📡 Station Settings");
-                    ui.add_space(5.0);
-                    ui.label("Stations:");
-                    egui::ScrollArea::vertical().id_salt("station_list_scroll").max_height(150.0).show(ui, |ui| {
-                        if self.stations.is_empty() {
-                            ui.label("Load stations from station.txt");
-                        } else {
-                            for station in &mut self.stations {
-                                ui.checkbox(&mut station.selected, &station.name);
-                            }
-                        }
-                    });
-                    ui.add_space(5.0);
-                    ui.horizontal(|ui| {
-                        ui.label("Station File:");
-                        ui.text_edit_singleline(&mut self.station_file_path);
-                        if ui.button("Load").clicked() {
-                            match pick_file_dialog("Select station.txt") {
-                                Ok(Some(path)) => {
-                                    self.station_file_path = path.to_string_lossy().to_string();
-                                    match self.load_stations() {
-                                        Ok(_) => self.error_msg = None,
-                                        Err(e) => self.error_msg = Some(e),
-                                    }
-                                }
-                                Ok(None) => {}
-                                Err(e) => self.error_msg = Some(e),
-                            }
-                        }
-                        if ui.button("Reload").clicked() {
-                            match self.load_stations() {
-                                Ok(_) => self.error_msg = None,
-                                Err(e) => self.error_msg = Some(e),
-                            }
-                        }
-                        if ui.button("Open").clicked() {
-                            match utils::open_file_in_external_editor(&self.station_file_path) {
-                                Ok(_) => self.error_msg = None,
-                                Err(e) => self.error_msg = Some(e),
-                            }
-                        }
-                    });
-                });
-                ui.add_space(10.0);
-
-                // --- Observation Settings ---
-                egui::Frame::group(ui.style()).show(ui, |ui| {
-                    ui.heading("Observation Settings");
-                    ui.add_space(5.0);
-                    egui::Grid::new("obs_grid").num_columns(2).spacing([40.0, 4.0]).striped(true).show(ui, |ui| {
-                        ui.label("Observation Date:");
-                        if ui.button(self.selected_date.format("%Y-%m-%d").to_string()).clicked() {
-                            self.show_calendar = !self.show_calendar;
-                        }
-                        ui.end_row();
-
-                        ui.label("LST at 00:00 UT:");
-                        if let Some(station_pos) = self.station_position() {
-                            if let Some(lst_hours) = self.lst_from_ut_hour(station_pos, 0.0) {
-                                ui.label(format_hour_hms(lst_hours));
-                            } else {
-                                ui.label("N/A");
-                            }
-                        } else {
-                            ui.label("N/A");
-                        }
-                        ui.end_row();
-                    });
-                });
-                ui.add_space(10.0);
 
-                // --- Source Settings ---
-                egui::Frame::group(ui.style()).show(ui, |ui| {
-                    ui.heading("🔭 Source Settings");
-                    ui.add_space(5.0);
-                    egui::Grid::new("source_settings_grid").num_columns(2).spacing([40.0, 4.0]).striped(true).show(ui, |ui| {
-                        ui.label("Source List File:");
-                        ui.horizontal(|ui| {
-                            ui.text_edit_singleline(&mut self.source_file_path);
-                            if ui.button("Load").clicked() {
-                                match pick_file_dialog("Select source.txt") {
-                                    Ok(Some(path)) => {
-                                        self.source_file_path = path.to_string_lossy().to_string();
-                                        match self.load_sources() {
-                                            Ok(_) => self.error_msg = None,
-                                            Err(e) => self.error_msg = Some(e),
-                                        }
-                                    }
-                                    Ok(None) => {}
-                                    Err(e) => self.error_msg = Some(e),
-                                }
-                            }
-                            if ui.button("Reload").clicked() {
-                                match self.load_sources() {
-                                    Ok(_) => self.error_msg = None,
-                                    Err(e) => self.error_msg = Some(e),
-                                }
-                            }
-                            if ui.button("Open").clicked() {
-                                match utils::open_file_in_external_editor(&self.source_file_path) {
-                                    Ok(_) => self.error_msg = None,
-                                    Err(e) => self.error_msg = Some(e),
-                                }
-                            }
-                        });
-                        ui.end_row();
+        let lst_at_midnight = self.lst_at_midnight();
+        let ut_to_lst = |ut: f64| {
+            lst_at_midnight.map_or(f64::NAN, |lst0| {
+                (lst0 + ut * SIDEREAL_PER_UT_HOUR).rem_euclid(24.0)
+            })
+        };
+        let az_response = plot_az.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, -5.0],
+                [24.7, 365.0],
+            ));
+            self.draw_cursor_line(plot_ui, ut_to_lst);
+            for (series_idx, series) in self.lst_plot_data.iter().enumerate() {
+                draw_group_legend_header(plot_ui, &self.lst_plot_data, series_idx);
+                let name = format!("{}:{}", series.source_name, series.station_name);
+                for line in track_lines(&name, &series.az_points) {
+                    plot_ui.line(apply_station_line_style(line, series.station_idx));
+                }
+            }
+        });
 
-                        ui.label("Search Filter:");
-                        ui.add(egui::TextEdit::singleline(&mut self.search_query));
-                        ui.end_row();
-                    });
+        ui.add_space(-10.0);
 
-                    ui.separator();
-                    ui.label("Select Sources to Plot:");
-                    ui.horizontal(|ui|{
-                        if ui.button("Plot Selected").clicked() {
-                            self.calculate_plots();
-                        }
-                        if ui.button("output").clicked() {
-                            match self.start_output_capture(ui.ctx()) {
-                                Ok(_) => self.error_msg = Some("Output started...".to_string()),
-                                Err(e) => self.error_msg = Some(e),
-                            }
-                        }
-                        if ui.button("Reset Source Selection").clicked() {
-                            for (_, selected) in &mut self.sources {
-                                *selected = false;
-                            }
-                        }
-                    });
+        let el_response = plot_el.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, 0.0],
+                [24.7, 91.0],
+            ));
+            self.draw_cursor_line(plot_ui, ut_to_lst);
+            for (series_idx, series) in self.lst_plot_data.iter().enumerate() {
+                draw_group_legend_header(plot_ui, &self.lst_plot_data, series_idx);
+                let name = format!("{}:{}", series.source_name, series.station_name);
+                for line in track_lines(&name, &series.el_points) {
+                    plot_ui.line(apply_station_line_style(line, series.station_idx));
+                }
+            }
+        });
 
-                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-                        if self.sources.is_empty() {
-                            ui.label("(No sources loaded)");
-                        } else {
-                            egui::Grid::new("source_grid").show(ui, |ui| {
-                                let mut displayed_count = 0;
-                                for (_i, (source, selected)) in self.sources.iter_mut().enumerate() {
-                                    if self.search_query.is_empty() || source.name.to_lowercase().contains(&self.search_query.to_lowercase()) {
-                                        ui.checkbox(selected, &source.name);
-                                        displayed_count += 1;
-                                        if displayed_count % 8 == 0 {
-                                            ui.end_row();
-                                        }
-                                    }
-                                }
-                            });
-                        }
-                    });
-                });
-                ui.add_space(10.0);
+        self.lst_plot_rect = Some(az_response.response.rect.union(el_response.response.rect));
+        let hovered_lst = hovered_plot_x(&az_response).or_else(|| hovered_plot_x(&el_response));
+        // The first UT of the day with that LST.
+        if let (Some(lst), Some(lst0)) = (hovered_lst, lst_at_midnight) {
+            self.cursor_ut_next = Some((lst - lst0).rem_euclid(24.0) / SIDEREAL_PER_UT_HOUR);
+        }
+        let names: Vec<String> = self
+            .lst_plot_data
+            .iter()
+            .map(|series| format!("{}:{}", series.source_name, series.station_name))
+            .collect();
+        self.sync_hidden_series(ui.ctx(), egui::Id::new("lst_az_plot"), names.clone());
+        self.sync_hidden_series(ui.ctx(), egui::Id::new("lst_el_plot"), names);
+    }
 
-                // --- File Formats (Moved here) ---
-                egui::Frame::group(ui.style()).show(ui, |ui| {
-                    ui.heading("📄 File Format Information");
-                    ui.add_space(5.0);
-                    ui.label("station.txt format (ECEF): NAME X_POS Y_POS Z_POS");
-                    ui.label("e.g. YAMAGU32 -3502544.587 3950966.235 3566381.192");
-                    ui.separator();
-                    ui.label("source.txt format: NAME  RA_H  RA_M  RA_S  DEC_D  DEC_M  DEC_S");
-                    ui.label("e.g. 3C273  12 29 06.7 +02 03 08.6");
-                });
+    fn ui_ha_plot_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        let time_grid = self.time_grid();
+        let plot_ha = Plot::new("ha_plot")
+            .id(egui::Id::new("ha_plot"))
+            .width(ui.available_width())
+            .height(ui.available_height())
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label("Hour Angle (h)")
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
+            .include_x(0.0)
+            .include_x(24.0)
+            .include_y(-12.0)
+            .include_y(12.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .y_grid_spacer(|_input| {
+                [-12.0, -9.0, -6.0, -3.0, 0.0, 3.0, 6.0, 9.0, 12.0]
+                    .into_iter()
+                    .map(|v| GridMark {
+                        value: v,
+                        step_size: 3.0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .y_axis_formatter(|m, _| format!("{:+.0}", m.value))
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(move |plot_point, _plot_bounds| {
+                    format!(
+                        "UT: {}\nHA: {:+.2}h",
+                        time_display.format(plot_point.x),
+                        plot_point.y
+                    )
+                }),
+            )
+            .legend(self.legend_with_hidden_series(
+                ui.ctx(),
+                egui::Id::new("ha_plot"),
+                Legend::default(),
+            ));
 
-                if let Some(err) = &self.error_msg {
-                    ui.add_space(10.0);
-                    ui.colored_label(egui::Color32::RED, err);
+        let ha_response = plot_ha.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, -12.5],
+                [self.ut_plot_end(), 12.5],
+            ));
+            self.draw_cursor_line(plot_ui, |ut| ut);
+            for (station_idx, (station, selected)) in self.stations.iter().enumerate() {
+                let Some((ha_min, ha_max)) = station.ha_limits.filter(|_| *selected) else {
+                    continue;
+                };
+                for ha_limit in [ha_min, ha_max] {
+                    let line = Line::new(
+                        format!("HA limit:{}", station.name),
+                        PlotPoints::from(vec![[0.0, ha_limit], [24.0, ha_limit]]),
+                    );
+                    plot_ui.line(apply_station_line_style(line, station_idx).width(1.0));
                 }
-            });
-
-            // --- Right Column: Usage Only ---
-            egui::ScrollArea::vertical().show(&mut columns[1], |ui| {
-                egui::Frame::group(ui.style()).show(ui, |ui| {
-                    ui.heading("ℹProgram Usage");
-                    ui.add_space(5.0);
-                    let mut help_text = CliArgs::command().render_help().to_string();
-                    // Revert to ui.code() for now
-                    ui.add(egui::TextEdit::multiline(&mut help_text)
-                        .desired_width(f32::INFINITY)
-                        .interactive(false)
-                        .font(egui::TextStyle::Monospace));
-                    ui.label("1. Push the Load button in Source settings.");
-                    ui.label("2. Select some targets for drawing an uptime plot.");
-                    ui.label("3. Push the Plot Selected button in Source settings.");
-                    ui.label("4. Push the Uptime Plotters button in the upper left corner in this tab.");
-                    ui.label("");
-                    ui.label("To create a new uptime plot graph, first, push the \"Reset Source Selection\" button in Source Settings to clear previous selections. Then, you can repeat steps 1 to 4 to generate a new plot.");
-                    ui.label("");
-                    ui.label("If you want to edit the data files for sources or stations, please push the \"Open\" buttons in Station Settings and Source Settings.");
-                });
-            });
+            }
+            for series in &self.plot_data {
+                let mut ha_points = Vec::with_capacity(series.ha_points.len());
+                for point in &series.ha_points {
+                    // Lift the pen where the hour angle wraps from +12h to -12h.
+                    let wrapped = ha_points
+                        .last()
+                        .is_some_and(|prev: &[f64; 2]| (point[1] - prev[1]).abs() > 12.0);
+                    if wrapped {
+                        ha_points.push([f64::NAN, f64::NAN]);
+                    }
+                    ha_points.push(*point);
+                }
+                let name = format!("{}:{}", series.source_name, series.station_name);
+                for line in track_lines(&name, &ha_points) {
+                    plot_ui.line(apply_station_line_style(line, series.station_idx));
+                }
+            }
         });
+        if let Some(ut) = hovered_plot_x(&ha_response) {
+            self.cursor_ut_next = Some(ut);
+        }
+        self.sync_hidden_series(
+            ui.ctx(),
+            egui::Id::new("ha_plot"),
+            self.plot_data_legend_names(),
+        );
     }
 
-    fn ui_polar_plot_tab(&mut self, ui: &mut egui::Ui) {
-        //ui.heading("Polar Plot");
+    /// Modeled scans per plot series: the scan-length/cadence grid fitted into its visibility
+    /// windows, restricted to the session when one is set.
+    fn scan_coverage(&self) -> Vec<Vec<(f64, f64)>> {
+        let session = self.session_windows();
+        self.plot_data
+            .iter()
+            .map(|series| {
+                let windows = match &session {
+                    Some(session) => utils::intersect_windows(&series.windows, session),
+                    None => series.windows.clone(),
+                };
+                utils::fit_scans(
+                    &windows,
+                    self.scan_length_min / 60.0,
+                    self.scan_cadence_min / 60.0,
+                )
+            })
+            .collect()
+    }
 
-        let plot = Plot::new("polar_plot")
-            .width(ui.available_width()) // Added
-            .height(ui.available_height()) // Added
-            .data_aspect(1.0) // Ensure circular aspect ratio
-            .view_aspect(1.0) // Ensure circular aspect ratio
-            .include_x(-1.0)
-            .include_x(1.0) // Cartesian coordinates for polar plot
-            .include_y(-1.0)
-            .include_y(1.0) // Cartesian coordinates for polar plot
-            .center_x_axis(true)
-            .center_y_axis(true)
-            .show_x(false) // Hide Cartesian x-axis
-            .show_y(false) // Hide Cartesian y-axis
-            .x_grid_spacer(|_input| vec![]) // Disable x-grid
-            .y_grid_spacer(|_input| vec![]) // Disable y-grid
-            .legend(Legend::default());
+    fn ui_strip_chart_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        let time_grid = self.time_grid();
+        if self.plot_data.is_empty() {
+            ui.label("Select sources and run Plot Selected.");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.scan_coverage_enabled, "Scan coverage");
+            ui.add_enabled(
+                self.scan_coverage_enabled,
+                egui::DragValue::new(&mut self.scan_length_min)
+                    .range(1.0..=240.0)
+                    .speed(1.0)
+                    .prefix("scan ")
+                    .suffix(" min"),
+            );
+            ui.add_enabled(
+                self.scan_coverage_enabled,
+                egui::DragValue::new(&mut self.scan_cadence_min)
+                    .range(1.0..=720.0)
+                    .speed(1.0)
+                    .prefix("every ")
+                    .suffix(" min"),
+            );
+            ui.separator();
+            ui.checkbox(&mut self.down_time_enabled, "Down time")
+                .on_hover_text("Highlight when none of the plotted sources is above the floor at any station, e.g. for maintenance or pointing runs");
+        });
+        let coverage = if self.scan_coverage_enabled {
+            self.scan_coverage()
+        } else {
+            Vec::new()
+        };
 
-        let polar_response = plot.show(ui, |plot_ui| {
-            // Draw circles for elevation levels (e.g., 0, 30, 60, 90)
-            // 90 deg el is center (radius 0), 0 deg el is outer edge (radius 1)
-            // So, radius = (90 - el) / 90
-            for el_level in [0.0, 15.0, 30.0, 45.0, 60.0, 75.0, 90.0] {
-                let radius = (90.0 - el_level) / 90.0;
-                if radius >= 0.0 {
-                    // Ensure radius is non-negative
-                    let num_segments = 100;
-                    let mut circle_points = Vec::new();
-                    for i in 0..=num_segments {
-                        let angle = i as f64 * 2.0 * std::f64::consts::PI / num_segments as f64;
-                        let x = radius * angle.cos();
-                        let y = radius * angle.sin();
-                        circle_points.push([x, y]);
-                    }
+        let row_labels: Vec<String> = self
+            .plot_data
+            .iter()
+            .map(|series| format!("{}:{}", series.source_name, series.station_name))
+            .collect();
+        let row_count = row_labels.len();
+        let chart_labels = row_labels.clone();
+        let plot = Plot::new("strip_chart_plot")
+            .width(ui.available_width())
+            .height((row_count as f32 * 18.0 + 40.0).clamp(160.0, ui.available_height() * 0.6))
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH * 2.0)
+            .include_x(0.0)
+            .include_x(24.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .y_grid_spacer(move |_input| {
+                (0..row_count)
+                    .map(|v| GridMark {
+                        value: v as f64,
+                        step_size: 1.0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .y_axis_formatter(move |m, _| {
+                let row = m.value.round();
+                if (m.value - row).abs() < 1e-6 && row >= 0.0 {
+                    chart_labels.get(row as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(|plot_point, _plot_bounds| {
+                    format!("UT: {}", time_display.format(plot_point.x))
+                }),
+            );
+
+        // The complement of the union of the bars below, over the sampled UT range.
+        let down_time = if self.down_time_enabled {
+            let up = utils::union_windows(
+                self.plot_data
+                    .iter()
+                    .flat_map(|series| series.windows.iter().copied()),
+            );
+            utils::complement_windows(&up, 0.0, self.ut_sample_end())
+        } else {
+            Vec::new()
+        };
+        let response = plot.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, -0.5],
+                [self.ut_plot_end(), row_count as f64 - 0.5],
+            ));
+            self.shade_outside_session(plot_ui);
+            for &(start, end) in &down_time {
+                plot_ui.span(
+                    egui_plot::Span::new("", start..=end)
+                        .fill(egui::Color32::from_rgba_unmultiplied(230, 140, 0, 56))
+                        .border_width(0.0),
+                );
+            }
+            for (row, series) in self.plot_data.iter().enumerate() {
+                let color = series
+                    .group
+                    .as_deref()
+                    .map_or(egui::Color32::from_rgb(0, 160, 220), group_color);
+                let width = if self.strip_chart_expanded.contains(&row_labels[row]) {
+                    12.0
+                } else {
+                    8.0
+                };
+                // The merged windows, so the bars match the summary and the merge setting.
+                for &(start, end) in &series.windows {
                     plot_ui.line(
-                        Line::new("", PlotPoints::from(circle_points))
-                            .stroke(egui::Stroke::new(2.0, egui::Color32::DARK_GRAY)),
+                        Line::new(
+                            "",
+                            PlotPoints::from(vec![[start, row as f64], [end, row as f64]]),
+                        )
+                        .stroke(egui::Stroke::new(width, color)),
+                    );
+                }
+                for &(start, end) in coverage.get(row).into_iter().flatten() {
+                    plot_ui.line(
+                        Line::new(
+                            "",
+                            PlotPoints::from(vec![[start, row as f64], [end, row as f64]]),
+                        )
+                        .stroke(egui::Stroke::new(width / 2.0, egui::Color32::from_gray(30))),
                     );
+                }
+            }
+            if plot_ui.response().clicked() {
+                plot_ui
+                    .pointer_coordinate()
+                    .map(|point| point.y.round())
+                    .filter(|row| *row >= 0.0 && (*row as usize) < row_count)
+                    .map(|row| row as usize)
+            } else {
+                None
+            }
+        });
+        if let Some(row) = response.inner {
+            let label = &row_labels[row];
+            if !self.strip_chart_expanded.remove(label) {
+                self.strip_chart_expanded.insert(label.clone());
+            }
+        }
 
-                    // Add elevation labels
-                    if el_level != 90.0 {
-                        // Don't label the center point
-                        let label_text = format!("{:.0}°", el_level);
-                        // Position the label slightly inside the circle, at 0 azimuth (North)
-                        let label_x = radius * (72.0f64).to_radians().cos();
-                        let label_y = radius * (72.0f64).to_radians().sin();
-                        plot_ui.text(
-                            egui_plot::Text::new(
-                                "",
-                                egui_plot::PlotPoint::new(label_x, label_y),
-                                label_text,
-                            )
-                            .color(egui::Color32::DARK_GRAY),
+        ui.label(format!(
+            "Bars mark the visibility windows above the {:.0}° elevation floor, merged as in the summary. Click a row to toggle its elevation line.",
+            self.effective_floor_deg()
+        ));
+        if self.down_time_enabled {
+            if down_time.is_empty() {
+                ui.label("Down time: none; some source is up all day.");
+            } else {
+                let spans: Vec<String> = down_time
+                    .iter()
+                    .map(|(start, end)| {
+                        format!(
+                            "{}-{}",
+                            time_display.format(*start),
+                            time_display.format(*end)
+                        )
+                    })
+                    .collect();
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 140, 0),
+                    format!(
+                        "Down time (no source up): {} UT, {:.2} h in total",
+                        spans.join(", "),
+                        utils::total_window_hours(&down_time)
+                    ),
+                );
+            }
+        }
+        if self.scan_coverage_enabled {
+            egui::CollapsingHeader::new("Scan coverage")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("scan_coverage_grid")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Source:Station");
+                            ui.strong("Scans");
+                            ui.strong("On-source");
+                            ui.end_row();
+                            for (label, scans) in row_labels.iter().zip(&coverage) {
+                                ui.label(label);
+                                ui.label(scans.len().to_string());
+                                ui.label(format!("{:.2} h", utils::total_window_hours(scans)));
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+        if self.strip_chart_expanded.is_empty() {
+            return;
+        }
+        let plot_el = Plot::new("strip_chart_el_plot")
+            .width(ui.available_width())
+            .height(ui.available_height())
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label("Elevation (deg)")
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH * 2.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .legend(Legend::default());
+        plot_el.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, 0.0],
+                [self.ut_plot_end(), 91.0],
+            ));
+            for (series, label) in self.plot_data.iter().zip(&row_labels) {
+                if self.strip_chart_expanded.contains(label) {
+                    for line in track_lines(label, &series.el_points) {
+                        plot_ui.line(
+                            apply_station_line_style(line, series.station_idx)
+                                .width(self.line_width),
                         );
                     }
                 }
             }
+        });
+    }
 
-            // Draw radial lines for azimuth (e.g., 0, 90, 180, 270)
-            for az_level in [0.0, 45.0, 90.0, 135.0, 180.0, 225.0, 270.0, 315.0] {
-                let angle_rad = (90.0f64 - az_level).to_radians(); // Adjust for egui_plot's 0 deg at positive x-axis, clockwise
-                let x = 1.0 * angle_rad.cos();
-                let y = 1.0 * angle_rad.sin();
+    fn ui_separation_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        let time_grid = self.time_grid();
+        if self.separation_data.is_empty() {
+            ui.label("Select sources and run Plot Selected.");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Sun avoidance:");
+            ui.add(
+                egui::DragValue::new(&mut self.sun_avoid_deg)
+                    .range(0.0..=90.0)
+                    .speed(0.5)
+                    .suffix("°"),
+            );
+            ui.label("Moon avoidance:");
+            ui.add(
+                egui::DragValue::new(&mut self.moon_avoid_deg)
+                    .range(0.0..=90.0)
+                    .speed(0.5)
+                    .suffix("°"),
+            );
+        });
+
+        let plot = Plot::new("separation_plot")
+            .width(ui.available_width())
+            .height(ui.available_height() * 0.7)
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label("Separation (deg)")
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(|plot_point, _plot_bounds| {
+                    format!(
+                        "UT: {}\nSep: {:.1}°",
+                        time_display.format(plot_point.x),
+                        plot_point.y
+                    )
+                }),
+            )
+            .legend(Legend::default());
+        plot.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, 0.0],
+                [self.ut_plot_end(), 181.0],
+            ));
+            self.shade_outside_session(plot_ui);
+            for (idx, entry) in self.separation_data.iter().enumerate() {
+                let color = series_color(idx);
                 plot_ui.line(
-                    Line::new("", PlotPoints::from(vec![[0.0, 0.0], [x, y]]))
-                        .stroke(egui::Stroke::new(2.0, egui::Color32::DARK_GRAY)),
+                    Line::new(
+                        format!("{} Sun", entry.source_name),
+                        PlotPoints::from(entry.sun.clone()),
+                    )
+                    .color(color)
+                    .width(self.line_width),
                 );
-
-                // Add azimuth labels
-                let label_text = format!("{:.0}°", az_level);
-                plot_ui.text(
-                    egui_plot::Text::new(
-                        "",
-                        egui_plot::PlotPoint::new(x * 1.1, y * 1.1),
-                        label_text,
+                plot_ui.line(
+                    Line::new(
+                        format!("{} Moon", entry.source_name),
+                        PlotPoints::from(entry.moon.clone()),
                     )
-                    .color(egui::Color32::DARK_GRAY),
+                    .color(color)
+                    .width(self.line_width)
+                    .style(egui_plot::LineStyle::Dashed { length: 8.0 }),
                 );
             }
+            plot_ui.hline(
+                egui_plot::HLine::new(
+                    format!("Sun limit {:.0}°", self.sun_avoid_deg),
+                    self.sun_avoid_deg,
+                )
+                .color(egui::Color32::from_rgb(255, 170, 0)),
+            );
+            plot_ui.hline(
+                egui_plot::HLine::new(
+                    format!("Moon limit {:.0}°", self.moon_avoid_deg),
+                    self.moon_avoid_deg,
+                )
+                .color(egui::Color32::LIGHT_GRAY)
+                .style(egui_plot::LineStyle::Dashed { length: 8.0 }),
+            );
+        });
 
-            for (source_name, station_name, polar_points, hour_marker_points, hour_labels, station_idx) in
-                &self.polar_plot_data
-            {
-                if !polar_points.is_empty() {
-                    let mut line = Line::new(
-                        format!("{}:{}", source_name, station_name),
-                        PlotPoints::from_iter(polar_points.iter().copied()),
-                    );
-                    line = apply_station_line_style(line, *station_idx);
-                    plot_ui.line(line);
-                }
-                if !hour_marker_points.is_empty() {
-                    plot_ui.points(
-                        Points::new("", PlotPoints::from(hour_marker_points.clone())).radius(3.5),
-                    );
-                    for (label_x, label_y, label_text) in hour_labels {
-                        plot_ui.text(
-                            egui_plot::Text::new(
-                                "",
-                                egui_plot::PlotPoint::new(*label_x, *label_y),
-                                label_text.clone(),
-                            )
-                            .color(egui::Color32::LIGHT_GRAY),
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("separation_list")
+            .show(ui, |ui| {
+                for entry in &self.separation_data {
+                    let windows: Vec<(f64, f64)> = self
+                        .plot_data
+                        .iter()
+                        .filter(|series| series.source_name == entry.source_name)
+                        .flat_map(|series| series.windows.iter().copied())
+                        .collect();
+                    let in_window = |hour: f64| {
+                        windows
+                            .iter()
+                            .any(|&(start, end)| hour >= start && hour <= end)
+                    };
+                    let best = entry
+                        .sun
+                        .iter()
+                        .zip(&entry.moon)
+                        .filter(|(sun, _)| in_window(sun[0]))
+                        .map(|(sun, moon)| (sun[0], sun[1].min(moon[1]), sun[1], moon[1]))
+                        .max_by(|a, b| a.1.total_cmp(&b.1));
+                    let min_sun = entry.sun.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+                    let min_moon = entry.moon.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+                    let text = match best {
+                        Some((hour, _, sun, moon)) => format!(
+                            "{}: largest Sun/Moon separation while visible at {} UT (Sun {:.1}°, Moon {:.1}°)",
+                            entry.source_name,
+                            time_display.format(hour),
+                            sun,
+                            moon
+                        ),
+                        None => format!("{}: not visible from any plotted station", entry.source_name),
+                    };
+                    if min_sun < self.sun_avoid_deg || min_moon < self.moon_avoid_deg {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!(
+                                "{} (closest approach: Sun {:.1}°, Moon {:.1}°)",
+                                text, min_sun, min_moon
+                            ),
                         );
+                    } else {
+                        ui.label(text);
                     }
                 }
-            }
-        });
-        self.polar_plot_rect = Some(polar_response.response.rect);
+            });
     }
 
-    fn ui_lst_plot_tab(&mut self, ui: &mut egui::Ui) {
+    /// Samples one source at every loaded station on the selected date, ranked by hours above
+    /// the floor and then by peak elevation, to pick the site that sees it best.
+    fn run_station_comparison(&mut self) -> Result<(), String> {
+        let source = self
+            .sources
+            .iter()
+            .map(|(source, _)| source)
+            .find(|source| source.name == self.station_comparison.source_name)
+            .ok_or_else(|| "Pick a source to compare.".to_string())?;
         if self.stations.is_empty() {
-            ui.label("No station selected.");
-            self.lst_plot_rect = None;
-            return;
+            return Err("No stations loaded.".to_string());
+        }
+        let floor_deg = self.effective_floor_deg();
+        let last_minute = (self.ut_sample_end() * 60.0).round() as usize;
+        let mut tracks = Vec::new();
+        for (station, _) in &self.stations {
+            let mut el_points = Vec::new();
+            let mut margins = Vec::new();
+            for minute in (0..=last_minute).step_by(PLOT_STEP_MIN) {
+                let hour = minute as f64 / 60.0;
+                let Some(time) = utc_datetime_from_hour(self.selected_date, hour) else {
+                    continue;
+                };
+                let pointing = utils::radec2azalt_with(
+                    self.ellipsoid,
+                    self.sidereal_mode,
+                    self.reference_frame,
+                    station.pos,
+                    utils::ut1_from_utc(time, self.dut1_seconds),
+                    source.ra_rad,
+                    source.dec_rad,
+                );
+                // The limits see the true elevation; the curve shows the commanded one.
+                let (_, commanded_el) = station.commanded_az_el(pointing.az_deg, pointing.el_deg);
+                let allowed = pointing.el_deg >= 0.0 && station.ha_allows(pointing.hour_angle);
+                el_points.push([hour, if allowed { commanded_el } else { f64::NAN }]);
+                margins.push((
+                    hour,
+                    (pointing.el_deg - floor_deg)
+                        .min(station.ha_margin(pointing.hour_angle))
+                        .min(utils::az_sector_margin(pointing.az_deg, &self.az_sectors)),
+                ));
+            }
+            let peak_el_deg = el_points
+                .iter()
+                .map(|point| point[1])
+                .filter(|el| el.is_finite())
+                .fold(f64::NEG_INFINITY, f64::max);
+            tracks.push(StationTrack {
+                station_name: station.name.clone(),
+                el_points,
+                peak_el_deg,
+                up_hours: utils::total_window_hours(&utils::visibility_windows_from_margin(
+                    &margins,
+                )),
+            });
         }
+        tracks.sort_by(|a, b| {
+            b.up_hours
+                .total_cmp(&a.up_hours)
+                .then(b.peak_el_deg.total_cmp(&a.peak_el_deg))
+        });
+        self.station_comparison.result = Some((self.selected_date, tracks));
+        Ok(())
+    }
 
-        let az_pointer_formatter =
-            |x: f64, y: f64| format!("LST: {}\nAz: {:.1}°", format_hour_hms(x), y);
-        let el_pointer_formatter =
-            |x: f64, y: f64| format!("LST: {}\nEl: {:.1}°", format_hour_hms(x), y);
+    fn ui_station_comparison_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        // The tracks belong to many stations, so no one station's LST can grid the axis.
+        let time_grid = self.time_grid_for(None);
+        ui.horizontal(|ui| {
+            ui.label("Source:");
+            if self.station_comparison.source_name.is_empty() {
+                if let Some((source, _)) = self.sources.iter().find(|(_, selected)| *selected) {
+                    self.station_comparison.source_name = source.name.clone();
+                }
+            }
+            egui::ComboBox::from_id_salt("station_comparison_source")
+                .selected_text(if self.station_comparison.source_name.is_empty() {
+                    "(pick)"
+                } else {
+                    &self.station_comparison.source_name
+                })
+                .show_ui(ui, |ui| {
+                    for (source, _) in self.sources.iter().filter(|(_, selected)| *selected) {
+                        ui.selectable_value(
+                            &mut self.station_comparison.source_name,
+                            source.name.clone(),
+                            &source.name,
+                        );
+                    }
+                });
+            ui.label("Draw best:");
+            ui.add(
+                egui::DragValue::new(&mut self.station_comparison.max_stations)
+                    .range(1..=50)
+                    .suffix(" stations"),
+            );
+            if ui
+                .button("Compare")
+                .on_hover_text("Track the source at every loaded station on the selected date")
+                .clicked()
+            {
+                if let Err(e) = self.run_station_comparison() {
+                    self.log(LogLevel::Error, e);
+                }
+            }
+        });
+        let Some((date, tracks)) = &self.station_comparison.result else {
+            ui.label("Pick one of the selected sources and press Compare.");
+            return;
+        };
+        let drawn = self.station_comparison.max_stations.min(tracks.len());
+        ui.label(format!(
+            "{} on {} from {} stations; the best {} are drawn.",
+            self.station_comparison.source_name,
+            date.format("%Y-%m-%d"),
+            tracks.len(),
+            drawn
+        ));
 
-        let plot_az = Plot::new("lst_az_plot")
+        let floor = self.effective_floor_deg();
+        let plot = Plot::new("station_comparison_plot")
             .width(ui.available_width())
-            .height(ui.available_height() / 2.0)
-            .y_axis_label("Azimuth (deg)")
+            .height(ui.available_height() * 0.65)
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label("Elevation (deg)")
             .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
-            .include_x(0.0)
-            .include_x(24.0)
-            .include_y(-5.0)
-            .include_y(365.0)
             .allow_drag(false)
             .allow_zoom(false)
             .allow_scroll(false)
-            .x_axis_label("")
-            .x_axis_formatter(|_, _| "".to_string())
-            .x_grid_spacer(|_input| {
-                [
-                    0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0,
-                    15.0, 16.0, 17.0, 18.0, 19.0, 20.0, 21.0, 22.0, 23.0, 24.0,
-                ]
-                .into_iter()
-                .map(|v| GridMark {
-                    value: v,
-                    step_size: 3.0,
-                })
-                .collect::<Vec<_>>()
-            })
-            .y_grid_spacer(|_input| {
-                [
-                    0.0, 30.0, 60.0, 90.0, 120.0, 150.0, 180.0, 210.0, 240.0, 270.0, 300.0, 330.0,
-                    360.0,
-                ]
-                .into_iter()
-                .map(|v| GridMark {
-                    value: v,
-                    step_size: 30.0,
-                })
-                .collect::<Vec<_>>()
-            })
-            .y_axis_formatter(|m, _| format!("{:.0}", m.value))
-            .show_y(true)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
             .coordinates_formatter(
                 Corner::LeftTop,
-                egui_plot::CoordinatesFormatter::new(move |plot_point, _plot_bounds| {
-                    az_pointer_formatter(plot_point.x, plot_point.y)
+                egui_plot::CoordinatesFormatter::new(|plot_point, _plot_bounds| {
+                    format!(
+                        "UT: {}\nEl: {:.1}°",
+                        time_display.format(plot_point.x),
+                        plot_point.y
+                    )
                 }),
             )
             .legend(Legend::default());
+        plot.show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                [0.0, 0.0],
+                [self.ut_plot_end(), 91.0],
+            ));
+            self.shade_outside_session(plot_ui);
+            for (idx, track) in tracks.iter().take(drawn).enumerate() {
+                let points = self.display_track(&track.el_points, None);
+                for line in track_lines(&track.station_name, &points) {
+                    plot_ui.line(line.color(series_color(idx)).width(self.line_width));
+                }
+            }
+            plot_ui.hline(
+                egui_plot::HLine::new(format!("Floor {:.1}°", floor), floor)
+                    .color(egui::Color32::GRAY)
+                    .style(egui_plot::LineStyle::Dashed { length: 8.0 }),
+            );
+        });
 
-        let plot_el = Plot::new("lst_el_plot")
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("station_comparison_list")
+            .show(ui, |ui| {
+                egui::Grid::new("station_comparison_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Station");
+                        ui.strong("Up above floor");
+                        ui.strong("Peak el");
+                        ui.label("");
+                        ui.end_row();
+                        for (idx, track) in tracks.iter().enumerate() {
+                            ui.label(&track.station_name);
+                            ui.label(format!("{:.1} h", track.up_hours));
+                            if track.peak_el_deg.is_finite() {
+                                ui.label(format!("{:.1}°", track.peak_el_deg));
+                            } else {
+                                ui.label("never up");
+                            }
+                            ui.label(if idx < drawn { "drawn" } else { "" });
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// (UT hour, value) samples of the station-pair plot: elevation A − B in degrees, or the
+    /// geometric delay of B relative to A in µs, and NaN where the source is down at either
+    /// station so the drawn curve breaks there.
+    fn station_pair_points(&self) -> Vec<[f64; 2]> {
+        let pair = &self.station_pair;
+        let series_for = |station: &str| {
+            self.plot_data.iter().find(|series| {
+                series.source_name == pair.source_name && series.station_name == station
+            })
+        };
+        let (Some(a), Some(b)) = (series_for(&pair.station_a), series_for(&pair.station_b)) else {
+            return Vec::new();
+        };
+        let station_pos = |idx: usize| self.stations.get(idx).map(|(station, _)| station.pos);
+        let source = self.series_source(&pair.source_name);
+        let geometry = station_pos(a.station_idx).zip(station_pos(b.station_idx));
+        // The series share sample times but may start one sample apart, so match by minute.
+        let minute = |hour: f64| (hour * 60.0).round() as i64;
+        let el_b: HashMap<i64, f64> = b
+            .el_points
+            .iter()
+            .map(|point| (minute(point[0]), point[1]))
+            .collect();
+        let mut points = Vec::new();
+        for el_a in &a.el_points {
+            let value = match el_b.get(&minute(el_a[0])) {
+                Some(el_b) if el_a[1].is_finite() && el_b.is_finite() => {
+                    if pair.delay {
+                        self.pair_delay_us(source, geometry, el_a[0])
+                    } else {
+                        el_a[1] - el_b
+                    }
+                }
+                _ => f64::NAN,
+            };
+            points.push([el_a[0], value]);
+        }
+        points
+    }
+
+    /// Geometric delay (µs) of station B relative to A at UT `hour`, along the true direction
+    /// of the source from A. Taking it from A's azimuth and elevation keeps the hour angle and
+    /// declination in the same frame whatever the reference frame and sidereal mode.
+    fn pair_delay_us(
+        &self,
+        source: Option<&Source>,
+        geometry: Option<([f64; 3], [f64; 3])>,
+        hour: f64,
+    ) -> f64 {
+        let (Some(source), Some((pos_a, pos_b)), Some(time)) = (
+            source,
+            geometry,
+            utc_datetime_from_hour(self.selected_date, hour),
+        ) else {
+            return f64::NAN;
+        };
+        let pointing = utils::radec2azalt_with(
+            self.ellipsoid,
+            self.sidereal_mode,
+            self.reference_frame,
+            pos_a,
+            utils::ut1_from_utc(time, self.dut1_seconds),
+            source.ra_rad,
+            source.dec_rad,
+        );
+        let (latitude, longitude, _) = self.ellipsoid.geodetic(pos_a);
+        let direction =
+            utils::ecef_direction(latitude, longitude, pointing.az_deg, pointing.el_deg);
+        utils::geometric_delay_along(pos_a, pos_b, direction) * 1e6
+    }
+
+    fn ui_station_pair_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        let time_grid = self.time_grid();
+        let mut sources: Vec<String> = Vec::new();
+        let mut stations: Vec<String> = Vec::new();
+        for series in &self.plot_data {
+            if !sources.contains(&series.source_name) {
+                sources.push(series.source_name.clone());
+            }
+            if !stations.contains(&series.station_name) {
+                stations.push(series.station_name.clone());
+            }
+        }
+        if stations.len() < 2 {
+            ui.label("Select at least two stations and run Plot Selected.");
+            return;
+        }
+        let pair = &mut self.station_pair;
+        if !sources.contains(&pair.source_name) {
+            pair.source_name = sources[0].clone();
+        }
+        if !stations.contains(&pair.station_a) {
+            pair.station_a = stations[0].clone();
+        }
+        if !stations.contains(&pair.station_b) || pair.station_b == pair.station_a {
+            pair.station_b = stations
+                .iter()
+                .find(|name| **name != pair.station_a)
+                .cloned()
+                .unwrap_or_default();
+        }
+        ui.horizontal(|ui| {
+            ui.label("Source:");
+            egui::ComboBox::from_id_salt("station_pair_source")
+                .selected_text(&pair.source_name)
+                .show_ui(ui, |ui| {
+                    for name in &sources {
+                        ui.selectable_value(&mut pair.source_name, name.clone(), name);
+                    }
+                });
+            for (label, salt, value) in [
+                ("Station A:", "station_pair_a", &mut pair.station_a),
+                ("Station B:", "station_pair_b", &mut pair.station_b),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_salt(salt)
+                    .selected_text(value.as_str())
+                    .show_ui(ui, |ui| {
+                        for name in &stations {
+                            ui.selectable_value(value, name.clone(), name);
+                        }
+                    });
+            }
+            ui.separator();
+            ui.radio_value(&mut pair.delay, false, "Elevation difference");
+            ui.radio_value(&mut pair.delay, true, "Geometric delay")
+                .on_hover_text("B relative to A: positive when the wavefront reaches B later");
+        });
+
+        let points = self.station_pair_points();
+        let pair = &self.station_pair;
+        let (y_label, unit) = if pair.delay {
+            (
+                format!("Delay {} − {} (µs)", pair.station_b, pair.station_a),
+                "µs",
+            )
+        } else {
+            (
+                format!("El {} − {} (deg)", pair.station_a, pair.station_b),
+                "°",
+            )
+        };
+        if points.iter().all(|point| !point[1].is_finite()) {
+            ui.label(format!(
+                "{} is never up at both {} and {} on this date.",
+                pair.source_name, pair.station_a, pair.station_b
+            ));
+        }
+        let plot = Plot::new("station_pair_plot")
             .width(ui.available_width())
-            .height(ui.available_height() / 2.0)
-            .x_axis_label("Time (LST)")
-            .y_axis_label("Elevation (deg)")
+            .height(ui.available_height())
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_label(y_label.clone())
             .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH)
             .include_x(0.0)
-            .include_x(24.0)
+            .include_x(self.ut_plot_end())
             .include_y(0.0)
-            .include_y(91.0)
             .allow_drag(false)
             .allow_zoom(false)
             .allow_scroll(false)
-            .x_grid_spacer(|_input| {
-                [
-                    0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0,
-                    15.0, 16.0, 17.0, 18.0, 19.0, 20.0, 21.0, 22.0, 23.0, 24.0,
-                ]
-                .into_iter()
-                .map(|v| GridMark {
-                    value: v,
-                    step_size: 3.0,
-                })
-                .collect::<Vec<_>>()
-            })
-            .y_grid_spacer(|_input| {
-                [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0]
-                    .into_iter()
-                    .map(|v| GridMark {
-                        value: v,
-                        step_size: 10.0,
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .x_axis_formatter(|m, _| format!("{:.0}", m.value as u32))
-            .y_axis_formatter(|m, _| format!("{:.0}", m.value))
-            .show_x(true)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
             .coordinates_formatter(
                 Corner::LeftTop,
                 egui_plot::CoordinatesFormatter::new(move |plot_point, _plot_bounds| {
-                    el_pointer_formatter(plot_point.x, plot_point.y)
+                    format!(
+                        "UT: {}\n{:.3}{}",
+                        time_display.format(plot_point.x),
+                        plot_point.y,
+                        unit
+                    )
                 }),
             )
             .legend(Legend::default());
-
-        let az_response = plot_az.show(ui, |plot_ui| {
-            plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
-                [0.0, -5.0],
-                [24.7, 365.0],
-            ));
-            for (source_name, station_name, az_points, _, station_idx) in &self.lst_plot_data {
-                let mut line = Line::new(
-                    format!("{}:{}", source_name, station_name),
-                    PlotPoints::from_iter(az_points.iter().copied()),
-                );
-                line = apply_station_line_style(line, *station_idx);
-                plot_ui.line(line);
+        let name = format!("{} {}", pair.source_name, y_label);
+        plot.show(ui, |plot_ui| {
+            self.shade_outside_session(plot_ui);
+            plot_ui.hline(egui_plot::HLine::new("", 0.0).color(egui::Color32::GRAY));
+            for line in track_lines(&name, &points) {
+                plot_ui.line(line.color(series_color(0)).width(self.line_width));
             }
         });
+    }
 
-        ui.add_space(-10.0);
+    /// Pairs every two stations plotted for the same source and intersects their windows.
+    fn build_co_visibility(&self) -> Vec<CoVisibility> {
+        let mut result = Vec::new();
+        for (i, a) in self.plot_data.iter().enumerate() {
+            for (j, b) in self.plot_data.iter().enumerate().skip(i + 1) {
+                if a.source_name != b.source_name || a.station_name == b.station_name {
+                    continue;
+                }
+                result.push(CoVisibility {
+                    source_name: a.source_name.clone(),
+                    baseline: format!("{}-{}", a.station_name, b.station_name),
+                    windows: utils::intersect_windows(&a.windows, &b.windows),
+                    series: (i, j),
+                });
+            }
+        }
+        result
+    }
+
+    fn ui_co_visibility_tab(&mut self, ui: &mut egui::Ui) {
+        let time_display = self.time_display;
+        let time_grid = self.time_grid();
+        let co_visibility = self.build_co_visibility();
+        if co_visibility.is_empty() {
+            ui.label("Select at least two stations and run Plot Selected.");
+            return;
+        }
+
+        let row_labels: Vec<String> = co_visibility
+            .iter()
+            .map(|entry| format!("{} {}", entry.source_name, entry.baseline))
+            .collect();
+        let row_count = row_labels.len();
+        let plot = Plot::new("co_visibility_plot")
+            .width(ui.available_width())
+            .height((ui.available_height() * 0.6).max(160.0))
+            .x_axis_label(time_grid.axis_label())
+            .y_axis_min_width(PLOT_Y_AXIS_MIN_WIDTH * 2.0)
+            .include_x(0.0)
+            .include_x(24.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_grid_spacer(move |input| time_grid.marks(input))
+            .y_grid_spacer(move |_input| {
+                (0..row_count)
+                    .map(|v| GridMark {
+                        value: v as f64,
+                        step_size: 1.0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .x_axis_formatter(move |m, _| time_grid.label(m.value))
+            .y_axis_formatter(move |m, _| {
+                let row = m.value.round();
+                if (m.value - row).abs() < 1e-6 && row >= 0.0 {
+                    row_labels.get(row as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .coordinates_formatter(
+                Corner::LeftTop,
+                egui_plot::CoordinatesFormatter::new(|plot_point, _plot_bounds| {
+                    format!("UT: {}", time_display.format(plot_point.x))
+                }),
+            );
 
-        let el_response = plot_el.show(ui, |plot_ui| {
+        plot.show(ui, |plot_ui| {
             plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
-                [0.0, 0.0],
-                [24.7, 91.0],
+                [0.0, -0.5],
+                [self.ut_plot_end(), row_count as f64 - 0.5],
             ));
-            for (source_name, station_name, _, el_points, station_idx) in &self.lst_plot_data {
-                let mut line = Line::new(
-                    format!("{}:{}", source_name, station_name),
-                    PlotPoints::from_iter(el_points.iter().copied()),
-                );
-                line = apply_station_line_style(line, *station_idx);
-                plot_ui.line(line);
+            for (row, entry) in co_visibility.iter().enumerate() {
+                for &(start, end) in &entry.windows {
+                    plot_ui.line(
+                        Line::new(
+                            format!("{} {}", entry.source_name, entry.baseline),
+                            PlotPoints::from(vec![[start, row as f64], [end, row as f64]]),
+                        )
+                        .stroke(egui::Stroke::new(8.0, egui::Color32::from_rgb(0, 160, 220))),
+                    );
+                }
             }
         });
 
-        self.lst_plot_rect = Some(az_response.response.rect.union(el_response.response.rect));
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("co_visibility_list")
+            .show(ui, |ui| {
+                for entry in &co_visibility {
+                    if entry.windows.is_empty() {
+                        ui.label(format!(
+                            "{} is never co-visible on {}.",
+                            entry.source_name, entry.baseline
+                        ));
+                    }
+                    for &(start, end) in &entry.windows {
+                        ui.label(format!(
+                            "{} is co-visible on {} from {} to {} UT.",
+                            entry.source_name,
+                            entry.baseline,
+                            time_display.format(start),
+                            time_display.format(end)
+                        ));
+                    }
+                }
+            });
     }
 }
 
 const DEFAULT_SOURCE_TXT: &str = include_str!("../source.txt");
 const DEFAULT_ANTENNA_SCH: &str = include_str!("../antenna.sch");
 const DEFAULT_STATION_TXT: &str = include_str!("../station.txt");
+const DEFAULT_BANDS_TOML: &str = include_str!("../bands.toml");
 
 fn uptimeplot_data_dir() -> Option<PathBuf> {
     home::home_dir().map(|home| home.join(".uptimeplot"))
@@ -2872,6 +11686,107 @@ fn runtime_app_dir() -> PathBuf {
         .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
 }
 
+/// Reads a catalogue file, saying plainly when it is missing (e.g. deleted since the last load).
+fn read_catalog_file(path: &str, what: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("{} file {} not found", what, path),
+        _ => format!(
+            "Failed to read {} file {}: {}",
+            what.to_lowercase(),
+            path,
+            e
+        ),
+    })
+}
+
+/// `error` with a note that the `count` entries loaded before are still in use.
+fn kept_catalog_message(error: String, count: usize, what: &str) -> String {
+    if count == 0 {
+        error
+    } else {
+        format!("{}; keeping the {} {} loaded before.", error, count, what)
+    }
+}
+
+/// Reads and parses a source catalogue (CSV with a header row, or positional `source.txt`
+/// lines), returning the sources and a message per skipped line.
+fn read_source_file(path: &str) -> Result<(Vec<Source>, Vec<String>), String> {
+    let content = read_catalog_file(path, "Source")?;
+    if is_csv_catalog(Path::new(path), &content) {
+        return parse_source_csv(&content).map_err(|e| format!("{}: {}", path, e));
+    }
+    if guess_catalog_kind(&content) == Some(CatalogKind::Station) {
+        return Err(format!(
+            "{} looks like a station file, not a source catalog.",
+            path
+        ));
+    }
+
+    let mut sources = Vec::new();
+    let mut invalid = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 7 {
+            continue;
+        }
+
+        // A bad line is skipped rather than failing the load, so one typo does not hide the
+        // rest of the catalogue.
+        match parse_source_tokens(&parts, 0, 1, line) {
+            Ok(mut source) => {
+                (source.group, source.priority) = parse_source_extras(&parts[parts.len().min(8)..]);
+                sources.push(source);
+            }
+            Err(e) => invalid.push(format!("line {}: {}", line_no + 1, e)),
+        }
+    }
+    Ok((sources, invalid))
+}
+
+/// Reads and parses a station file: a VEX schedule's `$SITE` block or `station.txt` lines.
+fn read_station_file(path: &str) -> Result<Vec<Station>, String> {
+    let content = read_catalog_file(path, "Station")?;
+    if guess_catalog_kind(&content) == Some(CatalogKind::Source) {
+        return Err(format!(
+            "{} looks like a source catalog, not a station file.",
+            path
+        ));
+    }
+    if is_vex_file(Path::new(path), &content) {
+        return parse_vex_sites(&content);
+    }
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('*'))
+        .map(parse_station_line)
+        .collect()
+}
+
+/// Why a station or source file gave no entries, or `None` when `count` entries were loaded.
+fn catalog_file_problem(path: &str, count: usize, what: &str) -> Option<String> {
+    if count > 0 {
+        return None;
+    }
+    if path.is_empty() {
+        return Some(format!("No {} file is set.", what));
+    }
+    match fs::read_to_string(path) {
+        Err(_) if !Path::new(path).exists() => {
+            Some(format!("The {} file {} does not exist.", what, path))
+        }
+        Err(e) => Some(format!("The {} file {} cannot be read: {}", what, path, e)),
+        Ok(content) if content.trim().is_empty() => {
+            Some(format!("The {} file {} is empty.", what, path))
+        }
+        Ok(_) => Some(format!("No {}s could be read from {}.", what, path)),
+    }
+}
+
 fn ensure_user_data_file(dir: &Path, filename: &str, default_content: &str) -> Option<PathBuf> {
     if fs::create_dir_all(dir).is_err() {
         return None;
@@ -2883,6 +11798,79 @@ fn ensure_user_data_file(dir: &Path, filename: &str, default_content: &str) -> O
     Some(path)
 }
 
+/// The computation features this build supports, for `--capabilities`. Unlike
+/// `effective_config_json` it reads no files or settings, so wrappers can probe the binary
+/// without side effects.
+fn capabilities_json() -> String {
+    let list = |items: Vec<&str>| {
+        items
+            .into_iter()
+            .map(json_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        concat!(
+            "{{\n",
+            "  \"version\": {},\n",
+            "  \"ellipsoids\": [{}],\n",
+            "  \"sidereal_time\": [{}],\n",
+            "  \"coordinate_frames\": [{}],\n",
+            "  \"refraction\": {{\"model\": {}, \"radio_scale\": {}, \"apparent_horizon_deg\": {}}},\n",
+            "  \"dut1_max_seconds\": {},\n",
+            "  \"polar_orientations\": [{}],\n",
+            "  \"plot_images\": [{}],\n",
+            "  \"image_size_px\": {{\"min\": {}, \"max\": {}}},\n",
+            "  \"csv_delimiters\": [{}],\n",
+            "  \"plot_log_formats\": [{}],\n",
+            "  \"calendar_windows\": [{}]\n",
+            "}}"
+        ),
+        json_string(env!("CARGO_PKG_VERSION")),
+        list(utils::Ellipsoid::ALL.iter().map(|e| e.label()).collect()),
+        list(utils::SiderealMode::ALL.iter().map(|m| m.key()).collect()),
+        list(utils::ReferenceFrame::ALL.iter().map(|f| f.key()).collect()),
+        json_string(utils::REFRACTION_MODEL),
+        utils::RADIO_REFRACTION_SCALE,
+        utils::APPARENT_HORIZON_DEG,
+        MAX_DUT1_SECONDS,
+        list(PolarOrientation::ALL.iter().map(|o| o.key()).collect()),
+        list(OutputTarget::ALL.iter().map(|t| t.file_name()).collect()),
+        MIN_IMAGE_SIDE,
+        MAX_IMAGE_SIDE,
+        list(CsvDelimiter::ALL.iter().map(|d| d.key()).collect()),
+        list(PlotLogFormat::ALL.iter().map(|f| f.key()).collect()),
+        list(CalendarWindows::ALL.iter().map(|w| w.label()).collect()),
+    )
+}
+
+/// `text` as a CSV field, quoted when it holds a comma, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Quotes `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 fn pick_file_dialog(title: &str) -> Result<Option<PathBuf>, String> {
     #[cfg(target_os = "windows")]
     {
@@ -2919,6 +11907,58 @@ fn pick_file_dialog(title: &str) -> Result<Option<PathBuf>, String> {
     Err("File selection dialog is not supported on this platform.".to_string())
 }
 
+fn save_file_dialog(title: &str, default_name: &str) -> Result<Option<PathBuf>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $d = New-Object System.Windows.Forms.SaveFileDialog; \
+             $d.Title = '{}'; $d.FileName = '{}'; \
+             if ($d.ShowDialog() -eq [System.Windows.Forms.DialogResult]::OK) {{ $d.FileName }}",
+            title.replace('\'', "''"),
+            default_name.replace('\'', "''")
+        );
+        return pick_file_from_command("powershell", &["-NoProfile", "-Command", &script]);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "POSIX path of (choose file name with prompt \"{}\" default name \"{}\")",
+            title.replace('"', "\\\""),
+            default_name.replace('"', "\\\"")
+        );
+        return pick_file_from_command("osascript", &["-e", &script]);
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        match pick_file_from_command(
+            "zenity",
+            &[
+                "--file-selection",
+                "--save",
+                "--confirm-overwrite",
+                "--filename",
+                default_name,
+                "--title",
+                title,
+            ],
+        ) {
+            Ok(result) => return Ok(result),
+            Err(_) => {
+                return pick_file_from_command(
+                    "kdialog",
+                    &["--getsavefilename", default_name, "*", title],
+                )
+            }
+        }
+    }
+
+    #[allow(unreachable_code)]
+    Err("File save dialog is not supported on this platform.".to_string())
+}
+
 fn pick_file_from_command(program: &str, args: &[&str]) -> Result<Option<PathBuf>, String> {
     let output = std::process::Command::new(program)
         .args(args)
@@ -2935,63 +11975,142 @@ fn pick_file_from_command(program: &str, args: &[&str]) -> Result<Option<PathBuf
     }
 }
 
-fn parse_source_tokens(
-    parts: &[&str],
-    name_idx: usize,
-    coord_start: usize,
-    line: &str,
-) -> Result<Source, String> {
-    if parts.len() <= coord_start + 6 {
-        return Err(format!("Invalid source line: {}", line));
+fn with_default_selection(station: Station) -> (Station, bool) {
+    let selected = station.name == "YAMAGU32";
+    (station, selected)
+}
+
+/// Source name as listed in Source Settings, tagged with its priority when not the default.
+fn source_list_name(source: &Source) -> String {
+    if source.priority == DEFAULT_PRIORITY {
+        source.name.clone()
+    } else {
+        format!("{} [P{}]", source.name, source.priority)
     }
+}
 
-    let name = parts[name_idx].to_string();
-    let ra_h: i32 = parts[coord_start]
-        .parse()
-        .map_err(|_| format!("Invalid RA hour: {}", line))?;
-    let ra_m: i32 = parts[coord_start + 1]
-        .parse()
-        .map_err(|_| format!("Invalid RA minute: {}", line))?;
-    let ra_s: f64 = parts[coord_start + 2]
-        .parse()
-        .map_err(|_| format!("Invalid RA second: {}", line))?;
-    let ra_hours = ra_h as f64 + ra_m as f64 / 60.0 + ra_s / 3600.0;
-    let ra_rad = ra_hours * 15.0_f64.to_radians();
+/// Formats a source in the `source.txt` layout read by `load_sources`.
+fn format_source_catalog_line(source: &Source) -> String {
+    let line = format!(
+        "{} {:02} {:02} {:06.3} {}{:02} {:02} {:08.5} {}",
+        source.name,
+        source.ra_h,
+        source.ra_m,
+        source.ra_s,
+        source.dec_sign,
+        source.dec_d,
+        source.dec_m,
+        source.dec_s,
+        source.epoch
+    );
+    let line = match &source.group {
+        Some(group) => format!("{} {}", line, group),
+        None => line,
+    };
+    if source.priority == DEFAULT_PRIORITY {
+        line
+    } else {
+        format!("{} P{}", line, source.priority)
+    }
+}
 
-    let dec_d_str = parts[coord_start + 3];
-    let dec_sign = if dec_d_str.starts_with('-') { '-' } else { '+' };
-    let dec_d_raw: i32 = dec_d_str
-        .parse()
-        .map_err(|_| format!("Invalid Dec degree: {}", line))?;
-    let dec_m: i32 = parts[coord_start + 4]
-        .parse()
-        .map_err(|_| format!("Invalid Dec minute: {}", line))?;
-    let dec_s: f64 = parts[coord_start + 5]
-        .parse()
-        .map_err(|_| format!("Invalid Dec second: {}", line))?;
-    let dec_d = dec_d_raw.abs();
-    let sign = if dec_sign == '-' { -1.0 } else { 1.0 };
-    let dec_deg = sign * (dec_d as f64 + dec_m as f64 / 60.0 + dec_s / 3600.0);
-    let dec_rad = dec_deg.to_radians();
-    let epoch = parts
-        .get(coord_start + 6)
-        .copied()
-        .unwrap_or("2000.0")
-        .to_string();
-
-    Ok(Source {
-        name,
-        ra_rad,
-        dec_rad,
-        ra_h,
-        ra_m,
-        ra_s,
-        dec_sign,
-        dec_d,
-        dec_m,
-        dec_s,
-        epoch,
-    })
+/// Share of the session (percent) covered by `up_hours` inside it; 0 for an empty session.
+fn session_visible_percent(up_hours: f64, session_hours: f64) -> f64 {
+    if session_hours <= 0.0 {
+        return 0.0;
+    }
+    (up_hours / session_hours * 100.0).clamp(0.0, 100.0)
+}
+
+/// Airmass values of the polar rings in airmass mode; the horizon ring is drawn unlabeled.
+const POLAR_AIRMASS_RINGS: [f64; 5] = [1.2, 1.5, 2.0, 3.0, 5.0];
+
+/// Elevation (degrees) of each polar-plot ring and its label, by degrees or by plane-parallel
+/// airmass (X = 1 / sin el). The zenith is left unlabeled.
+fn polar_ring_levels(airmass: bool) -> Vec<(f64, Option<String>)> {
+    if airmass {
+        std::iter::once((0.0, None))
+            .chain(
+                POLAR_AIRMASS_RINGS
+                    .iter()
+                    .map(|&x| ((1.0 / x).asin().to_degrees(), Some(format!("X={}", x)))),
+            )
+            .chain(std::iter::once((90.0, None)))
+            .collect()
+    } else {
+        [0.0, 15.0, 30.0, 45.0, 60.0, 75.0, 90.0]
+            .into_iter()
+            .map(|el: f64| (el, (el != 90.0).then(|| format!("{:.0}°", el))))
+            .collect()
+    }
+}
+
+/// Sources whose names contain the search filter, grouped in list order, for the query and
+/// catalogue size they were computed for.
+struct SourceListCache {
+    query: String,
+    source_count: usize,
+    groups: Vec<(Option<String>, Vec<usize>)>,
+}
+
+/// Sources per row of the source list.
+const SOURCE_LIST_COLUMNS: usize = 8;
+
+/// One fixed-height row of the virtualised source list.
+enum SourceListRow {
+    Header(Option<String>),
+    /// Up to `SOURCE_LIST_COLUMNS` `self.sources` indices, the first at list position
+    /// `first_pos`.
+    Sources {
+        group: Option<String>,
+        first_pos: usize,
+        indices: Vec<usize>,
+    },
+}
+
+/// Lays the listed groups out as rows: a header per group when `sectioned`, then their sources
+/// `SOURCE_LIST_COLUMNS` to a row.
+fn source_list_rows(
+    groups: &[(Option<String>, Vec<usize>)],
+    sectioned: bool,
+) -> Vec<SourceListRow> {
+    let mut rows = Vec::new();
+    let mut pos = 0;
+    for (group, indices) in groups {
+        if sectioned {
+            rows.push(SourceListRow::Header(group.clone()));
+        }
+        for chunk in indices.chunks(SOURCE_LIST_COLUMNS) {
+            rows.push(SourceListRow::Sources {
+                group: group.clone(),
+                first_pos: pos,
+                indices: chunk.to_vec(),
+            });
+            pos += chunk.len();
+        }
+    }
+    rows
+}
+
+/// Orders named groups alphabetically with untagged sources last.
+fn group_sort_key(group: Option<&str>) -> (bool, String) {
+    (group.is_none(), group.unwrap_or_default().to_string())
+}
+
+/// A stable colour per group tag, so the legend and source list sections match.
+fn group_color(group: &str) -> egui::Color32 {
+    const PALETTE: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(230, 120, 40),
+        egui::Color32::from_rgb(60, 170, 230),
+        egui::Color32::from_rgb(110, 200, 80),
+        egui::Color32::from_rgb(220, 90, 180),
+        egui::Color32::from_rgb(240, 210, 60),
+        egui::Color32::from_rgb(150, 120, 240),
+    ];
+    let hash = group.bytes().fold(0usize, |acc, b| {
+        acc.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    PALETTE[hash % PALETTE.len()]
 }
 
 fn format_source_drg_line(source: &Source) -> String {
@@ -3015,6 +12134,9 @@ fn scan_az_el_for(
     source: &Source,
     ant_pos: [f64; 3],
     at_end: bool,
+    ellipsoid: utils::Ellipsoid,
+    sidereal: utils::SiderealMode,
+    frame: utils::ReferenceFrame,
 ) -> Option<(chrono::NaiveDateTime, f64, f64)> {
     let start = schedule_datetime(row.start_date, &row.start_time).ok()?;
     let time = if at_end {
@@ -3026,11 +12148,11 @@ fn scan_az_el_for(
     let ra = source.ra_rad + row.ra_offset_deg.to_radians();
     let dec = (source.dec_rad + row.dec_offset_deg.to_radians())
         .clamp((-90.0_f64).to_radians(), 90.0_f64.to_radians());
-    let (az, el, _) = utils::radec2azalt(ant_pos, utc, ra, dec);
+    let pointing = utils::radec2azalt_with(ellipsoid, sidereal, frame, ant_pos, utc, ra, dec);
     Some((
         time,
-        (az + row.az_offset_deg / 60.0).rem_euclid(360.0),
-        el + row.el_offset_deg / 60.0,
+        (pointing.az_deg + row.az_offset_deg / 60.0).rem_euclid(360.0),
+        pointing.el_deg + row.el_offset_deg / 60.0,
     ))
 }
 
@@ -3370,12 +12492,142 @@ fn format_hour_hms(hour: f64) -> String {
     format!("{:02}:{:02}:{:02}", hh, mm, ss)
 }
 
-fn save_plot_region_png(
+/// Formats signed degrees as `±DD°MM'SS.SS"`.
+fn format_dms(deg: f64) -> String {
+    let sign = if deg < 0.0 { '-' } else { '+' };
+    let total_centiseconds = (deg.abs() * 360_000.0).round() as i64;
+    let d = total_centiseconds / 360_000;
+    let m = (total_centiseconds % 360_000) / 6_000;
+    let cs = total_centiseconds % 6_000;
+    format!("{}{}°{:02}'{:02}.{:02}\"", sign, d, m, cs / 100, cs % 100)
+}
+
+/// Canvas sizes tried when rendering a fixed-size image offscreen before fitting what remains.
+const OFFSCREEN_LAYOUT_ATTEMPTS: usize = 3;
+
+/// Paints tessellated egui output into a new `size` texture on the app's GPU device and reads
+/// it back, over an opaque `background`.
+fn paint_offscreen(
+    render_state: &eframe::egui_wgpu::RenderState,
+    primitives: &[egui::ClippedPrimitive],
+    textures: &[egui::TexturesDelta],
+    [width, height]: [u32; 2],
+    pixels_per_point: f32,
+    background: egui::Color32,
+) -> Result<egui::ColorImage, String> {
+    use eframe::{egui_wgpu, wgpu};
+
+    let (device, queue) = (&render_state.device, &render_state.queue);
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let mut renderer = egui_wgpu::Renderer::new(device, format, Default::default());
+    for delta in textures {
+        for (id, image_delta) in &delta.set {
+            renderer.update_texture(device, queue, *id, image_delta);
+        }
+    }
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("uptimeplot_offscreen"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+    let screen = egui_wgpu::ScreenDescriptor {
+        size_in_pixels: [width, height],
+        pixels_per_point,
+    };
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("uptimeplot_offscreen"),
+    });
+    let callbacks = renderer.update_buffers(device, queue, &mut encoder, primitives, &screen);
+    {
+        let [r, g, b, a] = background.to_array().map(|c| c as f64 / 255.0);
+        let pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("uptimeplot_offscreen"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        renderer.render(&mut pass.forget_lifetime(), primitives, &screen);
+    }
+
+    let row_bytes = width * 4;
+    let padded_row_bytes = wgpu::util::align_to(row_bytes, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("uptimeplot_offscreen"),
+        size: padded_row_bytes as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(callbacks.into_iter().chain([encoder.finish()]));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(|e| format!("Offscreen render failed: {}", e))?;
+    receiver
+        .recv()
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to read the offscreen image: {}", e))?;
+    let pixels = slice
+        .get_mapped_range()
+        .chunks(padded_row_bytes as usize)
+        .flat_map(|row| row[..row_bytes as usize].chunks(4))
+        .map(|px| egui::Color32::from_rgba_premultiplied(px[0], px[1], px[2], px[3]))
+        .collect();
+    buffer.unmap();
+    Ok(egui::ColorImage::new(
+        [width as usize, height as usize],
+        pixels,
+    ))
+}
+
+/// Margin in points kept around a plot's frame when cropping, so axis labels and ticks are
+/// included.
+const PLOT_CROP_MARGIN: f32 = 56.0;
+
+fn crop_plot_region(
     image: &egui::ColorImage,
     rect_points: egui::Rect,
     pixels_per_point: f32,
-    path: &PathBuf,
-) -> Result<(), String> {
+    pad_points: f32,
+) -> Result<image::RgbaImage, String> {
     if image.size[0] == 0 || image.size[1] == 0 {
         return Err("Screenshot image was empty.".to_string());
     }
@@ -3394,8 +12646,7 @@ fn save_plot_region_png(
     let rgba = image::RgbaImage::from_raw(width, height, raw_pixels)
         .ok_or_else(|| "Failed to build screenshot buffer.".to_string())?;
 
-    // Keep margins so axis labels/ticks around plot frames are included.
-    let pad_px = (56.0 * pixels_per_point).round() as i32;
+    let pad_px = (pad_points * pixels_per_point).round() as i32;
     let mut x = (rect_points.min.x * pixels_per_point).floor() as i32 - pad_px;
     let mut y = (rect_points.min.y * pixels_per_point).floor() as i32 - pad_px;
     let mut w =
@@ -3408,11 +12659,61 @@ fn save_plot_region_png(
     w = w.max(1).min(width as i32 - x);
     h = h.max(1).min(height as i32 - y);
 
-    let cropped =
-        image::imageops::crop_imm(&rgba, x as u32, y as u32, w as u32, h as u32).to_image();
-    cropped
-        .save(path)
-        .map_err(|e| format!("Failed to save {:?}: {}", path, e))
+    Ok(image::imageops::crop_imm(&rgba, x as u32, y as u32, w as u32, h as u32).to_image())
+}
+
+/// Scales `image` to fit `size` pixels and centres it on the image's corner colour, so a
+/// fixed-size export keeps the plot's aspect ratio.
+fn fit_image_to_size(image: &image::RgbaImage, [width, height]: [u32; 2]) -> image::RgbaImage {
+    if image.dimensions() == (width, height) || image.width() == 0 || image.height() == 0 {
+        return image.clone();
+    }
+    let scale = (width as f64 / image.width() as f64).min(height as f64 / image.height() as f64);
+    let scaled_width = ((image.width() as f64 * scale).round() as u32).clamp(1, width);
+    let scaled_height = ((image.height() as f64 * scale).round() as u32).clamp(1, height);
+    let scaled = image::imageops::resize(
+        image,
+        scaled_width,
+        scaled_height,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let mut canvas = image::RgbaImage::from_pixel(width, height, *image.get_pixel(0, 0));
+    image::imageops::overlay(
+        &mut canvas,
+        &scaled,
+        ((width - scaled_width) / 2) as i64,
+        ((height - scaled_height) / 2) as i64,
+    );
+    canvas
+}
+
+/// Parses an image size written as `WIDTHxHEIGHT` pixels.
+fn parse_image_size(text: &str) -> Result<[u32; 2], String> {
+    let (width, height) = text
+        .trim()
+        .split_once(['x', 'X', '×'])
+        .ok_or_else(|| format!("Expected WIDTHxHEIGHT, got {:?}", text))?;
+    let side = |value: &str| {
+        value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|side| (MIN_IMAGE_SIDE..=MAX_IMAGE_SIDE).contains(side))
+            .ok_or_else(|| {
+                format!(
+                    "Image sides must be {}-{} pixels, got {:?}",
+                    MIN_IMAGE_SIDE, MAX_IMAGE_SIDE, value
+                )
+            })
+    };
+    Ok([side(width)?, side(height)?])
+}
+
+fn image_size_label(size: Option<[u32; 2]>) -> String {
+    match size {
+        Some([width, height]) => format!("{}×{}", width, height),
+        None => "Window".to_string(),
+    }
 }
 
 fn calendar_ui(ui: &mut egui::Ui, date: &mut NaiveDate) -> bool {
@@ -3503,6 +12804,352 @@ fn calendar_ui(ui: &mut egui::Ui, date: &mut NaiveDate) -> bool {
 mod tests {
     use super::*;
 
+    /// A fresh app reading `station.txt` and `source.txt` from a new temporary `dir`.
+    fn test_app(dir: &Path, stations: &str, sources: &str) -> UptimePlotApp {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("station.txt"), stations).unwrap();
+        fs::write(dir.join("source.txt"), sources).unwrap();
+        UptimePlotApp::new(CliArgs {
+            station_path: Some(dir.join("station.txt")),
+            source_path: Some(dir.join("source.txt")),
+            print_config: false,
+            fresh: true,
+            export_azel: None,
+            image_size: None,
+            capabilities: false,
+        })
+    }
+
+    fn source_names(app: &UptimePlotApp) -> Vec<&str> {
+        app.sources.iter().map(|(s, _)| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ics_calendar() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 22, 30, 0).unwrap();
+        let events = [CalendarEvent {
+            summary: "3C273 at YAMAGU32".to_string(),
+            description: format!(
+                "Transit at az 180.0°, el 55.2°; {}",
+                "long text ".repeat(10)
+            ),
+            start,
+            end: start + Duration::hours(5),
+        }];
+        let text = ics_calendar(&events, start);
+        assert!(text.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        assert!(text.contains("\r\nDTSTART:20250301T223000Z\r\n"));
+        // The event runs past midnight into the next UTC date.
+        assert!(text.contains("\r\nDTEND:20250302T033000Z\r\n"));
+        assert!(text.contains("SUMMARY:3C273 at YAMAGU32\r\n"));
+        // Commas and semicolons are escaped and long lines folded within 75 octets.
+        assert!(text.contains("az 180.0°\\, el 55.2°\\;"));
+        assert!(text.split("\r\n").all(|line| line.len() <= 75));
+        assert!(text.split("\r\n").any(|line| line.starts_with(' ')));
+    }
+
+    #[test]
+    fn test_source_list_rows() {
+        let groups = vec![
+            (Some("target".to_string()), (0..10).collect::<Vec<usize>>()),
+            (None, vec![10, 11]),
+        ];
+        let rows = source_list_rows(&groups, true);
+        // A header per group, then rows of at most SOURCE_LIST_COLUMNS sources.
+        let shape: Vec<(bool, usize, usize)> = rows
+            .iter()
+            .map(|row| match row {
+                SourceListRow::Header(_) => (true, 0, 0),
+                SourceListRow::Sources {
+                    first_pos, indices, ..
+                } => (false, *first_pos, indices.len()),
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            vec![
+                (true, 0, 0),
+                (false, 0, 8),
+                (false, 8, 2),
+                (true, 0, 0),
+                (false, 10, 2)
+            ]
+        );
+        assert_eq!(source_list_rows(&groups[1..], false).len(), 1);
+    }
+
+    #[test]
+    fn test_polar_orientation() {
+        // Due east at 30° elevation sits two thirds of the way out, on the east side.
+        let [x, y] = PolarOrientation::NorthCw.polar_xy(90.0, 30.0);
+        assert!((x - 2.0 / 3.0).abs() < 1e-12 && y.abs() < 1e-12);
+        let [x, y] = PolarOrientation::NorthCcw.polar_xy(90.0, 30.0);
+        assert!((x + 2.0 / 3.0).abs() < 1e-12 && y.abs() < 1e-12);
+
+        // North is at the top unless the plot is south-up; the zenith is always the centre.
+        let [x, y] = PolarOrientation::NorthCw.polar_xy(0.0, 0.0);
+        assert!(x.abs() < 1e-12 && (y - 1.0).abs() < 1e-12);
+        let [x, y] = PolarOrientation::SouthCw.polar_xy(180.0, 0.0);
+        assert!(x.abs() < 1e-12 && (y - 1.0).abs() < 1e-12);
+        let [x, y] = PolarOrientation::SouthCw.polar_xy(270.0, 0.0);
+        assert!((x - 1.0).abs() < 1e-12 && y.abs() < 1e-12);
+        assert_eq!(PolarOrientation::SouthCcw.polar_xy(123.0, 90.0), [0.0, 0.0]);
+
+        // The cursor readout inverts the mapping for every orientation.
+        for orientation in PolarOrientation::ALL {
+            let [x, y] = orientation.polar_xy(250.0, 40.0);
+            let (az, el) = orientation.az_el_at(x, y).unwrap();
+            assert!((az - 250.0).abs() < 1e-9 && (el - 40.0).abs() < 1e-9);
+        }
+        assert_eq!(PolarOrientation::NorthCw.az_el_at(0.9, 0.9), None);
+    }
+
+    #[test]
+    fn test_time_display_rounding() {
+        let hm = TimeDisplay::default();
+        assert_eq!(hm.format(1.9999), "02:00");
+        assert_eq!(hm.format(23.9999), "00:00");
+        let hms = TimeDisplay {
+            twelve_hour: false,
+            seconds: true,
+        };
+        assert_eq!(hms.format(1.5 + 30.4 / 3600.0), "01:30:30");
+        let twelve = TimeDisplay {
+            twelve_hour: true,
+            seconds: false,
+        };
+        assert_eq!(twelve.format(0.25), "12:15 AM");
+        assert_eq!(twelve.format(12.0), "12:00 PM");
+        assert_eq!(twelve.format(13.5), "01:30 PM");
+    }
+
+    #[test]
+    fn test_lst_grid_marks() {
+        let grid = TimeGrid {
+            last_hour: 24.0,
+            lst_at_midnight: Some(22.5),
+        };
+        let marks = grid.marks(GridInput {
+            bounds: (0.0, 24.0),
+            base_step_size: 1.0,
+        });
+        // A UT day spans a little over 24 sidereal hours: 23h LST through 22h LST next day.
+        assert_eq!(marks.len(), 24);
+        assert!((marks[0].value - 0.5 / SIDEREAL_PER_UT_HOUR).abs() < 1e-12);
+        assert_eq!(grid.label(marks[0].value), "23");
+        assert_eq!(grid.label(marks[1].value), "0");
+        assert!(marks
+            .windows(2)
+            .all(|pair| pair[1].value - pair[0].value < 1.0));
+        assert_eq!(
+            TimeGrid {
+                lst_at_midnight: None,
+                ..grid
+            }
+            .label(6.0),
+            "6"
+        );
+    }
+
+    #[test]
+    fn test_catalog_file_deleted_after_load() {
+        let dir = std::env::temp_dir().join(format!("uptimeplot_test_{}", std::process::id()));
+        let mut app = test_app(
+            &dir,
+            "YAMAGU32 -3502544.587 3950966.235 3566381.192\n",
+            "3C84 03 19 48.160 +41 30 42.10600 2000.0\n",
+        );
+        app.stations[0].1 = true;
+        app.sources[0].1 = true;
+        app.calculate_plots();
+        assert_eq!(app.plot_data.len(), 1);
+        assert!(app.station_watch.is_active() && app.source_watch.is_active());
+
+        // The watcher sees the deletion as a change once it has outlasted the debounce.
+        fs::remove_dir_all(&dir).unwrap();
+        let now = Instant::now();
+        let later = now + CATALOG_RELOAD_DEBOUNCE;
+        assert!(!app.station_watch.poll(&app.station_file_path, now));
+        assert!(app.station_watch.poll(&app.station_file_path, later));
+        assert!(!app.source_watch.poll(&app.source_file_path, now));
+        assert!(app.source_watch.poll(&app.source_file_path, later));
+        app.station_file_changed = true;
+        app.source_file_changed = true;
+        app.reload_changed_catalogs();
+
+        assert_eq!(source_names(&app), ["3C84"]);
+        assert_eq!(app.stations.len(), 1);
+        assert!(app.stations[0].1 && app.sources[0].1);
+        assert_eq!(app.plot_data.len(), 1);
+        assert!(!app.station_file_changed && !app.source_file_changed);
+        let messages: Vec<&str> = app
+            .log_entries
+            .iter()
+            .filter(|entry| matches!(entry.level, LogLevel::Error))
+            .map(|entry| entry.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages
+            .iter()
+            .all(|message| message.contains("not found") && message.contains("keeping the 1")));
+
+        // Reload & Replot reports the failures and replots from the kept catalogues.
+        assert!(app.reload_all_and_replot().is_err());
+        assert_eq!(source_names(&app), ["3C84"]);
+        assert_eq!(app.plot_data.len(), 1);
+    }
+
+    #[test]
+    fn test_adhoc_sources_survive_reload() {
+        let dir = std::env::temp_dir().join(format!("uptimeplot_adhoc_{}", std::process::id()));
+        // A catalogue group spelled like the ad-hoc tag is still a catalogue group.
+        let mut app = test_app(
+            &dir,
+            "YAMAGU32 -3502544.587 3950966.235 3566381.192\n",
+            "3C84 03 19 48.160 +41 30 42.10600 2000.0 ad-hoc\n",
+        );
+        app.adhoc_form = SourceEditor {
+            name: "MYPOS".to_string(),
+            ra: "12 00 00".to_string(),
+            dec: "+10 00 00".to_string(),
+            ..SourceEditor::new_source()
+        };
+        app.add_adhoc_source().unwrap();
+        app.load_sources().unwrap();
+        assert_eq!(source_names(&app), vec!["3C84", "MYPOS"]);
+        assert!(!app.is_adhoc(&app.sources[0].0));
+        assert!(app.is_adhoc(&app.sources[1].0));
+
+        // A catalogue entry of the same name replaces the ad-hoc position.
+        fs::write(
+            dir.join("source.txt"),
+            "3C84 03 19 48.160 +41 30 42.10600 2000.0 ad-hoc\nMYPOS 01 00 00.0 +20 00 00.0 2000.0\n",
+        )
+        .unwrap();
+        app.load_sources().unwrap();
+        assert_eq!(source_names(&app), vec!["3C84", "MYPOS"]);
+        assert!(app.sources.iter().all(|(source, _)| !app.is_adhoc(source)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_settings_migrate_to_preferences() {
+        let dir = std::env::temp_dir().join("uptimeplot_test_migrate_session");
+        let mut app = test_app(&dir, "", "");
+        let (preferences, session) = (dir.join("preferences.txt"), dir.join("session.txt"));
+        fs::write(&preferences, "line_width 3\n").unwrap();
+        fs::write(
+            &session,
+            "sidereal_mode apparent\nrise_set_refracted true\n",
+        )
+        .unwrap();
+        app.preferences_path = Some(preferences.clone());
+        app.session_path = Some(session.clone());
+
+        app.load_preferences();
+        assert_eq!(app.sidereal_mode, utils::SiderealMode::Apparent);
+        assert!(app.rise_set_refracted);
+        let saved = fs::read_to_string(&preferences).unwrap();
+        assert!(saved.contains("sidereal_mode apparent"));
+        assert!(saved.contains("rise_set_refracted true"));
+
+        // Once migrated, the preferences win over a stale session.
+        fs::write(&session, "sidereal_mode mean\nrise_set_refracted false\n").unwrap();
+        app.load_preferences();
+        assert_eq!(app.sidereal_mode, utils::SiderealMode::Apparent);
+        assert!(app.rise_set_refracted);
+
+        // The old `icrs` sidereal mode becomes the ICRS frame.
+        fs::write(&preferences, "sidereal_mode icrs\n").unwrap();
+        app.load_preferences();
+        assert_eq!(app.reference_frame, utils::ReferenceFrame::Icrs);
+        assert_eq!(app.sidereal_mode, utils::SiderealMode::Apparent);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pointing_offset_keeps_windows() {
+        let dir = std::env::temp_dir().join("uptimeplot_test_pointing_offset");
+        let source = "3C84 03 19 48.160 +41 30 42.10600 2000.0\n";
+        let plot = |stations: &str| {
+            let mut app = test_app(&dir, stations, source);
+            app.stations[0].1 = true;
+            app.sources[0].1 = true;
+            app.calculate_plots();
+            app.plot_data.remove(0)
+        };
+        let plain = plot("YAMAGU32 -3502544.587 3950966.235 3566381.192\n");
+        let offset = plot("YAMAGU32 -3502544.587 3950966.235 3566381.192 - - 0 60\n");
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The floor is compared with the true elevation; only the plotted angle moves.
+        assert_eq!(offset.windows, plain.windows);
+        assert_eq!(offset.rise_hours, plain.rise_hours);
+        for (shifted, true_point) in offset.el_points.iter().zip(&plain.el_points) {
+            if true_point[1].is_finite() {
+                assert!((shifted[1] - true_point[1] - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_manual_station_form() {
+        let ellipsoid = utils::Ellipsoid::default();
+        let geodetic = |name: &str, latitude: &str, longitude: &str| ManualStationForm {
+            name: name.to_string(),
+            latitude: latitude.to_string(),
+            longitude: longitude.to_string(),
+            height: "136.9".to_string(),
+            ..ManualStationForm::default()
+        };
+        let station = geodetic("YAMAGU32", "34.2156", "131.5570")
+            .to_station(ellipsoid)
+            .unwrap();
+        assert_eq!(station.name, "YAMAGU32");
+        assert!(
+            (station.pos[0] + 3502544.0).abs() < 100.0,
+            "{:?}",
+            station.pos
+        );
+
+        let ecef = |x: &str, y: &str, z: &str| ManualStationForm {
+            name: "YAMAGU32".to_string(),
+            geodetic: false,
+            x: x.to_string(),
+            y: y.to_string(),
+            z: z.to_string(),
+            ..ManualStationForm::default()
+        };
+        assert!(ecef("-3502544.587", "3950966.235", "3566381.192")
+            .to_station(ellipsoid)
+            .is_ok());
+        // Kilometres instead of metres put the station deep inside the Earth.
+        let error = ecef("-3502.544587", "3950.966235", "3566.381192")
+            .to_station(ellipsoid)
+            .unwrap_err();
+        assert!(error.contains("check the units"), "{}", error);
+
+        for (latitude, longitude) in [
+            ("91", "131.5"),
+            ("-90.5", "0"),
+            ("34.2", "400"),
+            ("34.2", "-181"),
+        ] {
+            let form = geodetic("YAMAGU32", latitude, longitude);
+            assert!(
+                form.to_station(ellipsoid).is_err(),
+                "{} {}",
+                latitude,
+                longitude
+            );
+        }
+        for name in ["YAMA 32", "", "  "] {
+            let form = geodetic(name, "34.2156", "131.5570");
+            assert!(form.to_station(ellipsoid).is_err(), "'{}'", name);
+        }
+    }
+
     #[test]
     fn test_slew_seconds() {
         let antenna = Antenna {
@@ -3530,4 +13177,34 @@ mod tests {
         // max(5, 25) + 2 = 27.0
         assert_eq!(antenna.slew_seconds(0.0, 45.0, 0.0, 55.0), Some(27.0));
     }
+
+    #[test]
+    fn test_parse_image_size() {
+        assert_eq!(parse_image_size("1920x1080"), Ok([1920, 1080]));
+        assert_eq!(parse_image_size(" 1280 × 720 "), Ok([1280, 720]));
+        assert_eq!(parse_image_size("1920X1080"), Ok([1920, 1080]));
+        for bad in ["1920", "100x100", "abcx100", "8000x1000", "1920x"] {
+            assert!(
+                parse_image_size(bad).is_err(),
+                "{:?} should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_fit_image_to_size() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let white = image::Rgba([255, 255, 255, 255]);
+        let mut image = image::RgbaImage::from_pixel(200, 100, red);
+        assert_eq!(fit_image_to_size(&image, [200, 100]), image);
+
+        // A 2:1 image fitted into a square is letterboxed with the corner colour.
+        image.put_pixel(0, 0, white);
+        let fitted = fit_image_to_size(&image, [100, 100]);
+        assert_eq!(fitted.dimensions(), (100, 100));
+        assert_eq!(*fitted.get_pixel(50, 5), white);
+        assert_eq!(*fitted.get_pixel(50, 95), white);
+        assert_eq!(*fitted.get_pixel(50, 50), red);
+    }
 }
@@ -0,0 +1,276 @@
+//! Minimal single-page PDF writer for plot reports: RGB images and Helvetica/Courier text.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+/// An 8-bit RGB raster, row-major from the top-left pixel.
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgb: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PdfFont {
+    Helvetica,
+    HelveticaBold,
+    Courier,
+}
+
+impl PdfFont {
+    const ALL: [PdfFont; 3] = [PdfFont::Helvetica, PdfFont::HelveticaBold, PdfFont::Courier];
+
+    fn resource_name(self) -> &'static str {
+        match self {
+            PdfFont::Helvetica => "F1",
+            PdfFont::HelveticaBold => "F2",
+            PdfFont::Courier => "F3",
+        }
+    }
+
+    fn base_font(self) -> &'static str {
+        match self {
+            PdfFont::Helvetica => "Helvetica",
+            PdfFont::HelveticaBold => "Helvetica-Bold",
+            PdfFont::Courier => "Courier",
+        }
+    }
+}
+
+/// One page laid out in PDF points (1/72 inch) with the origin at the bottom-left corner.
+pub struct PdfPage {
+    width: f64,
+    height: f64,
+    images: Vec<(PdfImage, [f64; 4])>,
+    content: String,
+}
+
+impl PdfPage {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            images: Vec::new(),
+            content: String::new(),
+        }
+    }
+
+    /// A4 landscape.
+    pub fn a4_landscape() -> Self {
+        Self::new(842.0, 595.0)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// Draws `text` with its baseline starting at (`x`, `y`).
+    pub fn text(&mut self, x: f64, y: f64, size: f64, font: PdfFont, text: &str) {
+        let _ = writeln!(
+            self.content,
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ({}) Tj ET",
+            font.resource_name(),
+            size,
+            x,
+            y,
+            escape_pdf_text(text)
+        );
+    }
+
+    /// Places `image` in the box with lower-left corner (`x`, `y`), keeping its aspect ratio.
+    pub fn image(&mut self, image: PdfImage, x: f64, y: f64, max_width: f64, max_height: f64) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        let scale = (max_width / image.width as f64).min(max_height / image.height as f64);
+        let width = image.width as f64 * scale;
+        let height = image.height as f64 * scale;
+        // Centre horizontally and top-align inside the box.
+        let rect = [
+            x + (max_width - width) / 2.0,
+            y + max_height - height,
+            width,
+            height,
+        ];
+        let _ = writeln!(
+            self.content,
+            "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q",
+            rect[2],
+            rect[3],
+            rect[0],
+            rect[1],
+            self.images.len()
+        );
+        self.images.push((image, rect));
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        let font_base = 5;
+        let image_base = font_base + PdfFont::ALL.len();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec());
+
+        let mut resources = String::from("<< /Font <<");
+        for (i, font) in PdfFont::ALL.iter().enumerate() {
+            let _ = write!(
+                resources,
+                " /{} {} 0 R",
+                font.resource_name(),
+                font_base + i
+            );
+        }
+        resources.push_str(" >> /XObject <<");
+        for i in 0..self.images.len() {
+            let _ = write!(resources, " /Im{} {} 0 R", i, image_base + i);
+        }
+        resources.push_str(" >> >>");
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.0} {:.0}] /Resources {} /Contents 4 0 R >>",
+                self.width, self.height, resources
+            )
+            .into_bytes(),
+        );
+        objects.push(stream_object("", self.content.as_bytes()));
+
+        for font in PdfFont::ALL {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font.base_font()
+                )
+                .into_bytes(),
+            );
+        }
+
+        for (image, _) in &self.images {
+            if image.rgb.len() != (image.width * image.height * 3) as usize {
+                return Err("Report image buffer has the wrong size.".to_string());
+            }
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(&image.rgb)
+                .and_then(|_| encoder.flush())
+                .map_err(|e| format!("Failed to compress report image: {}", e))?;
+            let data = encoder
+                .finish()
+                .map_err(|e| format!("Failed to compress report image: {}", e))?;
+            let dict = format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode",
+                image.width, image.height
+            );
+            objects.push(stream_object(&dict, &data));
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref_offset = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+        out.extend_from_slice(b"0000000000 65535 f \n");
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref_offset
+            )
+            .as_bytes(),
+        );
+        Ok(out)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes()?)
+            .map_err(|e| format!("Failed to save {:?}: {}", path, e))
+    }
+}
+
+fn stream_object(dict_entries: &str, data: &[u8]) -> Vec<u8> {
+    let mut object =
+        format!("<< {} /Length {} >>\nstream\n", dict_entries, data.len()).into_bytes();
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+/// Escapes a string for a PDF literal, mapping characters outside WinAnsi to '?'.
+fn escape_pdf_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_page_structure() {
+        assert_eq!(
+            escape_pdf_text("Az (deg) 12°\\"),
+            "Az \\(deg\\) 12\\260\\\\"
+        );
+
+        let mut page = PdfPage::a4_landscape();
+        page.text(40.0, 550.0, 12.0, PdfFont::HelveticaBold, "Report");
+        let bytes = page.to_bytes().unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+
+        // The cross-reference table must point at each object header.
+        let xref_at: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(text[xref_at..].starts_with("xref"));
+        for (i, entry) in text[xref_at..].lines().skip(3).take(7).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+
+        page.image(
+            PdfImage {
+                width: 2,
+                height: 1,
+                rgb: vec![255, 0, 0, 0, 0, 255],
+            },
+            40.0,
+            40.0,
+            200.0,
+            200.0,
+        );
+        assert!(page.to_bytes().is_ok());
+    }
+}
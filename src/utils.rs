@@ -1,28 +1,105 @@
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+
+use crate::catalog::{Source, Station};
 
 use astro::coords;
 use astro::time;
+use astro::{aberr, ecliptic, lunar, nutation, sun};
 use nav_types::{ECEF, WGS84};
 use std::path::Path;
 use std::process::Command;
 
-pub fn radec2azalt(
-    ant_position: [f64; 3],
-    time: DateTime<Utc>,
-    obs_ra: f64,
-    obs_dec: f64,
-) -> (f64, f64, f64) {
+/// Topocentric pointing of a source at one instant.
+#[derive(Clone, Copy, Debug)]
+pub struct PointingResult {
+    pub az_deg: f64,
+    pub el_deg: f64,
+    /// Local hour angle in hours, wrapped to [-12, 12).
+    pub hour_angle: f64,
+    /// Local mean sidereal time in hours, wrapped to [0, 24).
+    pub lst: f64,
+}
+
+/// Reference ellipsoid for converting station ECEF positions to geodetic latitude.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Ellipsoid {
+    #[default]
+    Wgs84,
+    Grs80,
+}
+
+impl Ellipsoid {
+    pub const ALL: [Ellipsoid; 2] = [Ellipsoid::Wgs84, Ellipsoid::Grs80];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Ellipsoid::Wgs84 => "WGS84",
+            Ellipsoid::Grs80 => "GRS80",
+        }
+    }
+
+    /// Semi-major axis (m) and flattening.
+    pub fn parameters(self) -> (f64, f64) {
+        match self {
+            Ellipsoid::Wgs84 => (6_378_137.0, 1.0 / 298.257_223_563),
+            Ellipsoid::Grs80 => (6_378_137.0, 1.0 / 298.257_222_101),
+        }
+    }
+
+    /// Geodetic (latitude, longitude) in radians and ellipsoidal height in metres.
+    pub fn geodetic(self, ecef: [f64; 3]) -> (f64, f64, f64) {
+        if self == Ellipsoid::Wgs84 {
+            let wgs84: WGS84<f64> = ECEF::new(ecef[0], ecef[1], ecef[2]).into();
+            return (
+                wgs84.latitude_radians(),
+                wgs84.longitude_radians(),
+                wgs84.altitude(),
+            );
+        }
+        geodetic_from_ecef(ecef, self.parameters())
+    }
+
+    /// ECEF position (m) of geodetic latitude and longitude (radians) and ellipsoidal height
+    /// (m); the inverse of `geodetic`.
+    pub fn ecef(self, latitude: f64, longitude: f64, height: f64) -> [f64; 3] {
+        let (a, f) = self.parameters();
+        let e2 = f * (2.0 - f);
+        let n = a / (1.0 - e2 * latitude.sin().powi(2)).sqrt();
+        [
+            (n + height) * latitude.cos() * longitude.cos(),
+            (n + height) * latitude.cos() * longitude.sin(),
+            (n * (1.0 - e2) + height) * latitude.sin(),
+        ]
+    }
+}
+
+/// Iterative ECEF to geodetic conversion for an ellipsoid `(a, f)`.
+fn geodetic_from_ecef([x, y, z]: [f64; 3], (a, f): (f64, f64)) -> (f64, f64, f64) {
+    let e2 = f * (2.0 - f);
+    let p = x.hypot(y);
+    let longitude = y.atan2(x);
+    let mut latitude = z.atan2(p * (1.0 - e2));
+    let mut height = 0.0;
+    for _ in 0..10 {
+        let n = a / (1.0 - e2 * latitude.sin().powi(2)).sqrt();
+        height = p / latitude.cos() - n;
+        latitude = z.atan2(p * (1.0 - e2 * n / (n + height)));
+    }
+    (latitude, longitude, height)
+}
+
+fn julian_day_from_utc(time: DateTime<Utc>) -> f64 {
     let obs_year = time.year() as i16;
     let obs_month = time.month() as u8;
     let obs_day = time.day() as u8;
     let obs_hour = time.hour() as u8;
     let obs_minute = time.minute() as u8;
-    let obs_second = time.second() as f64; // + (time.nanosecond() as f64 / 1_000_000_000.0);
+    let obs_second = time.second() as f64 + time.nanosecond() as f64 / 1_000_000_000.0;
 
     let decimal_day_calc = obs_day as f64
         + obs_hour as f64 / 24.0
         + obs_minute as f64 / 60.0 / 24.0
-        + obs_second as f64 / 24.0 / 60.0 / 60.0;
+        + obs_second / 24.0 / 60.0 / 60.0;
 
     let date = time::Date {
         year: obs_year,
@@ -30,54 +107,1128 @@ pub fn radec2azalt(
         decimal_day: decimal_day_calc,
         cal_type: time::CalType::Gregorian,
     };
+    time::julian_day(&date)
+}
 
-    let ecef_position = ECEF::new(ant_position[0], ant_position[1], ant_position[2]);
-    let wgs84_position: WGS84<f64> = ecef_position.into();
-    let longitude_radian = wgs84_position.longitude_radians();
-    let latitude_radian = wgs84_position.latitude_radians();
-    let height_meter = wgs84_position.altitude();
+/// Shifts a UTC instant by dUT1 = UT1 - UTC (seconds, |dUT1| < 0.9 s), giving the UT1 instant
+/// the sidereal-time routines expect. One second of dUT1 moves LST by about 1.0027 s, i.e.
+/// roughly 15″ of hour angle.
+pub fn ut1_from_utc(time: DateTime<Utc>, dut1_seconds: f64) -> DateTime<Utc> {
+    time + chrono::Duration::nanoseconds((dut1_seconds * 1e9).round() as i64)
+}
 
-    let julian_day = time::julian_day(&date);
-    let mean_sidereal = time::mn_sidr(julian_day);
-    let hour_angle = coords::hr_angl_frm_observer_long(mean_sidereal, -longitude_radian, obs_ra);
+/// Which Greenwich sidereal time the pointing is computed from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SiderealMode {
+    /// Mean sidereal time; pairs with mean catalogue positions (J2000 or mean of date).
+    #[default]
+    Mean,
+    /// Mean plus the equation of the equinoxes (nutation, up to about ±1.2 s); pairs with
+    /// apparent RA of date.
+    Apparent,
+}
+
+impl SiderealMode {
+    pub const ALL: [SiderealMode; 2] = [SiderealMode::Mean, SiderealMode::Apparent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SiderealMode::Mean => "Mean (GMST)",
+            SiderealMode::Apparent => "Apparent (GAST)",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            SiderealMode::Mean => "mean",
+            SiderealMode::Apparent => "apparent",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+}
+
+/// The frame catalogue RA/Dec are given in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReferenceFrame {
+    /// Used as given: mean (J2000 or mean of date) or apparent of date, matching the chosen
+    /// `SiderealMode`.
+    #[default]
+    Catalogue,
+    /// ICRS, reduced to apparent place of date (aberration, frame bias, precession, nutation;
+    /// see `icrs_to_apparent`) and paired with apparent sidereal time. The astropy `AltAz`
+    /// chain without refraction.
+    Icrs,
+}
+
+impl ReferenceFrame {
+    pub const ALL: [ReferenceFrame; 2] = [ReferenceFrame::Catalogue, ReferenceFrame::Icrs];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReferenceFrame::Catalogue => "Catalogue (as given)",
+            ReferenceFrame::Icrs => "ICRS → apparent",
+        }
+    }
 
+    pub fn key(self) -> &'static str {
+        match self {
+            ReferenceFrame::Catalogue => "catalogue",
+            ReferenceFrame::Icrs => "icrs",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|frame| frame.key() == key)
+    }
+
+    /// The sidereal time used with this frame: `chosen` for catalogue positions, apparent for
+    /// ICRS positions once they are reduced to apparent place.
+    pub fn sidereal_mode(self, chosen: SiderealMode) -> SiderealMode {
+        match self {
+            ReferenceFrame::Catalogue => chosen,
+            ReferenceFrame::Icrs => SiderealMode::Apparent,
+        }
+    }
+}
+
+/// Equation of the equinoxes in radians: nutation in longitude projected on the true equator.
+fn equation_of_equinoxes(julian_day: f64) -> f64 {
+    let (nut_in_long, nut_in_oblq) = nutation::nutation(julian_day);
+    let true_oblq = ecliptic::mn_oblq_IAU(julian_day) + nut_in_oblq;
+    time::apprnt_sidr(0.0, nut_in_long, true_oblq)
+}
+
+/// Greenwich sidereal time in radians for `mode`.
+fn greenwich_sidereal(julian_day: f64, mode: SiderealMode) -> f64 {
+    let mean = time::mn_sidr(julian_day);
+    match mode {
+        SiderealMode::Mean => mean,
+        SiderealMode::Apparent => mean + equation_of_equinoxes(julian_day),
+    }
+}
+
+type Matrix3 = [[f64; 3]; 3];
+
+fn matrix_product(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let mut product = [[0.0; 3]; 3];
+    for (i, row) in product.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    product
+}
+
+/// Rotation of the coordinate frame by `angle` about axis 0 (x), 1 (y) or 2 (z).
+fn frame_rotation(axis: usize, angle: f64) -> Matrix3 {
+    let (s, c) = angle.sin_cos();
+    let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotation = [[0.0; 3]; 3];
+    rotation[axis][axis] = 1.0;
+    rotation[i][i] = c;
+    rotation[j][j] = c;
+    rotation[i][j] = s;
+    rotation[j][i] = -s;
+    rotation
+}
+
+fn arcsec(value: f64) -> f64 {
+    (value / 3600.0).to_radians()
+}
+
+/// Reduces an ICRS position to the apparent place of date (true equator and equinox), both
+/// in radians: annual aberration (Ron-Vondrák, in the J2000 frame), the ICRS-to-J2000 frame
+/// bias, IAU 1976 precession and IAU 1980 nutation. Light deflection, diurnal aberration and
+/// polar motion are left out; together they stay below about 0.5″ away from the Sun.
+pub fn icrs_to_apparent(julian_day: f64, ra: f64, dec: f64) -> (f64, f64) {
+    let (d_ra, d_dec) =
+        aberr::stell_aberr_in_eq_coords(&coords::EqPoint { asc: ra, dec }, julian_day);
+    let (ra, dec) = (ra + d_ra, dec + d_dec);
+
+    // Frame bias (IERS Conventions 2010, eq. 5.21) as a small rotation.
+    let (d_alpha0, xi0, eta0) = (arcsec(-0.014_6), arcsec(-0.016_617), arcsec(-0.006_819));
+    let bias = [
+        [1.0, d_alpha0, -xi0],
+        [-d_alpha0, 1.0, -eta0],
+        [xi0, eta0, 1.0],
+    ];
+
+    let t = (julian_day - 2_451_545.0) / 36_525.0;
+    let zeta = arcsec((2306.2181 + (0.30188 + 0.017998 * t) * t) * t);
+    let z = arcsec((2306.2181 + (1.09468 + 0.018203 * t) * t) * t);
+    let theta = arcsec((2004.3109 - (0.42665 + 0.041833 * t) * t) * t);
+    let precession = matrix_product(
+        &frame_rotation(2, -z),
+        &matrix_product(&frame_rotation(1, theta), &frame_rotation(2, -zeta)),
+    );
+
+    let (nut_in_long, nut_in_oblq) = nutation::nutation(julian_day);
+    let mean_oblq = ecliptic::mn_oblq_IAU(julian_day);
+    let nutation = matrix_product(
+        &frame_rotation(0, -(mean_oblq + nut_in_oblq)),
+        &matrix_product(
+            &frame_rotation(2, -nut_in_long),
+            &frame_rotation(0, mean_oblq),
+        ),
+    );
+
+    let rotation = matrix_product(&nutation, &matrix_product(&precession, &bias));
+    let v = [dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin()];
+    let [x, y, z]: [f64; 3] = std::array::from_fn(|i| (0..3).map(|k| rotation[i][k] * v[k]).sum());
     (
-        coords::az_frm_eq(hour_angle, obs_dec, latitude_radian).to_degrees() + 180.0,
-        coords::alt_frm_eq(hour_angle, obs_dec, latitude_radian).to_degrees(),
-        height_meter,
+        y.atan2(x).rem_euclid(2.0 * std::f64::consts::PI),
+        z.atan2(x.hypot(y)),
     )
 }
 
-pub fn utc_to_lst_hours(ant_position: [f64; 3], time: DateTime<Utc>) -> f64 {
-    let obs_year = time.year() as i16;
-    let obs_month = time.month() as u8;
-    let obs_day = time.day() as u8;
-    let obs_hour = time.hour() as u8;
-    let obs_minute = time.minute() as u8;
-    let obs_second = time.second() as f64;
+fn radians_to_hours(angle: f64) -> f64 {
+    angle.rem_euclid(2.0 * std::f64::consts::PI) * 24.0 / (2.0 * std::f64::consts::PI)
+}
 
-    let decimal_day_calc = obs_day as f64
-        + obs_hour as f64 / 24.0
-        + obs_minute as f64 / 60.0 / 24.0
-        + obs_second as f64 / 24.0 / 60.0 / 60.0;
+pub fn radec2azalt(
+    ant_position: [f64; 3],
+    time: DateTime<Utc>,
+    obs_ra: f64,
+    obs_dec: f64,
+) -> PointingResult {
+    radec2azalt_on(Ellipsoid::Wgs84, ant_position, time, obs_ra, obs_dec)
+}
 
-    let date = time::Date {
-        year: obs_year,
-        month: obs_month,
-        decimal_day: decimal_day_calc,
-        cal_type: time::CalType::Gregorian,
+/// `radec2azalt` with the station latitude taken on `ellipsoid`.
+pub fn radec2azalt_on(
+    ellipsoid: Ellipsoid,
+    ant_position: [f64; 3],
+    time: DateTime<Utc>,
+    obs_ra: f64,
+    obs_dec: f64,
+) -> PointingResult {
+    radec2azalt_with(
+        ellipsoid,
+        SiderealMode::Mean,
+        ReferenceFrame::Catalogue,
+        ant_position,
+        time,
+        obs_ra,
+        obs_dec,
+    )
+}
+
+/// Azimuth (N=0, E=90) and elevation in degrees, kept finite for every finite input.
+///
+/// At the zenith or nadir the `asin` argument can round just past ±1, giving a NaN
+/// elevation; it is clamped instead. Azimuth is undefined there, so a non-finite azimuth
+/// becomes 180° (the meridian) when the elevation is good. Non-finite inputs stay NaN in
+/// both, which the plots and exports treat as masked samples.
+fn horizontal_deg(hour_angle: f64, dec: f64, latitude: f64) -> (f64, f64) {
+    let mut el_deg = coords::alt_frm_eq(hour_angle, dec, latitude).to_degrees();
+    if !el_deg.is_finite() {
+        let sin_el = latitude.sin() * dec.sin() + latitude.cos() * dec.cos() * hour_angle.cos();
+        el_deg = sin_el.clamp(-1.0, 1.0).asin().to_degrees();
+    }
+    let az_deg = coords::az_frm_eq(hour_angle, dec, latitude).to_degrees() + 180.0;
+    let az_deg = match (az_deg.is_finite(), el_deg.is_finite()) {
+        (true, true) => az_deg,
+        (false, true) => 180.0,
+        _ => f64::NAN,
+    };
+    (az_deg, if az_deg.is_finite() { el_deg } else { f64::NAN })
+}
+
+/// Local sidereal time (radians) from Greenwich sidereal time and an east-positive longitude.
+///
+/// `astro` follows Meeus, where longitudes are positive west and the local hour angle is
+/// `θ0 - L - α`; our geodetic longitudes are positive east, so the sign is flipped here.
+/// Getting it wrong mirrors the hour angle about the station meridian, which the
+/// eastern/western station test catches.
+fn local_sidereal_radian(greenwich_sidereal: f64, east_longitude_radian: f64) -> f64 {
+    coords::hr_angl_frm_observer_long(greenwich_sidereal, -east_longitude_radian, 0.0)
+}
+
+/// `radec2azalt_on` with the hour angle taken from mean or apparent sidereal time, and for
+/// `ReferenceFrame::Icrs` the position first reduced to apparent place (which always uses
+/// apparent sidereal time). The returned `lst` is of the kind used.
+pub fn radec2azalt_with(
+    ellipsoid: Ellipsoid,
+    sidereal: SiderealMode,
+    frame: ReferenceFrame,
+    ant_position: [f64; 3],
+    time: DateTime<Utc>,
+    obs_ra: f64,
+    obs_dec: f64,
+) -> PointingResult {
+    let (latitude_radian, longitude_radian, _) = ellipsoid.geodetic(ant_position);
+
+    let julian_day = julian_day_from_utc(time);
+    let (obs_ra, obs_dec) = match frame {
+        ReferenceFrame::Icrs => icrs_to_apparent(julian_day, obs_ra, obs_dec),
+        ReferenceFrame::Catalogue => (obs_ra, obs_dec),
     };
+    let sidereal = greenwich_sidereal(julian_day, frame.sidereal_mode(sidereal));
+    let lst_radian = local_sidereal_radian(sidereal, longitude_radian);
+    let hour_angle = lst_radian - obs_ra;
+    let (az_deg, el_deg) = horizontal_deg(hour_angle, obs_dec, latitude_radian);
 
+    PointingResult {
+        az_deg,
+        el_deg,
+        hour_angle: (radians_to_hours(hour_angle) + 12.0).rem_euclid(24.0) - 12.0,
+        lst: radians_to_hours(lst_radian),
+    }
+}
+
+/// The time quantities `radec2azalt_on` works from, for cross-checking against other software.
+#[derive(Clone, Copy, Debug)]
+pub struct SiderealTime {
+    pub julian_day: f64,
+    /// Greenwich mean sidereal time in hours.
+    pub gmst_hours: f64,
+    /// Local mean sidereal time in hours.
+    pub lmst_hours: f64,
+    /// Greenwich and local apparent sidereal time in hours.
+    pub gast_hours: f64,
+    pub last_hours: f64,
+    /// GAST - GMST in seconds of time.
+    pub equation_of_equinoxes_seconds: f64,
+    /// Station longitude in degrees, east positive.
+    pub longitude_deg: f64,
+}
+
+impl SiderealTime {
+    /// Local sidereal time in hours under `mode`.
+    pub fn local_hours(&self, mode: SiderealMode) -> f64 {
+        match mode {
+            SiderealMode::Mean => self.lmst_hours,
+            SiderealMode::Apparent => self.last_hours,
+        }
+    }
+}
+
+/// Julian Day and mean and apparent sidereal times at `time` (UT1) for the station at `ant_position`.
+pub fn sidereal_time(
+    ellipsoid: Ellipsoid,
+    ant_position: [f64; 3],
+    time: DateTime<Utc>,
+) -> SiderealTime {
+    let (_, longitude_radian, _) = ellipsoid.geodetic(ant_position);
+    let julian_day = julian_day_from_utc(time);
+    let mean_sidereal = time::mn_sidr(julian_day);
+    let lst_radian = local_sidereal_radian(mean_sidereal, longitude_radian);
+    let equinoxes = equation_of_equinoxes(julian_day);
+    SiderealTime {
+        julian_day,
+        gmst_hours: radians_to_hours(mean_sidereal),
+        lmst_hours: radians_to_hours(lst_radian),
+        gast_hours: radians_to_hours(mean_sidereal + equinoxes),
+        last_hours: radians_to_hours(lst_radian + equinoxes),
+        equation_of_equinoxes_seconds: equinoxes.to_degrees() / 15.0 * 3600.0,
+        longitude_deg: longitude_radian.to_degrees(),
+    }
+}
+
+pub fn utc_to_lst_hours(ant_position: [f64; 3], time: DateTime<Utc>) -> f64 {
     let ecef_position = ECEF::new(ant_position[0], ant_position[1], ant_position[2]);
     let wgs84_position: WGS84<f64> = ecef_position.into();
     let longitude_radian = wgs84_position.longitude_radians();
 
-    let julian_day = time::julian_day(&date);
+    let julian_day = julian_day_from_utc(time);
     let mean_sidereal = time::mn_sidr(julian_day);
-    let lst_radian = coords::hr_angl_frm_observer_long(mean_sidereal, -longitude_radian, 0.0);
-    let wrapped = lst_radian.rem_euclid(2.0 * std::f64::consts::PI);
+    let lst_radian = local_sidereal_radian(mean_sidereal, longitude_radian);
+
+    radians_to_hours(lst_radian)
+}
+
+/// Formats right ascension as `HH:MM:SS.sss`, wrapped to [0h, 24h). Rounding happens on the
+/// total milliseconds, so 59.9996s carries into the minute.
+pub fn format_ra(ra_rad: f64) -> String {
+    if !ra_rad.is_finite() {
+        return "--:--:--.---".to_string();
+    }
+    let hours = radians_to_hours(ra_rad);
+    let total_ms = ((hours * 3_600_000.0).round() as i64).rem_euclid(24 * 3_600_000);
+    let (h, m) = (total_ms / 3_600_000, (total_ms / 60_000) % 60);
+    let ms = total_ms % 60_000;
+    format!("{:02}:{:02}:{:02}.{:03}", h, m, ms / 1000, ms % 1000)
+}
 
-    wrapped * 24.0 / (2.0 * std::f64::consts::PI)
+/// Formats declination as `±DD:MM:SS.ss`, with the sign kept for values between 0° and -1°.
+pub fn format_dec(dec_rad: f64) -> String {
+    if !dec_rad.is_finite() {
+        return "---:--:--.--".to_string();
+    }
+    let deg = dec_rad.to_degrees();
+    let total_cs = (deg.abs() * 360_000.0).round() as i64;
+    let sign = if deg < 0.0 && total_cs > 0 { '-' } else { '+' };
+    let (d, m) = (total_cs / 360_000, (total_cs / 6_000) % 60);
+    let cs = total_cs % 6_000;
+    format!("{}{:02}:{:02}:{:02}.{:02}", sign, d, m, cs / 100, cs % 100)
+}
+
+/// Splits sexagesimal text into up to three fields on whitespace, `:` or unit letters and
+/// symbols (`h m s`, `d ° ' "`), returning the leading sign separately.
+fn sexagesimal_fields(text: &str) -> Result<(f64, Vec<f64>), String> {
+    let text = text.trim();
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let fields: Vec<f64> = rest
+        .split(|c: char| c.is_whitespace() || ":hmsd°'\"′″".contains(c))
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            field
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid sexagesimal field '{}' in '{}'.", field, text))
+        })
+        .collect::<Result<_, _>>()?;
+    if fields.is_empty() || fields.len() > 3 || fields.iter().any(|v| *v < 0.0) {
+        return Err(format!("Expected 1-3 sexagesimal fields in '{}'.", text));
+    }
+    for (i, &value) in fields.iter().enumerate().skip(1) {
+        if value >= 60.0 {
+            return Err(format!(
+                "Minutes and seconds must be below 60 in '{}'.",
+                text
+            ));
+        }
+        if i < fields.len() - 1 && value.fract() != 0.0 {
+            return Err(format!(
+                "Only the last field may have decimals in '{}'.",
+                text
+            ));
+        }
+    }
+    Ok((sign, fields))
+}
+
+fn sexagesimal_value(fields: &[f64]) -> f64 {
+    fields
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(value, scale)| value / scale)
+        .sum()
+}
+
+/// Parses right ascension such as `12:34:56.7`, `12 34 56.7` or `12h34m56.7s` into radians.
+pub fn parse_ra(text: &str) -> Result<f64, String> {
+    let (sign, fields) = sexagesimal_fields(text)?;
+    let hours = sexagesimal_value(&fields);
+    if sign < 0.0 || hours >= 24.0 {
+        return Err(format!("RA must be in 0h-24h: '{}'.", text.trim()));
+    }
+    Ok((hours * 15.0).to_radians())
+}
+
+/// Parses declination such as `-05:06:07.8`, `+45 30 00` or `-0°30'00"` into radians.
+pub fn parse_dec(text: &str) -> Result<f64, String> {
+    let (sign, fields) = sexagesimal_fields(text)?;
+    let deg = sexagesimal_value(&fields);
+    if deg > 90.0 {
+        return Err(format!("Dec must be in -90° to +90°: '{}'.", text.trim()));
+    }
+    Ok((sign * deg).to_radians())
+}
+
+/// RA/Dec (radians) of a `columns` x `rows` grid of pointings spaced `spacing_arcmin` on the
+/// sky and centred on (`ra_rad`, `dec_rad`), row by row from the most negative offsets. RA
+/// offsets are scaled by `1 / cos(dec)` so the spacing holds away from the equator.
+pub fn mosaic_offsets(
+    ra_rad: f64,
+    dec_rad: f64,
+    columns: usize,
+    rows: usize,
+    spacing_arcmin: f64,
+) -> Vec<(f64, f64)> {
+    let spacing = (spacing_arcmin / 60.0).to_radians();
+    let cos_dec = dec_rad.cos().max(1e-6);
+    let centred = |i: usize, n: usize| i as f64 - (n as f64 - 1.0) / 2.0;
+    let half_pi = std::f64::consts::FRAC_PI_2;
+    (0..rows)
+        .flat_map(|row| {
+            (0..columns).map(move |column| {
+                let ra = ra_rad + centred(column, columns) * spacing / cos_dec;
+                let dec = dec_rad + centred(row, rows) * spacing;
+                (
+                    ra.rem_euclid(2.0 * std::f64::consts::PI),
+                    dec.clamp(-half_pi, half_pi),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Geocentric (RA, Dec) in radians of the Sun at `time`, referred to the mean equinox of date.
+/// Accurate to well under a degree, which is enough for avoidance checks.
+pub fn sun_radec(time: DateTime<Utc>) -> (f64, f64) {
+    let julian_day = julian_day_from_utc(time);
+    let (point, _) = sun::geocent_ecl_pos(julian_day);
+    ecliptic_to_radec(julian_day, point)
+}
+
+/// Geocentric (RA, Dec) in radians of the Moon at `time`. Topocentric parallax (up to ~1°)
+/// is ignored.
+pub fn moon_radec(time: DateTime<Utc>) -> (f64, f64) {
+    let julian_day = julian_day_from_utc(time);
+    let (point, _) = lunar::geocent_ecl_pos(julian_day);
+    ecliptic_to_radec(julian_day, point)
+}
+
+fn ecliptic_to_radec(julian_day: f64, point: coords::EclPoint) -> (f64, f64) {
+    let obliquity = ecliptic::mn_oblq_IAU(julian_day);
+    (
+        coords::asc_frm_ecl(point.long, point.lat, obliquity)
+            .rem_euclid(2.0 * std::f64::consts::PI),
+        coords::dec_frm_ecl(point.long, point.lat, obliquity),
+    )
+}
+
+/// (RA, Dec) in radians of the point at ecliptic longitude `longitude` (radians) on the
+/// ecliptic of date at `time`.
+pub fn ecliptic_radec(time: DateTime<Utc>, longitude: f64) -> (f64, f64) {
+    ecliptic_to_radec(
+        julian_day_from_utc(time),
+        coords::EclPoint {
+            long: longitude,
+            lat: 0.0,
+        },
+    )
+}
+
+/// J2000 right ascension and declination (degrees) of the north Galactic pole, and the
+/// Galactic longitude (degrees) of the north celestial pole.
+const GALACTIC_POLE_RA_DEG: f64 = 192.859_48;
+const GALACTIC_POLE_DEC_DEG: f64 = 27.128_25;
+const GALACTIC_NCP_LONGITUDE_DEG: f64 = 122.931_92;
+
+/// J2000 (RA, Dec) in radians of Galactic coordinates (`l`, `b`) in radians.
+pub fn galactic_to_radec(l: f64, b: f64) -> (f64, f64) {
+    let pole_dec = GALACTIC_POLE_DEC_DEG.to_radians();
+    let dl = GALACTIC_NCP_LONGITUDE_DEG.to_radians() - l;
+    let dec = (b.sin() * pole_dec.sin() + b.cos() * pole_dec.cos() * dl.cos()).asin();
+    let ra = GALACTIC_POLE_RA_DEG.to_radians()
+        + (b.cos() * dl.sin())
+            .atan2(b.sin() * pole_dec.cos() - b.cos() * pole_dec.sin() * dl.cos());
+    (ra.rem_euclid(2.0 * std::f64::consts::PI), dec)
+}
+
+/// Great-circle separation in degrees between two (RA, Dec) positions given in radians.
+pub fn angular_separation_deg(ra1: f64, dec1: f64, ra2: f64, dec2: f64) -> f64 {
+    let cos_sep = dec1.sin() * dec2.sin() + dec1.cos() * dec2.cos() * (ra1 - ra2).cos();
+    cos_sep.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+pub const SPEED_OF_LIGHT_M_PER_S: f64 = 299_792_458.0;
+
+/// Geometric delay (s) of station `b` relative to station `a` (ECEF, m) for a source at
+/// Greenwich hour angle `gha` (west positive) and declination `dec`, both in radians: positive
+/// when the wavefront reaches `b` after `a`. Earth rotation during the light travel time and
+/// aberration are ignored.
+pub fn geometric_delay_seconds(a: [f64; 3], b: [f64; 3], gha: f64, dec: f64) -> f64 {
+    let direction = [dec.cos() * gha.cos(), -dec.cos() * gha.sin(), dec.sin()];
+    geometric_delay_along(a, b, direction)
+}
+
+/// `geometric_delay_seconds` for a unit ECEF `direction` towards the source.
+pub fn geometric_delay_along(a: [f64; 3], b: [f64; 3], direction: [f64; 3]) -> f64 {
+    -(0..3).map(|i| (b[i] - a[i]) * direction[i]).sum::<f64>() / SPEED_OF_LIGHT_M_PER_S
+}
+
+/// Unit ECEF vector towards azimuth `az_deg` (N=0, E=90) and elevation `el_deg` seen from
+/// geodetic `latitude` and east `longitude` in radians.
+pub fn ecef_direction(latitude: f64, longitude: f64, az_deg: f64, el_deg: f64) -> [f64; 3] {
+    let (az, el) = (az_deg.to_radians(), el_deg.to_radians());
+    let (east, north, up) = (el.cos() * az.sin(), el.cos() * az.cos(), el.sin());
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    let (sin_lon, cos_lon) = longitude.sin_cos();
+    [
+        -east * sin_lon - north * sin_lat * cos_lon + up * cos_lat * cos_lon,
+        east * cos_lon - north * sin_lat * sin_lon + up * cos_lat * sin_lon,
+        north * cos_lat + up * sin_lat,
+    ]
+}
+
+/// Pairs of positions (RA, Dec in radians) closer than `max_sep_deg`, as index pairs with
+/// their separation in degrees, closest first.
+pub fn close_pairs(positions: &[(f64, f64)], max_sep_deg: f64) -> Vec<(usize, usize, f64)> {
+    let mut pairs = Vec::new();
+    for (i, &(ra1, dec1)) in positions.iter().enumerate() {
+        for (j, &(ra2, dec2)) in positions.iter().enumerate().skip(i + 1) {
+            let separation = angular_separation_deg(ra1, dec1, ra2, dec2);
+            if separation < max_sep_deg {
+                pairs.push((i, j, separation));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.2.total_cmp(&b.2));
+    pairs
+}
+
+/// Collects the intervals where `margin >= 0` from `(hour, margin)` samples, linearly
+/// interpolating the zero crossings between samples. Non-finite margins count as not visible.
+pub fn visibility_windows_from_margin(samples: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut windows = Vec::new();
+    let mut start: Option<f64> = None;
+    let mut prev: Option<(f64, f64)> = None;
+
+    for &(hour, margin) in samples {
+        let up = margin.is_finite() && margin >= 0.0;
+        match (start, prev) {
+            (None, None) if up => start = Some(hour),
+            (None, Some((prev_hour, prev_margin))) if up => {
+                start = Some(margin_crossing(prev_hour, prev_margin, hour, margin, hour));
+            }
+            (Some(window_start), Some((prev_hour, prev_margin))) if !up => {
+                let end = margin_crossing(prev_hour, prev_margin, hour, margin, prev_hour);
+                windows.push((window_start, end));
+                start = None;
+            }
+            _ => {}
+        }
+        prev = Some((hour, margin));
+    }
+    if let (Some(window_start), Some((last_hour, _))) = (start, prev) {
+        windows.push((window_start, last_hour));
+    }
+    windows
+}
+
+fn margin_crossing(h0: f64, m0: f64, h1: f64, m1: f64, fallback: f64) -> f64 {
+    if m0.is_finite() && m1.is_finite() && m0 != m1 {
+        h0 + (h1 - h0) * m0 / (m0 - m1)
+    } else {
+        fallback
+    }
+}
+
+/// Intersects two sorted, non-overlapping window lists.
+pub fn intersect_windows(a: &[(f64, f64)], b: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if start < end {
+            result.push((start, end));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Returns the parts of `[start, end]` not covered by the sorted `windows`.
+pub fn complement_windows(windows: &[(f64, f64)], start: f64, end: f64) -> Vec<(f64, f64)> {
+    let mut result = Vec::new();
+    let mut cursor = start;
+    for &(window_start, window_end) in windows {
+        if window_start > cursor {
+            result.push((cursor, window_start.min(end)));
+        }
+        cursor = cursor.max(window_end);
+        if cursor >= end {
+            break;
+        }
+    }
+    if cursor < end {
+        result.push((cursor, end));
+    }
+    result
+}
+
+/// Splits a plotted track at its non-finite samples into runs of finite points, so each run
+/// can be drawn as its own line instead of relying on the renderer to lift the pen at NaN.
+pub fn finite_runs(points: &[[f64; 2]]) -> Vec<Vec<[f64; 2]>> {
+    points
+        .split(|point| !(point[0].is_finite() && point[1].is_finite()))
+        .filter(|run| !run.is_empty())
+        .map(<[[f64; 2]]>::to_vec)
+        .collect()
+}
+
+/// Joins sorted windows separated by gaps of at most `max_gap_hours`, so a brief dip below the
+/// floor reads as one window. A gap of zero or less leaves the windows unchanged.
+pub fn merge_close_windows(windows: &[(f64, f64)], max_gap_hours: f64) -> Vec<(f64, f64)> {
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(windows.len());
+    for &(start, end) in windows {
+        match merged.last_mut() {
+            Some(last) if max_gap_hours > 0.0 && start - last.1 <= max_gap_hours => {
+                last.1 = last.1.max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Union of windows in any order, as sorted, non-overlapping windows.
+pub fn union_windows(windows: impl IntoIterator<Item = (f64, f64)>) -> Vec<(f64, f64)> {
+    let mut windows: Vec<(f64, f64)> = windows.into_iter().filter(|(s, e)| s < e).collect();
+    windows.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut union: Vec<(f64, f64)> = Vec::with_capacity(windows.len());
+    for (start, end) in windows {
+        match union.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => union.push((start, end)),
+        }
+    }
+    union
+}
+
+pub fn total_window_hours(windows: &[(f64, f64)]) -> f64 {
+    windows.iter().map(|(start, end)| end - start).sum()
+}
+
+/// Scans of `scan_hours` started every `cadence_hours` from the start of each window, keeping
+/// those that end inside it. A cadence shorter than the scan is treated as back-to-back scans.
+pub fn fit_scans(windows: &[(f64, f64)], scan_hours: f64, cadence_hours: f64) -> Vec<(f64, f64)> {
+    if scan_hours <= 0.0 {
+        return Vec::new();
+    }
+    let cadence = cadence_hours.max(scan_hours);
+    let mut scans = Vec::new();
+    for &(start, end) in windows {
+        let mut scan_start = start;
+        // The epsilon keeps a scan that exactly fills the rest of the window.
+        while scan_start + scan_hours <= end + 1e-9 {
+            scans.push((scan_start, scan_start + scan_hours));
+            scan_start += cadence;
+        }
+    }
+    scans
+}
+
+/// `(UT hour, margin)` samples from 00:00 to 24:00 UT of `date` every `interval`, where the
+/// margin is the smaller of the elevation above `min_el_deg` and the hour-angle margin in hours.
+fn visibility_margins(
+    station: &Station,
+    source: &Source,
+    date: NaiveDate,
+    interval: chrono::Duration,
+    min_el_deg: f64,
+) -> Vec<(f64, f64)> {
+    let step_hours = interval.num_milliseconds() as f64 / 3_600_000.0;
+    if step_hours <= 0.0 {
+        return Vec::new();
+    }
+    let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let count = (24.0 / step_hours + 1e-9).floor() as i32 + 1;
+    (0..count)
+        .map(|i| {
+            let time = start + interval * i;
+            let pointing = radec2azalt(station.pos, time, source.ra_rad, source.dec_rad);
+            let margin = (pointing.el_deg - min_el_deg).min(station.ha_margin(pointing.hour_angle));
+            (i as f64 * step_hours, margin)
+        })
+        .collect()
+}
+
+/// Per-sample up/down flags for `source` at `station` from 00:00 to 24:00 UT of `date` every
+/// `interval`: up means at or above `min_el_deg` and inside the station's HA limits.
+pub fn visibility_mask(
+    station: &Station,
+    source: &Source,
+    date: NaiveDate,
+    interval: chrono::Duration,
+    min_el_deg: f64,
+) -> Vec<bool> {
+    visibility_margins(station, source, date, interval, min_el_deg)
+        .into_iter()
+        .map(|(_, margin)| margin >= 0.0)
+        .collect()
+}
+
+/// The up intervals of `visibility_mask` in UT hours, with rise and set interpolated between
+/// samples.
+pub fn visibility_windows(
+    station: &Station,
+    source: &Source,
+    date: NaiveDate,
+    interval: chrono::Duration,
+    min_el_deg: f64,
+) -> Vec<(f64, f64)> {
+    visibility_windows_from_margin(&visibility_margins(
+        station, source, date, interval, min_el_deg,
+    ))
+}
+
+/// The best night-time placement of a source on one date, from `best_night_dates`.
+#[derive(Clone, Copy, Debug)]
+pub struct NightVisibility {
+    pub date: NaiveDate,
+    /// When the source is highest while the Sun is below the limit.
+    pub best_time: DateTime<Utc>,
+    pub el_deg: f64,
+    /// Hour angle (hours) at `best_time`; near zero when the source transits in the dark.
+    pub hour_angle: f64,
+    pub sun_el_deg: f64,
+    /// Hours of the night with the source above the elevation floor.
+    pub dark_up_hours: f64,
+    /// Hour angle (hours) at the darkest moment of the night, i.e. how far from midnight
+    /// the source transits.
+    pub midnight_hour_angle: f64,
+}
+
+const NIGHT_STEP_MIN: i64 = 10;
+
+/// Scans `days` dates from `start` and, for the night around each date's local midnight at
+/// `station` (Sun below `max_sun_el_deg`), finds where `source` is highest. Dates are ranked
+/// by that elevation, then by how close to midnight the source transits. Dates with the source never above
+/// `min_el_deg` (or outside the HA limits) in the dark are left out.
+pub fn best_night_dates(
+    station: &Station,
+    source: &Source,
+    start: NaiveDate,
+    days: u32,
+    max_sun_el_deg: f64,
+    min_el_deg: f64,
+) -> Vec<NightVisibility> {
+    let (_, longitude, _) = Ellipsoid::Wgs84.geodetic(station.pos);
+    // Local noon in UT, so each scan covers one whole night.
+    let noon_offset_min = (12.0 * 60.0 - longitude.to_degrees() * 4.0).round() as i64;
+    let samples = 24 * 60 / NIGHT_STEP_MIN;
+    let mut nights: Vec<NightVisibility> = start
+        .iter_days()
+        .take(days as usize)
+        .filter_map(|date| {
+            let noon =
+                date.and_hms_opt(0, 0, 0)?.and_utc() + chrono::Duration::minutes(noon_offset_min);
+            let mut best: Option<NightVisibility> = None;
+            let mut dark_up_hours = 0.0;
+            let mut darkest = (f64::INFINITY, 0.0);
+            for i in 0..samples {
+                let time = noon + chrono::Duration::minutes(i * NIGHT_STEP_MIN);
+                let (sun_ra, sun_dec) = sun_radec(time);
+                let sun_el_deg = radec2azalt(station.pos, time, sun_ra, sun_dec).el_deg;
+                if sun_el_deg >= max_sun_el_deg {
+                    continue;
+                }
+                let pointing = radec2azalt(station.pos, time, source.ra_rad, source.dec_rad);
+                if sun_el_deg < darkest.0 {
+                    darkest = (sun_el_deg, pointing.hour_angle);
+                }
+                if pointing.el_deg < min_el_deg || !station.ha_allows(pointing.hour_angle) {
+                    continue;
+                }
+                dark_up_hours += NIGHT_STEP_MIN as f64 / 60.0;
+                if best.is_none_or(|best| pointing.el_deg > best.el_deg) {
+                    best = Some(NightVisibility {
+                        date,
+                        best_time: time,
+                        el_deg: pointing.el_deg,
+                        hour_angle: pointing.hour_angle,
+                        sun_el_deg,
+                        dark_up_hours: 0.0,
+                        midnight_hour_angle: 0.0,
+                    });
+                }
+            }
+            best.map(|best| NightVisibility {
+                dark_up_hours,
+                midnight_hour_angle: darkest.1,
+                ..best
+            })
+        })
+        .collect();
+    // Elevations within 0.1° count as equal, so the transit time decides between dates on
+    // which the source transits in the dark.
+    nights.sort_by(|a, b| {
+        let key = |night: &NightVisibility| (night.el_deg * 10.0).round();
+        key(b).total_cmp(&key(a)).then(
+            a.midnight_hour_angle
+                .abs()
+                .total_cmp(&b.midnight_hour_angle.abs()),
+        )
+    });
+    nights
+}
+
+/// Parses allowed azimuth sectors written as `MIN-MAX` degree ranges separated by commas or
+/// spaces, e.g. `"30-150, 200-330"`. A range with `MIN > MAX` wraps through north
+/// (`"300-60"`). Empty text means all azimuths are allowed.
+pub fn parse_az_sectors(text: &str) -> Result<Vec<(f64, f64)>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let (min, max) = token
+                .split_once('-')
+                .ok_or_else(|| format!("Azimuth sector '{}' is not MIN-MAX.", token))?;
+            let (Ok(min), Ok(max)) = (min.trim().parse::<f64>(), max.trim().parse::<f64>()) else {
+                return Err(format!("Invalid number in azimuth sector '{}'.", token));
+            };
+            if !(0.0..=360.0).contains(&min) || !(0.0..=360.0).contains(&max) || min == max {
+                return Err(format!(
+                    "Azimuth sector '{}' must have distinct ends in 0-360°.",
+                    token
+                ));
+            }
+            let width = if max > min {
+                max - min
+            } else {
+                max + 360.0 - min
+            };
+            Ok((min.rem_euclid(360.0), min.rem_euclid(360.0) + width))
+        })
+        .collect()
+}
+
+/// Degrees inside the nearest allowed sector from `parse_az_sectors`; negative outside,
+/// infinite when there are no sectors or one covers every azimuth.
+pub fn az_sector_margin(az_deg: f64, sectors: &[(f64, f64)]) -> f64 {
+    if sectors.is_empty() {
+        return f64::INFINITY;
+    }
+    sectors
+        .iter()
+        .map(|&(min, max)| {
+            let width = max - min;
+            if width >= 360.0 {
+                return f64::INFINITY;
+            }
+            let offset = (az_deg - min).rem_euclid(360.0);
+            if offset <= width {
+                offset.min(width - offset)
+            } else {
+                // Outside: distance back to the nearer end, as a negative margin.
+                -(offset - width).min(360.0 - offset)
+            }
+        })
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+/// Maps a track of 0-360° azimuths into an antenna's cable-wrap range `min_deg..=max_deg`
+/// (e.g. -90..450), moving continuously from sample to sample as the drive would. Of the
+/// starting wraps that keep the whole track inside the range, the one leaving the most room
+/// to the limits is used. `None` if every wrap runs into a limit.
+pub fn unwrap_azimuths(az_deg: &[f64], min_deg: f64, max_deg: f64) -> Option<Vec<f64>> {
+    let first = *az_deg.first()?;
+    let mut best: Option<(f64, Vec<f64>)> = None;
+    for turn in -2..=2 {
+        let start = first.rem_euclid(360.0) + 360.0 * turn as f64;
+        if start < min_deg || start > max_deg {
+            continue;
+        }
+        let mut track = Vec::with_capacity(az_deg.len());
+        let mut previous = start;
+        for &az in az_deg {
+            let step = (az - previous + 180.0).rem_euclid(360.0) - 180.0;
+            previous += step;
+            track.push(previous);
+        }
+        let margin = track
+            .iter()
+            .map(|&az| (az - min_deg).min(max_deg - az))
+            .fold(f64::INFINITY, f64::min);
+        if margin >= 0.0 && best.as_ref().is_none_or(|(m, _)| margin > *m) {
+            best = Some((margin, track));
+        }
+    }
+    best.map(|(_, track)| track)
+}
+
+/// Geometric elevation (degrees) of the apparent horizon: standard refraction of 34′ lifts a
+/// source at this true elevation onto the horizon.
+pub const APPARENT_HORIZON_DEG: f64 = -34.0 / 60.0;
+
+/// Scale applied to the optical refraction formula at radio wavelengths, where water vapour
+/// raises the refractivity of the troposphere.
+pub const RADIO_REFRACTION_SCALE: f64 = 1.25;
+
+/// Name of the refraction formula `refraction_deg` implements.
+pub const REFRACTION_MODEL: &str = "Bennett";
+
+/// Approximate atmospheric refraction (degrees) at apparent elevation `el_deg`, using
+/// Bennett's formula scaled for radio frequencies (below 1 THz) when `frequency_ghz` is set.
+pub fn refraction_deg(el_deg: f64, frequency_ghz: Option<f64>) -> f64 {
+    let el = el_deg.max(-1.0);
+    let optical_arcmin = 1.0 / (el + 7.31 / (el + 4.4)).to_radians().tan();
+    let scale = match frequency_ghz {
+        Some(freq) if freq < 1000.0 => RADIO_REFRACTION_SCALE,
+        _ => 1.0,
+    };
+    optical_arcmin * scale / 60.0
+}
+
+/// Elevation (degrees) where the line-of-sight optical depth `tau0 * airmass` reaches
+/// `max_opacity`, using the plane-parallel airmass `1 / sin(el)`. Returns 90° when even the
+/// zenith is too opaque and `None` without a positive `tau0`.
+pub fn opacity_floor_deg(tau0: f64, max_opacity: f64) -> Option<f64> {
+    if !tau0.is_finite() || tau0 <= 0.0 || !max_opacity.is_finite() || max_opacity <= 0.0 {
+        return None;
+    }
+    let max_airmass = max_opacity / tau0;
+    if max_airmass <= 1.0 {
+        return Some(90.0);
+    }
+    Some((1.0 / max_airmass).asin().to_degrees())
+}
+
+/// Elevation (degrees) below which the plane-parallel airmass exceeds the advisory limit for
+/// `frequency_ghz`, or `None` when opacity is not a concern at that frequency.
+pub fn high_opacity_elevation_deg(frequency_ghz: f64) -> Option<f64> {
+    let max_airmass = if frequency_ghz >= 120.0 {
+        1.5
+    } else if frequency_ghz >= 50.0 {
+        2.0
+    } else if frequency_ghz >= 10.0 {
+        3.0
+    } else {
+        return None;
+    };
+    Some((1.0_f64 / max_airmass).asin().to_degrees())
+}
+
+/// Catmull-Rom interpolation of `[x, y]` samples with `subdivisions` points per interval.
+///
+/// Runs of finite samples are interpolated separately and NaN gaps are kept, so a run of `n`
+/// samples becomes `(n - 1) * subdivisions + 1` points. With `period` set (360 for azimuth),
+/// `y` is unwrapped before interpolating and the result is reduced back to `[0, period)`.
+/// `x` is interpolated linearly, so every original sample is reproduced exactly.
+pub fn catmull_rom_track(
+    points: &[[f64; 2]],
+    subdivisions: usize,
+    period: Option<f64>,
+) -> Vec<[f64; 2]> {
+    if subdivisions <= 1 {
+        return points.to_vec();
+    }
+    let mut result = Vec::with_capacity(points.len() * subdivisions);
+    for run in points.split_inclusive(|p| !p[1].is_finite()) {
+        let (run, gap) = match run.last() {
+            Some(last) if !last[1].is_finite() => (&run[..run.len() - 1], Some(*last)),
+            _ => (run, None),
+        };
+        let mut ys: Vec<f64> = run.iter().map(|p| p[1]).collect();
+        if let Some(period) = period {
+            for i in 1..ys.len() {
+                let step = (ys[i] - ys[i - 1] + period / 2.0).rem_euclid(period) - period / 2.0;
+                ys[i] = ys[i - 1] + step;
+            }
+        }
+        let reduce = |y: f64| period.map_or(y, |period| y.rem_euclid(period));
+        for i in 0..run.len() {
+            result.push([run[i][0], reduce(ys[i])]);
+            if i + 1 == run.len() {
+                break;
+            }
+            // End segments use reflected neighbours so straight runs stay straight.
+            let (p1, p2) = (ys[i], ys[i + 1]);
+            let p0 = if i > 0 { ys[i - 1] } else { 2.0 * p1 - p2 };
+            let p3 = ys.get(i + 2).copied().unwrap_or(2.0 * p2 - p1);
+            for k in 1..subdivisions {
+                let t = k as f64 / subdivisions as f64;
+                let y = 0.5
+                    * (2.0 * p1
+                        + (p2 - p0) * t
+                        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+                        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+                let x = run[i][0] + (run[i + 1][0] - run[i][0]) * t;
+                result.push([x, reduce(y)]);
+            }
+        }
+        result.extend(gap);
+    }
+    result
+}
+
+const MAX_TWO_OPT_PASSES: usize = 50;
+
+/// Ranking weight of a source priority: each level above the default is worth 10% more
+/// (and each level below 10% less, compounding), so priority biases geometric scores
+/// without overriding them.
+pub fn priority_weight(priority: i32) -> f64 {
+    1.1_f64.powi(priority - crate::catalog::DEFAULT_PRIORITY)
+}
+
+/// Sum of `cost[a][b]` over consecutive entries of `order`.
+pub fn path_cost(cost: &[Vec<f64>], order: &[usize]) -> f64 {
+    order.windows(2).map(|pair| cost[pair[0]][pair[1]]).sum()
+}
+
+/// Sum of `visit_penalty[node]` times the node's index in `order`: what it costs to make
+/// each node wait for the ones visited before it.
+fn waiting_cost(visit_penalty: &[f64], order: &[usize]) -> f64 {
+    order
+        .iter()
+        .enumerate()
+        .map(|(position, &node)| position as f64 * visit_penalty[node])
+        .sum()
+}
+
+/// Orders the nodes of the `cost` matrix into an open path with a low total of
+/// `path_cost` plus each node's `visit_penalty` per position it waits, so nodes with a
+/// larger penalty are visited earlier: greedy nearest neighbour from every start node,
+/// refined by 2-opt segment reversals. Ties keep the lower index, so the result is
+/// deterministic; 2-opt stops after `MAX_TWO_OPT_PASSES`.
+pub fn order_minimizing_cost(cost: &[Vec<f64>], visit_penalty: &[f64]) -> Vec<usize> {
+    let n = cost.len();
+    if n < 2 {
+        return (0..n).collect();
+    }
+
+    let mut best: Vec<usize> = (0..n).collect();
+    let mut best_cost = f64::INFINITY;
+    for start in 0..n {
+        let mut visited = vec![false; n];
+        let mut order = vec![start];
+        visited[start] = true;
+        while order.len() < n {
+            let last = order[order.len() - 1];
+            let next = (0..n)
+                .filter(|&j| !visited[j])
+                .min_by(|&a, &b| cost[last][a].total_cmp(&cost[last][b]))
+                .unwrap_or(0);
+            visited[next] = true;
+            order.push(next);
+        }
+        let order_cost = path_cost(cost, &order) + waiting_cost(visit_penalty, &order);
+        // The first greedy path is kept even when every cost is infinite.
+        if start == 0 || order_cost < best_cost {
+            best_cost = order_cost;
+            best = order;
+        }
+    }
+
+    for _ in 0..MAX_TWO_OPT_PASSES {
+        let mut improved = false;
+        for i in 0..n - 1 {
+            // The cost may be asymmetric, so reversing `best[i..=j]` also flips the edges
+            // inside the segment; both directions are summed as `j` grows. The node at
+            // position `p` moves to `i + j - p`, which changes the waiting cost by
+            // `(i + j) * penalties - 2 * weighted`, both also summed over the segment.
+            let (mut forward, mut backward) = (0.0, 0.0);
+            let (mut penalties, mut weighted) =
+                (visit_penalty[best[i]], i as f64 * visit_penalty[best[i]]);
+            for j in i + 1..n {
+                forward += cost[best[j - 1]][best[j]];
+                backward += cost[best[j]][best[j - 1]];
+                penalties += visit_penalty[best[j]];
+                weighted += j as f64 * visit_penalty[best[j]];
+                let mut change = backward - forward + (i + j) as f64 * penalties - 2.0 * weighted;
+                if i > 0 {
+                    change += cost[best[i - 1]][best[j]] - cost[best[i - 1]][best[i]];
+                }
+                if j + 1 < n {
+                    change += cost[best[i]][best[j + 1]] - cost[best[j]][best[j + 1]];
+                }
+                // A NaN change (from infinite costs) is never taken.
+                if change < -1e-9 {
+                    best[i..=j].reverse();
+                    improved = true;
+                    // The running sums describe the old order; move on to the next `i`.
+                    break;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    best
 }
 
 pub fn open_file_in_external_editor(file_path: &str) -> Result<(), String> {
@@ -117,3 +1268,759 @@ pub fn open_file_in_external_editor(file_path: &str) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const YAMAGU32: [f64; 3] = [-3502544.587, 3950966.235, 3566381.192];
+
+    #[test]
+    fn test_radec2azalt_transit() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let lst = utc_to_lst_hours(YAMAGU32, time);
+        let wgs84: WGS84<f64> = ECEF::new(YAMAGU32[0], YAMAGU32[1], YAMAGU32[2]).into();
+        let latitude_deg = wgs84.latitude_degrees();
+
+        // A source with RA equal to the LST transits: HA = 0, due south, el = 90 - (lat - dec).
+        let dec_deg: f64 = 10.0;
+        let ra = (lst * 15.0).to_radians();
+        let pointing = radec2azalt(YAMAGU32, time, ra, dec_deg.to_radians());
+
+        assert!((pointing.lst - lst).abs() < 1e-9);
+        assert!(pointing.hour_angle.abs() < 1e-9);
+        assert!((pointing.az_deg - 180.0).abs() < 1e-6);
+        assert!((pointing.el_deg - (90.0 - (latitude_deg - dec_deg))).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zenith_transit_stays_finite() {
+        // A source at dec = latitude passes through the zenith at transit, where azimuth is
+        // undefined and the elevation's asin argument rounds to about 1.
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let (latitude, _, _) = Ellipsoid::Wgs84.geodetic(YAMAGU32);
+        let ra = (utc_to_lst_hours(YAMAGU32, time) * 15.0).to_radians();
+        let pointing = radec2azalt(YAMAGU32, time, ra, latitude);
+        assert!(pointing.az_deg.is_finite() && (0.0..=360.0).contains(&pointing.az_deg));
+        assert!((pointing.el_deg - 90.0).abs() < 1e-6);
+
+        for (ha, dec, lat) in [
+            (0.0, 0.6, 0.6),
+            (0.0, -0.6, -0.6),
+            (std::f64::consts::PI, 0.6, -0.6),
+            (0.3, std::f64::consts::PI / 2.0, std::f64::consts::PI / 2.0),
+        ] {
+            let (az, el) = horizontal_deg(ha, dec, lat);
+            assert!(az.is_finite() && el.is_finite(), "{} {} {}", ha, dec, lat);
+            assert!((-90.0..=90.0).contains(&el));
+        }
+        // At dec = latitude = 0.623 rad the unclamped `asin` argument rounds to 1 + 2^-52,
+        // which gave a NaN elevation before the clamp: the zenith, azimuth on the meridian.
+        let latitude = 0.623_f64;
+        assert!(coords::alt_frm_eq(0.0, latitude, latitude).is_nan());
+        assert_eq!(horizontal_deg(0.0, latitude, latitude), (180.0, 90.0));
+        // The same rounding on the other side of the sky is the nadir.
+        assert!(coords::alt_frm_eq(std::f64::consts::PI, -latitude, latitude).is_nan());
+        let (az, el) = horizontal_deg(std::f64::consts::PI, -latitude, latitude);
+        assert_eq!(el, -90.0);
+        assert!((0.0..=360.0).contains(&az));
+
+        let (az, el) = horizontal_deg(0.0, f64::NAN, 0.6);
+        assert!(az.is_nan() && el.is_nan());
+    }
+
+    #[test]
+    fn test_eastern_and_western_longitudes() {
+        // (name, ECEF, east longitude, latitude): Yamaguchi in Japan and VLBA Pie Town in
+        // New Mexico.
+        let stations = [
+            ("YAMAGU32", YAMAGU32, 131.557_0, 34.216_0_f64),
+            (
+                "PIETOWN",
+                [-1640953.922, -5014816.015, 3575411.784],
+                -108.119_2,
+                34.301_0,
+            ),
+        ];
+        let time = Utc.with_ymd_and_hms(2025, 3, 20, 6, 0, 0).unwrap();
+        // Meeus (12.4) GMST, independent of the astro crate.
+        let d = julian_day_from_utc(time) - 2_451_545.0;
+        let t = d / 36_525.0;
+        let gmst_deg =
+            280.460_618_37 + 360.985_647_366_29 * d + 0.000_387_933 * t * t - t * t * t / 3.871e7;
+
+        for (name, pos, east_lon, lat) in stations {
+            let st = sidereal_time(Ellipsoid::Wgs84, pos, time);
+            assert!((st.longitude_deg - east_lon).abs() < 1e-3, "{}", name);
+            // LMST adds an east longitude: 0.1 s of time tolerance.
+            let lmst_hours = ((gmst_deg + east_lon) / 15.0).rem_euclid(24.0);
+            assert!(
+                (st.lmst_hours - lmst_hours).abs() < 0.1 / 3600.0,
+                "{}",
+                name
+            );
+
+            // Three hours after transit a source on the equator is in the west (HA > 0),
+            // at the elevation and azimuth of the spherical triangle.
+            let ha = 45.0_f64.to_radians();
+            let ra = (lmst_hours * 15.0).to_radians() - ha;
+            let pointing = radec2azalt(pos, time, ra, 0.0);
+            let phi = lat.to_radians();
+            let el = (phi.cos() * ha.cos()).asin().to_degrees();
+            let az = (-ha.sin())
+                .atan2(-ha.cos() * phi.sin())
+                .to_degrees()
+                .rem_euclid(360.0);
+            assert!((pointing.hour_angle - 3.0).abs() < 1e-4, "{}", name);
+            assert!((pointing.el_deg - el).abs() < 0.01, "{}", name);
+            assert!(
+                (pointing.az_deg.rem_euclid(360.0) - az).abs() < 0.01,
+                "{}",
+                name
+            );
+            assert!(pointing.az_deg.rem_euclid(360.0) > 180.0, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_sun_position_and_separation() {
+        // Near the March 2025 equinox the Sun sits at RA 0h, Dec 0°.
+        let equinox = Utc.with_ymd_and_hms(2025, 3, 20, 9, 1, 0).unwrap();
+        let (ra, dec) = sun_radec(equinox);
+        assert!(angular_separation_deg(ra, dec, 0.0, 0.0) < 0.05);
+
+        // Near the June solstice it sits at RA 6h, Dec +23.44°.
+        let solstice = Utc.with_ymd_and_hms(2025, 6, 21, 2, 42, 0).unwrap();
+        let (ra, dec) = sun_radec(solstice);
+        assert!((ra.to_degrees() - 90.0).abs() < 0.1);
+        assert!((dec.to_degrees() - 23.44).abs() < 0.05);
+
+        assert!((angular_separation_deg(0.0, 0.0, 0.0, 0.0)).abs() < 1e-9);
+        let quarter = std::f64::consts::FRAC_PI_2;
+        assert!((angular_separation_deg(0.0, 0.0, quarter, 0.0) - 90.0).abs() < 1e-9);
+        assert!((angular_separation_deg(1.0, quarter, 2.0, quarter)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dut1_shifts_sidereal_time() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let lst = utc_to_lst_hours(YAMAGU32, time);
+        let lst_ut1 = utc_to_lst_hours(YAMAGU32, ut1_from_utc(time, 0.5));
+        // Half a second of UT1 advances sidereal time by 0.5 * 1.0027 s.
+        assert!(((lst_ut1 - lst) * 3600.0 - 0.5 * 1.002_737_9).abs() < 1e-3);
+        assert_eq!(ut1_from_utc(time, 0.0), time);
+    }
+
+    #[test]
+    fn test_ellipsoid_geodetic() {
+        let wgs84: WGS84<f64> = ECEF::new(YAMAGU32[0], YAMAGU32[1], YAMAGU32[2]).into();
+        let (lat, lon, height) = geodetic_from_ecef(YAMAGU32, Ellipsoid::Wgs84.parameters());
+        assert!((lat - wgs84.latitude_radians()).abs() < 1e-12);
+        assert!((lon - wgs84.longitude_radians()).abs() < 1e-12);
+        assert!((height - wgs84.altitude()).abs() < 1e-4);
+
+        // GRS80 differs from WGS84 by ~0.1 mm in the semi-minor axis.
+        let (grs80_lat, _, grs80_height) = Ellipsoid::Grs80.geodetic(YAMAGU32);
+        assert!((grs80_lat - lat).abs() < 1e-9);
+        assert!((grs80_height - height).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_visibility_windows_and_intersection() {
+        // Rises between 1h and 2h (margin -1 -> +1) and sets between 4h and 5h (+3 -> -1).
+        let samples = [
+            (0.0, -2.0),
+            (1.0, -1.0),
+            (2.0, 1.0),
+            (3.0, 2.0),
+            (4.0, 3.0),
+            (5.0, -1.0),
+        ];
+        let windows = visibility_windows_from_margin(&samples);
+        assert_eq!(windows, vec![(1.5, 4.75)]);
+
+        // Windows still open at the last sample end there.
+        assert_eq!(
+            visibility_windows_from_margin(&[(0.0, 1.0), (1.0, f64::NAN), (2.0, 1.0)]),
+            vec![(0.0, 0.0), (2.0, 2.0)]
+        );
+
+        let other = [(0.0, 2.0), (4.0, 6.0)];
+        assert_eq!(
+            intersect_windows(&windows, &other),
+            vec![(1.5, 2.0), (4.0, 4.75)]
+        );
+    }
+
+    #[test]
+    fn test_finite_runs_split_at_horizon_dips() {
+        // Transiting at 00 UT, the source is up at both ends of the day and down in between;
+        // masking below 30° must leave two separate runs, not one line across the gap.
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let ra = (utc_to_lst_hours(YAMAGU32, midnight) * 15.0).to_radians();
+        let track: Vec<[f64; 2]> = (0..=24 * 6)
+            .map(|i| {
+                let hour = i as f64 / 6.0;
+                let time = midnight + chrono::Duration::minutes(i * 10);
+                let el = radec2azalt(YAMAGU32, time, ra, 0.5).el_deg;
+                [hour, if el >= 30.0 { el } else { f64::NAN }]
+            })
+            .collect();
+        let runs = finite_runs(&track);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0][0][0], 0.0);
+        assert_eq!(runs[1].last().unwrap()[0], 24.0);
+        assert!(runs[0].last().unwrap()[0] < runs[1][0][0] - 6.0);
+        assert_eq!(
+            runs.iter().map(Vec::len).sum::<usize>(),
+            track.iter().filter(|p| p[1].is_finite()).count()
+        );
+
+        assert!(finite_runs(&[[0.0, f64::NAN], [1.0, f64::NAN]]).is_empty());
+        assert_eq!(finite_runs(&[[0.0, 1.0], [1.0, 2.0]]).len(), 1);
+    }
+
+    #[test]
+    fn test_merge_close_windows() {
+        let windows = [(1.0, 2.0), (2.1, 3.0), (5.0, 6.0)];
+        assert_eq!(merge_close_windows(&windows, 0.0), windows.to_vec());
+        assert_eq!(
+            merge_close_windows(&windows, 0.25),
+            vec![(1.0, 3.0), (5.0, 6.0)]
+        );
+        assert_eq!(merge_close_windows(&windows, 2.0), vec![(1.0, 6.0)]);
+        assert!(merge_close_windows(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_union_windows_complement() {
+        let union = union_windows([(5.0, 6.0), (1.0, 3.0), (2.0, 4.0), (7.0, 7.0)]);
+        assert_eq!(union, vec![(1.0, 4.0), (5.0, 6.0)]);
+        assert_eq!(
+            complement_windows(&union, 0.0, 24.0),
+            vec![(0.0, 1.0), (4.0, 5.0), (6.0, 24.0)]
+        );
+        assert!(union_windows([]).is_empty());
+    }
+
+    #[test]
+    fn test_order_minimizing_cost() {
+        // Points on a line visited out of order; the best open path walks them in sequence.
+        let positions = [0.0, 3.0, 1.0, 4.0, 2.0];
+        let cost: Vec<Vec<f64>> = positions
+            .iter()
+            .map(|a: &f64| positions.iter().map(|b| (a - b).abs()).collect())
+            .collect();
+        let no_penalty = [0.0; 5];
+        let order = order_minimizing_cost(&cost, &no_penalty);
+        assert_eq!(path_cost(&cost, &order), 4.0);
+        assert_eq!(order, vec![0, 2, 4, 1, 3]);
+
+        // A waiting penalty on the far end (position 4) makes the path start there instead.
+        let mut penalty = no_penalty;
+        penalty[3] = 2.0;
+        let order = order_minimizing_cost(&cost, &penalty);
+        assert_eq!(order, vec![3, 1, 4, 2, 0]);
+        assert_eq!(path_cost(&cost, &order), 4.0);
+
+        // Unreachable slews (no slew model) must not leave the path empty.
+        let unreachable = vec![vec![f64::INFINITY; 4]; 4];
+        let order = order_minimizing_cost(&unreachable, &[0.0; 4]);
+        assert_eq!(order, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_refraction_and_opacity_limit() {
+        // Bennett's formula gives about 34' at the horizon and under 1' at 45 degrees.
+        assert!((refraction_deg(0.0, None) * 60.0 - 34.5).abs() < 0.5);
+        assert!(refraction_deg(45.0, None) * 60.0 < 1.1);
+        assert!(refraction_deg(10.0, Some(22.0)) > refraction_deg(10.0, None));
+
+        assert_eq!(high_opacity_elevation_deg(2.0), None);
+        assert!((high_opacity_elevation_deg(86.0).unwrap() - 30.0).abs() < 1e-9);
+        assert!(
+            high_opacity_elevation_deg(230.0).unwrap() > high_opacity_elevation_deg(43.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_galactic_and_ecliptic_points() {
+        // The Galactic centre is at RA 17h45m37s, Dec -28°56'.
+        let (ra, dec) = galactic_to_radec(0.0, 0.0);
+        assert!((ra.to_degrees() - 266.405).abs() < 0.01);
+        assert!((dec.to_degrees() + 28.936).abs() < 0.01);
+        // The north Galactic pole.
+        let (_, dec) = galactic_to_radec(1.0, std::f64::consts::FRAC_PI_2);
+        assert!((dec.to_degrees() - GALACTIC_POLE_DEC_DEG).abs() < 1e-6);
+
+        // Ecliptic longitude 90° sits at RA 6h, Dec = obliquity.
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let (ra, dec) = ecliptic_radec(time, std::f64::consts::FRAC_PI_2);
+        assert!((ra.to_degrees() - 90.0).abs() < 1e-6);
+        assert!((dec.to_degrees() - 23.44).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_sidereal_time_at_j2000() {
+        // J2000.0: JD 2451545.0, GMST 18h41m50.5s.
+        let time = Utc.with_ymd_and_hms(2000, 1, 1, 12, 0, 0).unwrap();
+        let st = sidereal_time(Ellipsoid::Wgs84, YAMAGU32, time);
+        assert!((st.julian_day - 2_451_545.0).abs() < 1e-9);
+        assert!((st.gmst_hours - 18.697_374_558).abs() < 1e-6);
+        let expected_lmst = (st.gmst_hours + st.longitude_deg / 15.0).rem_euclid(24.0);
+        assert!((st.lmst_hours - expected_lmst).abs() < 1e-9);
+        assert!((st.lmst_hours - utc_to_lst_hours(YAMAGU32, time)).abs() < 1e-9);
+        // Meeus example 12.a: on 1987-04-10 0h UT the equation of the equinoxes is -0.2317 s.
+        let st = sidereal_time(
+            Ellipsoid::Wgs84,
+            YAMAGU32,
+            Utc.with_ymd_and_hms(1987, 4, 10, 0, 0, 0).unwrap(),
+        );
+        assert!((st.equation_of_equinoxes_seconds + 0.2317).abs() < 0.002);
+        assert!(
+            ((st.gast_hours - st.gmst_hours) * 3600.0 - st.equation_of_equinoxes_seconds).abs()
+                < 1e-6
+        );
+        let pointing = radec2azalt_with(
+            Ellipsoid::Wgs84,
+            SiderealMode::Apparent,
+            ReferenceFrame::Catalogue,
+            YAMAGU32,
+            time,
+            1.0,
+            0.3,
+        );
+        let mean = radec2azalt_on(Ellipsoid::Wgs84, YAMAGU32, time, 1.0, 0.3);
+        assert!(((pointing.hour_angle - mean.hour_angle) * 3600.0).abs() < 1.5);
+    }
+
+    #[test]
+    fn test_icrs_to_apparent_place() {
+        // Meeus example 23.a: θ Persei on 2028 Nov 13.19 TD (JDE 2462088.69), J2000 position
+        // with proper motion applied 2h44m12.975s +49°13'39.90", apparent place of date
+        // 2h46m14.390s +49°21'07.45".
+        let ra = ((2.0 + 44.0 / 60.0 + 12.975 / 3600.0) * 15.0_f64).to_radians();
+        let dec = (49.0 + 13.0 / 60.0 + 39.90 / 3600.0_f64).to_radians();
+        let (ra, dec) = icrs_to_apparent(2_462_088.69, ra, dec);
+        let expected_ra = ((2.0 + 46.0 / 60.0 + 14.390 / 3600.0) * 15.0_f64).to_radians();
+        let expected_dec = (49.0 + 21.0 / 60.0 + 7.45 / 3600.0_f64).to_radians();
+        let ra_error = ((ra - expected_ra) * dec.cos()).to_degrees() * 3600.0;
+        let dec_error = (dec - expected_dec).to_degrees() * 3600.0;
+        assert!(ra_error.abs() < 0.2, "RA off by {ra_error}″");
+        assert!(dec_error.abs() < 0.2, "Dec off by {dec_error}″");
+
+        // The ICRS frame points at the reduced position with apparent sidereal time, whatever
+        // sidereal mode is chosen.
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap();
+        let (ra, dec) = (1.0, 0.5);
+        let icrs = radec2azalt_with(
+            Ellipsoid::Wgs84,
+            SiderealMode::Mean,
+            ReferenceFrame::Icrs,
+            YAMAGU32,
+            time,
+            ra,
+            dec,
+        );
+        let (apparent_ra, apparent_dec) = icrs_to_apparent(julian_day_from_utc(time), ra, dec);
+        let apparent = radec2azalt_with(
+            Ellipsoid::Wgs84,
+            SiderealMode::Apparent,
+            ReferenceFrame::Catalogue,
+            YAMAGU32,
+            time,
+            apparent_ra,
+            apparent_dec,
+        );
+        assert!((icrs.az_deg - apparent.az_deg).abs() < 1e-9);
+        assert!((icrs.el_deg - apparent.el_deg).abs() < 1e-9);
+        // 24 years of precession move the pointing by several arcminutes.
+        let mean = radec2azalt_on(Ellipsoid::Wgs84, YAMAGU32, time, ra, dec);
+        assert!(
+            (icrs.el_deg - mean.el_deg).abs() > 0.02 || (icrs.az_deg - mean.az_deg).abs() > 0.02
+        );
+    }
+
+    #[test]
+    fn test_icrs_altaz_matches_astropy() {
+        // The astropy AltAz example: M33 (ICRS 23.46206906°, +30.66017511°) from Bear Mountain
+        // (41.3° N, 74° W, 390 m) at 2012-07-13 03:00 UTC, "M33's Altitude = 0.13 deg". astropy
+        // applies the IERS dUT1 of 0.5648 s for that date; the check is at its printed precision.
+        let bear_mountain =
+            Ellipsoid::Wgs84.ecef(41.3_f64.to_radians(), -74_f64.to_radians(), 390.0);
+        let time = Utc.with_ymd_and_hms(2012, 7, 13, 3, 0, 0).unwrap()
+            + chrono::Duration::microseconds(564_800);
+        let (ra, dec) = (
+            23.462_069_06_f64.to_radians(),
+            30.660_175_11_f64.to_radians(),
+        );
+        let icrs = radec2azalt_with(
+            Ellipsoid::Wgs84,
+            SiderealMode::Mean,
+            ReferenceFrame::Icrs,
+            bear_mountain,
+            time,
+            ra,
+            dec,
+        );
+        assert!(
+            (icrs.el_deg - 0.13).abs() < 0.005,
+            "altitude {}",
+            icrs.el_deg
+        );
+        // Taking the ICRS position as a mean place of date misses by about 0.05°.
+        let mean = radec2azalt_on(Ellipsoid::Wgs84, bear_mountain, time, ra, dec);
+        assert!(
+            (mean.el_deg - 0.13).abs() > 0.03,
+            "altitude {}",
+            mean.el_deg
+        );
+    }
+
+    #[test]
+    fn test_geodetic_ecef_round_trip() {
+        for ellipsoid in Ellipsoid::ALL {
+            let (lat, lon, height) = ellipsoid.geodetic(YAMAGU32);
+            let ecef = ellipsoid.ecef(lat, lon, height);
+            for (a, b) in ecef.iter().zip(YAMAGU32) {
+                assert!((a - b).abs() < 1e-3, "{:?}: {} vs {}", ellipsoid, a, b);
+            }
+        }
+        // On the equator at the prime meridian the position is the semi-major axis plus height.
+        let [x, y, z] = Ellipsoid::Grs80.ecef(0.0, 0.0, 100.0);
+        assert!((x - 6_378_237.0).abs() < 1e-6 && y.abs() < 1e-6 && z.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_geometric_delay() {
+        // A polar baseline pointing at the celestial pole: the wavefront reaches b first.
+        let pole = geometric_delay_seconds(
+            [0.0; 3],
+            [0.0, 0.0, 1000.0],
+            1.0,
+            std::f64::consts::FRAC_PI_2,
+        );
+        assert!((pole + 1000.0 / SPEED_OF_LIGHT_M_PER_S).abs() < 1e-15);
+        // An x-axis baseline with the source on the Greenwich meridian at the equator, then
+        // six hours later: along the baseline, then perpendicular to it.
+        let on_meridian = geometric_delay_seconds([0.0; 3], [3000.0, 0.0, 0.0], 0.0, 0.0);
+        assert!((on_meridian + 3000.0 / SPEED_OF_LIGHT_M_PER_S).abs() < 1e-15);
+        let six_hours = geometric_delay_seconds(
+            [0.0; 3],
+            [3000.0, 0.0, 0.0],
+            std::f64::consts::FRAC_PI_2,
+            0.0,
+        );
+        assert!(six_hours.abs() < 1e-15);
+        // Swapping the stations flips the sign.
+        let a = [-3502544.587, 3950966.235, 3566381.192];
+        let b = [-3961788.9, 3243597.5, 3790595.8];
+        let forward = geometric_delay_seconds(a, b, 0.7, 0.3);
+        assert!((forward + geometric_delay_seconds(b, a, 0.7, 0.3)).abs() < 1e-18);
+
+        // The direction rebuilt from station a's azimuth and elevation gives the same delay.
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap();
+        let pointing = radec2azalt(a, time, 1.0, 0.3);
+        let (latitude, longitude, _) = Ellipsoid::Wgs84.geodetic(a);
+        let gha = (pointing.hour_angle * 15.0).to_radians() - longitude;
+        let direction = ecef_direction(latitude, longitude, pointing.az_deg, pointing.el_deg);
+        let along = geometric_delay_along(a, b, direction);
+        assert!((along - geometric_delay_seconds(a, b, gha, 0.3)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_close_pairs() {
+        let arcmin = (1.0_f64 / 60.0).to_radians();
+        let positions = [
+            (1.0, 0.5),
+            (1.0 + 3.0 * arcmin / 0.5_f64.cos(), 0.5),
+            (2.0, -0.3),
+            (1.0, 0.5 + arcmin),
+        ];
+        let pairs = close_pairs(&positions, 5.0 / 60.0);
+        let found: Vec<(usize, usize)> = pairs.iter().map(|&(i, j, _)| (i, j)).collect();
+        assert_eq!(found, vec![(0, 3), (0, 1), (1, 3)]);
+        assert!((pairs[0].2 * 60.0 - 1.0).abs() < 1e-6);
+        assert!((pairs[1].2 * 60.0 - 3.0).abs() < 1e-3);
+        assert!(close_pairs(&positions, 0.5 / 60.0).is_empty());
+    }
+
+    fn source_at(ra_rad: f64, dec_rad: f64) -> Source {
+        Source {
+            name: "TEST".to_string(),
+            ra_rad,
+            dec_rad,
+            ra_h: 0,
+            ra_m: 0,
+            ra_s: 0.0,
+            dec_sign: '+',
+            dec_d: 0,
+            dec_m: 0,
+            dec_s: 0.0,
+            epoch: "2000".to_string(),
+            group: None,
+            priority: crate::catalog::DEFAULT_PRIORITY,
+        }
+    }
+
+    #[test]
+    fn test_visibility_mask_and_windows() {
+        let station = Station {
+            name: "YAMAGU32".to_string(),
+            pos: YAMAGU32,
+            ha_limits: None,
+            az_offset_arcmin: 0.0,
+            el_offset_arcmin: 0.0,
+        };
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let interval = chrono::Duration::minutes(10);
+
+        // Circumpolar at latitude 34°N: up at every sample, one window over the whole day.
+        let polar = source_at(0.0, 80f64.to_radians());
+        let mask = visibility_mask(&station, &polar, date, interval, 10.0);
+        assert_eq!(mask.len(), 145);
+        assert!(mask.iter().all(|&up| up));
+        assert_eq!(
+            visibility_windows(&station, &polar, date, interval, 10.0),
+            vec![(0.0, 24.0)]
+        );
+
+        // Never rises.
+        let southern = source_at(0.0, (-70f64).to_radians());
+        assert!(!visibility_mask(&station, &southern, date, interval, 0.0)
+            .iter()
+            .any(|&up| up));
+        assert!(visibility_windows(&station, &southern, date, interval, 0.0).is_empty());
+
+        // An equatorial source transiting at 12:00 UT is up for about 12 h around noon.
+        let noon = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let ra = (utc_to_lst_hours(YAMAGU32, noon) * 15.0).to_radians();
+        let equatorial = source_at(ra, 0.0);
+        let windows = visibility_windows(&station, &equatorial, date, interval, 0.0);
+        assert_eq!(windows.len(), 1);
+        let (rise, set) = windows[0];
+        assert!((rise - 6.0).abs() < 0.3 && (set - 18.0).abs() < 0.3);
+        let mask = visibility_mask(&station, &equatorial, date, interval, 0.0);
+        assert!(!mask[0] && mask[72] && !mask[144]);
+        let up_hours = mask.iter().filter(|&&up| up).count() as f64 / 6.0;
+        assert!((up_hours - (set - rise)).abs() < 0.2);
+
+        // HA limits cut the window down to the allowed range around transit.
+        let limited = Station {
+            ha_limits: Some((-2.0, 2.0)),
+            ..station.clone()
+        };
+        let windows = visibility_windows(&limited, &equatorial, date, interval, 0.0);
+        assert_eq!(windows.len(), 1);
+        assert!((windows[0].0 - 10.0).abs() < 0.05 && (windows[0].1 - 14.0).abs() < 0.05);
+
+        assert!(visibility_mask(&station, &polar, date, chrono::Duration::zero(), 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_best_night_dates() {
+        let station = Station {
+            name: "YAMAGU32".to_string(),
+            pos: YAMAGU32,
+            ha_limits: None,
+            az_offset_arcmin: 0.0,
+            el_offset_arcmin: 0.0,
+        };
+        // A source opposite the Sun on 2025-10-01 transits near local midnight then.
+        let opposition = NaiveDate::from_ymd_opt(2025, 10, 1).unwrap();
+        let (sun_ra, _) = sun_radec(opposition.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        let source = source_at(sun_ra + std::f64::consts::PI, 30.0_f64.to_radians());
+        let start = opposition - chrono::Duration::days(20);
+        let nights = best_night_dates(&station, &source, start, 41, -12.0, 10.0);
+        let best = nights[0];
+        assert!(
+            (best.date - opposition).num_days().abs() <= 12,
+            "{}",
+            best.date
+        );
+        assert!(best.hour_angle.abs() < 0.2);
+        assert!(best.sun_el_deg < -12.0);
+        // Transit elevation is 90° - |latitude - dec|.
+        let (latitude, _, _) = Ellipsoid::Wgs84.geodetic(YAMAGU32);
+        assert!((best.el_deg - (90.0 - (latitude.to_degrees() - 30.0).abs())).abs() < 0.5);
+        // Three months off the best night, the source is lower in the dark.
+        let early = opposition - chrono::Duration::days(90);
+        let early = best_night_dates(&station, &source, early, 1, -12.0, 10.0)[0];
+        assert!(early.el_deg < best.el_deg - 5.0);
+    }
+
+    #[test]
+    fn test_az_sectors() {
+        assert!(parse_az_sectors("  ").unwrap().is_empty());
+        assert_eq!(az_sector_margin(123.0, &[]), f64::INFINITY);
+
+        let sectors = parse_az_sectors("30-150, 300-60").unwrap();
+        assert_eq!(sectors, vec![(30.0, 150.0), (300.0, 420.0)]);
+        assert!((az_sector_margin(90.0, &sectors) - 60.0).abs() < 1e-9);
+        // Wrapped sector, on either side of north.
+        assert!((az_sector_margin(350.0, &sectors) - 50.0).abs() < 1e-9);
+        assert!((az_sector_margin(10.0, &sectors) - 50.0).abs() < 1e-9);
+        assert!((az_sector_margin(200.0, &sectors) + 50.0).abs() < 1e-9);
+        assert!((az_sector_margin(160.0, &sectors) + 10.0).abs() < 1e-9);
+
+        assert_eq!(
+            az_sector_margin(77.0, &parse_az_sectors("0-360").unwrap()),
+            f64::INFINITY
+        );
+        assert!(parse_az_sectors("30").is_err());
+        assert!(parse_az_sectors("30-400").is_err());
+        assert!(parse_az_sectors("90-90").is_err());
+        assert!(parse_az_sectors("a-90").is_err());
+    }
+
+    #[test]
+    fn test_sexagesimal_round_trip() {
+        for text in [
+            "00:00:00.000",
+            "05:34:31.940",
+            "12:00:00.001",
+            "23:59:59.999",
+        ] {
+            assert_eq!(format_ra(parse_ra(text).unwrap()), text);
+        }
+        for text in [
+            "+00:00:00.00",
+            "-00:30:00.00",
+            "+22:00:52.06",
+            "-89:59:59.99",
+            "+90:00:00.00",
+        ] {
+            assert_eq!(format_dec(parse_dec(text).unwrap()), text);
+        }
+        let ra = parse_ra("12h34m56.789s").unwrap();
+        assert!((ra - parse_ra("12 34 56.789").unwrap()).abs() < 1e-15);
+        assert!((parse_ra(&format_ra(ra)).unwrap() - ra).abs() < 1e-9);
+        let dec = parse_dec("-5°06'07.8\"").unwrap();
+        assert!((dec - (-(5.0 + 6.0 / 60.0 + 7.8 / 3600.0_f64)).to_radians()).abs() < 1e-15);
+        assert!((parse_dec(&format_dec(dec)).unwrap() - dec).abs() < 1e-9);
+
+        // Rounding carries through seconds, minutes and the 24h wrap.
+        assert_eq!(
+            format_ra(parse_ra("01:59:59.9996").unwrap()),
+            "02:00:00.000"
+        );
+        assert_eq!(
+            format_ra(parse_ra("23:59:59.9999").unwrap()),
+            "00:00:00.000"
+        );
+        assert_eq!(
+            format_dec(parse_dec("+10:59:59.996").unwrap()),
+            "+11:00:00.00"
+        );
+        // A value that rounds to zero drops the minus sign.
+        assert_eq!(format_dec((-1e-9f64).to_radians()), "+00:00:00.00");
+        assert_eq!(format_ra(-(15f64).to_radians()), "23:00:00.000");
+
+        assert!(parse_ra("24:00:00").is_err());
+        assert!(parse_ra("-01:00:00").is_err());
+        assert!(parse_ra("12:60:00").is_err());
+        assert!(parse_ra("12:30.5:00").is_err());
+        assert!(parse_ra("12:00:00:00").is_err());
+        assert!(parse_dec("+90:00:01").is_err());
+        assert!(parse_dec("abc").is_err());
+    }
+
+    #[test]
+    fn test_opacity_floor() {
+        // tau0 = 0.1 with a limit of 0.2 allows airmass 2, i.e. 30° elevation.
+        assert!((opacity_floor_deg(0.1, 0.2).unwrap() - 30.0).abs() < 1e-9);
+        let floor = opacity_floor_deg(0.05, 0.3).unwrap();
+        assert!((0.05 / floor.to_radians().sin() - 0.3).abs() < 1e-9);
+        assert_eq!(opacity_floor_deg(0.5, 0.4), Some(90.0));
+        assert_eq!(opacity_floor_deg(0.0, 0.4), None);
+        assert_eq!(opacity_floor_deg(f64::NAN, 0.4), None);
+    }
+
+    #[test]
+    fn test_mosaic_offsets() {
+        let (ra, dec) = (1.0, 0.5);
+        assert_eq!(mosaic_offsets(ra, dec, 1, 1, 10.0), vec![(ra, dec)]);
+
+        let grid = mosaic_offsets(ra, dec, 3, 3, 10.0);
+        assert_eq!(grid.len(), 9);
+        assert!((grid[4].0 - ra).abs() < 1e-12 && (grid[4].1 - dec).abs() < 1e-12);
+        // Neighbours are one spacing apart on the sky, corners about √2 spacings.
+        let sep = |(ra1, dec1): (f64, f64), (ra2, dec2): (f64, f64)| {
+            angular_separation_deg(ra1, dec1, ra2, dec2) * 60.0
+        };
+        assert!((sep(grid[4], grid[5]) - 10.0).abs() < 0.01);
+        assert!((sep(grid[4], grid[7]) - 10.0).abs() < 1e-6);
+        assert!((sep(grid[4], grid[0]) - 10.0 * 2f64.sqrt()).abs() < 0.02);
+
+        // Even sizes straddle the centre.
+        let pair = mosaic_offsets(ra, dec, 2, 1, 10.0);
+        assert!((pair[0].0 + pair[1].0 - 2.0 * ra).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fit_scans() {
+        let windows = [(1.0, 2.0), (5.0, 5.2)];
+        // 10-min scans every 20 min: 1:00, 1:20, 1:40 fit; 5:00 fits, 5:20 does not start.
+        let scans = fit_scans(&windows, 10.0 / 60.0, 20.0 / 60.0);
+        assert_eq!(scans.len(), 4);
+        assert!((scans[2].1 - (1.0 + 50.0 / 60.0)).abs() < 1e-9);
+        assert_eq!(scans[3].0, 5.0);
+        // A cadence shorter than the scan degrades to back-to-back scans filling the window.
+        let scans = fit_scans(&[(0.0, 1.0)], 0.25, 0.1);
+        assert_eq!(scans.len(), 4);
+        assert!((total_window_hours(&scans) - 1.0).abs() < 1e-9);
+        assert!(fit_scans(&windows, 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_unwrap_azimuths() {
+        // Through north on a -90..450 mount: continuous, starting on the wrap with most room.
+        let track = unwrap_azimuths(&[340.0, 350.0, 0.0, 10.0], -90.0, 450.0).unwrap();
+        assert_eq!(track, vec![340.0, 350.0, 360.0, 370.0]);
+        let track = unwrap_azimuths(&[100.0, 90.0, 80.0], -90.0, 450.0).unwrap();
+        assert_eq!(track, vec![100.0, 90.0, 80.0]);
+
+        // A 2-358 mount cannot follow a source through north.
+        assert_eq!(unwrap_azimuths(&[350.0, 355.0, 5.0], 2.0, 358.0), None);
+        assert_eq!(
+            unwrap_azimuths(&[200.0, 180.0, 160.0], 2.0, 358.0),
+            Some(vec![200.0, 180.0, 160.0])
+        );
+        // On a 120-690 mount the 60° start needs the upper wrap.
+        let track = unwrap_azimuths(&[60.0, 40.0], 120.0, 690.0).unwrap();
+        assert_eq!(track, vec![420.0, 400.0]);
+        assert_eq!(unwrap_azimuths(&[], 0.0, 360.0), None);
+    }
+
+    #[test]
+    fn test_catmull_rom_track() {
+        // A straight line stays straight and passes through every sample.
+        let line = [[0.0, 0.0], [1.0, 10.0], [2.0, 20.0], [3.0, 30.0]];
+        let smooth = catmull_rom_track(&line, 4, None);
+        assert_eq!(smooth.len(), 13);
+        for p in &smooth {
+            assert!((p[1] - p[0] * 10.0).abs() < 1e-9);
+        }
+
+        // Azimuth crossing north interpolates through 0°, not back across 180°.
+        let az = [[0.0, 350.0], [1.0, 355.0], [2.0, 5.0], [3.0, 10.0]];
+        let smooth = catmull_rom_track(&az, 2, Some(360.0));
+        assert!((smooth[3][1] - 0.0).abs() < 0.5 || (smooth[3][1] - 360.0).abs() < 0.5);
+        assert!(smooth.iter().all(|p| (0.0..360.0).contains(&p[1])));
+
+        // NaN gaps split the runs and are kept.
+        let gappy = [
+            [0.0, 1.0],
+            [1.0, 2.0],
+            [2.0, f64::NAN],
+            [3.0, 4.0],
+            [4.0, 5.0],
+        ];
+        let smooth = catmull_rom_track(&gappy, 3, None);
+        assert_eq!(smooth.len(), 4 + 1 + 4);
+        assert!(smooth[4][1].is_nan());
+        assert_eq!(smooth[4][0], 2.0);
+    }
+}